@@ -0,0 +1,121 @@
+//! Helpers for validating and invoking provider CLI binaries that may be
+//! symlinks or script wrappers rather than plain executables.
+
+use std::path::{Path, PathBuf};
+
+/// Wrapper extensions tried, in order, when a Windows path has none
+#[cfg(windows)]
+const WINDOWS_EXTENSIONS: &[&str] = &["exe", "cmd", "bat", "ps1"];
+
+#[cfg(unix)]
+fn is_executable(path: &Path) -> bool {
+    use std::os::unix::fs::PermissionsExt;
+    std::fs::metadata(path)
+        .map(|m| m.is_file() && m.permissions().mode() & 0o111 != 0)
+        .unwrap_or(false)
+}
+
+#[cfg(not(unix))]
+fn is_executable(path: &Path) -> bool {
+    // Windows decides by extension; anything that is a file is runnable via
+    // `command_for`
+    std::fs::metadata(path).map(|m| m.is_file()).unwrap_or(false)
+}
+
+/// Resolve a configured binary path to something that can be run.
+///
+/// Symlinks are followed for the check (npm-global installs are symlinks)
+/// but the original path is returned. On Windows a path without an extension
+/// also matches `.exe`, `.cmd`, `.bat` and `.ps1` wrappers.
+pub fn resolve_executable(path: &str) -> Option<PathBuf> {
+    let pb = PathBuf::from(path);
+    if is_executable(&pb) {
+        return Some(pb);
+    }
+    #[cfg(windows)]
+    if pb.extension().is_none() {
+        return WINDOWS_EXTENSIONS
+            .iter()
+            .map(|ext| pb.with_extension(ext))
+            .find(|p| is_executable(p));
+    }
+    None
+}
+
+/// Normalize a binary path before it is stored: expand a leading `~`, make it
+/// absolute, resolve `..` and symlinks, and check that the result can be run.
+///
+/// Errors describe what is wrong so the settings screen can show it instead
+/// of a chat failing to launch later.
+pub fn canonicalize_binary(path: &str) -> Result<String, String> {
+    let trimmed = path.trim();
+    if trimmed.is_empty() {
+        return Err("Binary path is empty".to_string());
+    }
+    let expanded = match trimmed.strip_prefix("~/").or_else(|| trimmed.strip_prefix("~\\")) {
+        Some(rest) => dirs::home_dir()
+            .ok_or("Cannot expand ~: no home directory")?
+            .join(rest),
+        None => PathBuf::from(trimmed),
+    };
+    let resolved = resolve_executable(&expanded.to_string_lossy())
+        .or_else(|| expanded.exists().then(|| expanded.clone()))
+        .ok_or_else(|| format!("{} does not exist", expanded.display()))?;
+    let canonical = std::fs::canonicalize(&resolved)
+        .map_err(|e| format!("Cannot resolve {}: {}", resolved.display(), e))?;
+    if !is_executable(&canonical) {
+        return Err(format!("{} is not an executable file", canonical.display()));
+    }
+    Ok(strip_verbatim(canonical).to_string_lossy().to_string())
+}
+
+/// `canonicalize` returns `\\?\C:\...` on Windows, which `cmd /C` and most
+/// tools don't accept; drop the prefix for plain drive paths
+#[cfg(windows)]
+pub(crate) fn strip_verbatim(path: PathBuf) -> PathBuf {
+    let s = path.to_string_lossy();
+    match s.strip_prefix(r"\\?\") {
+        Some(rest) if rest.as_bytes().get(1) == Some(&b':') => PathBuf::from(rest),
+        _ => path,
+    }
+}
+
+#[cfg(not(windows))]
+pub(crate) fn strip_verbatim(path: PathBuf) -> PathBuf {
+    path
+}
+
+/// Look a binary up on PATH (honours PATHEXT on Windows)
+pub fn which(name: &str) -> Option<String> {
+    which::which(name)
+        .ok()
+        .filter(|p| is_executable(p))
+        .map(|p| p.to_string_lossy().to_string())
+}
+
+/// Program and leading arguments needed to run `program`.
+///
+/// `.cmd`/`.bat` wrappers go through `cmd /C` and PowerShell scripts through
+/// `powershell -File`; everything else is run directly.
+pub fn command_for(program: &str) -> (String, Vec<String>) {
+    let ext = Path::new(program)
+        .extension()
+        .and_then(|e| e.to_str())
+        .map(|e| e.to_ascii_lowercase());
+    match ext.as_deref() {
+        Some("cmd") | Some("bat") if cfg!(windows) => {
+            ("cmd".to_string(), vec!["/C".to_string(), program.to_string()])
+        }
+        Some("ps1") => (
+            "powershell".to_string(),
+            vec![
+                "-NoProfile".to_string(),
+                "-ExecutionPolicy".to_string(),
+                "Bypass".to_string(),
+                "-File".to_string(),
+                program.to_string(),
+            ],
+        ),
+        _ => (program.to_string(), Vec::new()),
+    }
+}
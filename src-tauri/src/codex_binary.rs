@@ -1,56 +1,134 @@
 use log::{info, warn};
+use serde::Serialize;
 use std::path::PathBuf;
 use std::process::Command;
-use tauri::Manager;
 
 /// Find the OpenAI Codex CLI binary path.
 /// Checks app DB for a stored path first, then tries `which codex`,
 /// finally falls back to `codex` assuming it's in PATH.
 pub fn find_codex_binary(app_handle: &tauri::AppHandle) -> Result<String, String> {
     // 1) DB stored path
-    if let Ok(app_data_dir) = app_handle.path().app_data_dir() {
-        let db_path = app_data_dir.join("agents.db");
-        if db_path.exists() {
-            if let Ok(conn) = rusqlite::Connection::open(&db_path) {
-                if let Ok(stored_path) = conn.query_row(
-                    "SELECT value FROM app_settings WHERE key = 'codex_binary_path'",
-                    [],
-                    |row| row.get::<_, String>(0),
-                ) {
-                    let pb = PathBuf::from(&stored_path);
-                    if pb.exists() && pb.is_file() {
-                        info!("Using Codex binary from DB: {}", stored_path);
-                        return Ok(stored_path);
-                    } else {
-                        warn!("Stored codex path does not exist: {}", stored_path);
-                    }
-                }
+    if let Some(stored_path) = stored_codex_path(app_handle) {
+        match crate::binary_path::resolve_executable(&stored_path) {
+            Some(pb) => {
+                info!("Using Codex binary from DB: {}", pb.display());
+                return Ok(pb.to_string_lossy().to_string());
             }
+            None => warn!("Stored codex path is missing or not executable: {}", stored_path),
         }
     }
 
     // 2) which codex
-    if let Ok(output) = Command::new("which").arg("codex").output() {
-        if output.status.success() {
-            let path = String::from_utf8_lossy(&output.stdout).trim().to_string();
-            if !path.is_empty() {
-                let pb = PathBuf::from(&path);
-                if pb.exists() { return Ok(path); }
-            }
-        }
+    if let Some(path) = which_codex() {
+        return Ok(path);
     }
 
     // 3) assume in PATH
     Ok("codex".to_string())
 }
 
+/// Path pinned via `set_codex_binary_path` for the active profile, if any
+fn stored_codex_path(app_handle: &tauri::AppHandle) -> Option<String> {
+    crate::provider::profiles::read_profile_setting(app_handle, "codex_binary_path")
+}
+
+/// `codex` as found on PATH, when it resolves to an executable
+fn which_codex() -> Option<String> {
+    crate::binary_path::which("codex")
+}
+
+/// Common install locations for the codex binary (npm, Homebrew, cargo, nvm)
+fn scan_common_dirs() -> Vec<String> {
+    let mut dirs_to_check: Vec<PathBuf> = vec![
+        PathBuf::from("/usr/local/bin"),
+        PathBuf::from("/opt/homebrew/bin"),
+        PathBuf::from("/usr/bin"),
+    ];
+    if let Some(home) = dirs::home_dir() {
+        dirs_to_check.push(home.join(".local/bin"));
+        dirs_to_check.push(home.join(".npm-global/bin"));
+        dirs_to_check.push(home.join(".cargo/bin"));
+        dirs_to_check.push(home.join(".bun/bin"));
+        if let Ok(entries) = std::fs::read_dir(home.join(".nvm/versions/node")) {
+            for entry in entries.flatten() {
+                dirs_to_check.push(entry.path().join("bin"));
+            }
+        }
+    }
+    dirs_to_check
+        .into_iter()
+        .filter_map(|d| crate::binary_path::resolve_executable(&d.join("codex").to_string_lossy()))
+        .map(|p| p.to_string_lossy().to_string())
+        .collect()
+}
+
+/// One place a codex binary was found
+#[derive(Debug, Serialize)]
+pub struct BinaryCandidate {
+    pub path: String,
+    /// Where it came from: "database", "which" or "scan"
+    pub source: String,
+    pub exists: bool,
+    pub version: Option<String>,
+    /// This is the binary `find_codex_binary` resolves to
+    pub selected: bool,
+}
+
+/// Every codex binary candidate and the one that would actually run
+#[derive(Debug, Serialize)]
+pub struct BinaryDiagnosis {
+    pub selected: String,
+    pub candidates: Vec<BinaryCandidate>,
+}
+
+/// Collect all codex candidates with their versions.
+///
+/// Explains mismatches such as a pinned DB path shadowing a newer binary on PATH.
+pub fn diagnose_codex_binary(app_handle: &tauri::AppHandle) -> BinaryDiagnosis {
+    let selected = find_codex_binary(app_handle).unwrap_or_else(|_| "codex".to_string());
+
+    let mut found: Vec<(String, &str)> = Vec::new();
+    if let Some(path) = stored_codex_path(app_handle) {
+        found.push((path, "database"));
+    }
+    if let Some(path) = which_codex() {
+        found.push((path, "which"));
+    }
+    for path in scan_common_dirs() {
+        if !found.iter().any(|(p, _)| *p == path) {
+            found.push((path, "scan"));
+        }
+    }
+
+    let candidates = found
+        .into_iter()
+        .map(|(path, source)| {
+            let exists = crate::binary_path::resolve_executable(&path).is_some();
+            BinaryCandidate {
+                version: if exists { get_codex_version(&path) } else { None },
+                selected: path == selected,
+                source: source.to_string(),
+                exists,
+                path,
+            }
+        })
+        .collect();
+
+    BinaryDiagnosis { selected, candidates }
+}
+
 /// Try get version string using `codex --version` (best-effort)
 pub fn get_codex_version(path: &str) -> Option<String> {
-    if let Ok(output) = Command::new(path).arg("--version").output() {
-        if output.status.success() {
+    let (program, prefix) = crate::binary_path::command_for(path);
+    let mut cmd = Command::new(program);
+    cmd.args(prefix).arg("--version");
+    match crate::provider::probe::output_blocking(&mut cmd, "codex --version") {
+        Ok(Ok(output)) if output.status.success() => {
             let s = String::from_utf8_lossy(&output.stdout).trim().to_string();
             if !s.is_empty() { return Some(s); }
         }
+        Ok(_) => {}
+        Err(e) => warn!("{}", e),
     }
     None
 }
@@ -14,6 +14,8 @@ use tauri::{AppHandle, Emitter, Manager, State};
 use tokio::io::{AsyncBufReadExt, BufReader as TokioBufReader};
 use tokio::process::Command;
 
+use crate::provider::events::event_name;
+
 /// Finds the full path to the claude binary
 /// This is necessary because macOS apps have a limited PATH environment
 fn find_claude_binary(app_handle: &AppHandle) -> Result<String, String> {
@@ -215,14 +217,8 @@ pub async fn get_agent_run_with_metrics(run: AgentRun) -> AgentRunWithMetrics {
 
 /// Initialize the agents database
 pub fn init_database(app: &AppHandle) -> SqliteResult<Connection> {
-    let app_dir = app
-        .path()
-        .app_data_dir()
-        .expect("Failed to get app data dir");
-    std::fs::create_dir_all(&app_dir).expect("Failed to create app data dir");
-
-    let db_path = app_dir.join("agents.db");
-    let conn = Connection::open(db_path)?;
+    let db_path = crate::provider::settings::db_path(app).expect("Failed to resolve data dir");
+    let conn = crate::provider::settings::connect(&db_path)?;
 
     // Create agents table
     conn.execute(
@@ -856,11 +852,7 @@ async fn spawn_agent_system(
     let stderr_reader = TokioBufReader::new(stderr);
 
     // Create variables we need for the spawned tasks
-    let app_dir = app
-        .path()
-        .app_data_dir()
-        .expect("Failed to get app data dir");
-    let db_path = app_dir.join("agents.db");
+    let db_path = crate::provider::settings::db_path(&app)?;
 
     // Shared state for collecting session ID and live output
     let session_id = std::sync::Arc::new(Mutex::new(String::new()));
@@ -921,7 +913,7 @@ async fn spawn_agent_system(
                                 info!("🔑 Extracted session ID: {}", sid);
 
                                 // Update database immediately with session ID
-                                if let Ok(conn) = Connection::open(&db_path_for_stdout) {
+                                if let Ok(conn) = crate::provider::settings::connect(&db_path_for_stdout) {
                                     match conn.execute(
                                         "UPDATE agent_runs SET session_id = ?1 WHERE id = ?2",
                                         params![sid, run_id],
@@ -946,9 +938,9 @@ async fn spawn_agent_system(
             }
 
             // Emit the line to the frontend with run_id for isolation
-            let _ = app_handle.emit(&format!("agent-output:{}", run_id), &line);
+            let _ = app_handle.emit(&event_name(&format!("agent-output:{}", run_id)), &line);
             // Also emit to the generic event for backward compatibility
-            let _ = app_handle.emit("agent-output", &line);
+            let _ = app_handle.emit(&event_name("agent-output"), &line);
         }
 
         info!(
@@ -977,9 +969,9 @@ async fn spawn_agent_system(
 
             error!("stderr[{}]: {}", error_count, line);
             // Emit error lines to the frontend with run_id for isolation
-            let _ = app_handle_stderr.emit(&format!("agent-error:{}", run_id), &line);
+            let _ = app_handle_stderr.emit(&event_name(&format!("agent-error:{}", run_id)), &line);
             // Also emit to the generic event for backward compatibility
-            let _ = app_handle_stderr.emit("agent-error", &line);
+            let _ = app_handle_stderr.emit(&event_name("agent-error"), &line);
         }
 
         if error_count > 0 {
@@ -1060,15 +1052,15 @@ async fn spawn_agent_system(
                 }
 
                 // Update database
-                if let Ok(conn) = Connection::open(&db_path_for_monitor) {
+                if let Ok(conn) = crate::provider::settings::connect(&db_path_for_monitor) {
                     let _ = conn.execute(
                         "UPDATE agent_runs SET status = 'failed', completed_at = CURRENT_TIMESTAMP WHERE id = ?1",
                         params![run_id],
                     );
                 }
 
-                let _ = app.emit("agent-complete", false);
-                let _ = app.emit(&format!("agent-complete:{}", run_id), false);
+                let _ = app.emit(&event_name("agent-complete"), false);
+                let _ = app.emit(&event_name(&format!("agent-complete:{}", run_id)), false);
                 return;
             }
 
@@ -1094,7 +1086,7 @@ async fn spawn_agent_system(
         info!("✅ Claude process execution monitoring complete");
 
         // Update the run record with session ID and mark as completed - open a new connection
-        if let Ok(conn) = Connection::open(&db_path_for_monitor) {
+        if let Ok(conn) = crate::provider::settings::connect(&db_path_for_monitor) {
             info!(
                 "🔄 Updating database with extracted session ID: {}",
                 extracted_session_id
@@ -1123,8 +1115,8 @@ async fn spawn_agent_system(
 
         // Cleanup will be handled by the cleanup_finished_processes function
 
-        let _ = app.emit("agent-complete", true);
-        let _ = app.emit(&format!("agent-complete:{}", run_id), true);
+        let _ = app.emit(&event_name("agent-complete"), true);
+        let _ = app.emit(&event_name(&format!("agent-complete:{}", run_id)), true);
     });
 
     Ok(run_id)
@@ -1247,7 +1239,7 @@ pub async fn kill_agent_session(
     ).map_err(|e| e.to_string())?;
 
     // Emit cancellation event with run_id for proper isolation
-    let _ = app.emit(&format!("agent-cancelled:{}", run_id), true);
+    let _ = app.emit(&event_name(&format!("agent-cancelled:{}", run_id)), true);
 
     Ok(updated > 0 || killed_via_registry)
 }
@@ -1502,8 +1494,10 @@ pub async fn stream_session_output(
                     if current_size > last_size {
                         // File has grown, read new content
                         if let Ok(content) = tokio::fs::read_to_string(&session_file).await {
-                            let _ = app
-                                .emit("session-output-update", &format!("{}:{}", run_id, content));
+                            let _ = app.emit(
+                                &event_name("session-output-update"),
+                                &format!("{}:{}", run_id, content),
+                            );
                         }
                         last_size = current_size;
                     }
@@ -1516,12 +1510,7 @@ pub async fn stream_session_output(
 
             // Check if the session is still running by querying the database
             // If the session is no longer running, stop streaming
-            if let Ok(conn) = rusqlite::Connection::open(
-                app.path()
-                    .app_data_dir()
-                    .expect("Failed to get app data dir")
-                    .join("agents.db"),
-            ) {
+            if let Ok(conn) = crate::provider::settings::open_db(&app) {
                 if let Ok(status) = conn.query_row(
                     "SELECT status FROM agent_runs WHERE id = ?1",
                     rusqlite::params![run_id],
@@ -10,6 +10,7 @@ use tauri::{AppHandle, Emitter, Manager};
 use tokio::process::{Child, Command};
 use tokio::sync::Mutex;
 
+use crate::provider::events::event_name;
 
 /// Global state to track current Claude process
 pub struct ClaudeProcessState {
@@ -1112,15 +1113,15 @@ pub async fn cancel_claude_execution(
 
     // Always emit cancellation events for UI consistency
     if let Some(sid) = session_id {
-        let _ = app.emit(&format!("claude-cancelled:{}", sid), true);
+        let _ = app.emit(&event_name(&format!("claude-cancelled:{}", sid)), true);
         tokio::time::sleep(tokio::time::Duration::from_millis(100)).await;
-        let _ = app.emit(&format!("claude-complete:{}", sid), false);
+        let _ = app.emit(&event_name(&format!("claude-complete:{}", sid)), false);
     }
     
     // Also emit generic events for backward compatibility
-    let _ = app.emit("claude-cancelled", true);
+    let _ = app.emit(&event_name("claude-cancelled"), true);
     tokio::time::sleep(tokio::time::Duration::from_millis(100)).await;
-    let _ = app.emit("claude-complete", false);
+    let _ = app.emit(&event_name("claude-complete"), false);
     
     if killed {
         log::info!("Claude process cancellation completed successfully");
@@ -1246,10 +1247,13 @@ async fn spawn_claude_process(app: AppHandle, mut cmd: Command, prompt: String,
             
             // Emit the line to the frontend with session isolation if we have session ID
             if let Some(ref session_id) = *session_id_holder_clone.lock().unwrap() {
-                let _ = app_handle.emit(&format!("claude-output:{}", session_id), &line);
+                let _ = app_handle.emit(
+                    &event_name(&format!("claude-output:{}", session_id)),
+                    &line,
+                );
             }
             // Also emit to the generic event for backward compatibility
-            let _ = app_handle.emit("claude-output", &line);
+            let _ = app_handle.emit(&event_name("claude-output"), &line);
         }
     });
 
@@ -1261,10 +1265,13 @@ async fn spawn_claude_process(app: AppHandle, mut cmd: Command, prompt: String,
             log::error!("Claude stderr: {}", line);
             // Emit error lines to the frontend with session isolation if we have session ID
             if let Some(ref session_id) = *session_id_holder_clone2.lock().unwrap() {
-                let _ = app_handle_stderr.emit(&format!("claude-error:{}", session_id), &line);
+                let _ = app_handle_stderr.emit(
+                    &event_name(&format!("claude-error:{}", session_id)),
+                    &line,
+                );
             }
             // Also emit to the generic event for backward compatibility
-            let _ = app_handle_stderr.emit("claude-error", &line);
+            let _ = app_handle_stderr.emit(&event_name("claude-error"), &line);
         }
     });
 
@@ -1288,12 +1295,12 @@ async fn spawn_claude_process(app: AppHandle, mut cmd: Command, prompt: String,
                     tokio::time::sleep(tokio::time::Duration::from_millis(100)).await;
                     if let Some(ref session_id) = *session_id_holder_clone3.lock().unwrap() {
                         let _ = app_handle_wait.emit(
-                            &format!("claude-complete:{}", session_id),
+                            &event_name(&format!("claude-complete:{}", session_id)),
                             status.success(),
                         );
                     }
                     // Also emit to the generic event for backward compatibility
-                    let _ = app_handle_wait.emit("claude-complete", status.success());
+                    let _ = app_handle_wait.emit(&event_name("claude-complete"), status.success());
                 }
                 Err(e) => {
                     log::error!("Failed to wait for Claude process: {}", e);
@@ -1301,10 +1308,10 @@ async fn spawn_claude_process(app: AppHandle, mut cmd: Command, prompt: String,
                     tokio::time::sleep(tokio::time::Duration::from_millis(100)).await;
                     if let Some(ref session_id) = *session_id_holder_clone3.lock().unwrap() {
                         let _ = app_handle_wait
-                            .emit(&format!("claude-complete:{}", session_id), false);
+                            .emit(&event_name(&format!("claude-complete:{}", session_id)), false);
                     }
                     // Also emit to the generic event for backward compatibility
-                    let _ = app_handle_wait.emit("claude-complete", false);
+                    let _ = app_handle_wait.emit(&event_name("claude-complete"), false);
                 }
             }
         }
@@ -1,30 +1,55 @@
-use serde_json::json;
-use std::time::Duration;
-use tauri::{AppHandle, Emitter, Manager};
-use tokio::io::{AsyncBufReadExt, AsyncWriteExt, BufReader as AsyncBufReader};
-use tokio::process::{Child, Command};
+use tauri::{AppHandle, Manager};
+use tokio::process::Command;
 use tokio::sync::Mutex;
 use uuid::Uuid;
-use std::fs;
-use std::path::PathBuf;
-
-/// Global state to track current Codex process
+use std::time::{Duration, Instant};
+
+use crate::provider::config_files;
+use crate::provider::default_model;
+use crate::provider::model_args;
+use crate::provider::control::SessionHandle;
+use crate::provider::probe;
+use crate::provider::profiles;
+use crate::provider::reasoning;
+use crate::provider::structured;
+use crate::provider::warmup;
+use crate::provider::runner::{preview_command, spawn_provider_process, CommandPreview, SpawnRequest};
+use crate::provider::ChatOptions;
+
+/// How long a cached login status is trusted
+const LOGIN_CACHE_TTL: Duration = Duration::from_secs(60);
+/// How long a cached model list is trusted; models change with CLI updates, not by the minute
+const MODELS_CACHE_TTL: Duration = Duration::from_secs(600);
+
+/// Global Codex state; running sessions own their processes (see `control`)
 pub struct CodexProcessState {
-    pub current_process: std::sync::Arc<Mutex<Option<Child>>>,
+    /// Last login check result; auth can change outside the app so this is never persisted
+    pub login_cache: std::sync::Arc<Mutex<Option<(Instant, LoginStatus)>>>,
+    /// Last model listing, so the picker doesn't spawn the CLI every time it opens
+    pub models_cache: std::sync::Arc<Mutex<Option<(Instant, Vec<CodexModel>)>>>,
 }
 
 impl Default for CodexProcessState {
     fn default() -> Self {
-        Self { current_process: std::sync::Arc::new(Mutex::new(None)) }
+        Self {
+            login_cache: std::sync::Arc::new(Mutex::new(None)),
+            models_cache: std::sync::Arc::new(Mutex::new(None)),
+        }
     }
 }
 
-fn create_command_with_env(program: &str) -> Command {
+pub(crate) fn create_command_with_env(program: &str) -> Command {
     // Reuse the environment logic from claude module
     let _std_cmd = crate::claude_binary::create_command_with_env(program);
+    // Script wrappers (.cmd/.ps1) need their interpreter in front
+    let (program, prefix) = crate::binary_path::command_for(program);
     let mut cmd = Command::new(program);
+    cmd.args(prefix);
+    // Opt-in per profile; see `ProfileSettings::codex_inherit_full_env`
+    let inherit_all = profiles::inherits_full_env("codex");
     for (key, value) in std::env::vars() {
-        if key == "PATH"
+        if inherit_all
+            || key == "PATH"
             || key == "HOME"
             || key == "USER"
             || key == "SHELL"
@@ -37,144 +62,105 @@ fn create_command_with_env(program: &str) -> Command {
             cmd.env(&key, &value);
         }
     }
+    // Active profile's env overrides and base URL
+    cmd.envs(profiles::command_env("codex"));
     cmd
 }
 
-async fn spawn_codex_process(
+async fn spawn_codex_process(app: AppHandle, cmd: Command, request: SpawnRequest) -> Result<SessionHandle, String> {
+    spawn_provider_process(app, cmd, request).await
+}
+
+#[tauri::command]
+pub async fn execute_codex_chat(
     app: AppHandle,
-    mut cmd: Command,
-    session_id: String,
+    project_path: String,
     prompt: String,
     model: String,
-    project_path: String,
-) -> Result<(), String> {
-    use tauri::Manager as _;
-
-    cmd.current_dir(&project_path);
-    cmd.stdout(std::process::Stdio::piped())
-        .stderr(std::process::Stdio::piped())
-        .stdin(std::process::Stdio::piped());
-
-    let mut child = cmd.spawn().map_err(|e| format!("Failed to spawn codex: {}", e))?;
-
-    // Write prompt to stdin as a fallback (if CLI expects interactive input)
-    if let Some(mut stdin) = child.stdin.take() {
-        let p = prompt.clone();
-        tokio::spawn(async move {
-            let _ = stdin.write_all(p.as_bytes()).await;
-            let _ = stdin.write_all(b"\n").await;
-            let _ = stdin.shutdown().await;
-        });
-    }
+    options: Option<ChatOptions>,
+) -> Result<SessionHandle, String> {
+    start_codex_chat(app, project_path, prompt, model, options.unwrap_or_default(), None).await
+}
 
-    let pid = child.id().unwrap_or_default();
-
-    // Register session in process registry (without child handle)
-    {
-        let registry = app.state::<crate::process::ProcessRegistryState>();
-        let _ = registry.0.register_chat_session(
-            session_id.clone(),
-            "codex".to_string(),
-            pid,
-            project_path.clone(),
-            prompt.clone(),
-            model.clone(),
-        );
+/// Build the `codex` command for a chat turn.
+///
+/// Prompt goes in argv; stdin is only used if the capability probe says the CLI needs it.
+async fn build_codex_command(
+    app: &AppHandle,
+    codex_path: &str,
+    model: &str,
+    project_path: &str,
+    options: &ChatOptions,
+    prompt: &str,
+) -> Result<Command, String> {
+    let mut cmd = create_command_with_env(codex_path);
+    if !model.is_empty() {
+        cmd.arg("-m").arg(model);
     }
-
-    // Track current process for cancellation
-    {
-        let state = app.state::<CodexProcessState>();
-        let mut guard = state.current_process.lock().await;
-        *guard = Some(child);
+    // Flags the model rejects are dropped before they reach the CLI
+    let mut args = Vec::new();
+    apply_reasoning_effort(app, &mut args, model, project_path, options).await?;
+    if let Some(format) = &options.response_format {
+        args.push("--output-schema".to_string());
+        args.push(structured::schema_file(format)?.to_string_lossy().to_string());
     }
-
-    // Emit init message immediately so UI can bind to session-specific channel
-    let init_msg = json!({
-        "type": "system",
-        "subtype": "init",
-        "session_id": session_id,
-        "model": model,
-        "cwd": project_path,
-        "provider": "codex"
-    });
-    let init_line = init_msg.to_string();
-    let _ = app.emit("codex-output", &init_line);
-    let _ = app.emit(&format!("codex-output:{}", &init_msg["session_id"].as_str().unwrap_or("")), &init_line);
-
-    // Obtain readers
-    let state_for_read = app.state::<CodexProcessState>();
-    let mut child_for_read = state_for_read.current_process.lock().await;
-    let child_ref = child_for_read.as_mut().ok_or_else(|| "No codex process".to_string())?;
-    let stdout = child_ref.stdout.take().ok_or_else(|| "Failed to capture codex stdout".to_string())?;
-    let stderr = child_ref.stderr.take().ok_or_else(|| "Failed to capture codex stderr".to_string())?;
-    let app_handle_stdout = app.clone();
-    let app_handle_stderr = app.clone();
-    drop(child_for_read);
-
-    // Stream stdout
-    let sid_out = session_id.clone();
-    let stdout_task = tokio::spawn(async move {
-        let reader = AsyncBufReader::new(stdout);
-        let mut lines = reader.lines();
-        while let Ok(Some(line)) = lines.next_line().await {
-            // Normalize: treat each line as assistant text
-            let msg = json!({
-                "type": "assistant",
-                "message": { "content": [{"type": "text", "text": line}] }
-            });
-            let s = msg.to_string();
-            let _ = app_handle_stdout.emit(&format!("codex-output:{}", sid_out), &s);
-            let _ = app_handle_stdout.emit("codex-output", &s);
-        }
-    });
-
-    // Stream stderr
-    let sid_err = session_id.clone();
-    let stderr_task = tokio::spawn(async move {
-        let reader = AsyncBufReader::new(stderr);
-        let mut lines = reader.lines();
-        while let Ok(Some(line)) = lines.next_line().await {
-            let _ = app_handle_stderr.emit(&format!("codex-error:{}", sid_err), &line);
-            let _ = app_handle_stderr.emit("codex-error", &line);
-        }
-    });
-
-    // Wait for process end
-    let app_done = app.clone();
-    tokio::spawn(async move {
-        let _ = stdout_task.await;
-        let _ = stderr_task.await;
-
-        // Small delay to flush messages
-        tokio::time::sleep(Duration::from_millis(100)).await;
-        let _ = app_done.emit(&format!("codex-complete:{}", session_id), true);
-        let _ = app_done.emit("codex-complete", true);
-
-        // Clear state
-        let state = app_done.state::<CodexProcessState>();
-        let mut guard = state.current_process.lock().await;
-        *guard = None;
-    });
-
-    Ok(())
+    if let Some(extra_args) = &options.extra_args {
+        args.extend(extra_args.iter().cloned());
+    }
+    cmd.args(model_args::apply(&model_args::load_rules(app, "codex"), model, args));
+    cmd.arg(prompt);
+    Ok(cmd)
 }
 
+/// Show the command `execute_codex_chat` would run, without running it.
+///
+/// `extra_args` are added on top of any in `options`. The prompt is
+/// truncated and only the names of explicitly set env variables are listed.
 #[tauri::command]
-pub async fn execute_codex_chat(
+pub async fn preview_codex_command(
     app: AppHandle,
     project_path: String,
     prompt: String,
     model: String,
-) -> Result<(), String> {
+    extra_args: Option<Vec<String>>,
+    options: Option<ChatOptions>,
+) -> Result<CommandPreview, String> {
     let codex_path = crate::codex_binary::find_codex_binary(&app)?;
+    let model = default_model::for_launch(&app, &project_path, "codex", &model);
+    let mut options = options.unwrap_or_default();
+    if let Some(extra) = extra_args {
+        options.extra_args.get_or_insert_with(Vec::new).extend(extra);
+    }
+    let cmd = build_codex_command(&app, &codex_path, &model, &project_path, &options, &prompt).await?;
+    preview_command(&app, cmd, "codex", &project_path, &prompt, &options).await
+}
 
-    // Prefer codex chat --model <model> --stream <prompt>; also pipe to stdin as fallback
-    let mut cmd = create_command_with_env(&codex_path);
-    cmd.arg("-m").arg(&model).arg(&prompt);
-
-    let session_id = Uuid::new_v4().to_string();
-    spawn_codex_process(app, cmd, session_id, prompt, model, project_path).await
+/// Start a new Codex chat and return its session handle.
+///
+/// `group_id` links sessions started together by a multi-provider fan-out.
+pub(crate) async fn start_codex_chat(
+    app: AppHandle,
+    project_path: String,
+    prompt: String,
+    model: String,
+    options: ChatOptions,
+    group_id: Option<String>,
+) -> Result<SessionHandle, String> {
+    let codex_path = crate::codex_binary::find_codex_binary(&app)?;
+    // Reported back in the init event, so the UI shows what actually ran
+    let model = default_model::for_launch(&app, &project_path, "codex", &model);
+
+    let cmd = build_codex_command(&app, &codex_path, &model, &project_path, &options, &prompt).await?;
+    let request = SpawnRequest {
+        provider: "codex",
+        session_id: Uuid::new_v4().to_string(),
+        prompt,
+        model,
+        project_path,
+        options,
+        group_id,
+    };
+    spawn_codex_process(app, cmd, request).await
 }
 
 #[tauri::command]
@@ -184,24 +170,107 @@ pub async fn resume_codex_chat(
     session_id: String,
     prompt: String,
     model: String,
-) -> Result<(), String> {
+    options: Option<ChatOptions>,
+) -> Result<SessionHandle, String> {
     let codex_path = crate::codex_binary::find_codex_binary(&app)?;
-    let mut cmd = create_command_with_env(&codex_path);
-    cmd.arg("-m").arg(&model).arg(&prompt);
-    spawn_codex_process(app, cmd, session_id, prompt, model, project_path).await
+    let model = default_model::for_launch(&app, &project_path, "codex", &model);
+
+    // The CLI keeps no history between runs; replay (and if needed compact) the transcript
+    let summary_cmd = || {
+        let mut c = create_command_with_env(&codex_path);
+        if !model.is_empty() {
+            c.arg("-m").arg(&model);
+        }
+        c
+    };
+    let full_prompt =
+        crate::provider::compact::resume_prompt(&app, "codex", &project_path, &session_id, &prompt, summary_cmd)
+            .await;
+
+    let options = options.unwrap_or_default();
+    let cmd = build_codex_command(&app, &codex_path, &model, &project_path, &options, &full_prompt).await?;
+    let request = SpawnRequest {
+        provider: "codex",
+        session_id,
+        prompt,
+        model,
+        project_path,
+        options,
+        group_id: None,
+    };
+    spawn_codex_process(app, cmd, request).await
+}
+
+/// Add `-c model_reasoning_effort=<level>` when an effort is requested or set
+/// as the project default.
+///
+/// An explicit request for a model whose metadata says it can't reason is an
+/// error; an inherited project default is just skipped for that model.
+async fn apply_reasoning_effort(
+    app: &AppHandle,
+    args: &mut Vec<String>,
+    model: &str,
+    project_path: &str,
+    options: &ChatOptions,
+) -> Result<(), String> {
+    let resolved = match reasoning::resolve(app, project_path, options) {
+        Some(resolved) => resolved,
+        None => return Ok(()),
+    };
+    let supported = list_codex_models(app.clone(), None)
+        .await
+        .ok()
+        .and_then(|models| models.into_iter().find(|m| m.id == model))
+        .and_then(|m| m.supports_reasoning);
+    if supported == Some(false) {
+        if resolved.explicit {
+            return Err(format!("Codex model {} does not support reasoning effort", model));
+        }
+        log::info!("Skipping default reasoning effort for {}: not supported", model);
+        return Ok(());
+    }
+    args.push("-c".to_string());
+    args.push(format!("model_reasoning_effort={}", resolved.effort.as_str()));
+    Ok(())
 }
 
+/// Cancel a Codex run: the given session, or every running Codex session shown
+/// in the UI. Detached (backgrounded) sessions are only cancelled by id.
+///
+/// The session is flagged as cancelled first so its completion event reports
+/// `cancelled: true` instead of a normal finish.
 #[tauri::command]
-pub async fn cancel_codex_execution(app: AppHandle) -> Result<(), String> {
-    let state = app.state::<CodexProcessState>();
-    let mut guard = state.current_process.lock().await;
-    if let Some(child) = guard.as_mut() {
-        child.start_kill().map_err(|e| e.to_string())?;
-        *guard = None;
+pub async fn cancel_codex_execution(app: AppHandle, session_id: Option<String>) -> Result<(), String> {
+    if let Some(session_id) = session_id {
+        let control = crate::provider::control::get(&session_id)
+            .filter(|c| c.provider == "codex")
+            .ok_or_else(|| format!("No running codex session {}", session_id))?;
+        crate::provider::logs::log_cancel(&app, "codex", Some(control.pid()));
+        control.cancel().await;
+        return Ok(());
+    }
+
+    for control in crate::provider::control::of_provider("codex") {
+        if control.is_detached() {
+            continue;
+        }
+        crate::provider::logs::log_cancel(&app, "codex", Some(control.pid()));
+        control.cancel().await;
     }
     Ok(())
 }
 
+/// Answer a question the Codex CLI asked on stdout (`codex-input-request`).
+///
+/// Only works for sessions started with the `interactive` option.
+#[tauri::command]
+pub async fn send_to_codex_session(session_id: String, input: String) -> Result<(), String> {
+    let control = crate::provider::control::get(&session_id)
+        .filter(|c| c.provider == "codex")
+        .ok_or_else(|| format!("No running codex session {}", session_id))?;
+    control.write_stdin(&input).await
+}
+
 #[tauri::command]
 pub async fn list_running_codex_sessions(
     registry: tauri::State<'_, crate::process::ProcessRegistryState>,
@@ -217,30 +286,28 @@ pub async fn get_codex_binary_path(app: AppHandle) -> Result<String, String> {
 #[tauri::command]
 pub async fn check_codex_version(app: AppHandle) -> Result<Option<String>, String> {
     let path = crate::codex_binary::find_codex_binary(&app)?;
-    Ok(crate::codex_binary::get_codex_version(&path))
+    Ok(warmup::cached_version("codex", &path).await)
 }
 
+/// List every codex binary found (DB, `which`, common dirs) and which one runs
 #[tauri::command]
-pub async fn set_codex_binary_path(app: AppHandle, path: String) -> Result<(), String> {
-    let app_data_dir = app
-        .path()
-        .app_data_dir()
-        .map_err(|e| e.to_string())?;
-    std::fs::create_dir_all(&app_data_dir).map_err(|e| e.to_string())?;
-    let db_path = app_data_dir.join("agents.db");
-    let conn = rusqlite::Connection::open(&db_path).map_err(|e| e.to_string())?;
-    conn.execute(
-        "CREATE TABLE IF NOT EXISTS app_settings (key TEXT PRIMARY KEY, value TEXT)",
-        [],
-    )
-    .map_err(|e| e.to_string())?;
-    conn.execute(
-        "INSERT INTO app_settings(key, value) VALUES('codex_binary_path', ?1)
-         ON CONFLICT(key) DO UPDATE SET value=excluded.value",
-        rusqlite::params![path],
-    )
-    .map_err(|e| e.to_string())?;
-    Ok(())
+pub async fn diagnose_codex_binary(app: AppHandle) -> Result<crate::codex_binary::BinaryDiagnosis, String> {
+    Ok(crate::codex_binary::diagnose_codex_binary(&app))
+}
+
+#[tauri::command]
+pub async fn set_codex_binary_path(app: AppHandle, path: String) -> Result<String, String> {
+    // Fails on paths that don't exist or can't be run, so they are never stored
+    let path = crate::binary_path::canonicalize_binary(&path)?;
+    // Saving the path already in use keeps the caches
+    if profiles::read_profile_setting(&app, "codex_binary_path").as_deref() == Some(path.as_str()) {
+        return Ok(path);
+    }
+    // Stored for the active profile
+    profiles::write_profile_setting(&app, "codex_binary_path", &path)?;
+    // A different binary may know different models
+    *app.state::<CodexProcessState>().models_cache.lock().await = None;
+    Ok(path)
 }
 
 #[tauri::command]
@@ -250,11 +317,19 @@ pub async fn login_codex(app: AppHandle) -> Result<(), String> {
     cmd.arg("login");
     cmd.stdout(std::process::Stdio::null())
         .stderr(std::process::Stdio::null());
-    let _ = cmd.spawn().map_err(|e| e.to_string())?;
+    let mut child = cmd.spawn().map_err(|e| e.to_string())?;
+
+    // Drop the cached status now and again once the login flow finishes
+    let login_cache = app.state::<CodexProcessState>().login_cache.clone();
+    *login_cache.lock().await = None;
+    tokio::spawn(async move {
+        let _ = child.wait().await;
+        *login_cache.lock().await = None;
+    });
     Ok(())
 }
 
-#[derive(serde::Serialize)]
+#[derive(Clone, serde::Serialize)]
 pub struct LoginStatus {
     pub logged_in: bool,
     pub user: Option<String>,
@@ -262,12 +337,29 @@ pub struct LoginStatus {
 }
 
 #[tauri::command]
-pub async fn check_codex_login(app: AppHandle) -> Result<LoginStatus, String> {
-    let path = crate::codex_binary::find_codex_binary(&app)?;
+pub async fn check_codex_login(app: AppHandle, force: Option<bool>) -> Result<LoginStatus, String> {
+    let login_cache = app.state::<CodexProcessState>().login_cache.clone();
+    if !force.unwrap_or(false) {
+        if let Some((checked_at, status)) = login_cache.lock().await.as_ref() {
+            if checked_at.elapsed() < LOGIN_CACHE_TTL {
+                return Ok(status.clone());
+            }
+        }
+    }
+
+    let status = probe_codex_login(&app).await?;
+    *login_cache.lock().await = Some((Instant::now(), status.clone()));
+    Ok(status)
+}
+
+/// Spawn the CLI to find out whether the user is logged in
+async fn probe_codex_login(app: &AppHandle) -> Result<LoginStatus, String> {
+    let path = crate::codex_binary::find_codex_binary(app)?;
     // Try `codex whoami` first
     let mut cmd = create_command_with_env(&path);
     cmd.arg("whoami");
-    match cmd.output().await {
+    // A hang (proxy, prompt) is an error, not "logged out"
+    match probe::output(&mut cmd, "codex whoami").await? {
         Ok(out) if out.status.success() => {
             let s = String::from_utf8_lossy(&out.stdout).trim().to_string();
             let user = if !s.is_empty() { Some(s) } else { None };
@@ -279,7 +371,7 @@ pub async fn check_codex_login(app: AppHandle) -> Result<LoginStatus, String> {
     // Fallback: try a lightweight call that would fail if not logged in
     let mut cmd2 = create_command_with_env(&path);
     cmd2.arg("models").arg("list").arg("--limit").arg("1");
-    match cmd2.output().await {
+    match probe::output(&mut cmd2, "codex models list").await? {
         Ok(out) if out.status.success() => Ok(LoginStatus { logged_in: true, user: None, error: None }),
         Ok(out) => Ok(LoginStatus { logged_in: false, user: None, error: Some(String::from_utf8_lossy(&out.stderr).to_string()) }),
         Err(e) => Ok(LoginStatus { logged_in: false, user: None, error: Some(e.to_string()) }),
@@ -287,95 +379,17 @@ pub async fn check_codex_login(app: AppHandle) -> Result<LoginStatus, String> {
 }
 
 fn read_db_value(app: &AppHandle, key: &str) -> Option<String> {
-    if let Ok(app_data_dir) = app.path().app_data_dir() {
-        let db_path = app_data_dir.join("agents.db");
-        if db_path.exists() {
-            if let Ok(conn) = rusqlite::Connection::open(&db_path) {
-                if let Ok(val) = conn.query_row(
-                    "SELECT value FROM app_settings WHERE key = ?1",
-                    rusqlite::params![key],
-                    |row| row.get::<_, String>(0),
-                ) { return Some(val); }
-            }
-        }
-    }
-    None
+    profiles::read_profile_setting(app, key)
 }
 
 fn write_db_value(app: &AppHandle, key: &str, value: &str) -> Result<(), String> {
-    let app_data_dir = app.path().app_data_dir().map_err(|e| e.to_string())?;
-    fs::create_dir_all(&app_data_dir).map_err(|e| e.to_string())?;
-    let db_path = app_data_dir.join("agents.db");
-    let conn = rusqlite::Connection::open(&db_path).map_err(|e| e.to_string())?;
-    conn.execute(
-        "CREATE TABLE IF NOT EXISTS app_settings (key TEXT PRIMARY KEY, value TEXT)",
-        [],
-    ).map_err(|e| e.to_string())?;
-    conn.execute(
-        "INSERT INTO app_settings(key, value) VALUES(?1, ?2)
-         ON CONFLICT(key) DO UPDATE SET value=excluded.value",
-        rusqlite::params![key, value],
-    ).map_err(|e| e.to_string())?;
-    Ok(())
-}
-
-fn search_codex_config_for_default_model() -> Option<String> {
-    let candidates = vec![
-        "~/.config/openai",
-        "~/.config/codex",
-        "~/.openai",
-        "~/Library/Application Support/OpenAI",
-    ];
-    for root in candidates {
-        let path = expand_tilde(root);
-        if !path.exists() { continue; }
-        let walker = walkdir::WalkDir::new(path).max_depth(2);
-        for entry in walker.into_iter().flatten() {
-            let p = entry.path();
-            if p.is_file() {
-                if let Ok(data) = fs::read_to_string(p) {
-                    // naive search for default model key tokens
-                    // supports toml/yaml/json by regex-like scans
-                    for key in ["default_model", "model", "chat_model"] {
-                        if let Some(val) = extract_model_value(&data, key) {
-                            return Some(val);
-                        }
-                    }
-                }
-            }
-        }
-    }
-    None
-}
-
-fn extract_model_value(content: &str, key: &str) -> Option<String> {
-    // very permissive: key: value patterns (json/yaml/toml)
-    let patterns = vec![
-        format!("\"{}\"\s*[:=]\s*\"([^\"]+)\"", key),
-        format!("{}\s*[:=]\s*\"([^\"]+)\"", key),
-        format!("{}\s*[:=]\s*([A-Za-z0-9._-]+)", key),
-    ];
-    for pat in patterns {
-        if let Ok(re) = regex::Regex::new(&pat) {
-            if let Some(c) = re.captures(content) {
-                if let Some(m) = c.get(1) { return Some(m.as_str().to_string()); }
-            }
-        }
-    }
-    None
-}
-
-fn expand_tilde(p: &str) -> PathBuf {
-    if let Some(stripped) = p.strip_prefix("~/") {
-        if let Some(home) = dirs::home_dir() { return home.join(stripped); }
-    }
-    PathBuf::from(p)
+    profiles::write_profile_setting(app, key, value)
 }
 
 #[tauri::command]
 pub async fn get_codex_default_model(app: AppHandle) -> Result<Option<String>, String> {
     if let Some(v) = read_db_value(&app, "codex_default_model") { return Ok(Some(v)); }
-    Ok(search_codex_config_for_default_model())
+    config_files::find_default_model(&app, "codex")
 }
 
 #[tauri::command]
@@ -383,20 +397,128 @@ pub async fn set_codex_default_model(app: AppHandle, model: String) -> Result<()
     write_db_value(&app, "codex_default_model", &model)
 }
 
+/// Codex config directory pinned for the active profile; `None` means the
+/// usual locations are searched
 #[tauri::command]
-pub async fn list_codex_models(app: AppHandle) -> Result<Vec<String>, String> {
-    let path = crate::codex_binary::find_codex_binary(&app)?;
+pub async fn get_codex_config_dir(app: AppHandle) -> Result<Option<String>, String> {
+    Ok(config_files::pinned_dir(&app, "codex"))
+}
+
+/// Pin the directory Codex config is read from (for dotfile-managed or other
+/// non-standard setups) and return its canonical form. An empty path unpins
+/// it; anything else must be an existing directory.
+#[tauri::command]
+pub async fn set_codex_config_dir(app: AppHandle, path: String) -> Result<Option<String>, String> {
+    if path.trim().is_empty() {
+        write_db_value(&app, config_files::CODEX_CONFIG_DIR_KEY, "")?;
+        return Ok(None);
+    }
+    let dir = config_files::canonicalize_dir(&path)?;
+    write_db_value(&app, config_files::CODEX_CONFIG_DIR_KEY, &dir)?;
+    Ok(Some(dir))
+}
+
+/// A Codex model with whatever metadata `models list --json` exposes
+#[derive(Debug, Clone, Default, serde::Serialize)]
+pub struct CodexModel {
+    pub id: String,
+    pub context_window: Option<u64>,
+    pub supports_tools: Option<bool>,
+    pub supports_vision: Option<bool>,
+    /// Price per million input tokens, in USD
+    pub input_price: Option<f64>,
+    /// Price per million output tokens, in USD
+    pub output_price: Option<f64>,
+    /// Accepts a reasoning effort setting
+    pub supports_reasoning: Option<bool>,
+}
+
+impl CodexModel {
+    fn from_id(id: &str) -> Self {
+        Self { id: id.to_string(), ..Default::default() }
+    }
+
+    /// Parse one entry of the JSON listing: a bare id or an object whose
+    /// field names vary between CLI versions. Missing fields stay `None`.
+    fn from_json(item: &serde_json::Value) -> Option<Self> {
+        if let Some(id) = item.as_str() {
+            return Some(Self::from_id(id));
+        }
+        let field = |names: &[&str]| -> Option<&serde_json::Value> {
+            names.iter().find_map(|n| item.pointer(n)).filter(|v| !v.is_null())
+        };
+        let id = field(&["/id", "/name", "/model", "/slug"])?.as_str()?.to_string();
+        let context_window = field(&["/context_window", "/contextWindow", "/context_length", "/max_context_tokens"])
+            .and_then(|v| v.as_u64());
+        let supports_tools = field(&["/supports_tools", "/capabilities/tools", "/capabilities/function_calling"])
+            .and_then(|v| v.as_bool());
+        let supports_vision = field(&["/supports_vision", "/capabilities/vision"])
+            .and_then(|v| v.as_bool())
+            .or_else(|| {
+                field(&["/input_modalities", "/modalities"])
+                    .and_then(|v| v.as_array())
+                    .map(|m| m.iter().any(|x| x.as_str() == Some("image")))
+            });
+        let supports_reasoning = field(&["/supports_reasoning", "/capabilities/reasoning"])
+            .and_then(|v| v.as_bool())
+            .or_else(|| {
+                field(&["/reasoning_efforts", "/supported_reasoning_efforts"])
+                    .and_then(|v| v.as_array())
+                    .map(|efforts| !efforts.is_empty())
+            });
+        let price = |names: &[&str]| {
+            field(names).and_then(|v| v.as_f64().or_else(|| v.as_str().and_then(|s| s.parse().ok())))
+        };
+        Some(Self {
+            id,
+            context_window,
+            supports_tools,
+            supports_vision,
+            input_price: price(&["/input_price", "/pricing/input", "/pricing/prompt"]),
+            output_price: price(&["/output_price", "/pricing/output", "/pricing/completion"]),
+            supports_reasoning,
+        })
+    }
+}
+
+/// Models from the JSON listing, which is either an array or wrapped in `data`/`models`
+fn parse_codex_models(v: &serde_json::Value) -> Vec<CodexModel> {
+    let arr = v
+        .as_array()
+        .or_else(|| v.get("data").and_then(|d| d.as_array()))
+        .or_else(|| v.get("models").and_then(|d| d.as_array()));
+    arr.map(|items| items.iter().filter_map(CodexModel::from_json).collect())
+        .unwrap_or_default()
+}
+
+/// List the CLI's models, from the cache unless it is stale or `force` is set
+#[tauri::command]
+pub async fn list_codex_models(app: AppHandle, force: Option<bool>) -> Result<Vec<CodexModel>, String> {
+    let models_cache = app.state::<CodexProcessState>().models_cache.clone();
+    if !force.unwrap_or(false) {
+        if let Some((listed_at, models)) = models_cache.lock().await.as_ref() {
+            if listed_at.elapsed() < MODELS_CACHE_TTL {
+                return Ok(models.clone());
+            }
+        }
+    }
+
+    let models = fetch_codex_models(&app).await?;
+    *models_cache.lock().await = Some((Instant::now(), models.clone()));
+    Ok(models)
+}
+
+/// Spawn the CLI to list its models
+async fn fetch_codex_models(app: &AppHandle) -> Result<Vec<CodexModel>, String> {
+    let path = crate::codex_binary::find_codex_binary(app)?;
     // Try JSON listing first
     let mut cmd = create_command_with_env(&path);
     cmd.arg("models").arg("list").arg("--json");
-    match cmd.output().await {
+    match probe::output(&mut cmd, "codex models list --json").await? {
         Ok(out) if out.status.success() => {
             if let Ok(v) = serde_json::from_slice::<serde_json::Value>(&out.stdout) {
-                if let Some(arr) = v.as_array() {
-                    let mut list = Vec::new();
-                    for item in arr { if let Some(s) = item.as_str() { list.push(s.to_string()); } }
-                    if !list.is_empty() { return Ok(list); }
-                }
+                let list = parse_codex_models(&v);
+                if !list.is_empty() { return Ok(list); }
             }
         }
         _ => {}
@@ -404,13 +526,52 @@ pub async fn list_codex_models(app: AppHandle) -> Result<Vec<String>, String> {
     // Fallback: plaintext lines
     let mut cmd2 = create_command_with_env(&path);
     cmd2.arg("models").arg("list");
-    match cmd2.output().await {
+    match probe::output(&mut cmd2, "codex models list").await? {
         Ok(out) if out.status.success() => {
             let s = String::from_utf8_lossy(&out.stdout);
-            let list: Vec<String> = s.lines().map(|l| l.trim().to_string()).filter(|l| !l.is_empty()).collect();
+            let list: Vec<CodexModel> = s
+                .lines()
+                .map(|l| l.trim())
+                .filter(|l| !l.is_empty())
+                .map(CodexModel::from_id)
+                .collect();
             Ok(list)
         }
         Ok(out) => Err(String::from_utf8_lossy(&out.stderr).to_string()),
         Err(e) => Err(e.to_string()),
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_codex_models() {
+        let v = serde_json::json!({
+            "data": [
+                "o4-mini",
+                {
+                    "id": "gpt-5",
+                    "context_window": 400000,
+                    "capabilities": { "tools": true },
+                    "input_modalities": ["text", "image"],
+                    "pricing": { "input": "1.25", "output": 10.0 },
+                    "reasoning_efforts": ["low", "medium", "high"]
+                },
+                { "description": "no id" }
+            ]
+        });
+        let models = parse_codex_models(&v);
+        assert_eq!(models.len(), 2);
+        assert_eq!(models[0].id, "o4-mini");
+        assert_eq!(models[0].context_window, None);
+        assert_eq!(models[1].context_window, Some(400000));
+        assert_eq!(models[1].supports_tools, Some(true));
+        assert_eq!(models[1].supports_vision, Some(true));
+        assert_eq!(models[1].input_price, Some(1.25));
+        assert_eq!(models[1].output_price, Some(10.0));
+        assert_eq!(models[1].supports_reasoning, Some(true));
+        assert_eq!(models[0].supports_reasoning, None);
+    }
+}
@@ -1,29 +1,53 @@
-use serde_json::json;
-use std::time::Duration;
-use tauri::{AppHandle, Emitter, Manager};
-use tokio::io::{AsyncBufReadExt, AsyncWriteExt, BufReader as AsyncBufReader};
-use tokio::process::{Child, Command};
+use tauri::{AppHandle, Manager};
+use tokio::process::Command;
 use tokio::sync::Mutex;
 use uuid::Uuid;
-use std::fs;
-use std::path::PathBuf;
-
-/// Global state to track current Gemini process
+use std::time::{Duration, Instant};
+
+use crate::provider::config_files;
+use crate::provider::default_model;
+use crate::provider::model_args;
+use crate::provider::control::SessionHandle;
+use crate::provider::probe;
+use crate::provider::profiles;
+use crate::provider::reasoning;
+use crate::provider::warmup;
+use crate::provider::runner::{preview_command, spawn_provider_process, CommandPreview, SpawnRequest};
+use crate::provider::ChatOptions;
+
+/// How long a cached login status is trusted
+const LOGIN_CACHE_TTL: Duration = Duration::from_secs(60);
+/// How long a cached model list is trusted; models change with CLI updates, not by the minute
+const MODELS_CACHE_TTL: Duration = Duration::from_secs(600);
+
+/// Global Gemini state; running sessions own their processes (see `control`)
 pub struct GeminiProcessState {
-    pub current_process: std::sync::Arc<Mutex<Option<Child>>>,
+    /// Last login check result; auth can change outside the app so this is never persisted
+    pub login_cache: std::sync::Arc<Mutex<Option<(Instant, LoginStatus)>>>,
+    /// Last model listing, so the picker doesn't spawn the CLI every time it opens
+    pub models_cache: std::sync::Arc<Mutex<Option<(Instant, Vec<String>)>>>,
 }
 
 impl Default for GeminiProcessState {
     fn default() -> Self {
-        Self { current_process: std::sync::Arc::new(Mutex::new(None)) }
+        Self {
+            login_cache: std::sync::Arc::new(Mutex::new(None)),
+            models_cache: std::sync::Arc::new(Mutex::new(None)),
+        }
     }
 }
 
-fn create_command_with_env(program: &str) -> Command {
+pub(crate) fn create_command_with_env(program: &str) -> Command {
     let _std_cmd = crate::claude_binary::create_command_with_env(program);
+    // Script wrappers (.cmd/.ps1) need their interpreter in front
+    let (program, prefix) = crate::binary_path::command_for(program);
     let mut cmd = Command::new(program);
+    cmd.args(prefix);
+    // Opt-in per profile; see `ProfileSettings::gemini_inherit_full_env`
+    let inherit_all = profiles::inherits_full_env("gemini");
     for (key, value) in std::env::vars() {
-        if key == "PATH"
+        if inherit_all
+            || key == "PATH"
             || key == "HOME"
             || key == "USER"
             || key == "SHELL"
@@ -36,134 +60,105 @@ fn create_command_with_env(program: &str) -> Command {
             cmd.env(&key, &value);
         }
     }
+    // Active profile's env overrides and base URL
+    cmd.envs(profiles::command_env("gemini"));
     cmd
 }
 
-async fn spawn_gemini_process(
+async fn spawn_gemini_process(app: AppHandle, cmd: Command, request: SpawnRequest) -> Result<SessionHandle, String> {
+    spawn_provider_process(app, cmd, request).await
+}
+
+#[tauri::command]
+pub async fn execute_gemini_chat(
     app: AppHandle,
-    mut cmd: Command,
-    session_id: String,
+    project_path: String,
     prompt: String,
     model: String,
-    project_path: String,
-) -> Result<(), String> {
-    cmd.current_dir(&project_path);
-    cmd.stdout(std::process::Stdio::piped())
-        .stderr(std::process::Stdio::piped())
-        .stdin(std::process::Stdio::piped());
-
-    let mut child = cmd.spawn().map_err(|e| format!("Failed to spawn gemini: {}", e))?;
-
-    // Fallback: write prompt to stdin for interactive mode
-    if let Some(mut stdin) = child.stdin.take() {
-        let p = prompt.clone();
-        tokio::spawn(async move {
-            let _ = stdin.write_all(p.as_bytes()).await;
-            let _ = stdin.write_all(b"\n").await;
-            let _ = stdin.shutdown().await;
-        });
-    }
+    options: Option<ChatOptions>,
+) -> Result<SessionHandle, String> {
+    start_gemini_chat(app, project_path, prompt, model, options.unwrap_or_default(), None).await
+}
 
-    let pid = child.id().unwrap_or_default();
-
-    // Register session (without child)
-    {
-        let registry = app.state::<crate::process::ProcessRegistryState>();
-        let _ = registry.0.register_chat_session(
-            session_id.clone(),
-            "gemini".to_string(),
-            pid,
-            project_path.clone(),
-            prompt.clone(),
-            model.clone(),
-        );
+/// Build the `gemini` command for a chat turn.
+///
+/// `gemini -m <model> [options] <prompt>`; stdin is only used if the capability probe says the CLI needs it.
+async fn build_gemini_command(
+    app: &AppHandle,
+    gemini_path: &str,
+    model: &str,
+    project_path: &str,
+    options: &ChatOptions,
+    prompt: &str,
+) -> Result<Command, String> {
+    let mut cmd = create_command_with_env(gemini_path);
+    if !model.is_empty() {
+        cmd.arg("-m").arg(model);
     }
-
-    // Track process for cancellation
-    {
-        let state = app.state::<GeminiProcessState>();
-        let mut guard = state.current_process.lock().await;
-        *guard = Some(child);
+    // Flags the model rejects are dropped before they reach the CLI
+    let mut args = Vec::new();
+    apply_thinking_budget(app, &mut args, gemini_path, project_path, options).await?;
+    // Gemini has no schema flag; the answer is still validated as JSON
+    if options.response_format.is_some() {
+        args.push("--output-format".to_string());
+        args.push("json".to_string());
     }
-
-    // Emit init
-    let init_msg = json!({
-        "type": "system",
-        "subtype": "init",
-        "session_id": session_id,
-        "model": model,
-        "cwd": project_path,
-        "provider": "gemini"
-    });
-    let init_line = init_msg.to_string();
-    let _ = app.emit("gemini-output", &init_line);
-    let _ = app.emit(&format!("gemini-output:{}", init_msg["session_id"].as_str().unwrap_or("")), &init_line);
-
-    // Now stream outputs
-    let app_out = app.clone();
-    let app_err = app.clone();
-    let state_for_read = app.state::<GeminiProcessState>();
-    let mut guard = state_for_read.current_process.lock().await;
-    let child_mut = guard.as_mut().ok_or_else(|| "No gemini process".to_string())?;
-    let stdout = child_mut.stdout.take().ok_or_else(|| "Failed to capture gemini stdout".to_string())?;
-    let stderr = child_mut.stderr.take().ok_or_else(|| "Failed to capture gemini stderr".to_string())?;
-    drop(guard);
-
-    let sid = session_id.clone();
-    let stdout_task = tokio::spawn(async move {
-        let reader = AsyncBufReader::new(stdout);
-        let mut lines = reader.lines();
-        while let Ok(Some(line)) = lines.next_line().await {
-            // Normalize as assistant text message
-            let msg = json!({
-                "type": "assistant",
-                "message": { "content": [{"type": "text", "text": line}] }
-            });
-            let s = msg.to_string();
-            let _ = app_out.emit(&format!("gemini-output:{}", sid), &s);
-            let _ = app_out.emit("gemini-output", &s);
-        }
-    });
-
-    let sid_err = session_id.clone();
-    let stderr_task = tokio::spawn(async move {
-        let reader = AsyncBufReader::new(stderr);
-        let mut lines = reader.lines();
-        while let Ok(Some(line)) = lines.next_line().await {
-            let _ = app_err.emit(&format!("gemini-error:{}", sid_err), &line);
-            let _ = app_err.emit("gemini-error", &line);
-        }
-    });
-
-    // Completion
-    let app_done = app.clone();
-    tokio::spawn(async move {
-        let _ = stdout_task.await;
-        let _ = stderr_task.await;
-        tokio::time::sleep(Duration::from_millis(100)).await;
-        let _ = app_done.emit(&format!("gemini-complete:{}", session_id), true);
-        let _ = app_done.emit("gemini-complete", true);
-        let state = app_done.state::<GeminiProcessState>();
-        let mut guard = state.current_process.lock().await;
-        *guard = None;
-    });
-
-    Ok(())
+    if let Some(extra_args) = &options.extra_args {
+        args.extend(extra_args.iter().cloned());
+    }
+    cmd.args(model_args::apply(&model_args::load_rules(app, "gemini"), model, args));
+    cmd.arg(prompt);
+    Ok(cmd)
 }
 
+/// Show the command `execute_gemini_chat` would run, without running it.
+///
+/// `extra_args` are added on top of any in `options`. The prompt is
+/// truncated and only the names of explicitly set env variables are listed.
 #[tauri::command]
-pub async fn execute_gemini_chat(
+pub async fn preview_gemini_command(
     app: AppHandle,
     project_path: String,
     prompt: String,
     model: String,
-) -> Result<(), String> {
+    extra_args: Option<Vec<String>>,
+    options: Option<ChatOptions>,
+) -> Result<CommandPreview, String> {
+    let gemini_path = crate::gemini_binary::find_gemini_binary(&app)?;
+    let model = default_model::for_launch(&app, &project_path, "gemini", &model);
+    let mut options = options.unwrap_or_default();
+    if let Some(extra) = extra_args {
+        options.extra_args.get_or_insert_with(Vec::new).extend(extra);
+    }
+    let cmd = build_gemini_command(&app, &gemini_path, &model, &project_path, &options, &prompt).await?;
+    preview_command(&app, cmd, "gemini", &project_path, &prompt, &options).await
+}
+
+/// Start a new Gemini chat and return its session handle.
+///
+/// `group_id` links sessions started together by a multi-provider fan-out.
+pub(crate) async fn start_gemini_chat(
+    app: AppHandle,
+    project_path: String,
+    prompt: String,
+    model: String,
+    options: ChatOptions,
+    group_id: Option<String>,
+) -> Result<SessionHandle, String> {
     let gemini_path = crate::gemini_binary::find_gemini_binary(&app)?;
-    // Use `gemini -m <model>`; pass prompt via stdin for compatibility
-    let mut cmd = create_command_with_env(&gemini_path);
-    cmd.arg("-m").arg(&model).arg(&prompt);
-    let session_id = Uuid::new_v4().to_string();
-    spawn_gemini_process(app, cmd, session_id, prompt, model, project_path).await
+    // Reported back in the init event, so the UI shows what actually ran
+    let model = default_model::for_launch(&app, &project_path, "gemini", &model);
+    let cmd = build_gemini_command(&app, &gemini_path, &model, &project_path, &options, &prompt).await?;
+    let request = SpawnRequest {
+        provider: "gemini",
+        session_id: Uuid::new_v4().to_string(),
+        prompt,
+        model,
+        project_path,
+        options,
+        group_id,
+    };
+    spawn_gemini_process(app, cmd, request).await
 }
 
 #[tauri::command]
@@ -173,24 +168,105 @@ pub async fn resume_gemini_chat(
     session_id: String,
     prompt: String,
     model: String,
-) -> Result<(), String> {
+    options: Option<ChatOptions>,
+) -> Result<SessionHandle, String> {
     let gemini_path = crate::gemini_binary::find_gemini_binary(&app)?;
-    let mut cmd = create_command_with_env(&gemini_path);
-    cmd.arg("-m").arg(&model).arg(&prompt);
-    spawn_gemini_process(app, cmd, session_id, prompt, model, project_path).await
+    let model = default_model::for_launch(&app, &project_path, "gemini", &model);
+
+    // The CLI keeps no history between runs; replay (and if needed compact) the transcript
+    let summary_cmd = || {
+        let mut c = create_command_with_env(&gemini_path);
+        if !model.is_empty() {
+            c.arg("-m").arg(&model);
+        }
+        c
+    };
+    let full_prompt =
+        crate::provider::compact::resume_prompt(&app, "gemini", &project_path, &session_id, &prompt, summary_cmd)
+            .await;
+
+    let options = options.unwrap_or_default();
+    let cmd = build_gemini_command(&app, &gemini_path, &model, &project_path, &options, &full_prompt).await?;
+    let request = SpawnRequest {
+        provider: "gemini",
+        session_id,
+        prompt,
+        model,
+        project_path,
+        options,
+        group_id: None,
+    };
+    spawn_gemini_process(app, cmd, request).await
 }
 
+/// Pass the reasoning effort as a thinking token budget when one is requested
+/// or set as the project default.
+///
+/// Only CLIs whose `--help` lists a thinking-budget flag can take it; an
+/// explicit request against one that doesn't is an error, an inherited
+/// project default is skipped.
+async fn apply_thinking_budget(
+    app: &AppHandle,
+    args: &mut Vec<String>,
+    gemini_path: &str,
+    project_path: &str,
+    options: &ChatOptions,
+) -> Result<(), String> {
+    let resolved = match reasoning::resolve(app, project_path, options) {
+        Some(resolved) => resolved,
+        None => return Ok(()),
+    };
+    // Same program the runner probes, so the cached result is shared
+    let program = crate::binary_path::command_for(gemini_path).0;
+    match crate::provider::capabilities::probe(&program).await.thinking_flag {
+        Some(flag) => {
+            args.push(format!("{}={}", flag, resolved.effort.thinking_budget()));
+        }
+        None if resolved.explicit => {
+            return Err("This Gemini CLI does not support thinking settings".to_string());
+        }
+        None => log::info!("Skipping default reasoning effort: Gemini CLI has no thinking flag"),
+    }
+    Ok(())
+}
+
+/// Cancel a Gemini run: the given session, or every running Gemini session shown
+/// in the UI. Detached (backgrounded) sessions are only cancelled by id.
+///
+/// The session is flagged as cancelled first so its completion event reports
+/// `cancelled: true` instead of a normal finish.
 #[tauri::command]
-pub async fn cancel_gemini_execution(app: AppHandle) -> Result<(), String> {
-    let state = app.state::<GeminiProcessState>();
-    let mut guard = state.current_process.lock().await;
-    if let Some(child) = guard.as_mut() {
-        child.start_kill().map_err(|e| e.to_string())?;
-        *guard = None;
+pub async fn cancel_gemini_execution(app: AppHandle, session_id: Option<String>) -> Result<(), String> {
+    if let Some(session_id) = session_id {
+        let control = crate::provider::control::get(&session_id)
+            .filter(|c| c.provider == "gemini")
+            .ok_or_else(|| format!("No running gemini session {}", session_id))?;
+        crate::provider::logs::log_cancel(&app, "gemini", Some(control.pid()));
+        control.cancel().await;
+        return Ok(());
+    }
+
+    for control in crate::provider::control::of_provider("gemini") {
+        if control.is_detached() {
+            continue;
+        }
+        crate::provider::logs::log_cancel(&app, "gemini", Some(control.pid()));
+        control.cancel().await;
     }
     Ok(())
 }
 
+/// Answer a question the Gemini CLI asked on stdout (`gemini-input-request`).
+///
+/// Only works for sessions started with the `interactive` option.
+#[tauri::command]
+pub async fn send_to_gemini_session(session_id: String, input: String) -> Result<(), String> {
+    let control = crate::provider::control::get(&session_id)
+        .filter(|c| c.provider == "gemini")
+        .ok_or_else(|| format!("No running gemini session {}", session_id))?;
+    control.write_stdin(&input).await
+}
+
 #[tauri::command]
 pub async fn list_running_gemini_sessions(
     registry: tauri::State<'_, crate::process::ProcessRegistryState>,
@@ -206,30 +282,22 @@ pub async fn get_gemini_binary_path(app: AppHandle) -> Result<String, String> {
 #[tauri::command]
 pub async fn check_gemini_version(app: AppHandle) -> Result<Option<String>, String> {
     let path = crate::gemini_binary::find_gemini_binary(&app)?;
-    Ok(crate::gemini_binary::get_gemini_version(&path))
+    Ok(warmup::cached_version("gemini", &path).await)
 }
 
 #[tauri::command]
-pub async fn set_gemini_binary_path(app: AppHandle, path: String) -> Result<(), String> {
-    let app_data_dir = app
-        .path()
-        .app_data_dir()
-        .map_err(|e| e.to_string())?;
-    std::fs::create_dir_all(&app_data_dir).map_err(|e| e.to_string())?;
-    let db_path = app_data_dir.join("agents.db");
-    let conn = rusqlite::Connection::open(&db_path).map_err(|e| e.to_string())?;
-    conn.execute(
-        "CREATE TABLE IF NOT EXISTS app_settings (key TEXT PRIMARY KEY, value TEXT)",
-        [],
-    )
-    .map_err(|e| e.to_string())?;
-    conn.execute(
-        "INSERT INTO app_settings(key, value) VALUES('gemini_binary_path', ?1)
-         ON CONFLICT(key) DO UPDATE SET value=excluded.value",
-        rusqlite::params![path],
-    )
-    .map_err(|e| e.to_string())?;
-    Ok(())
+pub async fn set_gemini_binary_path(app: AppHandle, path: String) -> Result<String, String> {
+    // Fails on paths that don't exist or can't be run, so they are never stored
+    let path = crate::binary_path::canonicalize_binary(&path)?;
+    // Saving the path already in use keeps the caches
+    if profiles::read_profile_setting(&app, "gemini_binary_path").as_deref() == Some(path.as_str()) {
+        return Ok(path);
+    }
+    // Stored for the active profile
+    profiles::write_profile_setting(&app, "gemini_binary_path", &path)?;
+    // A different binary may know different models
+    *app.state::<GeminiProcessState>().models_cache.lock().await = None;
+    Ok(path)
 }
 
 #[tauri::command]
@@ -239,11 +307,19 @@ pub async fn login_gemini(app: AppHandle) -> Result<(), String> {
     cmd.arg("login");
     cmd.stdout(std::process::Stdio::null())
         .stderr(std::process::Stdio::null());
-    let _ = cmd.spawn().map_err(|e| e.to_string())?;
+    let mut child = cmd.spawn().map_err(|e| e.to_string())?;
+
+    // Drop the cached status now and again once the login flow finishes
+    let login_cache = app.state::<GeminiProcessState>().login_cache.clone();
+    *login_cache.lock().await = None;
+    tokio::spawn(async move {
+        let _ = child.wait().await;
+        *login_cache.lock().await = None;
+    });
     Ok(())
 }
 
-#[derive(serde::Serialize)]
+#[derive(Clone, serde::Serialize)]
 pub struct LoginStatus {
     pub logged_in: bool,
     pub user: Option<String>,
@@ -251,12 +327,29 @@ pub struct LoginStatus {
 }
 
 #[tauri::command]
-pub async fn check_gemini_login(app: AppHandle) -> Result<LoginStatus, String> {
-    let path = crate::gemini_binary::find_gemini_binary(&app)?;
+pub async fn check_gemini_login(app: AppHandle, force: Option<bool>) -> Result<LoginStatus, String> {
+    let login_cache = app.state::<GeminiProcessState>().login_cache.clone();
+    if !force.unwrap_or(false) {
+        if let Some((checked_at, status)) = login_cache.lock().await.as_ref() {
+            if checked_at.elapsed() < LOGIN_CACHE_TTL {
+                return Ok(status.clone());
+            }
+        }
+    }
+
+    let status = probe_gemini_login(&app).await?;
+    *login_cache.lock().await = Some((Instant::now(), status.clone()));
+    Ok(status)
+}
+
+/// Spawn the CLI to find out whether the user is logged in
+async fn probe_gemini_login(app: &AppHandle) -> Result<LoginStatus, String> {
+    let path = crate::gemini_binary::find_gemini_binary(app)?;
     // Try `gemini whoami` first if available
     let mut cmd = create_command_with_env(&path);
     cmd.arg("whoami");
-    if let Ok(out) = cmd.output().await {
+    // A hang (proxy, prompt) is an error, not "logged out"
+    if let Ok(out) = probe::output(&mut cmd, "gemini whoami").await? {
         if out.status.success() {
             let s = String::from_utf8_lossy(&out.stdout).trim().to_string();
             let user = if !s.is_empty() { Some(s) } else { None };
@@ -267,97 +360,25 @@ pub async fn check_gemini_login(app: AppHandle) -> Result<LoginStatus, String> {
     // Fallback: `gemini models list` to probe auth
     let mut cmd2 = create_command_with_env(&path);
     cmd2.arg("models").arg("list").arg("--json");
-    match cmd2.output().await {
+    match probe::output(&mut cmd2, "gemini models list --json").await? {
         Ok(out) if out.status.success() => Ok(LoginStatus { logged_in: true, user: None, error: None }),
         Ok(out) => Ok(LoginStatus { logged_in: false, user: None, error: Some(String::from_utf8_lossy(&out.stderr).to_string()) }),
         Err(e) => Ok(LoginStatus { logged_in: false, user: None, error: Some(e.to_string()) }),
     }
 }
 
-fn expand_tilde(p: &str) -> PathBuf {
-    if let Some(stripped) = p.strip_prefix("~/") {
-        if let Some(home) = dirs::home_dir() { return home.join(stripped); }
-    }
-    PathBuf::from(p)
-}
-
 fn read_db_value(app: &AppHandle, key: &str) -> Option<String> {
-    if let Ok(app_data_dir) = app.path().app_data_dir() {
-        let db_path = app_data_dir.join("agents.db");
-        if db_path.exists() {
-            if let Ok(conn) = rusqlite::Connection::open(&db_path) {
-                if let Ok(val) = conn.query_row(
-                    "SELECT value FROM app_settings WHERE key = ?1",
-                    rusqlite::params![key],
-                    |row| row.get::<_, String>(0),
-                ) { return Some(val); }
-            }
-        }
-    }
-    None
+    profiles::read_profile_setting(app, key)
 }
 
 fn write_db_value(app: &AppHandle, key: &str, value: &str) -> Result<(), String> {
-    let app_data_dir = app.path().app_data_dir().map_err(|e| e.to_string())?;
-    fs::create_dir_all(&app_data_dir).map_err(|e| e.to_string())?;
-    let db_path = app_data_dir.join("agents.db");
-    let conn = rusqlite::Connection::open(&db_path).map_err(|e| e.to_string())?;
-    conn.execute(
-        "CREATE TABLE IF NOT EXISTS app_settings (key TEXT PRIMARY KEY, value TEXT)",
-        [],
-    ).map_err(|e| e.to_string())?;
-    conn.execute(
-        "INSERT INTO app_settings(key, value) VALUES(?1, ?2)
-         ON CONFLICT(key) DO UPDATE SET value=excluded.value",
-        rusqlite::params![key, value],
-    ).map_err(|e| e.to_string())?;
-    Ok(())
-}
-
-fn search_gemini_config_for_default_model() -> Option<String> {
-    let candidates = vec![
-        "~/.config/gemini",
-        "~/.gemini",
-        "~/Library/Application Support/Gemini",
-    ];
-    for root in candidates {
-        let path = expand_tilde(root);
-        if !path.exists() { continue; }
-        let walker = walkdir::WalkDir::new(path).max_depth(2);
-        for entry in walker.into_iter().flatten() {
-            let p = entry.path();
-            if p.is_file() {
-                if let Ok(data) = fs::read_to_string(p) {
-                    for key in ["default_model", "model", "chat_model"] {
-                        if let Some(val) = extract_model_value(&data, key) { return Some(val); }
-                    }
-                }
-            }
-        }
-    }
-    None
-}
-
-fn extract_model_value(content: &str, key: &str) -> Option<String> {
-    let patterns = vec![
-        format!("\"{}\"\s*[:=]\s*\"([^\"]+)\"", key),
-        format!("{}\s*[:=]\s*\"([^\"]+)\"", key),
-        format!("{}\s*[:=]\s*([A-Za-z0-9._-]+)", key),
-    ];
-    for pat in patterns {
-        if let Ok(re) = regex::Regex::new(&pat) {
-            if let Some(c) = re.captures(content) {
-                if let Some(m) = c.get(1) { return Some(m.as_str().to_string()); }
-            }
-        }
-    }
-    None
+    profiles::write_profile_setting(app, key, value)
 }
 
 #[tauri::command]
 pub async fn get_gemini_default_model(app: AppHandle) -> Result<Option<String>, String> {
     if let Some(v) = read_db_value(&app, "gemini_default_model") { return Ok(Some(v)); }
-    Ok(search_gemini_config_for_default_model())
+    config_files::find_default_model(&app, "gemini")
 }
 
 #[tauri::command]
@@ -365,12 +386,29 @@ pub async fn set_gemini_default_model(app: AppHandle, model: String) -> Result<(
     write_db_value(&app, "gemini_default_model", &model)
 }
 
+/// List the CLI's models, from the cache unless it is stale or `force` is set
 #[tauri::command]
-pub async fn list_gemini_models(app: AppHandle) -> Result<Vec<String>, String> {
-    let path = crate::gemini_binary::find_gemini_binary(&app)?;
+pub async fn list_gemini_models(app: AppHandle, force: Option<bool>) -> Result<Vec<String>, String> {
+    let models_cache = app.state::<GeminiProcessState>().models_cache.clone();
+    if !force.unwrap_or(false) {
+        if let Some((listed_at, models)) = models_cache.lock().await.as_ref() {
+            if listed_at.elapsed() < MODELS_CACHE_TTL {
+                return Ok(models.clone());
+            }
+        }
+    }
+
+    let models = fetch_gemini_models(&app).await?;
+    *models_cache.lock().await = Some((Instant::now(), models.clone()));
+    Ok(models)
+}
+
+/// Spawn the CLI to list its models
+async fn fetch_gemini_models(app: &AppHandle) -> Result<Vec<String>, String> {
+    let path = crate::gemini_binary::find_gemini_binary(app)?;
     let mut cmd = create_command_with_env(&path);
     cmd.arg("models").arg("list").arg("--json");
-    match cmd.output().await {
+    match probe::output(&mut cmd, "gemini models list --json").await? {
         Ok(out) if out.status.success() => {
             if let Ok(v) = serde_json::from_slice::<serde_json::Value>(&out.stdout) {
                 if let Some(arr) = v.as_array() {
@@ -385,7 +423,7 @@ pub async fn list_gemini_models(app: AppHandle) -> Result<Vec<String>, String> {
     // Fallback: plaintext lines
     let mut cmd2 = create_command_with_env(&path);
     cmd2.arg("models").arg("list");
-    match cmd2.output().await {
+    match probe::output(&mut cmd2, "gemini models list").await? {
         Ok(out) if out.status.success() => {
             let s = String::from_utf8_lossy(&out.stdout);
             let list: Vec<String> = s.lines().map(|l| l.trim().to_string()).filter(|l| !l.is_empty()).collect();
@@ -0,0 +1,1414 @@
+use std::collections::HashMap;
+use tauri::{AppHandle, Manager};
+
+use crate::provider::capabilities::{self, ModelTraits, ProviderCapabilities};
+use crate::provider::compact::{self, COMPACTION_THRESHOLD_KEY};
+use crate::provider::config_files::{self, ConfigFile};
+use crate::provider::control::{self, SessionHandle};
+use crate::provider::default_model::{self, EffectiveModel, GLOBAL_DEFAULT_MODEL_KEY};
+use crate::provider::diff::{self, DiffMode, SessionDiff};
+use crate::provider::events::{self, EVENT_PROTOCOL_VERSION};
+use crate::provider::framing::{self, record_delimiter_key};
+use crate::provider::html_export;
+use crate::provider::interactive::AUTO_ACCEPT_DOWNLOADS_KEY;
+use crate::provider::last_error::{self, ProviderError};
+use crate::provider::login_shell;
+use crate::provider::logs::LogEntry;
+use crate::provider::model_args::{self, ModelArgRule};
+use crate::provider::model_usage::{self, FrequentModel, DEFAULT_FREQUENT_LIMIT};
+use crate::provider::normalize::{load_rules, rules_key, NormalizationRule};
+use crate::provider::probe;
+use crate::provider::profiles::{self, Profile, ProfileSettings};
+use crate::provider::project_env;
+use crate::provider::reasoning::{self, ReasoningEffort};
+use crate::provider::run_once::{self, RunOutput};
+use crate::provider::runner::{default_max_output_lines, MAX_OUTPUT_LINES_KEY};
+use crate::provider::redact::{load_user_patterns, REDACTION_PATTERNS_KEY};
+use crate::provider::replay::{self, ReplayHandle};
+use crate::provider::session_title::{self, SessionTitle};
+use crate::provider::settings::{open_db, read_setting, write_setting};
+use crate::provider::settings_snapshot::{self, ImportResult, SettingsSnapshot};
+use crate::provider::storage::{self, StorageStatus};
+use crate::provider::streaming;
+use crate::provider::tags::{self, TaggedSession};
+use crate::provider::transcript::{
+    self, persist_stderr_key, CompressResult, MergeResult, PartialSession, SessionSummary,
+    TranscriptTail, TruncateResult, COMPRESS_TRANSCRIPTS_KEY,
+};
+use crate::provider::warmup::{self, WarmedProvider, WARM_ON_STARTUP_KEY};
+use crate::provider::websocket::{self, WebSocketSettings};
+use crate::provider::ChatOptions;
+
+fn validate_provider(provider: &str) -> Result<(), String> {
+    match provider {
+        "codex" | "gemini" => Ok(()),
+        other => Err(format!("Unknown provider: {}", other)),
+    }
+}
+
+/// Get the user-defined secret redaction patterns
+#[tauri::command]
+pub async fn get_redaction_patterns(app: AppHandle) -> Result<Vec<String>, String> {
+    Ok(load_user_patterns(&app))
+}
+
+/// Replace the user-defined secret redaction patterns.
+///
+/// Built-in patterns (`sk-...`, `AIza...`, `Bearer <token>`) always apply;
+/// these are added on top of them.
+#[tauri::command]
+pub async fn set_redaction_patterns(app: AppHandle, patterns: Vec<String>) -> Result<(), String> {
+    for p in &patterns {
+        regex::Regex::new(p).map_err(|e| format!("Invalid pattern '{}': {}", p, e))?;
+    }
+    let value = serde_json::to_string(&patterns).map_err(|e| e.to_string())?;
+    write_setting(&app, REDACTION_PATTERNS_KEY, &value)
+}
+
+/// Get recent provider session log entries (spawn/stream/retry/complete/cancel), oldest first
+#[tauri::command]
+pub async fn get_recent_logs(limit: Option<usize>) -> Result<Vec<LogEntry>, String> {
+    Ok(crate::provider::logs::recent(limit.unwrap_or(100)))
+}
+
+/// Get the output normalization rules for a provider
+#[tauri::command]
+pub async fn get_normalization_rules(app: AppHandle, provider: String) -> Result<Vec<NormalizationRule>, String> {
+    validate_provider(&provider)?;
+    Ok(load_rules(&app, &provider))
+}
+
+/// Replace the ordered output normalization rules for a provider.
+///
+/// Rules run on every stdout line before it is wrapped into an assistant
+/// message; a line rewritten to an empty string is dropped.
+#[tauri::command]
+pub async fn set_normalization_rules(
+    app: AppHandle,
+    provider: String,
+    rules: Vec<NormalizationRule>,
+) -> Result<(), String> {
+    validate_provider(&provider)?;
+    for r in &rules {
+        regex::Regex::new(&r.pattern).map_err(|e| format!("Invalid pattern '{}': {}", r.pattern, e))?;
+    }
+    let value = serde_json::to_string(&rules).map_err(|e| e.to_string())?;
+    write_setting(&app, &rules_key(&provider), &value)
+}
+
+/// Per-model flag rules for a provider: the stored ones, or the built-in
+/// defaults when none are stored
+#[tauri::command]
+pub async fn get_model_arg_rules(app: AppHandle, provider: String) -> Result<Vec<ModelArgRule>, String> {
+    validate_provider(&provider)?;
+    Ok(model_args::load_rules(&app, &provider))
+}
+
+/// Replace the per-model flag rules for a provider; `None` restores the
+/// built-in defaults.
+///
+/// When a chat command is built, flags a rule drops for the model are
+/// removed (with their value) and renamed ones rewritten, so a model that
+/// rejects e.g. `--temperature` still runs.
+#[tauri::command]
+pub async fn set_model_arg_rules(
+    app: AppHandle,
+    provider: String,
+    rules: Option<Vec<ModelArgRule>>,
+) -> Result<(), String> {
+    validate_provider(&provider)?;
+    let value = match rules {
+        Some(rules) => {
+            model_args::validate(&rules)?;
+            serde_json::to_string(&rules).map_err(|e| e.to_string())?
+        }
+        None => String::new(),
+    };
+    write_setting(&app, &model_args::rules_key(&provider), &value)
+}
+
+/// Replace the tags attached to a session.
+///
+/// The project is taken from the running session when available, otherwise
+/// from `project_path` or from earlier tags on the same session.
+#[tauri::command]
+pub async fn tag_session(
+    app: AppHandle,
+    session_id: String,
+    tags: Vec<String>,
+    project_path: Option<String>,
+) -> Result<Vec<String>, String> {
+    let tags = tags::clean_tags(tags);
+    let registry = app.state::<crate::process::ProcessRegistryState>();
+    let running = registry.0.find_session(&session_id)?;
+
+    let conn = open_db(&app)?;
+    tags::ensure_tags_table(&conn)?;
+    let project_path = running
+        .map(|info| info.project_path)
+        .or(project_path)
+        .or_else(|| tags::stored_project_path(&conn, &session_id))
+        .unwrap_or_default();
+
+    tags::replace_tags(&conn, &session_id, &project_path, &tags)?;
+    registry.0.set_session_tags(&session_id, tags.clone())?;
+    Ok(tags)
+}
+
+/// List sessions in a project that carry the given tag
+#[tauri::command]
+pub async fn get_sessions_by_tag(
+    app: AppHandle,
+    project_path: String,
+    tag: String,
+) -> Result<Vec<TaggedSession>, String> {
+    let conn = open_db(&app)?;
+    tags::sessions_by_tag(&conn, &project_path, tag.trim())
+}
+
+/// Get the environment overrides applied to provider CLIs in a project
+#[tauri::command]
+pub async fn get_project_env(app: AppHandle, project_path: String) -> Result<HashMap<String, String>, String> {
+    let conn = open_db(&app)?;
+    project_env::load(&conn, &project_path)
+}
+
+/// Replace the environment overrides for a project.
+///
+/// These are merged over the allowlisted base environment when Codex/Gemini
+/// are spawned, so a project value always beats the global one.
+#[tauri::command]
+pub async fn set_project_env(
+    app: AppHandle,
+    project_path: String,
+    env: HashMap<String, String>,
+) -> Result<(), String> {
+    for key in env.keys() {
+        project_env::validate_key(key)?;
+    }
+    let conn = open_db(&app)?;
+    project_env::save(&conn, &project_path, &env)
+}
+
+/// Get the default reasoning effort used for a project's Codex/Gemini runs
+#[tauri::command]
+pub async fn get_reasoning_effort(app: AppHandle, project_path: String) -> Result<Option<ReasoningEffort>, String> {
+    let conn = open_db(&app)?;
+    reasoning::load(&conn, &project_path)
+}
+
+/// Set (or clear, with `None`) the default reasoning effort for a project
+#[tauri::command]
+pub async fn set_reasoning_effort(
+    app: AppHandle,
+    project_path: String,
+    effort: Option<ReasoningEffort>,
+) -> Result<(), String> {
+    let conn = open_db(&app)?;
+    reasoning::save(&conn, &project_path, effort)
+}
+
+/// Model a provider uses for a project, if one was set
+#[tauri::command]
+pub async fn get_project_model(app: AppHandle, project_path: String, provider: String) -> Result<Option<String>, String> {
+    validate_provider(&provider)?;
+    default_model::load_project(&open_db(&app)?, &project_path, &provider)
+}
+
+/// Set (or clear, with `None`) the model a provider uses for a project,
+/// ahead of the provider default
+#[tauri::command]
+pub async fn set_project_model(
+    app: AppHandle,
+    project_path: String,
+    provider: String,
+    model: Option<String>,
+) -> Result<(), String> {
+    validate_provider(&provider)?;
+    let model = model.map(|m| m.trim().to_string()).filter(|m| !m.is_empty());
+    default_model::save_project(&open_db(&app)?, &project_path, &provider, model.as_deref())
+}
+
+/// Model used by any provider without a default of its own (active profile)
+#[tauri::command]
+pub async fn get_global_default_model(app: AppHandle) -> Result<Option<String>, String> {
+    Ok(profiles::read_profile_setting(&app, GLOBAL_DEFAULT_MODEL_KEY).filter(|m| !m.is_empty()))
+}
+
+/// Set (or clear, with `None`) the last step of the model resolution chain
+#[tauri::command]
+pub async fn set_global_default_model(app: AppHandle, model: Option<String>) -> Result<(), String> {
+    let model = model.unwrap_or_default();
+    profiles::write_profile_setting(&app, GLOBAL_DEFAULT_MODEL_KEY, model.trim())
+}
+
+/// The model a chat with "default" (or no model) would launch with, and which
+/// step found it: project model, provider default, config file, then global
+/// default. Errors when none of them names one.
+#[tauri::command]
+pub async fn resolve_effective_model(
+    app: AppHandle,
+    project_path: Option<String>,
+    provider: String,
+) -> Result<EffectiveModel, String> {
+    validate_provider(&provider)?;
+    default_model::resolve(&app, project_path.as_deref(), &provider)
+}
+
+/// Schema version (`v`) stamped on Codex/Gemini event payloads
+#[tauri::command]
+pub async fn get_event_protocol_version() -> Result<u32, String> {
+    Ok(EVENT_PROTOCOL_VERSION)
+}
+
+/// A provider's most used models, recent runs weighing more, for the top of
+/// the model picker
+#[tauri::command]
+pub async fn get_frequent_models(
+    app: AppHandle,
+    provider: String,
+    limit: Option<usize>,
+) -> Result<Vec<FrequentModel>, String> {
+    validate_provider(&provider)?;
+    model_usage::frequent(&app, &provider, limit.unwrap_or(DEFAULT_FREQUENT_LIMIT))
+}
+
+/// Prefix in front of every emitted event name (empty unless configured
+/// through `ISHINEX_EVENT_NAMESPACE` at startup)
+#[tauri::command]
+pub async fn get_event_namespace() -> Result<String, String> {
+    Ok(events::namespace().to_string())
+}
+
+/// Merge several persisted session transcripts into a new one.
+///
+/// Messages are interleaved by timestamp and deduplicated; the result reports
+/// how many messages each source session contributed.
+#[tauri::command]
+pub async fn merge_sessions(
+    project_path: String,
+    session_ids: Vec<String>,
+    new_session_id: String,
+) -> Result<MergeResult, String> {
+    transcript::merge(&project_path, &session_ids, &new_session_id)
+}
+
+/// Re-run the last prompt of a session to get a different answer.
+///
+/// The previous prompt and response stay in the transcript marked as
+/// `superseded`, and the new answer streams as a regular resume of the same
+/// session. The model defaults to the one recorded when the session started.
+#[tauri::command]
+pub async fn regenerate_last(
+    app: AppHandle,
+    project_path: String,
+    session_id: String,
+    model: Option<String>,
+    options: Option<ChatOptions>,
+) -> Result<SessionHandle, String> {
+    if control::get(&session_id).is_some() {
+        return Err(format!("Session {} is still running", session_id));
+    }
+    let messages = transcript::read_transcript(&project_path, &session_id)?;
+    let init = messages
+        .iter()
+        .find(|m| m.get("subtype").and_then(|s| s.as_str()) == Some("init"));
+    let provider = init
+        .and_then(|m| m.get("provider"))
+        .and_then(|p| p.as_str())
+        .ok_or_else(|| format!("Unknown provider for session {}", session_id))?
+        .to_string();
+    validate_provider(&provider)?;
+    let model = model
+        .or_else(|| init.and_then(|m| m.get("model")).and_then(|m| m.as_str()).map(String::from))
+        .ok_or_else(|| format!("Unknown model for session {}", session_id))?;
+
+    // Keep the original so a failed start doesn't leave the answer hidden
+    let path = transcript::find_transcript(&project_path, &session_id)?
+        .ok_or_else(|| format!("No transcript for session {}", session_id))?;
+    let original = std::fs::read(&path).map_err(|e| e.to_string())?;
+    let prompt = transcript::supersede_last_exchange(&project_path, &session_id)?;
+
+    let started = match provider.as_str() {
+        "codex" => {
+            crate::commands::codex::resume_codex_chat(app, project_path, session_id, prompt, model, options).await
+        }
+        _ => crate::commands::gemini::resume_gemini_chat(app, project_path, session_id, prompt, model, options).await,
+    };
+    if started.is_err() {
+        if let Err(e) = std::fs::write(&path, original) {
+            log::warn!("Failed to restore transcript {}: {}", path.display(), e);
+        }
+    }
+    started
+}
+
+/// How long `restart_session_with_model` waits for the cancelled run to wind down
+const RESTART_TEARDOWN_TIMEOUT: std::time::Duration = std::time::Duration::from_secs(10);
+
+/// Cancel a running session and start its last prompt again on `new_model`,
+/// under the same session id.
+///
+/// The interrupted exchange is kept in the transcript as `superseded` and the
+/// new run is a resume, so earlier context carries over (see
+/// `regenerate_last`). Once it starts, `<provider>-session-restarted` is
+/// emitted with the previous and the new model.
+#[tauri::command]
+pub async fn restart_session_with_model(
+    app: AppHandle,
+    session_id: String,
+    new_model: String,
+    project_path: Option<String>,
+    options: Option<ChatOptions>,
+) -> Result<SessionHandle, String> {
+    if new_model.trim().is_empty() {
+        return Err("New model is empty".to_string());
+    }
+    let running = control::get(&session_id)
+        .ok_or_else(|| format!("No running session {}", session_id))?;
+    let info = app
+        .state::<crate::process::ProcessRegistryState>()
+        .0
+        .find_session(&session_id)?;
+    let project_path = info
+        .as_ref()
+        .map(|info| info.project_path.clone())
+        .or(project_path)
+        .ok_or_else(|| format!("Unknown project for session {}", session_id))?;
+    let previous_model = info.map(|info| info.model);
+    let provider = running.provider.clone();
+
+    running.cancel().await;
+    // Start over only once this run's own teardown is done: transcript closed
+    // and completion emitted
+    if tokio::time::timeout(RESTART_TEARDOWN_TIMEOUT, running.finished()).await.is_err() {
+        return Err(format!("Session {} did not stop after being cancelled", session_id));
+    }
+
+    let handle = regenerate_last(
+        app.clone(),
+        project_path,
+        session_id.clone(),
+        Some(new_model.trim().to_string()),
+        options,
+    )
+    .await?;
+    if let Some(control) = control::get(&session_id) {
+        let msg = events::envelope(serde_json::json!({
+            "session_id": session_id,
+            "provider": provider,
+            "previous_model": previous_model,
+            "model": new_model.trim(),
+        }));
+        control.emit(&app, "session-restarted", msg);
+    }
+    Ok(handle)
+}
+
+/// Drop every message after `keep_until_message_id` from a session's
+/// transcript (backing up the original) so a resume continues from there
+#[tauri::command]
+pub async fn truncate_session(
+    session_id: String,
+    keep_until_message_id: String,
+    project_path: String,
+) -> Result<TruncateResult, String> {
+    tokio::task::spawn_blocking(move || transcript::truncate(&project_path, &session_id, &keep_until_message_id))
+        .await
+        .map_err(|e| e.to_string())?
+}
+
+/// Placeholder in a `chain_session` template replaced by the source's answer
+const PREVIOUS_OUTPUT_PLACEHOLDER: &str = "{previous_output}";
+
+/// Start a session on `target_provider` whose prompt is `template` with
+/// `{previous_output}` replaced by the last assistant reply of a finished
+/// session (e.g. have Gemini critique Codex's answer).
+///
+/// The new session runs in the source's project unless `project_path` says
+/// otherwise; an empty `target_model` uses the provider's default.
+#[tauri::command]
+pub async fn chain_session(
+    app: AppHandle,
+    source_session_id: String,
+    target_provider: String,
+    target_model: Option<String>,
+    template: String,
+    project_path: Option<String>,
+    options: Option<ChatOptions>,
+) -> Result<SessionHandle, String> {
+    validate_provider(&target_provider)?;
+    if !template.contains(PREVIOUS_OUTPUT_PLACEHOLDER) {
+        return Err(format!("The template must reference {}", PREVIOUS_OUTPUT_PLACEHOLDER));
+    }
+    if control::get(&source_session_id).is_some() {
+        return Err(format!("Session {} is still running", source_session_id));
+    }
+    let path = match project_path.as_deref() {
+        Some(project) => transcript::find_transcript(project, &source_session_id)?,
+        None => transcript::locate_transcript(&source_session_id)?,
+    }
+    .ok_or_else(|| format!("No transcript for session {}", source_session_id))?;
+    let messages = crate::unified_history::read_jsonl(&path);
+    let previous_output = transcript::last_assistant_text(&messages)
+        .ok_or_else(|| format!("Session {} has no assistant reply to pass on", source_session_id))?;
+    let project_path = project_path
+        .or_else(|| {
+            messages
+                .iter()
+                .find_map(|m| m.get("project_path").and_then(|p| p.as_str()))
+                .map(String::from)
+        })
+        .ok_or_else(|| format!("Unknown project for session {}; pass project_path", source_session_id))?;
+
+    let prompt = template.replace(PREVIOUS_OUTPUT_PLACEHOLDER, &previous_output);
+    let model = target_model.unwrap_or_default();
+    let options = options.unwrap_or_default();
+    match target_provider.as_str() {
+        "codex" => crate::commands::codex::start_codex_chat(app, project_path, prompt, model, options, None).await,
+        _ => crate::commands::gemini::start_gemini_chat(app, project_path, prompt, model, options, None).await,
+    }
+}
+
+/// Whether new session transcripts are written gzip-compressed
+#[tauri::command]
+pub async fn get_compress_transcripts(app: AppHandle) -> Result<bool, String> {
+    Ok(read_setting(&app, COMPRESS_TRANSCRIPTS_KEY).as_deref() == Some("true"))
+}
+
+/// Write new session transcripts as `.jsonl.gz`; existing ones keep their format
+#[tauri::command]
+pub async fn set_compress_transcripts(app: AppHandle, enabled: bool) -> Result<(), String> {
+    write_setting(&app, COMPRESS_TRANSCRIPTS_KEY, if enabled { "true" } else { "false" })
+}
+
+/// Whether a provider's stderr lines are written to its session transcripts
+#[tauri::command]
+pub async fn get_persist_stderr(app: AppHandle, provider: String) -> Result<bool, String> {
+    validate_provider(&provider)?;
+    Ok(read_setting(&app, &persist_stderr_key(&provider)).as_deref() == Some("true"))
+}
+
+/// Write a provider's stderr lines to new session transcripts as
+/// `{type: "system", subtype: "stderr", text}` records, in arrival order with
+/// the output. Either way they are emitted as `diagnostic` events.
+#[tauri::command]
+pub async fn set_persist_stderr(app: AppHandle, provider: String, enabled: bool) -> Result<(), String> {
+    validate_provider(&provider)?;
+    write_setting(&app, &persist_stderr_key(&provider), if enabled { "true" } else { "false" })
+}
+
+/// Record delimiter of a provider's output in escaped form (`\n` unless set)
+#[tauri::command]
+pub async fn get_record_delimiter(app: AppHandle, provider: String) -> Result<String, String> {
+    validate_provider(&provider)?;
+    Ok(framing::escape_delimiter(&framing::load_delimiter(&app, &provider)))
+}
+
+/// Split a provider's stdout into records on `delimiter` instead of
+/// newlines, for CLIs that separate records with NUL bytes or a custom
+/// sequence; stderr is still read line by line. Escapes: `\n`, `\r`, `\t`, `\0`, `\\`, `\xHH`. `None` restores
+/// newlines; applies to sessions started afterwards.
+#[tauri::command]
+pub async fn set_record_delimiter(app: AppHandle, provider: String, delimiter: Option<String>) -> Result<(), String> {
+    validate_provider(&provider)?;
+    let delimiter = match delimiter.filter(|d| !d.is_empty()) {
+        Some(delimiter) => framing::escape_delimiter(&framing::parse_delimiter(&delimiter)?),
+        None => String::new(),
+    };
+    write_setting(&app, &record_delimiter_key(&provider), &delimiter)
+}
+
+/// Whether a provider's CLI is launched through the user's login shell
+#[tauri::command]
+pub async fn get_use_login_shell(app: AppHandle, provider: String) -> Result<bool, String> {
+    validate_provider(&provider)?;
+    Ok(login_shell::enabled(&app, &provider))
+}
+
+/// Launch a provider's CLI as `$SHELL -lc "exec <cli> <args>"` so the
+/// environment set up by the shell's init files (nvm, asdf, direnv) applies.
+/// Off by default; no effect on Windows.
+#[tauri::command]
+pub async fn set_use_login_shell(app: AppHandle, provider: String, enabled: bool) -> Result<(), String> {
+    validate_provider(&provider)?;
+    write_setting(&app, &login_shell::use_login_shell_key(&provider), if enabled { "true" } else { "false" })
+}
+
+/// Whether model download prompts are confirmed automatically
+#[tauri::command]
+pub async fn get_auto_accept_downloads(app: AppHandle) -> Result<bool, String> {
+    Ok(read_setting(&app, AUTO_ACCEPT_DOWNLOADS_KEY).as_deref() == Some("true"))
+}
+
+/// Answer model download prompts with "y" in sessions whose stdin is kept open
+/// (interactive ones); other sessions get a `setup-required` event either way
+#[tauri::command]
+pub async fn set_auto_accept_downloads(app: AppHandle, enabled: bool) -> Result<(), String> {
+    write_setting(&app, AUTO_ACCEPT_DOWNLOADS_KEY, if enabled { "true" } else { "false" })
+}
+
+/// Gzip a project's session transcripts not written to for `older_than_days`
+#[tauri::command]
+pub async fn compact_old_sessions(project_path: String, older_than_days: u64) -> Result<CompressResult, String> {
+    tokio::task::spawn_blocking(move || transcript::compress_old_sessions(&project_path, older_than_days))
+        .await
+        .map_err(|e| e.to_string())?
+}
+
+/// Last `last_n` messages of a session's transcript plus whether it is still
+/// running; meant for reconnecting to a session without loading its history.
+#[tauri::command]
+pub async fn tail_session_transcript(
+    session_id: String,
+    last_n: usize,
+    project_path: Option<String>,
+) -> Result<TranscriptTail, String> {
+    tokio::task::spawn_blocking(move || transcript::tail_transcript(project_path.as_deref(), &session_id, last_n))
+        .await
+        .map_err(|e| e.to_string())?
+}
+
+/// Every Codex/Gemini session of a project, running or finished, newest
+/// first: the stored transcripts plus running sessions that haven't written
+/// one yet
+#[tauri::command]
+pub async fn list_all_sessions(app: AppHandle, project_path: String) -> Result<Vec<SessionSummary>, String> {
+    let running = app
+        .state::<crate::process::ProcessRegistryState>()
+        .0
+        .get_running_chat_sessions(None)?;
+    let project = project_path.clone();
+    let mut sessions = tokio::task::spawn_blocking(move || transcript::list_sessions(&project))
+        .await
+        .map_err(|e| e.to_string())??;
+
+    let project = project_path.trim_end_matches(['/', '\\']);
+    for info in running {
+        let (session_id, provider) = match info.process_type {
+            crate::process::ProcessType::ChatSession { session_id, provider } => (session_id, provider),
+            _ => continue,
+        };
+        if info.project_path.trim_end_matches(['/', '\\']) != project {
+            continue;
+        }
+        match sessions.iter_mut().find(|s| s.session_id == session_id) {
+            Some(summary) => {
+                summary.status = "running".to_string();
+                summary.ended_at = None;
+            }
+            None => sessions.push(SessionSummary {
+                session_id,
+                provider: Some(provider),
+                model: Some(info.model).filter(|m| !m.is_empty()),
+                started_at: Some(info.started_at.to_rfc3339()),
+                ended_at: None,
+                status: "running".to_string(),
+                message_count: 0,
+                first_prompt: Some(info.task),
+                title: None,
+            }),
+        }
+    }
+    match session_title::cached_titles(&open_db(&app)?) {
+        Ok(mut titles) => {
+            for summary in sessions.iter_mut() {
+                summary.title = titles.remove(&summary.session_id);
+            }
+        }
+        Err(e) => log::warn!("Failed to load session titles: {}", e),
+    }
+    // RFC 3339 timestamps sort chronologically as strings
+    sessions.sort_by(|a, b| b.started_at.cmp(&a.started_at));
+    Ok(sessions)
+}
+
+/// One-line title (and a one-sentence summary) of a stored session, written
+/// by the provider that ran it with its default model. Cached until the
+/// conversation changes; `force` regenerates it. `list_all_sessions`
+/// includes the cached title.
+#[tauri::command]
+pub async fn summarize_session(
+    app: AppHandle,
+    session_id: String,
+    project_path: Option<String>,
+    force: Option<bool>,
+) -> Result<SessionTitle, String> {
+    let project_path = project_path.filter(|p| !p.is_empty());
+    let title_cmd = |provider: &str| -> Result<tokio::process::Command, String> {
+        let mut cmd = match provider {
+            "codex" => crate::commands::codex::create_command_with_env(&crate::codex_binary::find_codex_binary(&app)?),
+            _ => crate::commands::gemini::create_command_with_env(&crate::gemini_binary::find_gemini_binary(&app)?),
+        };
+        let model = default_model::for_launch(&app, project_path.as_deref().unwrap_or_default(), provider, "");
+        if !model.is_empty() {
+            cmd.arg("-m").arg(&model);
+        }
+        Ok(cmd)
+    };
+    session_title::summarize(&app, &session_id, project_path.as_deref(), force.unwrap_or(false), title_cmd).await
+}
+
+/// Line (default) or word diff of two stored sessions' final answers, with
+/// similarity metrics, for comparing models side by side
+#[tauri::command]
+pub async fn diff_sessions(
+    session_a: String,
+    session_b: String,
+    mode: Option<DiffMode>,
+    project_path: Option<String>,
+) -> Result<SessionDiff, String> {
+    tokio::task::spawn_blocking(move || {
+        diff::diff_sessions(project_path.as_deref(), &session_a, &session_b, mode.unwrap_or_default())
+    })
+    .await
+    .map_err(|e| e.to_string())?
+}
+
+/// Render a stored session as one self-contained HTML file (inline styles,
+/// rendered markdown, highlighted code) for sharing, and return its path
+/// under the project's `exports` directory
+#[tauri::command]
+pub async fn export_session_html(session_id: String, project_path: Option<String>) -> Result<String, String> {
+    tokio::task::spawn_blocking(move || html_export::export_session(project_path.as_deref(), &session_id))
+        .await
+        .map_err(|e| e.to_string())?
+        .map(|path| path.to_string_lossy().to_string())
+}
+
+/// Re-emit a finished session's stored messages on its output channels, then
+/// a completion event, for demos and reproducing rendering bugs.
+///
+/// `speed` scales the recorded timing: 1 (default) is real time, 0 instant.
+#[tauri::command]
+pub async fn replay_session(
+    app: AppHandle,
+    session_id: String,
+    speed: Option<f64>,
+    project_path: Option<String>,
+) -> Result<ReplayHandle, String> {
+    replay::start(&app, project_path.as_deref(), &session_id, speed.unwrap_or(1.0))
+}
+
+/// Stop emitting a running session's output without touching the CLI process.
+///
+/// Lines keep being read and are buffered until `resume_session_output`.
+#[tauri::command]
+pub async fn pause_session_output(session_id: String) -> Result<(), String> {
+    let control = control::get(&session_id)
+        .ok_or_else(|| format!("No running session {}", session_id))?;
+    control.pause();
+    Ok(())
+}
+
+/// Flush the output buffered while paused and resume live streaming.
+///
+/// Returns the number of buffered events that were delivered.
+#[tauri::command]
+pub async fn resume_session_output(app: AppHandle, session_id: String) -> Result<usize, String> {
+    let control = control::get(&session_id)
+        .ok_or_else(|| format!("No running session {}", session_id))?;
+    Ok(control.resume(&app))
+}
+
+/// Stop emitting a running session's events because no UI shows it anymore
+/// (its tab was closed). The CLI keeps running and its transcript keeps being
+/// written; `reattach_session` brings it back.
+#[tauri::command]
+pub async fn detach_session(session_id: String) -> Result<(), String> {
+    let control = control::get(&session_id)
+        .ok_or_else(|| format!("No running session {}", session_id))?;
+    control.detach();
+    Ok(())
+}
+
+/// Transcript messages replayed by `reattach_session` unless told otherwise
+const DEFAULT_REATTACH_TAIL: usize = 200;
+
+/// Resume a detached session's live events, first replaying the last
+/// `tail` messages of its transcript (default `DEFAULT_REATTACH_TAIL`) as
+/// `output` events marked `"replay": true`.
+///
+/// Returns the number of replayed messages. A session that finished while
+/// detached isn't running anymore; load its transcript instead.
+#[tauri::command]
+pub async fn reattach_session(
+    app: AppHandle,
+    session_id: String,
+    tail: Option<usize>,
+    project_path: Option<String>,
+) -> Result<usize, String> {
+    let control = control::get(&session_id)
+        .ok_or_else(|| format!("Session {} is not running; open its transcript instead", session_id))?;
+    let tail = tail.unwrap_or(DEFAULT_REATTACH_TAIL);
+    tokio::task::spawn_blocking(move || {
+        control.reattach(&app, || {
+            transcript::tail_transcript(project_path.as_deref(), &session_id, tail).map(|t| t.messages)
+        })
+    })
+    .await
+    .map_err(|e| e.to_string())?
+}
+
+/// Assistant text checkpointed by sessions that never finished (e.g. after a crash)
+#[tauri::command]
+pub async fn recover_partial_sessions(project_path: String) -> Result<Vec<PartialSession>, String> {
+    transcript::recover_partials(&project_path)
+}
+
+/// Check that session storage for a project is writable and has free space
+#[tauri::command]
+pub async fn preflight_storage(project_path: String) -> Result<StorageStatus, String> {
+    Ok(storage::preflight(&project_path))
+}
+
+/// One provider/model pair in a fan-out
+#[derive(Debug, Clone, serde::Deserialize)]
+pub struct ChatTarget {
+    pub provider: String,
+    pub model: String,
+}
+
+/// Session started for one target of a fan-out
+#[derive(Debug, serde::Serialize)]
+pub struct MultiChatSession {
+    pub provider: String,
+    pub model: String,
+    #[serde(flatten)]
+    pub handle: SessionHandle,
+}
+
+/// Sessions started by `execute_multi_chat`
+#[derive(Debug, serde::Serialize)]
+pub struct MultiChatResult {
+    /// Stamped as `group_id` on every event of the group's sessions
+    pub group_id: String,
+    /// One per target, in the order given
+    pub sessions: Vec<MultiChatSession>,
+}
+
+/// Send the same prompt to several providers at once.
+///
+/// Each provider streams on its usual channels with a shared `group_id` in
+/// the payloads. A provider may appear several times, e.g. to compare two of
+/// its models; every session runs its own process. If any target fails to
+/// start, the ones already started are cancelled.
+#[tauri::command]
+pub async fn execute_multi_chat(
+    app: AppHandle,
+    project_path: String,
+    prompt: String,
+    targets: Vec<ChatTarget>,
+    options: Option<ChatOptions>,
+) -> Result<MultiChatResult, String> {
+    if targets.is_empty() {
+        return Err("No targets given".to_string());
+    }
+    for target in &targets {
+        validate_provider(&target.provider)?;
+    }
+
+    let group_id = uuid::Uuid::new_v4().to_string();
+    let options = options.unwrap_or_default();
+    let mut sessions = Vec::new();
+    for target in targets {
+        let started = match target.provider.as_str() {
+            "codex" => {
+                crate::commands::codex::start_codex_chat(
+                    app.clone(),
+                    project_path.clone(),
+                    prompt.clone(),
+                    target.model.clone(),
+                    options.clone(),
+                    Some(group_id.clone()),
+                )
+                .await
+            }
+            _ => {
+                crate::commands::gemini::start_gemini_chat(
+                    app.clone(),
+                    project_path.clone(),
+                    prompt.clone(),
+                    target.model.clone(),
+                    options.clone(),
+                    Some(group_id.clone()),
+                )
+                .await
+            }
+        };
+        match started {
+            Ok(handle) => sessions.push(MultiChatSession {
+                provider: target.provider,
+                model: target.model,
+                handle,
+            }),
+            Err(e) => {
+                cancel_group(&group_id).await;
+                return Err(format!("Failed to start {}: {}", target.provider, e));
+            }
+        }
+    }
+    Ok(MultiChatResult { group_id, sessions })
+}
+
+async fn cancel_group(group_id: &str) -> usize {
+    let mut cancelled = 0;
+    for control in control::in_group(group_id) {
+        if control.cancel().await {
+            cancelled += 1;
+        }
+    }
+    cancelled
+}
+
+/// Cancel every session of a fan-out group; returns how many were stopped
+#[tauri::command]
+pub async fn cancel_multi_chat(app: AppHandle, group_id: String) -> Result<usize, String> {
+    for control in control::in_group(&group_id) {
+        crate::provider::logs::log_cancel(&app, &control.provider, Some(control.pid()));
+    }
+    Ok(cancel_group(&group_id).await)
+}
+
+/// Cancel the session a `SessionHandle` was issued for.
+///
+/// Works for any provider; an unknown or stale token is an error.
+#[tauri::command]
+pub async fn cancel_by_token(app: AppHandle, token: String) -> Result<(), String> {
+    let control = control::find_by_token(&token).ok_or_else(|| "No running session for this token".to_string())?;
+    crate::provider::logs::log_cancel(&app, &control.provider, Some(control.pid()));
+    control.cancel().await;
+    Ok(())
+}
+
+/// What `cancel_project_sessions` stopped
+#[derive(Debug, Default, serde::Serialize)]
+pub struct ProjectCancelResult {
+    pub stopped: usize,
+    /// Codex, Gemini and Claude sessions
+    pub session_ids: Vec<String>,
+    pub agent_run_ids: Vec<i64>,
+}
+
+/// Path without trailing separators, and with symlinks resolved when it exists
+fn project_key(path: &str) -> (String, Option<std::path::PathBuf>) {
+    let trimmed = path.trim_end_matches(['/', '\\']).to_string();
+    (trimmed, std::fs::canonicalize(path).ok())
+}
+
+/// Stop every running session and agent run whose working directory is the
+/// project, whichever provider it runs on, e.g. when its tab is closed
+#[tauri::command]
+pub async fn cancel_project_sessions(app: AppHandle, project_path: String) -> Result<ProjectCancelResult, String> {
+    use crate::process::registry::ProcessType;
+
+    let (target, target_canonical) = project_key(&project_path);
+    let running = app.state::<crate::process::ProcessRegistryState>().0.get_running_processes()?;
+    let mut result = ProjectCancelResult::default();
+    for info in running {
+        let (path, canonical) = project_key(&info.project_path);
+        let same = path == target || (canonical.is_some() && canonical == target_canonical);
+        if !same {
+            continue;
+        }
+        let stopped = match &info.process_type {
+            ProcessType::ChatSession { session_id, .. } => match control::get(session_id) {
+                Some(control) => {
+                    crate::provider::logs::log_cancel(&app, &control.provider, Some(control.pid()));
+                    // Each session is cancelled through its own handle. Between
+                    // retry attempts there is no child to kill, but the cancel
+                    // still ends the session, so it counts as stopped.
+                    control.cancel().await;
+                    true
+                }
+                None => false,
+            },
+            ProcessType::ClaudeSession { session_id } => {
+                crate::commands::claude::cancel_claude_execution(app.clone(), Some(session_id.clone()))
+                    .await
+                    .is_ok()
+            }
+            ProcessType::AgentRun { .. } => crate::commands::agents::kill_agent_session(
+                app.clone(),
+                app.state::<crate::commands::agents::AgentDb>(),
+                app.state::<crate::process::ProcessRegistryState>(),
+                info.run_id,
+            )
+            .await
+            .unwrap_or(false),
+        };
+        if !stopped {
+            continue;
+        }
+        result.stopped += 1;
+        match info.process_type {
+            ProcessType::ChatSession { session_id, .. } | ProcessType::ClaudeSession { session_id } => {
+                result.session_ids.push(session_id)
+            }
+            ProcessType::AgentRun { .. } => result.agent_run_ids.push(info.run_id),
+        }
+    }
+    log::info!("Stopped {} session(s) of {}", result.stopped, project_path);
+    Ok(result)
+}
+
+/// Estimated token count of earlier turns above which resumes are compacted
+#[tauri::command]
+pub async fn get_compaction_threshold(app: AppHandle) -> Result<usize, String> {
+    Ok(compact::threshold(&app))
+}
+
+/// Set the compaction threshold (in estimated tokens)
+#[tauri::command]
+pub async fn set_compaction_threshold(app: AppHandle, threshold: usize) -> Result<(), String> {
+    if threshold == 0 {
+        return Err("Threshold must be greater than zero".to_string());
+    }
+    write_setting(&app, COMPACTION_THRESHOLD_KEY, &threshold.to_string())
+}
+
+/// Output line cap applied to sessions that don't pass `max_output_lines`;
+/// `None` means unlimited
+#[tauri::command]
+pub async fn get_max_output_lines(app: AppHandle) -> Result<Option<usize>, String> {
+    Ok(default_max_output_lines(&app))
+}
+
+/// Set the default output line cap; `None` or 0 removes it
+#[tauri::command]
+pub async fn set_max_output_lines(app: AppHandle, limit: Option<usize>) -> Result<(), String> {
+    write_setting(&app, MAX_OUTPUT_LINES_KEY, &limit.unwrap_or(0).to_string())
+}
+
+/// Everything the status bar shows for one provider
+#[derive(Debug, serde::Serialize)]
+pub struct ProviderOverview {
+    pub provider: String,
+    pub installed: bool,
+    pub binary_path: Option<String>,
+    pub version: Option<String>,
+    /// `None` when the CLI isn't installed so login wasn't checked
+    pub logged_in: Option<bool>,
+    pub default_model: Option<String>,
+    pub running_sessions: usize,
+}
+
+async fn provider_overview(app: &AppHandle, provider: &str) -> ProviderOverview {
+    let binary_path = match provider {
+        "codex" => crate::codex_binary::find_codex_binary(app),
+        _ => crate::gemini_binary::find_gemini_binary(app),
+    }
+    .ok();
+
+    let version = match &binary_path {
+        Some(path) => warmup::cached_version(provider, path).await,
+        None => None,
+    };
+    let installed = version.is_some();
+
+    // Both go through the cached login status, so this rarely spawns the CLI
+    let logged_in = if installed {
+        let status = match provider {
+            "codex" => crate::commands::codex::check_codex_login(app.clone(), None).await,
+            _ => crate::commands::gemini::check_gemini_login(app.clone(), None).await,
+        };
+        status.ok().map(|s| s.logged_in)
+    } else {
+        None
+    };
+
+    let default_model = match provider {
+        "codex" => crate::commands::codex::get_codex_default_model(app.clone()).await,
+        _ => crate::commands::gemini::get_gemini_default_model(app.clone()).await,
+    }
+    .ok()
+    .flatten();
+
+    let running_sessions = app
+        .state::<crate::process::ProcessRegistryState>()
+        .0
+        .get_running_chat_sessions(Some(provider))
+        .map(|s| s.len())
+        .unwrap_or(0);
+
+    ProviderOverview {
+        provider: provider.to_string(),
+        installed,
+        binary_path,
+        version,
+        logged_in,
+        default_model,
+        running_sessions,
+    }
+}
+
+/// Install, version, login, default model and running-session count for every
+/// provider in one call
+#[tauri::command]
+pub async fn get_providers_overview(app: AppHandle) -> Result<Vec<ProviderOverview>, String> {
+    let (codex, gemini) = tokio::join!(provider_overview(&app, "codex"), provider_overview(&app, "gemini"));
+    Ok(vec![codex, gemini])
+}
+
+/// Why a provider can't run a chat yet
+#[derive(Debug, Clone, Copy, PartialEq, Eq, serde::Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum NotReadyReason {
+    /// No binary found, or it doesn't answer `--version`
+    NotInstalled,
+    /// The CLI reports no logged-in account, or the check failed
+    NotLoggedIn,
+    /// The model isn't among the ones the CLI lists
+    ModelMissing,
+}
+
+/// Outcome of `ensure_provider_ready`
+#[derive(Debug, Clone, serde::Serialize)]
+pub struct ProviderReadiness {
+    pub provider: String,
+    pub ready: bool,
+    /// First check that failed; `None` when ready
+    pub reason: Option<NotReadyReason>,
+    /// What to do about it
+    pub message: Option<String>,
+    pub binary_path: Option<String>,
+    pub version: Option<String>,
+    /// Model that was checked: the requested one or the resolved default;
+    /// `None` when the CLI would use its built-in default
+    pub model: Option<String>,
+    /// Whether the model was found in the CLI's list; `false` when the list
+    /// couldn't be fetched, which doesn't block readiness
+    pub model_verified: bool,
+}
+
+impl ProviderReadiness {
+    fn fail(mut self, reason: NotReadyReason, message: String) -> Self {
+        self.ready = false;
+        self.reason = Some(reason);
+        self.message = Some(message);
+        self
+    }
+}
+
+/// Single gate before automated runs: whether a provider is installed, logged
+/// in and has the model (the resolved default when `model` is unset). Stops
+/// at the first failing check and names it in `reason`. Uses the cached
+/// login and model lists unless `force` is set, so repeated calls are cheap.
+#[tauri::command]
+pub async fn ensure_provider_ready(
+    app: AppHandle,
+    provider: String,
+    model: Option<String>,
+    force: Option<bool>,
+) -> Result<ProviderReadiness, String> {
+    validate_provider(&provider)?;
+    let mut readiness = ProviderReadiness {
+        provider: provider.clone(),
+        ready: true,
+        reason: None,
+        message: None,
+        binary_path: None,
+        version: None,
+        model: None,
+        model_verified: false,
+    };
+
+    let binary_path = match provider.as_str() {
+        "codex" => crate::codex_binary::find_codex_binary(&app),
+        _ => crate::gemini_binary::find_gemini_binary(&app),
+    };
+    let binary_path = match binary_path {
+        Ok(path) => path,
+        Err(e) => return Ok(readiness.fail(NotReadyReason::NotInstalled, e)),
+    };
+    readiness.version = warmup::cached_version(&provider, &binary_path).await;
+    readiness.binary_path = Some(binary_path.clone());
+    if readiness.version.is_none() {
+        let message = format!("{} at {} doesn't run; reinstall it or set another binary path", provider, binary_path);
+        return Ok(readiness.fail(NotReadyReason::NotInstalled, message));
+    }
+
+    let login = match provider.as_str() {
+        "codex" => crate::commands::codex::check_codex_login(app.clone(), force).await,
+        _ => crate::commands::gemini::check_gemini_login(app.clone(), force).await,
+    };
+    let login_error = match login {
+        Ok(status) if status.logged_in => None,
+        Ok(status) => Some(status.error.unwrap_or_else(|| format!("Not logged in to {}", provider))),
+        Err(e) => Some(format!("Could not check the {} login: {}", provider, e)),
+    };
+    if let Some(message) = login_error {
+        return Ok(readiness.fail(NotReadyReason::NotLoggedIn, message));
+    }
+
+    let model = default_model::for_launch(&app, "", &provider, model.as_deref().unwrap_or_default());
+    if model.is_empty() {
+        return Ok(readiness);
+    }
+    readiness.model = Some(model.clone());
+    let listed = match provider.as_str() {
+        "codex" => crate::commands::codex::list_codex_models(app.clone(), force)
+            .await
+            .map(|models| models.into_iter().map(|m| m.id).collect::<Vec<_>>()),
+        _ => crate::commands::gemini::list_gemini_models(app.clone(), force).await,
+    };
+    match listed {
+        Ok(models) if models.iter().any(|m| m == &model || m.strip_prefix("models/") == Some(model.as_str())) => {
+            readiness.model_verified = true;
+        }
+        Ok(_) => {
+            let message = format!("{} doesn't list the model {}", provider, model);
+            return Ok(readiness.fail(NotReadyReason::ModelMissing, message));
+        }
+        Err(e) => log::warn!("Could not list {} models to check {}: {}", provider, model, e),
+    }
+    Ok(readiness)
+}
+
+/// Run a provider CLI subcommand the app doesn't wrap (e.g. `codex config get`)
+/// and return its output once it exits or `timeout_secs` (default 30) passes.
+///
+/// The binary and environment are the same ones chats use; flags that turn
+/// off the CLI's sandbox or approvals are refused.
+#[tauri::command]
+pub async fn run_provider_command(
+    app: AppHandle,
+    provider: String,
+    args: Vec<String>,
+    timeout_secs: Option<u64>,
+    project_path: Option<String>,
+) -> Result<RunOutput, String> {
+    validate_provider(&provider)?;
+    let path = match provider.as_str() {
+        "codex" => crate::codex_binary::find_codex_binary(&app)?,
+        _ => crate::gemini_binary::find_gemini_binary(&app)?,
+    };
+    let mut cmd = match provider.as_str() {
+        "codex" => crate::commands::codex::create_command_with_env(&path),
+        _ => crate::commands::gemini::create_command_with_env(&path),
+    };
+    if let Some(project_path) = project_path.filter(|p| !p.is_empty()) {
+        cmd.current_dir(&project_path);
+        cmd.envs(project_env::load(&open_db(&app)?, &project_path)?);
+    }
+    run_once::run(&app, &provider, cmd, args, run_once::timeout_for(timeout_secs)).await
+}
+
+/// Fill the version, login, capability and model caches of every installed
+/// provider now; also emits `providers-warmed`
+#[tauri::command]
+pub async fn warm_providers(app: AppHandle) -> Result<Vec<WarmedProvider>, String> {
+    Ok(warmup::warm(&app).await)
+}
+
+/// Whether providers are warmed in the background at startup
+#[tauri::command]
+pub async fn get_warm_providers_on_startup(app: AppHandle) -> Result<bool, String> {
+    Ok(warmup::enabled_on_startup(&app))
+}
+
+/// Turn the startup warm-up on or off; takes effect on the next launch
+#[tauri::command]
+pub async fn set_warm_providers_on_startup(app: AppHandle, enabled: bool) -> Result<(), String> {
+    write_setting(&app, WARM_ON_STARTUP_KEY, if enabled { "true" } else { "false" })
+}
+
+/// Seconds a login, version or model-list probe may run before it fails
+#[tauri::command]
+pub async fn get_probe_timeout() -> Result<u64, String> {
+    Ok(probe::timeout().as_secs())
+}
+
+/// Change the probe timeout (clamped to 1-300s); returns the value applied
+#[tauri::command]
+pub async fn set_probe_timeout(app: AppHandle, secs: u64) -> Result<u64, String> {
+    probe::set(&app, secs)
+}
+
+/// WebSocket relay settings, including the token clients must pass
+#[tauri::command]
+pub async fn get_websocket_settings(app: AppHandle) -> Result<WebSocketSettings, String> {
+    websocket::load_settings(&app)
+}
+
+/// Turn the local relay of session events (WebSocket, plus Server-Sent Events
+/// at `/sessions/<id>/events`) on or off. Unset
+/// arguments keep their stored value; `regenerate_token` disconnects
+/// clients holding the old token.
+#[tauri::command]
+pub async fn set_websocket_settings(
+    app: AppHandle,
+    enabled: bool,
+    port: Option<u16>,
+    regenerate_token: Option<bool>,
+) -> Result<WebSocketSettings, String> {
+    let port = match port {
+        Some(port) => port,
+        None => websocket::load_settings(&app)?.port,
+    };
+    websocket::apply_settings(&app, enabled, port, regenerate_token.unwrap_or(false)).await
+}
+
+/// Every profile, marking the active one
+#[tauri::command]
+pub async fn list_profiles(app: AppHandle) -> Result<Vec<Profile>, String> {
+    profiles::list(&app)
+}
+
+/// Add a profile; settings it doesn't set fall back to the default profile
+#[tauri::command]
+pub async fn create_profile(app: AppHandle, name: String) -> Result<Profile, String> {
+    profiles::create(&app, name.trim())
+}
+
+/// Delete an inactive profile and everything stored for it
+#[tauri::command]
+pub async fn delete_profile(app: AppHandle, name: String) -> Result<(), String> {
+    profiles::delete(&app, name.trim())
+}
+
+/// Make a profile active. Binary paths, default models, env and base URLs
+/// are resolved against it from now on; cached login and model lists are
+/// dropped since they belong to the previous account.
+#[tauri::command]
+pub async fn switch_profile(app: AppHandle, name: String) -> Result<(), String> {
+    profiles::switch(&app, name.trim())?;
+    clear_account_caches(&app).await;
+    Ok(())
+}
+
+/// Drop the cached login state and model lists of both providers
+async fn clear_account_caches(app: &AppHandle) {
+    let codex = app.state::<crate::commands::codex::CodexProcessState>();
+    *codex.login_cache.lock().await = None;
+    *codex.models_cache.lock().await = None;
+    let gemini = app.state::<crate::commands::gemini::GeminiProcessState>();
+    *gemini.login_cache.lock().await = None;
+    *gemini.models_cache.lock().await = None;
+}
+
+/// Env overrides and base URLs of a profile (the active one by default)
+#[tauri::command]
+pub async fn get_profile_settings(app: AppHandle, name: Option<String>) -> Result<ProfileSettings, String> {
+    let name = name.unwrap_or_else(|| profiles::active_name(&app));
+    profiles::load_settings(&open_db(&app)?, &name)
+}
+
+/// Replace the env overrides and base URLs of a profile (the active one by default)
+#[tauri::command]
+pub async fn set_profile_settings(
+    app: AppHandle,
+    name: Option<String>,
+    settings: ProfileSettings,
+) -> Result<(), String> {
+    let name = name.unwrap_or_else(|| profiles::active_name(&app));
+    profiles::save_settings(&app, &name, &settings)
+}
+
+/// Snapshot of every app setting, for backups or moving to another machine.
+/// Credentials (tokens, API keys, proxy passwords) are left out and listed
+/// under `sensitive` unless `include_sensitive` is set.
+#[tauri::command]
+pub async fn export_settings(app: AppHandle, include_sensitive: Option<bool>) -> Result<SettingsSnapshot, String> {
+    settings_snapshot::export(&app, include_sensitive.unwrap_or(false))
+}
+
+/// Restore a snapshot from `export_settings`. By default settings already
+/// set here are kept; with `overwrite` the snapshot's values replace them.
+#[tauri::command]
+pub async fn import_settings(app: AppHandle, json: String, overwrite: Option<bool>) -> Result<ImportResult, String> {
+    let result = settings_snapshot::import(&app, &json, overwrite.unwrap_or(false))?;
+    // Binary paths or the active profile may point elsewhere now
+    clear_account_caches(&app).await;
+    Ok(result)
+}
+
+/// Whether the active profile passes the app's whole environment to a provider CLI
+#[tauri::command]
+pub async fn get_inherit_full_env(app: AppHandle, provider: String) -> Result<bool, String> {
+    validate_provider(&provider)?;
+    let mut settings = profiles::load_settings(&open_db(&app)?, &profiles::active_name(&app))?;
+    Ok(*settings.inherit_full_env_mut(&provider))
+}
+
+/// Pass the app's whole environment to a provider CLI instead of only the
+/// allowlisted variables (active profile). Secrets in the app's environment
+/// then reach the CLI, so this is off by default.
+#[tauri::command]
+pub async fn set_inherit_full_env(app: AppHandle, provider: String, enabled: bool) -> Result<(), String> {
+    validate_provider(&provider)?;
+    let name = profiles::active_name(&app);
+    let mut settings = profiles::load_settings(&open_db(&app)?, &name)?;
+    *settings.inherit_full_env_mut(&provider) = enabled;
+    profiles::save_settings(&app, &name, &settings)
+}
+
+/// Most recent failure recorded for a provider, with its timestamp
+#[tauri::command]
+pub async fn get_last_provider_error(app: AppHandle, provider: String) -> Result<Option<ProviderError>, String> {
+    validate_provider(&provider)?;
+    Ok(last_error::get(&app, &provider))
+}
+
+/// Config files found for a provider, whether each parses, and which one the
+/// default model is read from, so the UI can offer to open them
+#[tauri::command]
+pub async fn get_provider_config_paths(app: AppHandle, provider: String) -> Result<Vec<ConfigFile>, String> {
+    config_files::discover_for(&app, &provider)
+}
+
+/// Which features a provider and model support (attachments, vision,
+/// structured output, tools, reasoning effort, streaming), so the UI can
+/// disable what won't work. Taken from the CLI's `--help` and the model
+/// listing and kept for a day, or until the binary changes; `force`
+/// rebuilds it.
+#[tauri::command]
+pub async fn get_provider_capabilities(
+    app: AppHandle,
+    provider: String,
+    model: String,
+    force: Option<bool>,
+) -> Result<ProviderCapabilities, String> {
+    validate_provider(&provider)?;
+    let path = match provider.as_str() {
+        "codex" => crate::codex_binary::find_codex_binary(&app)?,
+        _ => crate::gemini_binary::find_gemini_binary(&app)?,
+    };
+    let program = crate::binary_path::command_for(&path).0;
+
+    let cached = if force.unwrap_or(false) {
+        None
+    } else {
+        capabilities::load_matrix(&app, &provider, &model, &program)
+    };
+    let mut matrix = match cached {
+        Some(matrix) => matrix,
+        None => {
+            let cli = capabilities::probe(&program).await;
+            let traits = match provider.as_str() {
+                "codex" => crate::commands::codex::list_codex_models(app.clone(), None)
+                    .await
+                    .unwrap_or_default()
+                    .into_iter()
+                    .find(|m| m.id == model)
+                    .map(|m| ModelTraits {
+                        supports_tools: m.supports_tools,
+                        supports_vision: m.supports_vision,
+                        supports_reasoning: m.supports_reasoning,
+                        context_window: m.context_window,
+                    })
+                    .unwrap_or_default(),
+                // The Gemini listing is only names
+                _ => ModelTraits::default(),
+            };
+            let matrix = ProviderCapabilities::derive(&provider, &model, &cli, &traits);
+            if let Err(e) = capabilities::save_matrix(&app, &program, &matrix) {
+                log::warn!("Failed to store capabilities of {} {}: {}", provider, model, e);
+            }
+            matrix
+        }
+    };
+    // Observed per run, so always the latest verdict rather than the stored one
+    matrix.streams = streaming::lookup(&app, &provider, &model);
+    Ok(matrix)
+}
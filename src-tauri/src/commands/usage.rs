@@ -712,3 +712,150 @@ pub fn get_session_stats(
 
     Ok(by_session)
 }
+
+/// Per-session totals written as one CSV row
+#[derive(Debug, Default)]
+struct SessionUsage {
+    session_id: String,
+    models: Vec<String>,
+    input_tokens: u64,
+    output_tokens: u64,
+    cache_creation_tokens: u64,
+    cache_read_tokens: u64,
+    cost: f64,
+    started: String,
+    ended: String,
+}
+
+impl SessionUsage {
+    fn duration_secs(&self) -> i64 {
+        match (
+            DateTime::parse_from_rfc3339(&self.started),
+            DateTime::parse_from_rfc3339(&self.ended),
+        ) {
+            (Ok(start), Ok(end)) => (end - start).num_seconds().max(0),
+            _ => 0,
+        }
+    }
+}
+
+#[derive(Debug, Serialize)]
+pub struct UsageExport {
+    path: String,
+    sessions: u64,
+    total_input_tokens: u64,
+    total_output_tokens: u64,
+    total_cost: f64,
+}
+
+/// `YYYY-MM-DD` or an RFC 3339 timestamp, reduced to a date
+fn parse_date_bound(value: &str, label: &str) -> Result<NaiveDate, String> {
+    NaiveDate::parse_from_str(value, "%Y-%m-%d").or_else(|_| {
+        DateTime::parse_from_rfc3339(value)
+            .map(|dt| dt.naive_local().date())
+            .map_err(|e| format!("Invalid {} date: {}", label, e))
+    })
+}
+
+/// Quote a CSV field when it contains a separator, quote or newline
+fn csv_field(value: &str) -> String {
+    if value.contains([',', '"', '\n', '\r']) {
+        format!("\"{}\"", value.replace('"', "\"\""))
+    } else {
+        value.to_string()
+    }
+}
+
+/// Write per-session usage for a project (tokens, estimated cost, models and
+/// duration) to a CSV file and return its path with the totals.
+///
+/// `since`/`until` are inclusive dates (`YYYY-MM-DD` or RFC 3339). The file is
+/// written under `~/.ishinex/projects/<project_id>/reports`.
+#[command]
+pub fn export_usage_csv(
+    project_path: String,
+    since: Option<String>,
+    until: Option<String>,
+) -> Result<UsageExport, String> {
+    let claude_path = dirs::home_dir()
+        .ok_or("Failed to get home directory")?
+        .join(".claude");
+
+    let since_date = since.as_deref().map(|s| parse_date_bound(s, "start")).transpose()?;
+    let until_date = until.as_deref().map(|s| parse_date_bound(s, "end")).transpose()?;
+    let encoded_project = crate::unified_history::encode_project_id(&project_path);
+
+    let mut sessions: HashMap<String, SessionUsage> = HashMap::new();
+    for entry in get_all_usage_entries(&claude_path) {
+        if entry.project_path != project_path && entry.project_path != encoded_project {
+            continue;
+        }
+        let in_range = match DateTime::parse_from_rfc3339(&entry.timestamp) {
+            Ok(dt) => {
+                let date = dt.naive_local().date();
+                !since_date.is_some_and(|s| date < s) && !until_date.is_some_and(|u| date > u)
+            }
+            Err(_) => false,
+        };
+        if !in_range {
+            continue;
+        }
+
+        let session = sessions
+            .entry(entry.session_id.clone())
+            .or_insert_with(|| SessionUsage {
+                session_id: entry.session_id.clone(),
+                started: entry.timestamp.clone(),
+                ..Default::default()
+            });
+        if !session.models.contains(&entry.model) {
+            session.models.push(entry.model.clone());
+        }
+        session.input_tokens += entry.input_tokens;
+        session.output_tokens += entry.output_tokens;
+        session.cache_creation_tokens += entry.cache_creation_tokens;
+        session.cache_read_tokens += entry.cache_read_tokens;
+        session.cost += entry.cost;
+        // Entries arrive sorted by timestamp
+        session.ended = entry.timestamp;
+    }
+
+    let mut rows: Vec<SessionUsage> = sessions.into_values().collect();
+    rows.sort_by(|a, b| a.started.cmp(&b.started));
+
+    let mut csv = String::from(
+        "session_id,provider,models,started,ended,duration_secs,input_tokens,output_tokens,cache_creation_tokens,cache_read_tokens,estimated_cost_usd\n",
+    );
+    for row in &rows {
+        csv.push_str(&format!(
+            "{},{},{},{},{},{},{},{},{},{},{:.6}\n",
+            csv_field(&row.session_id),
+            "claude",
+            csv_field(&row.models.join(";")),
+            csv_field(&row.started),
+            csv_field(&row.ended),
+            row.duration_secs(),
+            row.input_tokens,
+            row.output_tokens,
+            row.cache_creation_tokens,
+            row.cache_read_tokens,
+            row.cost
+        ));
+    }
+
+    let reports_dir = crate::unified_history::ishinex_dir()?
+        .join("projects")
+        .join(&encoded_project)
+        .join("reports");
+    fs::create_dir_all(&reports_dir).map_err(|e| e.to_string())?;
+    let path = reports_dir.join(format!("usage_{}.csv", Local::now().format("%Y%m%d_%H%M%S")));
+    fs::write(&path, csv).map_err(|e| format!("Failed to write {}: {}", path.display(), e))?;
+
+    Ok(UsageExport {
+        path: path.to_string_lossy().to_string(),
+        sessions: rows.len() as u64,
+        total_input_tokens: rows.iter().map(|r| r.input_tokens).sum(),
+        total_output_tokens: rows.iter().map(|r| r.output_tokens).sum(),
+        total_cost: rows.iter().map(|r| r.cost).sum(),
+    })
+}
@@ -1,42 +1,23 @@
 use log::{info, warn};
-use std::path::PathBuf;
 use std::process::Command;
-use tauri::Manager;
 
 /// Find the Google Gemini CLI binary path.
 /// Checks app DB, then `which gemini`, else falls back to `gemini`.
 pub fn find_gemini_binary(app_handle: &tauri::AppHandle) -> Result<String, String> {
     // 1) DB stored path
-    if let Ok(app_data_dir) = app_handle.path().app_data_dir() {
-        let db_path = app_data_dir.join("agents.db");
-        if db_path.exists() {
-            if let Ok(conn) = rusqlite::Connection::open(&db_path) {
-                if let Ok(stored_path) = conn.query_row(
-                    "SELECT value FROM app_settings WHERE key = 'gemini_binary_path'",
-                    [],
-                    |row| row.get::<_, String>(0),
-                ) {
-                    let pb = PathBuf::from(&stored_path);
-                    if pb.exists() && pb.is_file() {
-                        info!("Using Gemini binary from DB: {}", stored_path);
-                        return Ok(stored_path);
-                    } else {
-                        warn!("Stored gemini path does not exist: {}", stored_path);
-                    }
-                }
+    if let Some(stored_path) = crate::provider::profiles::read_profile_setting(app_handle, "gemini_binary_path") {
+        match crate::binary_path::resolve_executable(&stored_path) {
+            Some(pb) => {
+                info!("Using Gemini binary from DB: {}", pb.display());
+                return Ok(pb.to_string_lossy().to_string());
             }
+            None => warn!("Stored gemini path is missing or not executable: {}", stored_path),
         }
     }
 
     // 2) which gemini
-    if let Ok(output) = Command::new("which").arg("gemini").output() {
-        if output.status.success() {
-            let path = String::from_utf8_lossy(&output.stdout).trim().to_string();
-            if !path.is_empty() {
-                let pb = PathBuf::from(&path);
-                if pb.exists() { return Ok(path); }
-            }
-        }
+    if let Some(path) = crate::binary_path::which("gemini") {
+        return Ok(path);
     }
 
     // 3) assume in PATH
@@ -45,11 +26,16 @@ pub fn find_gemini_binary(app_handle: &tauri::AppHandle) -> Result<String, Strin
 
 /// Try get version string using `gemini --version` (best-effort)
 pub fn get_gemini_version(path: &str) -> Option<String> {
-    if let Ok(output) = Command::new(path).arg("--version").output() {
-        if output.status.success() {
+    let (program, prefix) = crate::binary_path::command_for(path);
+    let mut cmd = Command::new(program);
+    cmd.args(prefix).arg("--version");
+    match crate::provider::probe::output_blocking(&mut cmd, "gemini --version") {
+        Ok(Ok(output)) if output.status.success() => {
             let s = String::from_utf8_lossy(&output.stdout).trim().to_string();
             if !s.is_empty() { return Some(s); }
         }
+        Ok(_) => {}
+        Err(e) => warn!("{}", e),
     }
     None
 }
@@ -1,12 +1,14 @@
 // Learn more about Tauri commands at https://tauri.app/develop/calling-rust/
 
 // Declare modules
+pub mod binary_path;
 pub mod checkpoint;
 pub mod claude_binary;
 pub mod codex_binary;
 pub mod gemini_binary;
 pub mod commands;
 pub mod process;
+pub mod provider;
 pub mod unified_history;
 
 #[cfg_attr(mobile, tauri::mobile_entry_point)]
@@ -1,12 +1,14 @@
 // Prevents additional console window on Windows in release, DO NOT REMOVE!!
 #![cfg_attr(not(debug_assertions), windows_subsystem = "windows")]
 
+mod binary_path;
 mod checkpoint;
 mod claude_binary;
 mod codex_binary;
 mod commands;
 mod gemini_binary;
 mod process;
+mod provider;
 mod unified_history;
 
 use checkpoint::state::CheckpointState;
@@ -34,13 +36,13 @@ use commands::claude::{
     update_hooks_config, validate_hook_command, ClaudeProcessState,
 };
 use commands::codex::{
-    cancel_codex_execution, check_codex_login, check_codex_version, execute_codex_chat, get_codex_binary_path,
-    get_codex_default_model, list_codex_models, list_running_codex_sessions, login_codex, set_codex_binary_path,
-    set_codex_default_model, CodexProcessState, resume_codex_chat,
+    cancel_codex_execution, check_codex_login, check_codex_version, diagnose_codex_binary, execute_codex_chat, preview_codex_command, get_codex_binary_path,
+    get_codex_config_dir, get_codex_default_model, list_codex_models, list_running_codex_sessions, send_to_codex_session, login_codex, set_codex_binary_path,
+    set_codex_config_dir, set_codex_default_model, CodexProcessState, resume_codex_chat,
 };
 use commands::gemini::{
-    cancel_gemini_execution, check_gemini_login, check_gemini_version, execute_gemini_chat, get_gemini_binary_path,
-    get_gemini_default_model, list_gemini_models, list_running_gemini_sessions, login_gemini, set_gemini_binary_path,
+    cancel_gemini_execution, check_gemini_login, check_gemini_version, execute_gemini_chat, preview_gemini_command, get_gemini_binary_path,
+    get_gemini_default_model, list_gemini_models, list_running_gemini_sessions, send_to_gemini_session, login_gemini, set_gemini_binary_path,
     set_gemini_default_model, GeminiProcessState, resume_gemini_chat,
 };
 use commands::mcp::{
@@ -49,15 +51,44 @@ use commands::mcp::{
     mcp_serve, mcp_test_connection,
 };
 
+use commands::provider::{
+    cancel_by_token, cancel_multi_chat, cancel_project_sessions, chain_session,
+    compact_old_sessions, create_profile, delete_profile, detach_session, diff_sessions,
+    ensure_provider_ready, execute_multi_chat, export_session_html, export_settings,
+    get_auto_accept_downloads, get_compaction_threshold, get_compress_transcripts,
+    get_event_namespace, get_event_protocol_version, get_frequent_models, get_global_default_model,
+    get_inherit_full_env, get_last_provider_error, get_max_output_lines, get_model_arg_rules,
+    get_normalization_rules, get_persist_stderr, get_probe_timeout, get_profile_settings,
+    get_project_env, get_project_model, get_provider_capabilities, get_provider_config_paths,
+    get_providers_overview, get_reasoning_effort, get_recent_logs, get_record_delimiter,
+    get_redaction_patterns, get_sessions_by_tag, get_use_login_shell, get_warm_providers_on_startup,
+    get_websocket_settings, import_settings, list_all_sessions, list_profiles, merge_sessions,
+    pause_session_output, preflight_storage, reattach_session, recover_partial_sessions,
+    regenerate_last, replay_session, resolve_effective_model, restart_session_with_model,
+    resume_session_output, run_provider_command, set_auto_accept_downloads,
+    set_compaction_threshold, set_compress_transcripts, set_global_default_model,
+    set_inherit_full_env, set_max_output_lines, set_model_arg_rules, set_normalization_rules,
+    set_persist_stderr, set_probe_timeout, set_profile_settings, set_project_env, set_project_model,
+    set_reasoning_effort, set_record_delimiter, set_redaction_patterns, set_use_login_shell,
+    set_warm_providers_on_startup, set_websocket_settings, summarize_session, switch_profile,
+    tag_session, tail_session_transcript, truncate_session, warm_providers,
+};
 use commands::proxy::{apply_proxy_settings, get_proxy_settings, save_proxy_settings};
 use commands::storage::{
     storage_delete_row, storage_execute_sql, storage_insert_row, storage_list_tables,
     storage_read_table, storage_reset_database, storage_update_row,
 };
 use commands::usage::{
-    get_session_stats, get_usage_by_date_range, get_usage_details, get_usage_stats,
+    export_usage_csv, get_session_stats, get_usage_by_date_range, get_usage_details,
+    get_usage_stats,
+};
+use unified_history::{
+    add_history_source, cancel_unify_all, count_provider_messages, estimate_unify_size,
+    get_history_walk_settings, get_unified_output_dir, import_transcript, list_claude_sessions,
+    list_history_sources, relabel_provider, remove_history_source, repair_jsonl,
+    set_history_walk_settings, set_unified_output_dir, unify_all_projects, unify_provider_histories,
+    validate_jsonl,
 };
-use unified_history::unify_provider_histories;
 use process::ProcessRegistryState;
 use std::sync::Mutex;
 use tauri::Manager;
@@ -69,6 +100,13 @@ fn main() {
     // Initialize logger
     env_logger::init();
 
+    // Optional prefix for every emitted event, for hosts with clashing names
+    if let Ok(namespace) = std::env::var(provider::events::EVENT_NAMESPACE_ENV) {
+        if let Err(e) = provider::events::set_namespace(&namespace) {
+            log::warn!("Ignoring {}: {}", provider::events::EVENT_NAMESPACE_ENV, e);
+        }
+    }
+
     tauri::Builder::default()
         .plugin(tauri_plugin_dialog::init())
         .plugin(tauri_plugin_shell::init())
@@ -165,6 +203,20 @@ fn main() {
             app.manage(CodexProcessState::default());
             app.manage(GeminiProcessState::default());
 
+            // Settings of the active profile are needed to build provider commands
+            if let Err(e) = provider::profiles::load_active(app.handle()) {
+                log::warn!("Failed to load the active profile: {}", e);
+            }
+
+            // Before the warm-up, which runs probes
+            provider::probe::load(app.handle());
+
+            // Opt-in: fill provider caches in the background so the settings panel opens instantly
+            provider::warmup::spawn_on_startup(app.handle().clone());
+
+            // Opt-in: relay session events to remote UIs over a local WebSocket
+            provider::websocket::start_on_startup(app.handle());
+
             // Apply window vibrancy with rounded corners on macOS
             #[cfg(target_os = "macos")]
             {
@@ -225,12 +277,17 @@ fn main() {
             get_claude_session_output,
             // Codex provider
             execute_codex_chat,
+            preview_codex_command,
             resume_codex_chat,
             cancel_codex_execution,
             list_running_codex_sessions,
+            send_to_codex_session,
             get_codex_binary_path,
             set_codex_binary_path,
+            get_codex_config_dir,
+            set_codex_config_dir,
             check_codex_version,
+            diagnose_codex_binary,
             check_codex_login,
             get_codex_default_model,
             set_codex_default_model,
@@ -238,9 +295,11 @@ fn main() {
             login_codex,
             // Gemini provider
             execute_gemini_chat,
+            preview_gemini_command,
             resume_gemini_chat,
             cancel_gemini_execution,
             list_running_gemini_sessions,
+            send_to_gemini_session,
             get_gemini_binary_path,
             set_gemini_binary_path,
             check_gemini_version,
@@ -305,6 +364,7 @@ fn main() {
             get_usage_by_date_range,
             get_usage_details,
             get_session_stats,
+            export_usage_csv,
             // MCP (Model Context Protocol)
             mcp_add,
             mcp_list,
@@ -334,9 +394,116 @@ fn main() {
             // Proxy Settings
             get_proxy_settings,
             save_proxy_settings,
+            // Provider output handling
+            get_redaction_patterns,
+            set_redaction_patterns,
+            get_recent_logs,
+            get_providers_overview,
+            ensure_provider_ready,
+            run_provider_command,
+            get_last_provider_error,
+            get_provider_config_paths,
+            get_provider_capabilities,
+            get_event_protocol_version,
+            get_event_namespace,
+            get_frequent_models,
+            warm_providers,
+            get_warm_providers_on_startup,
+            set_warm_providers_on_startup,
+            get_probe_timeout,
+            set_probe_timeout,
+            get_websocket_settings,
+            set_websocket_settings,
+            list_profiles,
+            create_profile,
+            delete_profile,
+            switch_profile,
+            get_profile_settings,
+            set_profile_settings,
+            export_settings,
+            import_settings,
+            get_inherit_full_env,
+            set_inherit_full_env,
+            get_compress_transcripts,
+            set_compress_transcripts,
+            get_auto_accept_downloads,
+            set_auto_accept_downloads,
+            get_persist_stderr,
+            set_persist_stderr,
+            get_record_delimiter,
+            set_record_delimiter,
+            get_use_login_shell,
+            set_use_login_shell,
+            compact_old_sessions,
+            tail_session_transcript,
+            list_all_sessions,
+            diff_sessions,
+            summarize_session,
+            export_session_html,
+            replay_session,
+            get_normalization_rules,
+            set_normalization_rules,
+            get_model_arg_rules,
+            set_model_arg_rules,
+            // Session metadata
+            tag_session,
+            get_sessions_by_tag,
+            merge_sessions,
+            regenerate_last,
+            restart_session_with_model,
+            truncate_session,
+            chain_session,
+            recover_partial_sessions,
+            preflight_storage,
+            execute_multi_chat,
+            cancel_multi_chat,
+            cancel_by_token,
+            cancel_project_sessions,
+            get_compaction_threshold,
+            set_compaction_threshold,
+            get_max_output_lines,
+            set_max_output_lines,
+            pause_session_output,
+            resume_session_output,
+            detach_session,
+            reattach_session,
+            get_project_env,
+            set_project_env,
+            get_reasoning_effort,
+            set_reasoning_effort,
+            get_project_model,
+            set_project_model,
+            get_global_default_model,
+            set_global_default_model,
+            resolve_effective_model,
             // Unified history
             unify_provider_histories,
+            unify_all_projects,
+            cancel_unify_all,
+            validate_jsonl,
+            repair_jsonl,
+            import_transcript,
+            relabel_provider,
+            list_claude_sessions,
+            get_unified_output_dir,
+            set_unified_output_dir,
+            get_history_walk_settings,
+            set_history_walk_settings,
+            add_history_source,
+            list_history_sources,
+            remove_history_source,
+            count_provider_messages,
+            estimate_unify_size,
         ])
-        .run(tauri::generate_context!())
-        .expect("error while running tauri application");
+        .build(tauri::generate_context!())
+        .expect("error while building tauri application")
+        .run(|app, event| {
+            // Don't leave provider CLIs running (and billing) after the app is gone
+            if let tauri::RunEvent::Exit = event {
+                let stopped = tauri::async_runtime::block_on(provider::shutdown::terminate_all(app));
+                if stopped > 0 {
+                    log::info!("Stopped {} process(es) on exit", stopped);
+                }
+            }
+        });
 }
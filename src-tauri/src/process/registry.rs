@@ -31,6 +31,9 @@ pub struct ProcessInfo {
     pub project_path: String,
     pub task: String,
     pub model: String,
+    /// User-attached labels (e.g. "bugfix", "spike")
+    #[serde(default)]
+    pub tags: Vec<String>,
 }
 
 /// Information about a running process with handle
@@ -83,6 +86,7 @@ impl ProcessRegistry {
             project_path,
             task,
             model,
+            tags: Vec::new(),
         };
 
         self.register_process_internal(run_id, process_info, child)
@@ -107,6 +111,7 @@ impl ProcessRegistry {
             project_path,
             task,
             model,
+            tags: Vec::new(),
         };
 
         // For sidecar processes, we register without the child handle since it's managed differently
@@ -141,6 +146,7 @@ impl ProcessRegistry {
             project_path,
             task,
             model,
+            tags: Vec::new(),
         };
 
         // Register without child - Claude sessions use ClaudeProcessState for process management
@@ -238,6 +244,7 @@ impl ProcessRegistry {
             project_path,
             task,
             model,
+            tags: Vec::new(),
         };
 
         let mut processes = self.processes.lock().map_err(|e| e.to_string())?;
@@ -270,6 +277,50 @@ impl ProcessRegistry {
             .collect())
     }
 
+    /// Replace the tags on a running Claude or chat session.
+    /// Returns false if no running session has that ID.
+    pub fn set_session_tags(&self, session_id: &str, tags: Vec<String>) -> Result<bool, String> {
+        let mut processes = self.processes.lock().map_err(|e| e.to_string())?;
+        let handle = processes.values_mut().find(|handle| match &handle.info.process_type {
+            ProcessType::ClaudeSession { session_id: sid } => sid == session_id,
+            ProcessType::ChatSession { session_id: sid, .. } => sid == session_id,
+            _ => false,
+        });
+        match handle {
+            Some(handle) => {
+                handle.info.tags = tags;
+                Ok(true)
+            }
+            None => Ok(false),
+        }
+    }
+
+    /// Record the pid of a process that was respawned under the same run ID
+    /// (e.g. a retried chat session). Returns false if the run is gone.
+    pub fn set_pid(&self, run_id: i64, pid: u32) -> Result<bool, String> {
+        let mut processes = self.processes.lock().map_err(|e| e.to_string())?;
+        match processes.get_mut(&run_id) {
+            Some(handle) => {
+                handle.info.pid = pid;
+                Ok(true)
+            }
+            None => Ok(false),
+        }
+    }
+
+    /// Find a running Claude or chat session by session ID
+    pub fn find_session(&self, session_id: &str) -> Result<Option<ProcessInfo>, String> {
+        let processes = self.processes.lock().map_err(|e| e.to_string())?;
+        Ok(processes
+            .values()
+            .find(|handle| match &handle.info.process_type {
+                ProcessType::ClaudeSession { session_id: sid } => sid == session_id,
+                ProcessType::ChatSession { session_id: sid, .. } => sid == session_id,
+                _ => false,
+            })
+            .map(|handle| handle.info.clone()))
+    }
+
     /// Unregister a process (called when it completes)
     #[allow(dead_code)]
     pub fn unregister_process(&self, run_id: i64) -> Result<(), String> {
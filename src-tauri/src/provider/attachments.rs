@@ -0,0 +1,29 @@
+use std::path::{Path, PathBuf};
+
+/// Largest file accepted as a prompt attachment
+pub const MAX_ATTACHMENT_BYTES: u64 = 20 * 1024 * 1024;
+
+/// Resolve attachment paths (relative ones against the project) and check
+/// that each is an existing file within the size limit
+pub fn validate(paths: &[String], project_path: &str) -> Result<Vec<PathBuf>, String> {
+    paths
+        .iter()
+        .map(|p| {
+            let path = Path::new(project_path).join(p);
+            let meta = std::fs::metadata(&path)
+                .map_err(|_| format!("Attachment not found: {}", path.display()))?;
+            if !meta.is_file() {
+                return Err(format!("Attachment is not a file: {}", path.display()));
+            }
+            if meta.len() > MAX_ATTACHMENT_BYTES {
+                return Err(format!(
+                    "Attachment {} is {} bytes; the limit is {} bytes",
+                    path.display(),
+                    meta.len(),
+                    MAX_ATTACHMENT_BYTES
+                ));
+            }
+            Ok(path)
+        })
+        .collect()
+}
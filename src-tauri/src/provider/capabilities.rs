@@ -0,0 +1,235 @@
+use chrono::{DateTime, Utc};
+use rusqlite::Connection;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::sync::{Mutex, OnceLock};
+use std::time::{Duration, SystemTime};
+use tauri::AppHandle;
+
+use super::settings::open_db;
+
+/// How long `--help` may take before the probe gives up
+const PROBE_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// Flags known to take file/image attachments, in order of preference
+const ATTACH_FLAGS: &[&str] = &["--image", "--attach", "--file"];
+
+/// Flags known to take a thinking token budget
+pub(crate) const THINKING_FLAGS: &[&str] = &["--thinking-budget", "--thinking_budget"];
+
+/// Flags the chat commands use for structured output (Codex, then Gemini)
+const STRUCTURED_FLAGS: &[&str] = &["--output-schema", "--output-format"];
+
+/// How long a stored capability matrix is trusted; CLIs are upgraded in place
+const MATRIX_TTL_HOURS: i64 = 24;
+
+/// What a provider CLI supports, derived from its `--help` output
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct CliCapabilities {
+    /// `--help` ran successfully; when false every other field is a guess
+    pub probed: bool,
+    /// The CLI takes the prompt as a positional argument or `-p/--prompt`
+    pub accepts_prompt_arg: bool,
+    /// Long flag used to attach files or images (e.g. `--image`), if any
+    pub attach_flag: Option<String>,
+    /// Long flag taking a thinking token budget, if any
+    pub thinking_flag: Option<String>,
+    /// Long flag used to request structured (JSON) output, if any
+    #[serde(default)]
+    pub structured_flag: Option<String>,
+}
+
+impl CliCapabilities {
+    fn from_help(help: &str) -> Self {
+        let lower = help.to_lowercase();
+        let accepts_prompt_arg = lower.contains("[prompt]")
+            || lower.contains("<prompt>")
+            || lower.contains("--prompt");
+        let find_flag = |flags: &[&str]| {
+            flags
+                .iter()
+                .find(|flag| {
+                    lower
+                        .match_indices(*flag)
+                        .any(|(i, _)| !lower[i + flag.len()..].starts_with(|c: char| c.is_alphanumeric() || c == '-'))
+                })
+                .map(|flag| flag.to_string())
+        };
+        Self {
+            probed: true,
+            accepts_prompt_arg,
+            attach_flag: find_flag(ATTACH_FLAGS),
+            thinking_flag: find_flag(THINKING_FLAGS),
+            structured_flag: find_flag(STRUCTURED_FLAGS),
+        }
+    }
+
+    /// Whether the prompt also needs to be written to stdin.
+    ///
+    /// Only when the probe succeeded and found no way to pass the prompt as
+    /// an argument; otherwise stdin would submit it a second time.
+    pub fn prompt_via_stdin(&self) -> bool {
+        self.probed && !self.accepts_prompt_arg
+    }
+}
+
+/// Probe results by program, with the modification time of the binary they
+/// were read from
+type ProbeCache = HashMap<String, (Option<SystemTime>, CliCapabilities)>;
+
+fn cache() -> &'static Mutex<ProbeCache> {
+    static CACHE: OnceLock<Mutex<ProbeCache>> = OnceLock::new();
+    CACHE.get_or_init(|| Mutex::new(HashMap::new()))
+}
+
+/// Modification time of the binary `program` runs (a path, or a name looked
+/// up on PATH); symlinks are followed so an in-place upgrade changes it
+pub(super) fn binary_mtime(program: &str) -> Option<SystemTime> {
+    let path = crate::binary_path::resolve_executable(program)
+        .or_else(|| crate::binary_path::which(program).map(std::path::PathBuf::from))?;
+    std::fs::metadata(path).and_then(|m| m.modified()).ok()
+}
+
+/// Probe a CLI binary's capabilities, cached per binary path until the
+/// binary changes (e.g. is upgraded or replaced)
+pub async fn probe(program: &str) -> CliCapabilities {
+    let mtime = binary_mtime(program);
+    let cached = cache()
+        .lock()
+        .ok()
+        .and_then(|c| c.get(program).filter(|(seen, _)| *seen == mtime).map(|(_, caps)| caps.clone()));
+    if let Some(caps) = cached {
+        return caps;
+    }
+
+    let mut cmd = tokio::process::Command::new(program);
+    cmd.arg("--help")
+        .stdin(std::process::Stdio::null())
+        .kill_on_drop(true);
+    let caps = match tokio::time::timeout(PROBE_TIMEOUT, cmd.output()).await {
+        Ok(Ok(out)) if out.status.success() => {
+            let mut help = String::from_utf8_lossy(&out.stdout).to_string();
+            help.push_str(&String::from_utf8_lossy(&out.stderr));
+            CliCapabilities::from_help(&help)
+        }
+        _ => {
+            log::warn!("Capability probe failed for {}", program);
+            CliCapabilities::default()
+        }
+    };
+
+    if let Ok(mut c) = cache().lock() {
+        c.insert(program.to_string(), (mtime, caps.clone()));
+    }
+    caps
+}
+
+/// Model metadata that goes into a capability matrix besides the probe
+#[derive(Debug, Clone, Default)]
+pub struct ModelTraits {
+    pub supports_tools: Option<bool>,
+    pub supports_vision: Option<bool>,
+    pub supports_reasoning: Option<bool>,
+    pub context_window: Option<u64>,
+}
+
+/// Which features a provider and model support, for gating them in the UI.
+///
+/// Probe results decide where the CLI has a flag for the feature; model
+/// metadata narrows it down when the model list says more. Fields the CLI
+/// couldn't be asked about are best guesses, see `probed`.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ProviderCapabilities {
+    pub provider: String,
+    pub model: String,
+    /// `--help` of the CLI could be read
+    pub probed: bool,
+    pub attachments: bool,
+    /// Images among the attachments
+    pub vision: bool,
+    pub structured_output: bool,
+    pub tools: bool,
+    pub reasoning_effort: bool,
+    /// Whether the model streamed its last conclusive run, when known
+    pub streams: Option<bool>,
+    pub context_window: Option<u64>,
+    pub checked_at: String,
+}
+
+impl ProviderCapabilities {
+    pub fn derive(provider: &str, model: &str, cli: &CliCapabilities, traits: &ModelTraits) -> Self {
+        // Unprobed CLIs are assumed to have what their builders rely on
+        let has = |flag: &Option<String>| flag.is_some() || !cli.probed;
+        let attachments = has(&cli.attach_flag);
+        let reasoning_effort = match provider {
+            // Codex takes the effort as a config override; only the model can refuse it
+            "codex" => traits.supports_reasoning != Some(false),
+            _ => has(&cli.thinking_flag),
+        };
+        Self {
+            provider: provider.to_string(),
+            model: model.to_string(),
+            probed: cli.probed,
+            attachments,
+            vision: attachments && traits.supports_vision != Some(false),
+            structured_output: has(&cli.structured_flag),
+            // Both CLIs run as agents with tools unless the model says otherwise
+            tools: traits.supports_tools.unwrap_or(true),
+            reasoning_effort,
+            streams: None,
+            context_window: traits.context_window,
+            checked_at: Utc::now().to_rfc3339(),
+        }
+    }
+}
+
+fn ensure_table(conn: &Connection) -> Result<(), String> {
+    conn.execute(
+        "CREATE TABLE IF NOT EXISTS provider_capabilities (
+            provider TEXT NOT NULL,
+            model TEXT NOT NULL,
+            binary TEXT NOT NULL,
+            matrix TEXT NOT NULL,
+            checked_at TEXT NOT NULL,
+            PRIMARY KEY (provider, model)
+        )",
+        [],
+    )
+    .map_err(|e| e.to_string())?;
+    Ok(())
+}
+
+/// Stored matrix for a provider and model, unless it is stale or was taken
+/// with another binary
+pub fn load_matrix(app: &AppHandle, provider: &str, model: &str, binary: &str) -> Option<ProviderCapabilities> {
+    let conn = open_db(app).ok()?;
+    ensure_table(&conn).ok()?;
+    let (stored_binary, matrix, checked_at): (String, String, String) = conn
+        .query_row(
+            "SELECT binary, matrix, checked_at FROM provider_capabilities WHERE provider = ?1 AND model = ?2",
+            rusqlite::params![provider, model],
+            |row| Ok((row.get(0)?, row.get(1)?, row.get(2)?)),
+        )
+        .ok()?;
+    let checked_at = DateTime::parse_from_rfc3339(&checked_at).ok()?;
+    if stored_binary != binary || Utc::now().signed_duration_since(checked_at).num_hours() >= MATRIX_TTL_HOURS {
+        return None;
+    }
+    serde_json::from_str(&matrix).ok()
+}
+
+/// Store a matrix taken with `binary`
+pub fn save_matrix(app: &AppHandle, binary: &str, matrix: &ProviderCapabilities) -> Result<(), String> {
+    let conn = open_db(app)?;
+    ensure_table(&conn)?;
+    let raw = serde_json::to_string(matrix).map_err(|e| e.to_string())?;
+    conn.execute(
+        "INSERT INTO provider_capabilities (provider, model, binary, matrix, checked_at)
+         VALUES (?1, ?2, ?3, ?4, ?5)
+         ON CONFLICT(provider, model) DO UPDATE SET
+            binary = excluded.binary, matrix = excluded.matrix, checked_at = excluded.checked_at",
+        rusqlite::params![matrix.provider, matrix.model, binary, raw, matrix.checked_at],
+    )
+    .map_err(|e| e.to_string())?;
+    Ok(())
+}
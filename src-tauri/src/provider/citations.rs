@@ -0,0 +1,110 @@
+use regex::Regex;
+use serde::Serialize;
+use serde_json::Value;
+use std::sync::OnceLock;
+
+/// Keys under which Gemini puts the sources of a grounded answer
+const SOURCE_KEYS: &[&str] = &["groundingChunks", "grounding_chunks", "citations", "citationSources", "sources"];
+
+/// A web source a grounded answer is based on
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct Citation {
+    /// Page title; the URL when the CLI gave none
+    pub title: String,
+    pub url: String,
+}
+
+/// `[1] Title (https://...)` or `[1] https://...`, as printed under "Sources:"
+fn source_line() -> &'static Regex {
+    static RE: OnceLock<Regex> = OnceLock::new();
+    RE.get_or_init(|| {
+        Regex::new(r"^\s*\[\d+\]\s*(?:(.*?)\s*\(\s*(https?://[^\s)]+)\s*\)|(https?://\S+))\s*$").unwrap()
+    })
+}
+
+fn is_url(s: &str) -> bool {
+    s.starts_with("http://") || s.starts_with("https://")
+}
+
+/// Citation described by one source object (`{web: {uri, title}}`,
+/// `{uri, title}` or `{url, title}`)
+fn from_source(source: &Value) -> Option<Citation> {
+    let source = source.get("web").unwrap_or(source);
+    let url = source
+        .get("uri")
+        .or_else(|| source.get("url"))
+        .and_then(|u| u.as_str())
+        .filter(|u| is_url(u))?;
+    let title = source
+        .get("title")
+        .and_then(|t| t.as_str())
+        .map(str::trim)
+        .filter(|t| !t.is_empty())
+        .unwrap_or(url);
+    Some(Citation { title: title.to_string(), url: url.to_string() })
+}
+
+/// Sources listed anywhere in a JSON value (the grounding metadata sits at
+/// different depths depending on the CLI version)
+fn collect_sources(value: &Value, out: &mut Vec<Citation>) {
+    match value {
+        Value::Object(map) => {
+            for (key, child) in map {
+                match (SOURCE_KEYS.contains(&key.as_str()), child) {
+                    (true, Value::Array(sources)) => out.extend(sources.iter().filter_map(from_source)),
+                    _ => collect_sources(child, out),
+                }
+            }
+        }
+        Value::Array(items) => items.iter().for_each(|item| collect_sources(item, out)),
+        _ => {}
+    }
+}
+
+/// Web sources named by one line of Gemini output: grounding metadata in a
+/// JSON line, or a numbered source line of the plain-text answer. Duplicate
+/// URLs are listed once.
+pub fn extract(line: &str) -> Vec<Citation> {
+    let mut citations = Vec::new();
+    let trimmed = line.trim();
+    if trimmed.starts_with('{') {
+        if let Ok(value) = serde_json::from_str::<Value>(trimmed) {
+            collect_sources(&value, &mut citations);
+        }
+    } else if let Some(caps) = source_line().captures(trimmed) {
+        let (title, url) = match (caps.get(1), caps.get(2), caps.get(3)) {
+            (title, Some(url), _) => (title.map(|t| t.as_str()).unwrap_or(""), url.as_str()),
+            (_, _, Some(url)) => ("", url.as_str()),
+            _ => return citations,
+        };
+        let title = if title.is_empty() { url } else { title };
+        citations.push(Citation { title: title.to_string(), url: url.to_string() });
+    }
+    let mut seen = std::collections::HashSet::new();
+    citations.retain(|c| seen.insert(c.url.clone()));
+    citations
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_extract() {
+        let json = r#"{"response":"Rust 1.80","groundingMetadata":{"groundingChunks":[
+            {"web":{"uri":"https://blog.rust-lang.org/","title":"Rust Blog"}},
+            {"web":{"uri":"https://blog.rust-lang.org/"}},
+            {"web":{"uri":"ftp://example.com"}}]}}"#;
+        assert_eq!(
+            extract(&json.replace('\n', "")),
+            vec![Citation { title: "Rust Blog".into(), url: "https://blog.rust-lang.org/".into() }]
+        );
+        assert_eq!(
+            extract("[1] The Rust Book (https://doc.rust-lang.org/book/)"),
+            vec![Citation { title: "The Rust Book".into(), url: "https://doc.rust-lang.org/book/".into() }]
+        );
+        assert_eq!(extract("  [2] https://crates.io")[0].title, "https://crates.io");
+        assert!(extract("Rust [1] is a language").is_empty());
+        assert!(extract(r#"{"response":"no sources"}"#).is_empty());
+    }
+}
@@ -0,0 +1,203 @@
+use log::Level;
+use serde_json::Value;
+use std::path::Path;
+use std::time::Duration;
+use tauri::AppHandle;
+use tokio::process::Command;
+
+use super::logs::session_event;
+use super::settings::read_setting;
+
+/// Settings key for the estimated token count above which resumes are compacted
+pub const COMPACTION_THRESHOLD_KEY: &str = "compaction_token_threshold";
+/// Default threshold; well under the smallest context window in common use
+pub const DEFAULT_COMPACTION_THRESHOLD: usize = 24_000;
+/// Most recent turns always kept verbatim
+const KEEP_RECENT_TURNS: usize = 6;
+/// How long the summarization call may run
+const SUMMARY_TIMEOUT: Duration = Duration::from_secs(120);
+
+/// A user or assistant turn rebuilt from a transcript
+#[derive(Debug, Clone, PartialEq)]
+pub(super) struct Turn {
+    pub(super) role: String,
+    pub(super) text: String,
+}
+
+/// Rough token estimate (~4 characters per token)
+fn estimate_tokens(text: &str) -> usize {
+    text.chars().count().div_ceil(4)
+}
+
+/// Text parts of a persisted message, joined by newlines
+pub(super) fn message_text(message: &Value) -> Option<String> {
+    let content = message.pointer("/message/content")?.as_array()?;
+    let text: Vec<&str> = content
+        .iter()
+        .filter(|c| c.get("type").and_then(|t| t.as_str()) == Some("text"))
+        .filter_map(|c| c.get("text").and_then(|t| t.as_str()))
+        .collect();
+    Some(text.join("\n"))
+}
+
+/// Collapse persisted messages into turns; consecutive assistant lines are one turn
+pub(super) fn turns_from_transcript(messages: &[Value]) -> Vec<Turn> {
+    let mut turns: Vec<Turn> = Vec::new();
+    for message in messages {
+        if super::transcript::is_superseded(message) {
+            continue;
+        }
+        let role = match message.get("type").and_then(|t| t.as_str()) {
+            Some(role @ ("user" | "assistant")) => role,
+            _ => continue,
+        };
+        let text = match message_text(message) {
+            Some(text) => text,
+            None => continue,
+        };
+        match turns.last_mut() {
+            Some(last) if last.role == role => {
+                last.text.push('\n');
+                last.text.push_str(&text);
+            }
+            _ => turns.push(Turn { role: role.to_string(), text }),
+        }
+    }
+    turns
+}
+
+pub(super) fn render(turns: &[Turn]) -> String {
+    turns
+        .iter()
+        .map(|t| format!("{}: {}", if t.role == "user" { "User" } else { "Assistant" }, t.text))
+        .collect::<Vec<_>>()
+        .join("\n\n")
+}
+
+/// Configured compaction threshold in estimated tokens
+pub fn threshold(app: &AppHandle) -> usize {
+    read_setting(app, COMPACTION_THRESHOLD_KEY)
+        .and_then(|v| v.parse().ok())
+        .unwrap_or(DEFAULT_COMPACTION_THRESHOLD)
+}
+
+/// Run `cmd` with `request` appended as the prompt and return its trimmed
+/// answer; an empty answer is an error
+pub(super) async fn ask(mut cmd: Command, dir: &Path, request: String, timeout: Duration) -> Result<String, String> {
+    cmd.arg(request)
+        .current_dir(dir)
+        .stdin(std::process::Stdio::null())
+        .kill_on_drop(true);
+    let output = tokio::time::timeout(timeout, cmd.output())
+        .await
+        .map_err(|_| "the provider call timed out".to_string())?
+        .map_err(|e| e.to_string())?;
+    if !output.status.success() {
+        return Err(String::from_utf8_lossy(&output.stderr).trim().to_string());
+    }
+    let answer = String::from_utf8_lossy(&output.stdout).trim().to_string();
+    if answer.is_empty() {
+        return Err("empty answer".to_string());
+    }
+    Ok(answer)
+}
+
+/// Ask the provider for a summary by appending a summarization prompt to `cmd`
+async fn summarize(cmd: Command, project_path: &str, history: &str) -> Result<String, String> {
+    let request = format!(
+        "Summarize the following conversation so it can be continued later. Keep decisions, \
+         open questions, file names and code identifiers. Reply with the summary only.\n\n{}",
+        history
+    );
+    ask(cmd, Path::new(project_path), request, SUMMARY_TIMEOUT).await
+}
+
+/// Build the prompt for resuming a session from its persisted transcript.
+///
+/// The provider CLIs keep no memory between runs, so earlier turns are sent
+/// along with the new message. When they are estimated to exceed the
+/// configured threshold, everything but the last few turns is replaced by a
+/// provider-generated summary. `summary_cmd` builds the CLI invocation
+/// (binary, env and model) used for that summary. Without a transcript the
+/// prompt is returned unchanged.
+pub async fn resume_prompt(
+    app: &AppHandle,
+    provider: &str,
+    project_path: &str,
+    session_id: &str,
+    prompt: &str,
+    summary_cmd: impl FnOnce() -> Command,
+) -> String {
+    let messages = match super::transcript::read_transcript(project_path, session_id) {
+        Ok(messages) => messages,
+        Err(_) => return prompt.to_string(),
+    };
+    let turns = turns_from_transcript(&messages);
+    if turns.is_empty() {
+        return prompt.to_string();
+    }
+
+    let full = render(&turns);
+    let limit = threshold(app);
+    let context = if estimate_tokens(&full) <= limit || turns.len() <= KEEP_RECENT_TURNS {
+        full
+    } else {
+        let (older, recent) = turns.split_at(turns.len() - KEEP_RECENT_TURNS);
+        let summary = match summarize(summary_cmd(), project_path, &render(older)).await {
+            Ok(summary) => summary,
+            Err(e) => {
+                log::warn!("Summarizing {} session {} failed: {}", provider, session_id, e);
+                format!("({} earlier turns omitted)", older.len())
+            }
+        };
+        session_event(
+            Level::Info,
+            "compact",
+            provider,
+            Some(session_id),
+            None,
+            format!("compacted {} turns (~{} tokens, limit {})", older.len(), estimate_tokens(&full), limit),
+        );
+        format!("Summary of earlier conversation:\n{}\n\n{}", summary, render(recent))
+    };
+
+    format!(
+        "Conversation so far:\n\n{}\n\nContinue the conversation. New message:\n\n{}",
+        context, prompt
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn line(kind: &str, text: &str) -> Value {
+        json!({"type": kind, "message": {"content": [{"type": "text", "text": text}]}})
+    }
+
+    #[test]
+    fn test_turns_from_transcript() {
+        let messages = vec![
+            json!({"type": "system", "subtype": "init"}),
+            line("user", "hi"),
+            line("assistant", "hello"),
+            line("assistant", "there"),
+            line("user", "bye"),
+        ];
+        let turns = turns_from_transcript(&messages);
+        assert_eq!(turns.len(), 3);
+        assert_eq!(turns[1].text, "hello\nthere");
+        assert_eq!(render(&turns[..1]), "User: hi");
+    }
+
+    #[test]
+    fn test_turns_skip_superseded() {
+        let mut old = line("assistant", "first try");
+        old["superseded"] = json!(true);
+        let messages = vec![line("user", "hi"), old, line("assistant", "second try")];
+        let turns = turns_from_transcript(&messages);
+        assert_eq!(turns.len(), 2);
+        assert_eq!(turns[1].text, "second try");
+    }
+}
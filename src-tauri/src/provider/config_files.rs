@@ -0,0 +1,213 @@
+use serde::Serialize;
+use std::fs;
+use std::path::{Path, PathBuf};
+use tauri::AppHandle;
+
+use super::profiles;
+
+/// Settings key of the Codex config directory pinned with `set_codex_config_dir`
+pub const CODEX_CONFIG_DIR_KEY: &str = "codex_config_dir";
+
+/// Directories searched for Codex CLI configuration
+pub const CODEX_CONFIG_ROOTS: &[&str] = &[
+    "~/.config/openai",
+    "~/.config/codex",
+    "~/.openai",
+    "~/Library/Application Support/OpenAI",
+];
+
+/// Directories searched for Gemini CLI configuration
+pub const GEMINI_CONFIG_ROOTS: &[&str] = &["~/.config/gemini", "~/.gemini", "~/Library/Application Support/Gemini"];
+
+/// How deep below each root config files are looked for
+const CONFIG_SEARCH_DEPTH: usize = 2;
+
+/// Keys that may hold the default model, most specific first
+const MODEL_KEYS: &[&str] = &["default_model", "model", "chat_model"];
+
+/// A configuration file found under a provider's config roots
+#[derive(Debug, Clone, Serialize)]
+pub struct ConfigFile {
+    pub path: String,
+    /// `json`, `toml` or `yaml` when the file parsed as one of them
+    pub format: Option<String>,
+    pub parseable: bool,
+    pub default_model: Option<String>,
+    /// This is the file the default model is taken from
+    pub selected: bool,
+}
+
+/// Config roots for a provider
+pub fn roots(provider: &str) -> Result<&'static [&'static str], String> {
+    match provider {
+        "codex" => Ok(CODEX_CONFIG_ROOTS),
+        "gemini" => Ok(GEMINI_CONFIG_ROOTS),
+        other => Err(format!("Unknown provider: {}", other)),
+    }
+}
+
+/// Config directory pinned for a provider in the active profile, if any
+pub fn pinned_dir(app: &AppHandle, provider: &str) -> Option<String> {
+    match provider {
+        "codex" => profiles::read_profile_setting(app, CODEX_CONFIG_DIR_KEY).filter(|d| !d.trim().is_empty()),
+        _ => None,
+    }
+}
+
+/// Normalize a config directory before it is pinned: expand a leading `~`,
+/// resolve it, and check that it is an existing directory
+pub fn canonicalize_dir(path: &str) -> Result<String, String> {
+    let trimmed = path.trim();
+    if trimmed.is_empty() {
+        return Err("Config directory is empty".to_string());
+    }
+    let expanded = if trimmed == "~" {
+        dirs::home_dir().ok_or("Cannot expand ~: no home directory")?
+    } else {
+        expand_tilde(trimmed)
+    };
+    let canonical = fs::canonicalize(&expanded)
+        .map_err(|e| format!("Cannot resolve {}: {}", expanded.display(), e))?;
+    if !canonical.is_dir() {
+        return Err(format!("{} is not a directory", canonical.display()));
+    }
+    Ok(crate::binary_path::strip_verbatim(canonical).to_string_lossy().to_string())
+}
+
+fn expand_tilde(p: &str) -> PathBuf {
+    if let Some(stripped) = p.strip_prefix("~/") {
+        if let Some(home) = dirs::home_dir() {
+            return home.join(stripped);
+        }
+    }
+    PathBuf::from(p)
+}
+
+/// Every file under the roots, in search order
+fn walk(roots: &[&str]) -> Vec<PathBuf> {
+    let mut files = Vec::new();
+    for root in roots {
+        let path = expand_tilde(root);
+        if !path.exists() {
+            continue;
+        }
+        for entry in walkdir::WalkDir::new(path).max_depth(CONFIG_SEARCH_DEPTH).into_iter().flatten() {
+            if entry.path().is_file() {
+                files.push(entry.into_path());
+            }
+        }
+    }
+    files
+}
+
+/// Value of `key` in json/yaml/toml-ish `key: value` or `key = value` text
+pub fn extract_model_value(content: &str, key: &str) -> Option<String> {
+    let key = regex::escape(key);
+    let patterns = [
+        format!(r#""{}"\s*[:=]\s*"([^"]+)""#, key),
+        format!(r#"{}\s*[:=]\s*"([^"]+)""#, key),
+        format!(r#"{}\s*[:=]\s*([A-Za-z0-9._-]+)"#, key),
+    ];
+    for pat in patterns {
+        if let Ok(re) = regex::Regex::new(&pat) {
+            if let Some(m) = re.captures(content).and_then(|c| c.get(1)) {
+                return Some(m.as_str().to_string());
+            }
+        }
+    }
+    None
+}
+
+fn default_model_in(content: &str) -> Option<String> {
+    MODEL_KEYS.iter().find_map(|key| extract_model_value(content, key))
+}
+
+/// Whether a requested model means "whatever the default is" rather than a
+/// model name the CLI would understand
+pub fn is_default_model(model: &str) -> bool {
+    let model = model.trim();
+    model.is_empty() || model.eq_ignore_ascii_case("default") || model.eq_ignore_ascii_case("auto")
+}
+
+/// Default model from the first config file that names one
+pub fn search_default_model(roots: &[&str]) -> Option<String> {
+    walk(roots)
+        .into_iter()
+        .filter_map(|p| fs::read_to_string(p).ok())
+        .find_map(|data| default_model_in(&data))
+}
+
+/// Default model of a provider: the pinned config directory is searched
+/// first, the guessed roots only when it doesn't name one
+pub fn find_default_model(app: &AppHandle, provider: &str) -> Result<Option<String>, String> {
+    let roots = roots(provider)?;
+    Ok(pinned_dir(app, provider)
+        .and_then(|dir| search_default_model(&[dir.as_str()]))
+        .or_else(|| search_default_model(roots)))
+}
+
+/// Format the content parses as, trying the one its extension suggests first
+fn detect_format(path: &Path, content: &str) -> Option<&'static str> {
+    let parses = |format: &str| match format {
+        "json" => serde_json::from_str::<serde_json::Value>(content).is_ok(),
+        "toml" => toml::from_str::<toml::Value>(content).is_ok(),
+        _ => serde_yaml::from_str::<serde_yaml::Value>(content).is_ok(),
+    };
+    let hinted = match path.extension().and_then(|e| e.to_str()) {
+        Some("json") => Some("json"),
+        Some("toml") => Some("toml"),
+        Some("yaml") | Some("yml") => Some("yaml"),
+        _ => None,
+    };
+    match hinted {
+        Some(format) => parses(format).then_some(format),
+        // YAML accepts almost any text, so only trust it for structured content
+        None => ["json", "toml"].into_iter().find(|f| parses(f)),
+    }
+}
+
+/// Config files under a provider's roots, with the file the default model
+/// is read from marked as `selected`
+pub fn discover(roots: &[&str]) -> Vec<ConfigFile> {
+    let mut files = Vec::new();
+    let mut selected_found = false;
+    for path in walk(roots) {
+        // Binary files and caches aren't configs
+        let content = match fs::read_to_string(&path) {
+            Ok(content) => content,
+            Err(_) => continue,
+        };
+        let format = detect_format(&path, &content);
+        let default_model = default_model_in(&content);
+        let is_config_ext = matches!(
+            path.extension().and_then(|e| e.to_str()),
+            Some("json") | Some("toml") | Some("yaml") | Some("yml")
+        );
+        if !is_config_ext && format.is_none() && default_model.is_none() {
+            continue;
+        }
+        let selected = !selected_found && default_model.is_some();
+        selected_found |= selected;
+        files.push(ConfigFile {
+            path: path.to_string_lossy().to_string(),
+            format: format.map(|f| f.to_string()),
+            parseable: format.is_some(),
+            default_model,
+            selected,
+        });
+    }
+    files
+}
+
+/// Config files of a provider: those in its pinned directory, or under the
+/// guessed roots when nothing is pinned or the pinned directory has none
+pub fn discover_for(app: &AppHandle, provider: &str) -> Result<Vec<ConfigFile>, String> {
+    let roots = roots(provider)?;
+    if let Some(dir) = pinned_dir(app, provider) {
+        let files = discover(&[dir.as_str()]);
+        if !files.is_empty() {
+            return Ok(files);
+        }
+    }
+    Ok(discover(roots))
+}
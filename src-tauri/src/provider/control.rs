@@ -0,0 +1,445 @@
+use serde_json::Value;
+use std::collections::{HashMap, VecDeque};
+use std::sync::atomic::{AtomicBool, AtomicU32, AtomicU64, Ordering};
+use std::sync::{Arc, Mutex, OnceLock};
+use tauri::{AppHandle, Emitter};
+use tokio::io::AsyncWriteExt;
+use tokio::process::{Child, ChildStdin};
+
+use super::events::event_name;
+
+/// Stdin of an interactive session, shared with the task writing the prompt
+pub type SharedStdin = Arc<tokio::sync::Mutex<Option<ChildStdin>>>;
+
+/// Events kept per session while its output is paused; oldest are dropped beyond this
+const MAX_PAUSED_EVENTS: usize = 10_000;
+
+/// Returned to the frontend when a session starts.
+///
+/// `cancel_token` is an opaque capability: holding it is enough to cancel the
+/// session through `cancel_by_token`, without knowing its provider.
+#[derive(Debug, Clone, serde::Serialize)]
+pub struct SessionHandle {
+    pub session_id: String,
+    pub cancel_token: String,
+}
+
+/// Live controls for a running provider session
+pub struct SessionControl {
+    pub provider: String,
+    pub session_id: String,
+    /// Random per-run token handed out in the session's `SessionHandle`
+    cancel_token: String,
+    /// Set for sessions started together by `execute_multi_chat`
+    pub group_id: Option<String>,
+    /// Child of the current attempt; each session owns its own, so sessions
+    /// of the same provider can run side by side. `wait` takes it out while
+    /// awaiting its exit.
+    process: tokio::sync::Mutex<Option<Child>>,
+    /// Set while `wait` holds the child outside the slot
+    awaited: AtomicBool,
+    /// Set once the session was killed; a killed session is never retried
+    killed: AtomicBool,
+    /// Set once the runner tore the session down and emitted its completion
+    finished: AtomicBool,
+    /// Wakes `wait`, retry backoffs and `finished` waiters when the session is
+    /// killed, cancelled or torn down
+    signal: tokio::sync::Notify,
+    /// Pid of the current attempt (changes when a run is retried)
+    pid: AtomicU32,
+    /// Stdin of the current attempt when the session is interactive
+    stdin: Mutex<Option<SharedStdin>>,
+    paused: AtomicBool,
+    /// Set while no UI shows the session: events are dropped instead of
+    /// emitted, the transcript still gets every line
+    detached: AtomicBool,
+    /// Set when the user cancelled, so completion isn't reported as a normal finish
+    cancelled: AtomicBool,
+    /// Events held back while paused, flushed in order on resume
+    pending: Mutex<VecDeque<(String, Value)>>,
+    /// `output` events actually emitted, reported with completion so the UI
+    /// can wait for the last lines instead of guessing with a delay
+    output_events: AtomicU64,
+    /// Last `seq` handed out; shared by the stdout and stderr readers so
+    /// events can be put back in generation order
+    seq: AtomicU64,
+}
+
+impl SessionControl {
+    fn new(
+        provider: &str,
+        session_id: &str,
+        group_id: Option<String>,
+        child: Child,
+    ) -> Self {
+        Self {
+            provider: provider.to_string(),
+            session_id: session_id.to_string(),
+            cancel_token: uuid::Uuid::new_v4().to_string(),
+            group_id,
+            pid: AtomicU32::new(child.id().unwrap_or_default()),
+            process: tokio::sync::Mutex::new(Some(child)),
+            awaited: AtomicBool::new(false),
+            killed: AtomicBool::new(false),
+            finished: AtomicBool::new(false),
+            signal: tokio::sync::Notify::new(),
+            stdin: Mutex::new(None),
+            paused: AtomicBool::new(false),
+            detached: AtomicBool::new(false),
+            cancelled: AtomicBool::new(false),
+            pending: Mutex::new(VecDeque::new()),
+            output_events: AtomicU64::new(0),
+            seq: AtomicU64::new(0),
+        }
+    }
+
+    /// Emit on both the session-specific and the generic channel
+    fn emit_now(&self, app: &AppHandle, kind: &str, payload: &Value) {
+        let _ = app.emit(
+            &event_name(&format!("{}-{}:{}", self.provider, kind, self.session_id)),
+            payload,
+        );
+        let _ = app.emit(&event_name(&format!("{}-{}", self.provider, kind)), payload);
+        super::websocket::publish(&self.provider, kind, &self.session_id, payload);
+        if kind == "output" {
+            self.output_events.fetch_add(1, Ordering::AcqRel);
+        }
+    }
+
+    pub fn handle(&self) -> SessionHandle {
+        SessionHandle {
+            session_id: self.session_id.clone(),
+            cancel_token: self.cancel_token.clone(),
+        }
+    }
+
+    /// Next per-session sequence number (starting at 1)
+    pub fn next_seq(&self) -> u64 {
+        self.seq.fetch_add(1, Ordering::AcqRel) + 1
+    }
+
+    /// Number of `output` events emitted so far
+    pub fn output_events(&self) -> u64 {
+        self.output_events.load(Ordering::Acquire)
+    }
+
+    /// Emit an event, or hold it back while the session output is paused
+    pub fn emit(&self, app: &AppHandle, kind: &str, payload: Value) {
+        if self.detached.load(Ordering::Acquire) {
+            // Relay clients aren't tied to the closed tab
+            super::websocket::publish(&self.provider, kind, &self.session_id, &payload);
+            return;
+        }
+        if !self.paused.load(Ordering::Acquire) {
+            self.emit_now(app, kind, &payload);
+            return;
+        }
+        let mut pending = match self.pending.lock() {
+            Ok(pending) => pending,
+            Err(_) => return,
+        };
+        // Re-check under the lock: a resume may have flushed in between
+        if !self.paused.load(Ordering::Acquire) {
+            self.emit_now(app, kind, &payload);
+            return;
+        }
+        if pending.len() == MAX_PAUSED_EVENTS {
+            pending.pop_front();
+        }
+        pending.push_back((kind.to_string(), payload));
+    }
+
+    pub fn pid(&self) -> u32 {
+        self.pid.load(Ordering::Acquire)
+    }
+
+    /// Track the child of a new attempt (a retry). A session cancelled or
+    /// killed before the new child got here has it killed right away, since
+    /// the cancel only reached the previous attempt.
+    pub async fn set_child(&self, mut child: Child) {
+        let mut guard = self.process.lock().await;
+        self.pid.store(child.id().unwrap_or_default(), Ordering::Release);
+        if self.is_cancelled() || self.killed.load(Ordering::Acquire) {
+            let _ = child.start_kill();
+        }
+        *guard = Some(child);
+    }
+
+    /// Wait for the current attempt's child to exit.
+    ///
+    /// The child is taken out of the slot first so `kill` never waits behind
+    /// this; a kill during the wait is signalled and carried out here.
+    /// Returns `None` when the session was killed or waiting failed.
+    pub async fn wait(&self) -> Option<std::process::ExitStatus> {
+        let mut child = {
+            let mut guard = self.process.lock().await;
+            let child = guard.take()?;
+            self.awaited.store(true, Ordering::Release);
+            child
+        };
+        let status = tokio::select! {
+            status = child.wait() => status.ok(),
+            _ = self.killed() => {
+                let _ = child.start_kill();
+                let _ = child.wait().await;
+                None
+            }
+        };
+        self.awaited.store(false, Ordering::Release);
+        status
+    }
+
+    /// Resolve once `done` holds, re-checked whenever the session is killed
+    /// or cancelled
+    async fn until(&self, done: impl Fn(&Self) -> bool) {
+        loop {
+            let notified = self.signal.notified();
+            tokio::pin!(notified);
+            // Register before checking so a signal in between isn't missed
+            notified.as_mut().enable();
+            if done(self) {
+                return;
+            }
+            notified.await;
+        }
+    }
+
+    /// Resolves once the session was killed
+    pub async fn killed(&self) {
+        self.until(|c| c.killed.load(Ordering::Acquire)).await
+    }
+
+    /// Resolves once the session was killed or cancelled, e.g. to cut a retry
+    /// backoff short
+    pub async fn stopped(&self) {
+        self.until(|c| c.killed.load(Ordering::Acquire) || c.is_cancelled()).await
+    }
+
+    /// Resolves once the runner has finished the session: transcript closed,
+    /// controls removed and completion emitted
+    pub async fn finished(&self) {
+        self.until(|c| c.finished.load(Ordering::Acquire)).await
+    }
+
+    /// Called by the runner as the last step of tearing the session down
+    pub fn mark_finished(&self) {
+        self.finished.store(true, Ordering::Release);
+        self.signal.notify_waiters();
+    }
+
+    pub fn set_stdin(&self, stdin: Option<SharedStdin>) {
+        if let Ok(mut slot) = self.stdin.lock() {
+            *slot = stdin;
+        }
+    }
+
+    pub fn accepts_input(&self) -> bool {
+        self.stdin.lock().map(|s| s.is_some()).unwrap_or(false)
+    }
+
+    /// Write a line to the CLI's stdin (interactive sessions only)
+    pub async fn write_stdin(&self, text: &str) -> Result<(), String> {
+        let shared = self
+            .stdin
+            .lock()
+            .ok()
+            .and_then(|s| s.clone())
+            .ok_or_else(|| format!("Session {} was not started as interactive", self.session_id))?;
+        let mut guard = shared.lock().await;
+        let stdin = guard
+            .as_mut()
+            .ok_or_else(|| format!("Stdin of session {} is closed", self.session_id))?;
+        let mut line = text.to_string();
+        if !line.ends_with('\n') {
+            line.push('\n');
+        }
+        stdin.write_all(line.as_bytes()).await.map_err(|e| e.to_string())?;
+        stdin.flush().await.map_err(|e| e.to_string())
+    }
+
+    /// Kill this session's child if it is still running; the session won't
+    /// start another attempt afterwards.
+    ///
+    /// Returns whether a process was killed.
+    pub async fn kill(&self) -> bool {
+        self.killed.store(true, Ordering::Release);
+        self.signal.notify_waiters();
+        let mut guard = self.process.lock().await;
+        match guard.take() {
+            Some(mut child) => child.start_kill().is_ok(),
+            // `wait` holds the child and kills it on the signal
+            None => self.awaited.load(Ordering::Acquire),
+        }
+    }
+
+    pub fn is_cancelled(&self) -> bool {
+        self.cancelled.load(Ordering::Acquire)
+    }
+
+    /// Mark the session as cancelled by the user and kill its process
+    pub async fn cancel(&self) -> bool {
+        self.cancelled.store(true, Ordering::Release);
+        self.kill().await
+    }
+
+    /// Mark as cancelled when the process is being killed by someone else
+    pub fn mark_cancelled(&self) {
+        self.cancelled.store(true, Ordering::Release);
+        self.signal.notify_waiters();
+    }
+
+    pub fn pause(&self) {
+        self.paused.store(true, Ordering::Release);
+    }
+
+    pub fn is_paused(&self) -> bool {
+        self.paused.load(Ordering::Acquire)
+    }
+
+    /// Stop emitting to the app, e.g. because the tab showing the session was
+    /// closed; the process keeps running and its transcript keeps growing
+    pub fn detach(&self) {
+        self.detached.store(true, Ordering::Release);
+    }
+
+    pub fn is_detached(&self) -> bool {
+        self.detached.load(Ordering::Acquire)
+    }
+
+    /// Resume emitting after `detach`, starting with the transcript tail.
+    ///
+    /// Live events are held back while `read_tail` runs, then the tail is
+    /// emitted as replayed `output` events followed by the held-back events,
+    /// minus output already in the tail (matched by `seq`). A session paused
+    /// before it was detached stays paused. Returns the number of replayed
+    /// messages.
+    pub fn reattach<F>(&self, app: &AppHandle, read_tail: F) -> Result<usize, String>
+    where
+        F: FnOnce() -> Result<Vec<Value>, String>,
+    {
+        let was_paused = self.paused.swap(true, Ordering::AcqRel);
+        self.detached.store(false, Ordering::Release);
+        let tail = match read_tail() {
+            Ok(tail) => tail,
+            Err(e) => {
+                self.detached.store(true, Ordering::Release);
+                self.paused.store(was_paused, Ordering::Release);
+                return Err(e);
+            }
+        };
+        let mut pending = match self.pending.lock() {
+            Ok(pending) => pending,
+            Err(_) => return Err(format!("Event buffer of session {} is poisoned", self.session_id)),
+        };
+        // The UI counts outputs from here on
+        self.output_events.store(0, Ordering::Release);
+        let replayed = tail.len();
+        let mut last_seq = 0;
+        for mut message in tail {
+            last_seq = last_seq.max(message.get("seq").and_then(|s| s.as_u64()).unwrap_or(0));
+            if let Value::Object(map) = &mut message {
+                map.insert("replay".to_string(), Value::Bool(true));
+            }
+            // Output payloads are JSON strings, as when streaming live
+            self.emit_now(app, "output", &Value::String(message.to_string()));
+        }
+        pending.retain(|(kind, payload)| {
+            kind != "output" || output_seq(payload).map_or(true, |seq| seq > last_seq)
+        });
+        if !was_paused {
+            for (kind, payload) in pending.drain(..) {
+                self.emit_now(app, &kind, &payload);
+            }
+            self.paused.store(false, Ordering::Release);
+        }
+        Ok(replayed)
+    }
+
+    /// Flush held-back events in order, then let new events through.
+    ///
+    /// The flag is cleared while the buffer lock is held so no live event can
+    /// overtake a buffered one.
+    pub fn resume(&self, app: &AppHandle) -> usize {
+        let mut pending = match self.pending.lock() {
+            Ok(pending) => pending,
+            Err(_) => return 0,
+        };
+        let flushed = pending.len();
+        for (kind, payload) in pending.drain(..) {
+            self.emit_now(app, &kind, &payload);
+        }
+        self.paused.store(false, Ordering::Release);
+        flushed
+    }
+}
+
+/// `seq` of an `output` payload (a JSON string holding the message)
+fn output_seq(payload: &Value) -> Option<u64> {
+    let message: Value = serde_json::from_str(payload.as_str()?).ok()?;
+    message.get("seq")?.as_u64()
+}
+
+fn controls() -> &'static Mutex<HashMap<String, Arc<SessionControl>>> {
+    static CONTROLS: OnceLock<Mutex<HashMap<String, Arc<SessionControl>>>> = OnceLock::new();
+    CONTROLS.get_or_init(|| Mutex::new(HashMap::new()))
+}
+
+/// Create the controls for a newly spawned session (replacing any stale
+/// entry; `spawn_provider_process` refuses to start a session that is live)
+pub fn register(
+    provider: &str,
+    session_id: &str,
+    group_id: Option<String>,
+    child: Child,
+) -> Arc<SessionControl> {
+    let control = Arc::new(SessionControl::new(provider, session_id, group_id, child));
+    if let Ok(mut map) = controls().lock() {
+        map.insert(session_id.to_string(), control.clone());
+    }
+    control
+}
+
+/// Controls of a running session
+pub fn get(session_id: &str) -> Option<Arc<SessionControl>> {
+    controls().lock().ok()?.get(session_id).cloned()
+}
+
+/// Controls of every running session of `provider`
+pub fn of_provider(provider: &str) -> Vec<Arc<SessionControl>> {
+    match controls().lock() {
+        Ok(map) => map.values().filter(|c| c.provider == provider).cloned().collect(),
+        Err(_) => Vec::new(),
+    }
+}
+
+/// Controls of the running session a cancel token was issued for
+pub fn find_by_token(token: &str) -> Option<Arc<SessionControl>> {
+    controls()
+        .lock()
+        .ok()?
+        .values()
+        .find(|c| c.cancel_token == token)
+        .cloned()
+}
+
+/// Controls of every running session started under `group_id`
+pub fn in_group(group_id: &str) -> Vec<Arc<SessionControl>> {
+    match controls().lock() {
+        Ok(map) => map
+            .values()
+            .filter(|c| c.group_id.as_deref() == Some(group_id))
+            .cloned()
+            .collect(),
+        Err(_) => Vec::new(),
+    }
+}
+
+/// Drop a session's controls once it has finished. Only `control` itself is
+/// removed, never a later run of the same session that replaced it.
+pub fn remove(control: &Arc<SessionControl>) {
+    if let Ok(mut map) = controls().lock() {
+        if map.get(&control.session_id).is_some_and(|c| Arc::ptr_eq(c, control)) {
+            map.remove(&control.session_id);
+        }
+    }
+}
@@ -0,0 +1,122 @@
+use rusqlite::Connection;
+use serde::Serialize;
+use tauri::AppHandle;
+
+use super::config_files;
+use super::profiles;
+use super::settings::open_db;
+
+/// Profile setting: model used by any provider that has no default of its own
+pub const GLOBAL_DEFAULT_MODEL_KEY: &str = "global_default_model";
+
+/// Profile setting holding a provider's default model
+pub fn provider_default_key(provider: &str) -> String {
+    format!("{}_default_model", provider)
+}
+
+/// Step of the resolution chain a model came from
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ModelSource {
+    /// Set for this project and provider
+    Project,
+    /// The provider's default stored in the active profile
+    ProviderDefault,
+    /// Named by the provider's config file
+    ConfigFile,
+    /// The global default of the active profile
+    Global,
+}
+
+/// Model a provider runs with and where it was found
+#[derive(Debug, Clone, Serialize)]
+pub struct EffectiveModel {
+    pub provider: String,
+    pub model: String,
+    pub source: ModelSource,
+}
+
+fn ensure_table(conn: &Connection) -> Result<(), String> {
+    conn.execute(
+        "CREATE TABLE IF NOT EXISTS project_models (
+            project_path TEXT NOT NULL,
+            provider TEXT NOT NULL,
+            model TEXT NOT NULL,
+            updated_at TEXT NOT NULL DEFAULT CURRENT_TIMESTAMP,
+            PRIMARY KEY (project_path, provider)
+        )",
+        [],
+    )
+    .map_err(|e| e.to_string())?;
+    Ok(())
+}
+
+/// Model stored for a project and provider
+pub fn load_project(conn: &Connection, project_path: &str, provider: &str) -> Result<Option<String>, String> {
+    ensure_table(conn)?;
+    Ok(conn
+        .query_row(
+            "SELECT model FROM project_models WHERE project_path = ?1 AND provider = ?2",
+            rusqlite::params![project_path, provider],
+            |row| row.get(0),
+        )
+        .ok())
+}
+
+/// Set the model of a project and provider; `None` removes it
+pub fn save_project(conn: &Connection, project_path: &str, provider: &str, model: Option<&str>) -> Result<(), String> {
+    ensure_table(conn)?;
+    match model {
+        Some(model) => conn.execute(
+            "INSERT INTO project_models (project_path, provider, model) VALUES (?1, ?2, ?3)
+             ON CONFLICT(project_path, provider) DO UPDATE SET model = excluded.model, updated_at = CURRENT_TIMESTAMP",
+            rusqlite::params![project_path, provider, model],
+        ),
+        None => conn.execute(
+            "DELETE FROM project_models WHERE project_path = ?1 AND provider = ?2",
+            rusqlite::params![project_path, provider],
+        ),
+    }
+    .map_err(|e| e.to_string())?;
+    Ok(())
+}
+
+/// Model a provider runs with for a project: the project's model, the
+/// provider default, the provider's config file, then the global default.
+/// Placeholders ("default", "auto") are skipped at every step.
+///
+/// The model picker and the chat commands both go through this, so what the
+/// UI shows is what launches.
+pub fn resolve(app: &AppHandle, project_path: Option<&str>, provider: &str) -> Result<EffectiveModel, String> {
+    let named = |model: Option<String>| model.filter(|m| !config_files::is_default_model(m));
+    let project = project_path.filter(|p| !p.is_empty()).and_then(|project_path| {
+        let conn = open_db(app).ok()?;
+        load_project(&conn, project_path, provider).unwrap_or_else(|e| {
+            log::warn!("Failed to load the {} model of {}: {}", provider, project_path, e);
+            None
+        })
+    });
+    let steps = [
+        (ModelSource::Project, named(project)),
+        (ModelSource::ProviderDefault, named(profiles::read_profile_setting(app, &provider_default_key(provider)))),
+        (ModelSource::ConfigFile, named(config_files::find_default_model(app, provider)?)),
+        (ModelSource::Global, named(profiles::read_profile_setting(app, GLOBAL_DEFAULT_MODEL_KEY))),
+    ];
+    steps
+        .into_iter()
+        .find_map(|(source, model)| {
+            model.map(|model| EffectiveModel { provider: provider.to_string(), model: model.trim().to_string(), source })
+        })
+        .ok_or_else(|| format!("No model configured for {}", provider))
+}
+
+/// Model to launch a chat with: `requested` unless it is a placeholder, else
+/// the resolved one, else empty so the CLI falls back to its built-in default
+pub fn for_launch(app: &AppHandle, project_path: &str, provider: &str, requested: &str) -> String {
+    if !config_files::is_default_model(requested) {
+        return requested.trim().to_string();
+    }
+    resolve(app, Some(project_path), provider)
+        .map(|effective| effective.model)
+        .unwrap_or_default()
+}
@@ -0,0 +1,89 @@
+use serde::Serialize;
+
+/// Severity assigned to a line the CLI wrote to stderr
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum DiagnosticLevel {
+    Info,
+    Warn,
+    Error,
+}
+
+/// Level words used by log prefixes such as `2024-05-01T10:00:00Z  WARN codex_core: ...`
+/// or `[error] ...`; only looked for at the start of a line
+const LEVEL_TOKENS: &[(&str, DiagnosticLevel)] = &[
+    ("error", DiagnosticLevel::Error),
+    ("err", DiagnosticLevel::Error),
+    ("fatal", DiagnosticLevel::Error),
+    ("panic", DiagnosticLevel::Error),
+    ("critical", DiagnosticLevel::Error),
+    ("warn", DiagnosticLevel::Warn),
+    ("warning", DiagnosticLevel::Warn),
+    ("info", DiagnosticLevel::Info),
+    ("debug", DiagnosticLevel::Info),
+    ("trace", DiagnosticLevel::Info),
+];
+
+/// How many leading words may hold the level (timestamp, pid, ...)
+const LEVEL_TOKEN_WINDOW: usize = 3;
+
+/// Fragments that mark a line as a genuine failure
+const ERROR_PATTERNS: &[&str] = &[
+    "error:",
+    "error ",
+    "failed",
+    "failure",
+    "exception",
+    "traceback",
+    "panicked",
+    "unauthorized",
+    "forbidden",
+    "permission denied",
+    "not found",
+    "invalid api key",
+    "rate limit",
+    "too many requests",
+];
+
+/// Fragments that mark a line as a warning
+const WARN_PATTERNS: &[&str] = &["warning", "warn:", "deprecated", "deprecation", "retrying"];
+
+/// Classify a stderr line with simple heuristics.
+///
+/// An explicit level prefix wins; otherwise the line is searched for error,
+/// then warning keywords. Everything else (progress, "downloading model
+/// metadata", banners) is informational.
+pub fn classify(line: &str) -> DiagnosticLevel {
+    let lower = line.trim().to_lowercase();
+    for word in lower.split_whitespace().take(LEVEL_TOKEN_WINDOW) {
+        let word = word.trim_matches(|c: char| !c.is_ascii_alphanumeric());
+        if let Some((_, level)) = LEVEL_TOKENS.iter().find(|(token, _)| *token == word) {
+            return *level;
+        }
+    }
+    if ERROR_PATTERNS.iter().any(|p| lower.contains(p)) {
+        DiagnosticLevel::Error
+    } else if WARN_PATTERNS.iter().any(|p| lower.contains(p)) {
+        DiagnosticLevel::Warn
+    } else {
+        DiagnosticLevel::Info
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_classify() {
+        assert_eq!(classify("Downloading model metadata..."), DiagnosticLevel::Info);
+        assert_eq!(
+            classify("2024-05-01T10:00:00Z  WARN codex_core::config: unknown key"),
+            DiagnosticLevel::Warn
+        );
+        assert_eq!(classify("[ERROR] request failed"), DiagnosticLevel::Error);
+        assert_eq!(classify("Error: 429 Too Many Requests"), DiagnosticLevel::Error);
+        assert_eq!(classify("(node:123) DeprecationWarning: punycode"), DiagnosticLevel::Warn);
+        assert_eq!(classify("Loaded cached credentials."), DiagnosticLevel::Info);
+    }
+}
@@ -0,0 +1,246 @@
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+
+use super::transcript::{find_transcript, last_assistant_text, locate_transcript};
+use crate::unified_history::read_jsonl;
+
+/// Largest LCS table computed; bigger inputs only get their common prefix
+/// and suffix matched so a huge answer can't stall the command
+const MAX_DIFF_CELLS: usize = 4_000_000;
+
+/// Unit the answers are compared in
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum DiffMode {
+    #[default]
+    Line,
+    Word,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum DiffKind {
+    Equal,
+    Insert,
+    Delete,
+}
+
+/// A run of lines (or words) that is in both answers, only in B, or only in A
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct DiffChunk {
+    pub kind: DiffKind,
+    pub text: String,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct DiffMetrics {
+    /// Lines (or words) in each answer
+    pub units_a: usize,
+    pub units_b: usize,
+    pub unchanged: usize,
+    pub inserted: usize,
+    pub deleted: usize,
+    /// Share of units kept, 2 * unchanged / (units_a + units_b); 1.0 for two empty answers
+    pub similarity: f64,
+    /// Jaccard index of the lowercased word sets, insensitive to reordering
+    pub word_overlap: f64,
+}
+
+/// One side of a comparison
+#[derive(Debug, Clone, Serialize)]
+pub struct DiffSide {
+    pub session_id: String,
+    pub provider: Option<String>,
+    pub model: Option<String>,
+    /// Final assistant answer
+    pub text: String,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct SessionDiff {
+    pub a: DiffSide,
+    pub b: DiffSide,
+    pub mode: DiffMode,
+    pub chunks: Vec<DiffChunk>,
+    pub metrics: DiffMetrics,
+}
+
+fn tokens(text: &str, mode: DiffMode) -> Vec<&str> {
+    match mode {
+        DiffMode::Line => text.lines().collect(),
+        DiffMode::Word => text.split_whitespace().collect(),
+    }
+}
+
+/// Edit script turning `a` into `b`, one entry per unit, via a longest common
+/// subsequence
+fn edit_script(a: &[&str], b: &[&str]) -> Vec<DiffKind> {
+    let prefix = a.iter().zip(b).take_while(|(x, y)| x == y).count();
+    let suffix = a[prefix..]
+        .iter()
+        .rev()
+        .zip(b[prefix..].iter().rev())
+        .take_while(|(x, y)| x == y)
+        .count();
+    let (mid_a, mid_b) = (&a[prefix..a.len() - suffix], &b[prefix..b.len() - suffix]);
+
+    let mut script = vec![DiffKind::Equal; prefix];
+    if mid_a.len().saturating_mul(mid_b.len()) > MAX_DIFF_CELLS {
+        script.resize(script.len() + mid_a.len(), DiffKind::Delete);
+        script.resize(script.len() + mid_b.len(), DiffKind::Insert);
+    } else {
+        // lcs[i][j]: length of the LCS of mid_a[i..] and mid_b[j..]
+        let (n, m) = (mid_a.len(), mid_b.len());
+        let mut lcs = vec![vec![0usize; m + 1]; n + 1];
+        for i in (0..n).rev() {
+            for j in (0..m).rev() {
+                lcs[i][j] = if mid_a[i] == mid_b[j] {
+                    lcs[i + 1][j + 1] + 1
+                } else {
+                    lcs[i + 1][j].max(lcs[i][j + 1])
+                };
+            }
+        }
+        let (mut i, mut j) = (0, 0);
+        while i < n || j < m {
+            if i < n && j < m && mid_a[i] == mid_b[j] {
+                script.push(DiffKind::Equal);
+                i += 1;
+                j += 1;
+            } else if j < m && (i == n || lcs[i][j + 1] >= lcs[i + 1][j]) {
+                script.push(DiffKind::Insert);
+                j += 1;
+            } else {
+                script.push(DiffKind::Delete);
+                i += 1;
+            }
+        }
+    }
+    script.resize(script.len() + suffix, DiffKind::Equal);
+    script
+}
+
+fn word_overlap(a: &str, b: &str) -> f64 {
+    let words = |text: &str| -> std::collections::HashSet<String> {
+        text.split_whitespace().map(|w| w.to_lowercase()).collect()
+    };
+    let (a, b) = (words(a), words(b));
+    let union = a.union(&b).count();
+    if union == 0 {
+        return 1.0;
+    }
+    a.intersection(&b).count() as f64 / union as f64
+}
+
+/// Diff two texts by line or word; consecutive units of the same kind are
+/// merged into one chunk
+pub fn diff_texts(a: &str, b: &str, mode: DiffMode) -> (Vec<DiffChunk>, DiffMetrics) {
+    let (units_a, units_b) = (tokens(a, mode), tokens(b, mode));
+    let separator = match mode {
+        DiffMode::Line => "\n",
+        DiffMode::Word => " ",
+    };
+    let mut chunks: Vec<DiffChunk> = Vec::new();
+    let (mut i, mut j) = (0, 0);
+    let (mut unchanged, mut inserted, mut deleted) = (0, 0, 0);
+    for kind in edit_script(&units_a, &units_b) {
+        let unit = match kind {
+            DiffKind::Equal => {
+                unchanged += 1;
+                i += 1;
+                j += 1;
+                units_a[i - 1]
+            }
+            DiffKind::Insert => {
+                inserted += 1;
+                j += 1;
+                units_b[j - 1]
+            }
+            DiffKind::Delete => {
+                deleted += 1;
+                i += 1;
+                units_a[i - 1]
+            }
+        };
+        match chunks.last_mut() {
+            Some(last) if last.kind == kind => {
+                last.text.push_str(separator);
+                last.text.push_str(unit);
+            }
+            _ => chunks.push(DiffChunk { kind, text: unit.to_string() }),
+        }
+    }
+    let total = units_a.len() + units_b.len();
+    let metrics = DiffMetrics {
+        units_a: units_a.len(),
+        units_b: units_b.len(),
+        unchanged,
+        inserted,
+        deleted,
+        similarity: if total == 0 { 1.0 } else { 2.0 * unchanged as f64 / total as f64 },
+        word_overlap: word_overlap(a, b),
+    };
+    (chunks, metrics)
+}
+
+/// Final answer of a stored session with the provider and model it ran on
+fn side(project_path: Option<&str>, session_id: &str) -> Result<DiffSide, String> {
+    let path = match project_path {
+        Some(project) => find_transcript(project, session_id)?,
+        None => locate_transcript(session_id)?,
+    }
+    .ok_or_else(|| format!("No transcript for session {}", session_id))?;
+    let messages = read_jsonl(&path);
+    let field = |key: &str| {
+        messages
+            .iter()
+            .find_map(|m| m.get(key).and_then(Value::as_str))
+            .map(|v| v.to_string())
+    };
+    let text = last_assistant_text(&messages)
+        .ok_or_else(|| format!("Session {} has no assistant answer", session_id))?;
+    Ok(DiffSide {
+        session_id: session_id.to_string(),
+        provider: field("provider"),
+        model: field("model"),
+        text,
+    })
+}
+
+/// Compare the final answers of two stored sessions, e.g. two models run on
+/// the same prompt. Without a project every project's sessions are searched.
+pub fn diff_sessions(
+    project_path: Option<&str>,
+    session_a: &str,
+    session_b: &str,
+    mode: DiffMode,
+) -> Result<SessionDiff, String> {
+    let a = side(project_path, session_a)?;
+    let b = side(project_path, session_b)?;
+    let (chunks, metrics) = diff_texts(&a.text, &b.text, mode);
+    Ok(SessionDiff { a, b, mode, chunks, metrics })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_diff_texts() {
+        let (chunks, metrics) = diff_texts("one\ntwo\nthree", "one\n2\nthree\nfour", DiffMode::Line);
+        let kinds: Vec<DiffKind> = chunks.iter().map(|c| c.kind).collect();
+        assert_eq!(
+            kinds,
+            vec![DiffKind::Equal, DiffKind::Insert, DiffKind::Delete, DiffKind::Equal, DiffKind::Insert]
+        );
+        assert_eq!((metrics.unchanged, metrics.inserted, metrics.deleted), (2, 2, 1));
+        assert!((metrics.similarity - 4.0 / 7.0).abs() < 1e-9);
+
+        let (chunks, metrics) = diff_texts("the quick fox", "the slow fox", DiffMode::Word);
+        assert_eq!(chunks[0], DiffChunk { kind: DiffKind::Equal, text: "the".into() });
+        assert_eq!(metrics.unchanged, 2);
+        assert!((metrics.word_overlap - 0.5).abs() < 1e-9);
+
+        assert_eq!(diff_texts("", "", DiffMode::Line).1.similarity, 1.0);
+    }
+}
@@ -0,0 +1,47 @@
+use serde_json::{json, Value};
+use std::sync::OnceLock;
+
+/// Schema version stamped on every provider event envelope as `v`.
+///
+/// Bump this whenever the shape of an emitted payload changes so the
+/// frontend can tell which fields to expect.
+pub const EVENT_PROTOCOL_VERSION: u32 = 1;
+
+/// Environment variable read at startup to set the event namespace
+pub const EVENT_NAMESPACE_ENV: &str = "ISHINEX_EVENT_NAMESPACE";
+
+/// Prefix put in front of every emitted event name; unset means none, which
+/// keeps the historical names (`codex-output`, `claude-complete`, ...)
+static EVENT_NAMESPACE: OnceLock<String> = OnceLock::new();
+
+/// Add the `v` field to a JSON object payload (other values pass through)
+pub fn envelope(mut payload: Value) -> Value {
+    if let Value::Object(map) = &mut payload {
+        map.insert("v".to_string(), json!(EVENT_PROTOCOL_VERSION));
+    }
+    payload
+}
+
+/// Set the event namespace (e.g. `ishinex:`) for apps embedding this backend.
+///
+/// Must be called once before anything is emitted; the namespace can't change
+/// afterwards since listeners are already bound to the names in use. Only
+/// characters Tauri accepts in event names are allowed.
+pub fn set_namespace(namespace: &str) -> Result<(), String> {
+    if !namespace.chars().all(|c| c.is_ascii_alphanumeric() || matches!(c, '-' | '/' | ':' | '_')) {
+        return Err(format!("Invalid event namespace {:?}", namespace));
+    }
+    EVENT_NAMESPACE
+        .set(namespace.to_string())
+        .map_err(|_| "Event namespace is already set".to_string())
+}
+
+/// Configured namespace, empty when none was set
+pub fn namespace() -> &'static str {
+    EVENT_NAMESPACE.get().map(String::as_str).unwrap_or("")
+}
+
+/// Name to emit `name` under: the namespace followed by the name
+pub fn event_name(name: &str) -> String {
+    format!("{}{}", namespace(), name)
+}
@@ -0,0 +1,133 @@
+use tauri::AppHandle;
+use tokio::io::{AsyncBufRead, AsyncBufReadExt};
+
+use super::settings::read_setting;
+
+/// Delimiter used when none is configured
+pub const DEFAULT_DELIMITER: &[u8] = b"\n";
+
+/// Longest delimiter accepted
+const MAX_DELIMITER_BYTES: usize = 16;
+
+/// Settings key: record delimiter of a provider's output, in `escape` form
+pub fn record_delimiter_key(provider: &str) -> String {
+    format!("{}_record_delimiter", provider)
+}
+
+/// Bytes of a delimiter written with the escapes `\n`, `\r`, `\t`, `\0`,
+/// `\\` and `\xHH`; other characters stand for themselves
+pub fn parse_delimiter(raw: &str) -> Result<Vec<u8>, String> {
+    let mut bytes = Vec::new();
+    let mut chars = raw.chars();
+    while let Some(c) = chars.next() {
+        if c != '\\' {
+            let mut utf8 = [0; 4];
+            bytes.extend_from_slice(c.encode_utf8(&mut utf8).as_bytes());
+            continue;
+        }
+        match chars.next() {
+            Some('n') => bytes.push(b'\n'),
+            Some('r') => bytes.push(b'\r'),
+            Some('t') => bytes.push(b'\t'),
+            Some('0') => bytes.push(0),
+            Some('\\') => bytes.push(b'\\'),
+            Some('x') => {
+                let hex: String = chars.by_ref().take(2).collect();
+                let byte = u8::from_str_radix(&hex, 16)
+                    .ok()
+                    .filter(|_| hex.len() == 2)
+                    .ok_or_else(|| format!("Invalid escape \\x{} in delimiter", hex))?;
+                bytes.push(byte);
+            }
+            Some(other) => return Err(format!("Unknown escape \\{} in delimiter", other)),
+            None => return Err("Delimiter ends with a lone backslash".to_string()),
+        }
+    }
+    if bytes.is_empty() || bytes.len() > MAX_DELIMITER_BYTES {
+        return Err(format!("Delimiter must be 1 to {} bytes", MAX_DELIMITER_BYTES));
+    }
+    Ok(bytes)
+}
+
+/// Inverse of `parse_delimiter`, for showing the stored delimiter
+pub fn escape_delimiter(bytes: &[u8]) -> String {
+    bytes
+        .iter()
+        .map(|b| match b {
+            b'\n' => "\\n".to_string(),
+            b'\r' => "\\r".to_string(),
+            b'\t' => "\\t".to_string(),
+            0 => "\\0".to_string(),
+            b'\\' => "\\\\".to_string(),
+            b if b.is_ascii_graphic() || *b == b' ' => (*b as char).to_string(),
+            b => format!("\\x{:02x}", b),
+        })
+        .collect()
+}
+
+/// Configured delimiter of a provider; a malformed setting is logged and the
+/// default used
+pub fn load_delimiter(app: &AppHandle, provider: &str) -> Vec<u8> {
+    match read_setting(app, &record_delimiter_key(provider)).filter(|raw| !raw.is_empty()) {
+        Some(raw) => parse_delimiter(&raw).unwrap_or_else(|e| {
+            log::warn!("Ignoring {} record delimiter {:?}: {}", provider, raw, e);
+            DEFAULT_DELIMITER.to_vec()
+        }),
+        None => DEFAULT_DELIMITER.to_vec(),
+    }
+}
+
+/// Append bytes to `buf` up to and including the next `delimiter`, or to the
+/// end of the stream. Returns the bytes read by this call; 0 at the end.
+///
+/// Cancel safe like `read_until`: bytes read before a timeout or `select!`
+/// stay in `buf`, and a later call picks up the record where it stopped.
+pub async fn read_record<R: AsyncBufRead + Unpin>(
+    reader: &mut R,
+    delimiter: &[u8],
+    buf: &mut Vec<u8>,
+) -> std::io::Result<usize> {
+    let last = delimiter.last().copied().unwrap_or(b'\n');
+    let start = buf.len();
+    loop {
+        let n = reader.read_until(last, buf).await?;
+        if n == 0 || buf.ends_with(delimiter) {
+            return Ok(buf.len() - start);
+        }
+    }
+}
+
+/// Text of a record read by `read_record`, without its delimiter and any
+/// trailing line break
+pub fn record_text(buf: &[u8], delimiter: &[u8]) -> String {
+    let record = buf.strip_suffix(delimiter).unwrap_or(buf);
+    String::from_utf8_lossy(record).trim_end_matches(['\n', '\r']).to_string()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_delimiter() {
+        assert_eq!(parse_delimiter("\\0").unwrap(), vec![0]);
+        assert_eq!(parse_delimiter("\\x1e\\n").unwrap(), vec![0x1e, b'\n']);
+        assert_eq!(parse_delimiter("--").unwrap(), b"--".to_vec());
+        assert!(parse_delimiter("").is_err());
+        assert!(parse_delimiter("\\x1").is_err());
+        assert!(parse_delimiter("\\q").is_err());
+        assert_eq!(escape_delimiter(&parse_delimiter("\\0\\x1e|").unwrap()), "\\0\\x1e|");
+    }
+
+    #[tokio::test]
+    async fn test_read_record() {
+        let mut reader: &[u8] = b"{\"a\":\n1}\0{\"b\":2}\0tail";
+        let mut buf = Vec::new();
+        let mut records = Vec::new();
+        while read_record(&mut reader, b"\0", &mut buf).await.unwrap() > 0 {
+            records.push(record_text(&buf, b"\0"));
+            buf.clear();
+        }
+        assert_eq!(records, vec!["{\"a\":\n1}", "{\"b\":2}", "tail"]);
+    }
+}
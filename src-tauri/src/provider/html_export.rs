@@ -0,0 +1,368 @@
+use chrono::Utc;
+use serde_json::Value;
+use std::fs;
+use std::path::PathBuf;
+
+use super::compact::turns_from_transcript;
+use super::transcript::{find_transcript, locate_transcript, was_cancelled};
+use crate::unified_history::read_jsonl;
+
+/// Words shown as keywords in code blocks, across the languages answers
+/// usually contain
+const KEYWORDS: &[&str] = &[
+    "as", "async", "await", "break", "case", "catch", "class", "const", "continue", "def", "default", "do", "elif",
+    "else", "enum", "except", "export", "extends", "false", "False", "fn", "for", "from", "func", "function", "if",
+    "impl", "import", "in", "interface", "let", "match", "mod", "mut", "new", "nil", "None", "null", "pub", "return",
+    "self", "static", "struct", "switch", "this", "throw", "trait", "true", "True", "try", "type", "use", "var",
+    "while", "with", "yield",
+];
+
+/// Inline stylesheet; the export must open without network access
+const STYLE: &str = r#"
+body { margin: 0; background: #f6f7f9; color: #1f2328; font: 15px/1.6 -apple-system, "Segoe UI", Helvetica, Arial, sans-serif; }
+main { max-width: 860px; margin: 0 auto; padding: 32px 20px 64px; }
+header { border-bottom: 1px solid #d8dee4; margin-bottom: 24px; padding-bottom: 12px; }
+header h1 { font-size: 20px; margin: 0 0 6px; }
+header .meta { color: #59636e; font-size: 13px; }
+.message { background: #fff; border: 1px solid #d8dee4; border-radius: 8px; margin: 16px 0; padding: 12px 18px; }
+.message.user { background: #eef4ff; border-color: #c5d8fb; }
+.role { color: #59636e; font-size: 12px; font-weight: 600; letter-spacing: .04em; text-transform: uppercase; }
+pre { background: #1f2430; color: #e6e6e6; border-radius: 6px; overflow-x: auto; padding: 12px 14px; font-size: 13px; }
+code { font-family: ui-monospace, SFMono-Regular, Menlo, Consolas, monospace; }
+:not(pre) > code { background: rgba(127, 127, 127, .15); border-radius: 4px; padding: 1px 5px; font-size: 13px; }
+blockquote { border-left: 3px solid #d8dee4; color: #59636e; margin: 8px 0; padding-left: 12px; }
+.notice { background: #fff8e5; border: 1px solid #f0d58c; border-radius: 8px; color: #7a5700; margin: 16px 0; padding: 10px 18px; }
+.lang { color: #8b949e; display: block; font-size: 11px; margin-bottom: 6px; }
+.tok-k { color: #ff7b72; } .tok-s { color: #a5d6ff; } .tok-c { color: #8b949e; font-style: italic; } .tok-n { color: #79c0ff; }
+@media (prefers-color-scheme: dark) {
+  body { background: #0d1117; color: #e6edf3; }
+  header { border-color: #30363d; } header .meta, .role, blockquote { color: #8d96a0; }
+  .message { background: #161b22; border-color: #30363d; } .message.user { background: #132339; border-color: #1f3b63; }
+  .notice { background: #2b2111; border-color: #5c4613; color: #e3b341; }
+  a { color: #58a6ff; }
+}
+"#;
+
+fn escape(text: &str) -> String {
+    let mut out = String::with_capacity(text.len());
+    for c in text.chars() {
+        match c {
+            '&' => out.push_str("&amp;"),
+            '<' => out.push_str("&lt;"),
+            '>' => out.push_str("&gt;"),
+            '"' => out.push_str("&quot;"),
+            '\'' => out.push_str("&#39;"),
+            c => out.push(c),
+        }
+    }
+    out
+}
+
+/// `[label](url)` at the start of `text`: label, url and bytes consumed.
+/// Only web and mail links are kept, so a transcript can't smuggle in script URLs.
+fn parse_link(text: &str) -> Option<(&str, &str, usize)> {
+    let close = text.find("](")?;
+    let after = &text[close + 2..];
+    let end = after.find(')')?;
+    let url = &after[..end];
+    if !["http://", "https://", "mailto:"].iter().any(|scheme| url.starts_with(scheme)) {
+        return None;
+    }
+    Some((&text[1..close], url, close + 2 + end + 1))
+}
+
+/// Render inline markdown: code spans, bold, italics and links
+fn inline(text: &str) -> String {
+    let mut out = String::new();
+    let mut rest = text;
+    let mut prev = ' ';
+    while let Some(c) = rest.chars().next() {
+        if c == '`' {
+            if let Some(end) = rest[1..].find('`') {
+                out.push_str(&format!("<code>{}</code>", escape(&rest[1..1 + end])));
+                rest = &rest[end + 2..];
+                prev = c;
+                continue;
+            }
+        }
+        if rest.starts_with("**") {
+            if let Some(end) = rest[2..].find("**").filter(|&end| end > 0) {
+                out.push_str(&format!("<strong>{}</strong>", inline(&rest[2..2 + end])));
+                rest = &rest[end + 4..];
+                prev = c;
+                continue;
+            }
+        }
+        // `_` inside a word (snake_case) is not emphasis
+        if c == '*' || (c == '_' && !prev.is_alphanumeric()) {
+            let inner = &rest[1..];
+            if let Some(end) = inner.find(c).filter(|&end| end > 0 && !inner.starts_with(' ')) {
+                out.push_str(&format!("<em>{}</em>", inline(&inner[..end])));
+                rest = &inner[end + 1..];
+                prev = c;
+                continue;
+            }
+        }
+        if c == '[' {
+            if let Some((label, url, consumed)) = parse_link(rest) {
+                out.push_str(&format!("<a href=\"{}\">{}</a>", escape(url), inline(label)));
+                rest = &rest[consumed..];
+                prev = ')';
+                continue;
+            }
+        }
+        out.push_str(&escape(&rest[..c.len_utf8()]));
+        rest = &rest[c.len_utf8()..];
+        prev = c;
+    }
+    out
+}
+
+/// Line comment markers of a language; `None` leaves the code unhighlighted
+fn comment_markers(lang: &str) -> Option<&'static [&'static str]> {
+    match lang.to_ascii_lowercase().as_str() {
+        "" | "text" | "txt" | "plain" | "console" | "output" | "log" | "diff" | "markdown" | "md" => None,
+        "json" => Some(&[]),
+        "python" | "py" | "sh" | "bash" | "shell" | "zsh" | "ruby" | "rb" | "yaml" | "yml" | "toml" | "perl"
+        | "r" | "dockerfile" | "makefile" | "ini" => Some(&["#"]),
+        "sql" | "lua" | "haskell" | "hs" => Some(&["--"]),
+        _ => Some(&["//"]),
+    }
+}
+
+/// End of a string literal starting at `code[0]`, honoring backslash escapes;
+/// only backtick strings may span lines
+fn string_end(code: &str, quote: char) -> Option<usize> {
+    let mut escaped = false;
+    for (i, c) in code.char_indices().skip(1) {
+        match c {
+            _ if escaped => escaped = false,
+            '\\' => escaped = true,
+            '\n' if quote != '`' => return None,
+            c if c == quote => return Some(i + c.len_utf8()),
+            _ => {}
+        }
+    }
+    None
+}
+
+fn span(class: &str, text: &str) -> String {
+    format!("<span class=\"tok-{}\">{}</span>", class, escape(text))
+}
+
+/// Escape a code block and mark its keywords, strings, numbers and comments
+fn highlight(code: &str, lang: &str) -> String {
+    let markers = match comment_markers(lang) {
+        Some(markers) => markers,
+        None => return escape(code),
+    };
+    let block_comments = markers.contains(&"//");
+    let mut out = String::with_capacity(code.len() * 2);
+    let mut i = 0;
+    while i < code.len() {
+        let rest = &code[i..];
+        let c = rest.chars().next().unwrap_or(' ');
+        let token_end = if markers.iter().any(|m| rest.starts_with(m)) {
+            Some(("c", rest.find('\n').unwrap_or(rest.len())))
+        } else if block_comments && rest.starts_with("/*") {
+            Some(("c", rest[2..].find("*/").map_or(rest.len(), |end| end + 4)))
+        } else if matches!(c, '"' | '\'' | '`') {
+            string_end(rest, c).map(|end| ("s", end))
+        } else if c.is_ascii_digit() {
+            Some(("n", rest.find(|c: char| !(c.is_ascii_alphanumeric() || c == '.' || c == '_')).unwrap_or(rest.len())))
+        } else if c.is_alphabetic() || c == '_' {
+            let end = rest.find(|c: char| !(c.is_alphanumeric() || c == '_')).unwrap_or(rest.len());
+            Some((if KEYWORDS.contains(&&rest[..end]) { "k" } else { "" }, end))
+        } else {
+            None
+        };
+        match token_end {
+            Some(("", end)) => out.push_str(&escape(&rest[..end])),
+            Some((class, end)) => out.push_str(&span(class, &rest[..end])),
+            None => out.push_str(&escape(&rest[..c.len_utf8()])),
+        }
+        i += token_end.map_or(c.len_utf8(), |(_, end)| end);
+    }
+    out
+}
+
+/// Item text of a list line and whether the list is ordered
+fn list_item(line: &str) -> Option<(&'static str, &str)> {
+    for bullet in ["- ", "* ", "+ "] {
+        if let Some(item) = line.strip_prefix(bullet) {
+            return Some(("ul", item));
+        }
+    }
+    let (number, item) = line.split_once(". ")?;
+    (!number.is_empty() && number.chars().all(|c| c.is_ascii_digit())).then_some(("ol", item))
+}
+
+/// Close the open paragraph and list
+fn flush(html: &mut String, paragraph: &mut Vec<&str>, list: &mut Option<&'static str>) {
+    if !paragraph.is_empty() {
+        let lines: Vec<String> = paragraph.drain(..).map(inline).collect();
+        html.push_str(&format!("<p>{}</p>\n", lines.join("<br>\n")));
+    }
+    if let Some(tag) = list.take() {
+        html.push_str(&format!("</{}>\n", tag));
+    }
+}
+
+/// Markdown renderer covering what model answers use: fenced code, headings,
+/// lists, quotes, rules and paragraphs (single line breaks are kept)
+fn render_markdown(text: &str) -> String {
+    let mut html = String::new();
+    let mut paragraph: Vec<&str> = Vec::new();
+    let mut list: Option<&'static str> = None;
+
+    let mut lines = text.lines().peekable();
+    while let Some(line) = lines.next() {
+        let trimmed = line.trim();
+        if let Some(lang) = trimmed.strip_prefix("```") {
+            flush(&mut html, &mut paragraph, &mut list);
+            let lang = lang.trim();
+            let code: Vec<&str> = lines.by_ref().take_while(|l| !l.trim_start().starts_with("```")).collect();
+            let label = match lang {
+                "" => String::new(),
+                lang => format!("<span class=\"lang\">{}</span>", escape(lang)),
+            };
+            html.push_str(&format!(
+                "<pre>{}<code class=\"language-{}\">{}</code></pre>\n",
+                label,
+                escape(lang),
+                highlight(&code.join("\n"), lang)
+            ));
+            continue;
+        }
+        if trimmed.is_empty() {
+            flush(&mut html, &mut paragraph, &mut list);
+            continue;
+        }
+        let level = trimmed.chars().take_while(|&c| c == '#').count();
+        if (1..=6).contains(&level) && trimmed[level..].starts_with(' ') {
+            flush(&mut html, &mut paragraph, &mut list);
+            html.push_str(&format!("<h{0}>{1}</h{0}>\n", level, inline(trimmed[level..].trim())));
+            continue;
+        }
+        if matches!(trimmed, "---" | "***" | "___") {
+            flush(&mut html, &mut paragraph, &mut list);
+            html.push_str("<hr>\n");
+            continue;
+        }
+        if let Some(quote) = trimmed.strip_prefix('>') {
+            flush(&mut html, &mut paragraph, &mut list);
+            let mut quoted = vec![inline(quote.trim())];
+            while let Some(next) = lines.peek().and_then(|l| l.trim().strip_prefix('>')) {
+                quoted.push(inline(next.trim()));
+                lines.next();
+            }
+            html.push_str(&format!("<blockquote>{}</blockquote>\n", quoted.join("<br>\n")));
+            continue;
+        }
+        if let Some((tag, item)) = list_item(trimmed) {
+            if list != Some(tag) {
+                flush(&mut html, &mut paragraph, &mut list);
+                html.push_str(&format!("<{}>\n", tag));
+                list = Some(tag);
+            } else if !paragraph.is_empty() {
+                flush(&mut html, &mut paragraph, &mut None);
+            }
+            html.push_str(&format!("<li>{}</li>\n", inline(item)));
+            continue;
+        }
+        if let Some(tag) = list.take() {
+            html.push_str(&format!("</{}>\n", tag));
+        }
+        paragraph.push(trimmed);
+    }
+    flush(&mut html, &mut paragraph, &mut list);
+    html
+}
+
+/// Write a stored session as a standalone HTML page next to the project's
+/// sessions (`.../projects/<project_id>/exports/<session_id>.html`) and return
+/// its path. Without a project every project's sessions are searched; a
+/// cancelled last run gets a notice after its last turn.
+pub fn export_session(project_path: Option<&str>, session_id: &str) -> Result<PathBuf, String> {
+    let transcript = match project_path {
+        Some(project) => find_transcript(project, session_id)?,
+        None => locate_transcript(session_id)?,
+    }
+    .ok_or_else(|| format!("No transcript for session {}", session_id))?;
+    let messages = read_jsonl(&transcript);
+    let field = |key: &str| messages.iter().find_map(|m| m.get(key).and_then(Value::as_str));
+    let provider = field("provider").unwrap_or("unknown");
+    let model = field("model").unwrap_or("default model");
+    let started = field("timestamp").unwrap_or("");
+
+    let turns = turns_from_transcript(&messages);
+    if turns.is_empty() {
+        return Err(format!("Session {} has no messages to export", session_id));
+    }
+    let mut body: String = turns
+        .iter()
+        .map(|turn| {
+            let label = if turn.role == "user" { "User" } else { "Assistant" };
+            format!(
+                "<section class=\"message {}\">\n<div class=\"role\">{}</div>\n{}</section>\n",
+                turn.role,
+                label,
+                render_markdown(&turn.text)
+            )
+        })
+        .collect();
+    // The last turn is where the run stopped, not the end of the conversation
+    if was_cancelled(&messages) {
+        body.push_str("<div class=\"notice\"><strong>Cancelled</strong> &middot; this run was stopped before it finished</div>\n");
+    }
+    let page = format!(
+        "<!DOCTYPE html>\n<html lang=\"en\">\n<head>\n<meta charset=\"utf-8\">\n\
+         <meta name=\"viewport\" content=\"width=device-width, initial-scale=1\">\n\
+         <title>{provider} session {session}</title>\n<style>{style}</style>\n</head>\n<body>\n<main>\n\
+         <header>\n<h1>{provider} &middot; {model}</h1>\n\
+         <div class=\"meta\">Session {session} &middot; started {started} &middot; exported {exported}</div>\n\
+         </header>\n{body}</main>\n</body>\n</html>\n",
+        provider = escape(provider),
+        model = escape(model),
+        session = escape(session_id),
+        started = escape(started),
+        exported = Utc::now().format("%Y-%m-%d %H:%M UTC"),
+        style = STYLE,
+        body = body,
+    );
+
+    // <project>/sessions/<id>.jsonl -> <project>/exports/<id>.html
+    let exports = transcript
+        .parent()
+        .and_then(|sessions| sessions.parent())
+        .ok_or_else(|| format!("Unexpected transcript location {}", transcript.display()))?
+        .join("exports");
+    fs::create_dir_all(&exports).map_err(|e| format!("Cannot create {}: {}", exports.display(), e))?;
+    let path = exports.join(format!("{}.html", session_id));
+    fs::write(&path, page).map_err(|e| format!("Failed to write {}: {}", path.display(), e))?;
+    Ok(path)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_render_markdown() {
+        assert_eq!(
+            render_markdown("# Title\nUse `a<b` and **bold** _it_ [x](https://x.dev)\nsnake_case_name"),
+            "<h1>Title</h1>\n<p>Use <code>a&lt;b</code> and <strong>bold</strong> <em>it</em> \
+             <a href=\"https://x.dev\">x</a><br>\nsnake_case_name</p>\n"
+        );
+        assert_eq!(
+            render_markdown("- one\n- two\n\n1. first"),
+            "<ul>\n<li>one</li>\n<li>two</li>\n</ul>\n<ol>\n<li>first</li>\n</ol>\n"
+        );
+        assert_eq!(
+            render_markdown("```rust\nlet s = \"hi\"; // note\n```"),
+            "<pre><span class=\"lang\">rust</span><code class=\"language-rust\"><span class=\"tok-k\">let</span> s = \
+             <span class=\"tok-s\">&quot;hi&quot;</span>; <span class=\"tok-c\">// note</span></code></pre>\n"
+        );
+        assert_eq!(render_markdown("[x](javascript:alert(1))"), "<p>[x](javascript:alert(1))</p>\n");
+    }
+}
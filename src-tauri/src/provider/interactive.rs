@@ -0,0 +1,135 @@
+use regex::Regex;
+use std::sync::OnceLock;
+use std::time::Duration;
+
+/// Settings key: answer model download prompts with "y" ("true"); off by default
+pub const AUTO_ACCEPT_DOWNLOADS_KEY: &str = "auto_accept_downloads";
+
+/// How long stdout may sit on an unterminated line before it is checked for a prompt
+pub const PROMPT_IDLE: Duration = Duration::from_millis(500);
+
+fn prompt_pattern() -> &'static Regex {
+    static PATTERN: OnceLock<Regex> = OnceLock::new();
+    PATTERN.get_or_init(|| {
+        Regex::new(
+            r"(?i)(\[y/n\]|\(y/n\)|\[yes/no\]|\(yes/no\)|\by/n\b|press enter|continue\?|proceed\?|are you sure\?|\(default[^)]*\)\s*:?)\s*:?\s*$",
+        )
+        .expect("valid prompt pattern")
+    })
+}
+
+/// Whether a line of CLI output looks like it is waiting for an answer
+pub fn looks_like_input_request(text: &str) -> bool {
+    let text = text.trim_end();
+    !text.is_empty() && prompt_pattern().is_match(text)
+}
+
+fn download_pattern() -> &'static Regex {
+    static PATTERN: OnceLock<Regex> = OnceLock::new();
+    PATTERN.get_or_init(|| {
+        Regex::new(r"(?i)\b(download|pull|fetch)\w*\b.*\b(model|weights)\b|\b(model|weights)\b.*\b(download|pull|fetch)\w*")
+            .expect("valid download pattern")
+    })
+}
+
+/// Whether a line of CLI output asks to download a model before running,
+/// which hangs forever when stdin is closed
+pub fn looks_like_download_prompt(text: &str) -> bool {
+    let text = text.trim_end();
+    download_pattern().is_match(text) && (looks_like_input_request(text) || text.ends_with('?'))
+}
+
+/// Stdout bytes inspected for a terminal UI; later escape codes are left alone
+const TUI_PROBE_BYTES: usize = 16 * 1024;
+/// Cursor positioning codes in the probe window that mark a drawn screen
+const TUI_CURSOR_MOVES: usize = 10;
+/// DEC private modes only full-screen programs set: alternate screen buffers
+/// and mouse tracking
+const TUI_MODES: &[&str] = &["1049", "1047", "47", "1000", "1002", "1003", "1006"];
+
+/// Spots a CLI drawing a full-screen terminal UI instead of printing lines.
+///
+/// Colors and spinners (SGR codes, line erases) are normal CLI output; what
+/// gives a TUI away is switching to the alternate screen, enabling mouse
+/// tracking or positioning the cursor over and over.
+#[derive(Debug, Default)]
+pub struct TuiDetector {
+    probed: usize,
+    cursor_moves: usize,
+}
+
+impl TuiDetector {
+    /// Inspect the next chunk of stdout; true once it looks like a TUI
+    pub fn feed(&mut self, bytes: &[u8]) -> bool {
+        if self.probed >= TUI_PROBE_BYTES {
+            return false;
+        }
+        let bytes = &bytes[..bytes.len().min(TUI_PROBE_BYTES - self.probed)];
+        self.probed += bytes.len();
+        let mut i = 0;
+        while i + 1 < bytes.len() {
+            if bytes[i] != 0x1b || bytes[i + 1] != b'[' {
+                i += 1;
+                continue;
+            }
+            // CSI: parameters and intermediates, then one final byte
+            let start = i + 2;
+            let mut end = start;
+            while end < bytes.len() && (0x20..0x40).contains(&bytes[end]) {
+                end += 1;
+            }
+            let last = match bytes.get(end) {
+                Some(&last) => last,
+                None => break,
+            };
+            let params = &bytes[start..end];
+            match last {
+                b'h' if params.first() == Some(&b'?') => {
+                    let modes = String::from_utf8_lossy(&params[1..]);
+                    if modes.split(';').any(|m| TUI_MODES.contains(&m)) {
+                        return true;
+                    }
+                }
+                b'H' | b'f' => self.cursor_moves += 1,
+                _ => {}
+            }
+            i = end + 1;
+        }
+        self.cursor_moves >= TUI_CURSOR_MOVES
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_looks_like_input_request() {
+        assert!(looks_like_input_request("Apply this patch? [y/N] "));
+        assert!(looks_like_input_request("Overwrite file (yes/no):"));
+        assert!(looks_like_input_request("Do you want to proceed?"));
+        assert!(looks_like_input_request("Press Enter to continue"));
+        assert!(!looks_like_input_request("I will proceed with the refactor."));
+        assert!(!looks_like_input_request(""));
+    }
+
+    #[test]
+    fn test_tui_detector() {
+        assert!(TuiDetector::default().feed(b"\x1b[?1049h\x1b[H"));
+        let mut colored = TuiDetector::default();
+        for _ in 0..100 {
+            assert!(!colored.feed(b"\x1b[32mok\x1b[0m \r\x1b[2K spinner\n"));
+        }
+        let mut drawn = TuiDetector::default();
+        let screen: Vec<u8> = (1..=12).flat_map(|row| format!("\x1b[{};1Hline", row).into_bytes()).collect();
+        assert!(drawn.feed(&screen));
+    }
+
+    #[test]
+    fn test_looks_like_download_prompt() {
+        assert!(looks_like_download_prompt("Model gemma-2b is not available locally. Download it? [y/N]"));
+        assert!(looks_like_download_prompt("Pull model weights (4.1 GB)?"));
+        assert!(!looks_like_download_prompt("Downloading model weights: 42%"));
+        assert!(!looks_like_download_prompt("Apply this patch? [y/N]"));
+    }
+}
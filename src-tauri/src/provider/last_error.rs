@@ -0,0 +1,75 @@
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::sync::{Mutex, OnceLock};
+use tauri::AppHandle;
+
+use super::settings::{read_setting, write_setting};
+
+/// The most recent failure seen for a provider
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ProviderError {
+    pub provider: String,
+    pub session_id: Option<String>,
+    pub message: String,
+    pub exit_code: Option<i32>,
+    pub timestamp: DateTime<Utc>,
+}
+
+fn setting_key(provider: &str) -> String {
+    format!("last_error_{}", provider)
+}
+
+fn cache() -> &'static Mutex<HashMap<String, ProviderError>> {
+    static CACHE: OnceLock<Mutex<HashMap<String, ProviderError>>> = OnceLock::new();
+    CACHE.get_or_init(|| Mutex::new(HashMap::new()))
+}
+
+/// Remember a provider failure in memory and in the settings database so it
+/// survives a restart
+pub fn record(
+    app: &AppHandle,
+    provider: &str,
+    session_id: Option<&str>,
+    message: &str,
+    exit_code: Option<i32>,
+) {
+    let entry = ProviderError {
+        provider: provider.to_string(),
+        session_id: session_id.map(|s| s.to_string()),
+        message: message.to_string(),
+        exit_code,
+        timestamp: Utc::now(),
+    };
+    if let Ok(json) = serde_json::to_string(&entry) {
+        if let Err(e) = write_setting(app, &setting_key(provider), &json) {
+            log::warn!("Failed to persist last {} error: {}", provider, e);
+        }
+    }
+    if let Ok(mut cache) = cache().lock() {
+        cache.insert(provider.to_string(), entry);
+    }
+}
+
+/// Last recorded failure for a provider, if any
+pub fn get(app: &AppHandle, provider: &str) -> Option<ProviderError> {
+    if let Some(entry) = cache().lock().ok().and_then(|c| c.get(provider).cloned()) {
+        return Some(entry);
+    }
+    read_setting(app, &setting_key(provider)).and_then(|json| serde_json::from_str(&json).ok())
+}
+
+/// Short description of a failed run: the last non-empty stderr line, or the
+/// exit code when stderr was silent
+pub fn summarize_failure(exit_code: Option<i32>, stderr_tail: &str) -> String {
+    stderr_tail
+        .lines()
+        .rev()
+        .map(str::trim)
+        .find(|l| !l.is_empty())
+        .map(|l| l.to_string())
+        .unwrap_or_else(|| match exit_code {
+            Some(code) => format!("Process exited with code {}", code),
+            None => "Process terminated by a signal".to_string(),
+        })
+}
@@ -0,0 +1,84 @@
+use tauri::AppHandle;
+use tokio::process::Command;
+
+use super::settings::read_setting;
+
+/// Shell used when `$SHELL` isn't set
+#[cfg(unix)]
+const FALLBACK_SHELL: &str = "/bin/sh";
+
+/// Settings key: launch a provider's CLI through the user's login shell ("true")
+pub fn use_login_shell_key(provider: &str) -> String {
+    format!("{}_use_login_shell", provider)
+}
+
+/// Whether a provider's CLI is launched through the login shell
+pub fn enabled(app: &AppHandle, provider: &str) -> bool {
+    read_setting(app, &use_login_shell_key(provider)).as_deref() == Some("true")
+}
+
+/// Quote an argument for a POSIX shell: wrapped in single quotes, with
+/// embedded single quotes closed, escaped and reopened
+pub fn quote(arg: &str) -> String {
+    format!("'{}'", arg.replace('\'', r"'\''"))
+}
+
+/// Script the login shell runs: `exec` keeps the CLI on the shell's pid, so
+/// cancelling and killing the session reach the CLI itself
+pub fn script(program: &str, args: &[String]) -> String {
+    std::iter::once(program)
+        .chain(args.iter().map(String::as_str))
+        .map(quote)
+        .fold(String::from("exec"), |mut script, word| {
+            script.push(' ');
+            script.push_str(&word);
+            script
+        })
+}
+
+/// Rebuild `cmd` to run as `$SHELL -lc "exec <program> <args>"`, for CLIs
+/// only reachable after the shell's init files ran (nvm, asdf, direnv).
+///
+/// Working directory and explicitly set variables carry over; everything
+/// else comes from the shell's own initialization. Windows has no login
+/// shell, so the command is left as is there.
+#[cfg(unix)]
+pub fn wrap(cmd: &mut Command) {
+    let std_cmd = cmd.as_std();
+    let program = std_cmd.get_program().to_string_lossy().to_string();
+    let args: Vec<String> = std_cmd.get_args().map(|a| a.to_string_lossy().to_string()).collect();
+    let shell = std::env::var("SHELL")
+        .ok()
+        .filter(|s| !s.trim().is_empty())
+        .unwrap_or_else(|| FALLBACK_SHELL.to_string());
+
+    let mut wrapped = Command::new(shell);
+    wrapped.arg("-lc").arg(script(&program, &args));
+    for (key, value) in std_cmd.get_envs() {
+        match value {
+            Some(value) => wrapped.env(key, value),
+            None => wrapped.env_remove(key),
+        };
+    }
+    if let Some(dir) = std_cmd.get_current_dir() {
+        wrapped.current_dir(dir);
+    }
+    *cmd = wrapped;
+}
+
+#[cfg(not(unix))]
+pub fn wrap(_cmd: &mut Command) {}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_script() {
+        assert_eq!(quote("it's"), r"'it'\''s'");
+        assert_eq!(
+            script("/opt/codex", &["-m".to_string(), "say \"hi\" $HOME".to_string()]),
+            r#"exec '/opt/codex' '-m' 'say "hi" $HOME'"#
+        );
+    }
+}
@@ -0,0 +1,99 @@
+use chrono::{DateTime, Utc};
+use log::Level;
+use serde::Serialize;
+use std::collections::VecDeque;
+use std::sync::{Mutex, OnceLock};
+use tauri::{AppHandle, Manager};
+
+/// Number of entries kept for in-app diagnostics
+const RING_CAPACITY: usize = 500;
+
+/// A structured log entry for a provider session event
+#[derive(Debug, Clone, Serialize)]
+pub struct LogEntry {
+    pub timestamp: DateTime<Utc>,
+    pub level: String,
+    /// Lifecycle event: spawn, stream, retry, complete, cancel
+    pub event: String,
+    pub provider: String,
+    pub session_id: Option<String>,
+    pub pid: Option<u32>,
+    pub message: String,
+}
+
+fn ring() -> &'static Mutex<VecDeque<LogEntry>> {
+    static RING: OnceLock<Mutex<VecDeque<LogEntry>>> = OnceLock::new();
+    RING.get_or_init(|| Mutex::new(VecDeque::with_capacity(RING_CAPACITY)))
+}
+
+/// Log a session event with consistent `provider`/`session`/`pid` fields and
+/// keep a copy in the in-memory ring buffer.
+pub fn session_event(
+    level: Level,
+    event: &str,
+    provider: &str,
+    session_id: Option<&str>,
+    pid: Option<u32>,
+    message: impl Into<String>,
+) {
+    let message = message.into();
+    log::log!(
+        level,
+        "[{}] event={} session={} pid={} {}",
+        provider,
+        event,
+        session_id.unwrap_or("-"),
+        pid.map(|p| p.to_string()).unwrap_or_else(|| "-".to_string()),
+        message
+    );
+
+    let entry = LogEntry {
+        timestamp: Utc::now(),
+        level: level.to_string(),
+        event: event.to_string(),
+        provider: provider.to_string(),
+        session_id: session_id.map(|s| s.to_string()),
+        pid,
+        message,
+    };
+    if let Ok(mut ring) = ring().lock() {
+        if ring.len() == RING_CAPACITY {
+            ring.pop_front();
+        }
+        ring.push_back(entry);
+    }
+}
+
+/// Log a cancel request, resolving the session id from the registry by pid
+pub fn log_cancel(app: &AppHandle, provider: &str, pid: Option<u32>) {
+    let registry = app.state::<crate::process::ProcessRegistryState>();
+    let session_id = registry
+        .0
+        .get_running_chat_sessions(Some(provider))
+        .unwrap_or_default()
+        .into_iter()
+        .find(|info| Some(info.pid) == pid)
+        .and_then(|info| match info.process_type {
+            crate::process::ProcessType::ChatSession { session_id, .. } => Some(session_id),
+            _ => None,
+        });
+    session_event(
+        Level::Info,
+        "cancel",
+        provider,
+        session_id.as_deref(),
+        pid,
+        "cancel requested",
+    );
+}
+
+/// Most recent entries, oldest first
+pub fn recent(limit: usize) -> Vec<LogEntry> {
+    match ring().lock() {
+        Ok(ring) => {
+            let skip = ring.len().saturating_sub(limit);
+            ring.iter().skip(skip).cloned().collect()
+        }
+        Err(_) => Vec::new(),
+    }
+}
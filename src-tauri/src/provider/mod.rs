@@ -0,0 +1,90 @@
+use serde::{Deserialize, Serialize};
+
+pub mod attachments;
+pub mod capabilities;
+pub mod citations;
+pub mod compact;
+pub mod config_files;
+pub mod control;
+pub mod default_model;
+pub mod diagnostics;
+pub mod diff;
+pub mod events;
+pub mod framing;
+pub mod html_export;
+pub mod interactive;
+pub mod last_error;
+pub mod login_shell;
+pub mod logs;
+pub mod model_args;
+pub mod model_usage;
+pub mod normalize;
+pub mod probe;
+pub mod profiles;
+pub mod project_env;
+pub mod reasoning;
+pub mod reassemble;
+pub mod redact;
+pub mod replay;
+pub mod retry;
+pub mod run_once;
+pub mod runner;
+pub mod session_title;
+pub mod settings;
+pub mod settings_snapshot;
+pub mod shutdown;
+pub mod storage;
+pub mod streaming;
+pub mod structured;
+pub mod tags;
+pub mod transcript;
+pub mod warmup;
+pub mod websocket;
+
+/// Optional settings accepted by the Codex/Gemini execute commands.
+///
+/// Every field is optional so older frontends can keep calling the commands
+/// without passing anything.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct ChatOptions {
+    /// Total attempts (including the first) when the CLI fails transiently
+    pub max_attempts: Option<u32>,
+    /// Delay before the first retry in milliseconds; doubled on every attempt
+    pub retry_backoff_ms: Option<u64>,
+    /// Also write the prompt to stdin. When unset, decided by the CLI
+    /// capability probe (only if the CLI can't take the prompt as an argument)
+    pub prompt_via_stdin: Option<bool>,
+    /// Also append every stdout line (redacted, before normalization) to this
+    /// file; relative paths resolve against the project. For headless runs.
+    pub output_file: Option<String>,
+    /// Files (e.g. screenshots) passed to the CLI through its attach flag
+    pub attachments: Option<Vec<String>>,
+    /// Refuse to start when the session storage is unwritable or nearly full
+    pub preflight_storage: Option<bool>,
+    /// Keep stdin open so the CLI can ask for confirmations that the user
+    /// answers through `send_to_*_session`
+    pub interactive: Option<bool>,
+    /// Directory the CLI runs in (absolute or relative to the project). The
+    /// project path stays the key for history and transcripts.
+    pub working_dir: Option<String>,
+    /// Join JSON values pretty-printed across several stdout lines before
+    /// emitting them; plain text is unaffected
+    pub reassemble_json: Option<bool>,
+    /// Reasoning effort / thinking level; falls back to the project default
+    pub reasoning_effort: Option<reasoning::ReasoningEffort>,
+    /// Extra CLI arguments inserted before the prompt
+    pub extra_args: Option<Vec<String>>,
+    /// Request JSON output; the final answer is validated and emitted as a
+    /// `<provider>-structured-result` event
+    pub response_format: Option<structured::ResponseFormat>,
+    /// Also write the CLI's stdout/stderr lines, before normalization, to
+    /// `<session_id>.raw.log` next to the transcript
+    pub persist_raw: Option<bool>,
+    /// Read stdout and stderr through one loop so their lines keep their real
+    /// arrival order; each line is also emitted as a `<provider>-stream` event
+    /// tagged with `source` ("stdout" or "stderr")
+    pub combined_stream: Option<bool>,
+    /// Kill the CLI and emit an error once it printed more than this many
+    /// stdout and stderr lines; falls back to the stored default, 0 means no cap
+    pub max_output_lines: Option<usize>,
+}
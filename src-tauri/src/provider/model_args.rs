@@ -0,0 +1,135 @@
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use tauri::AppHandle;
+
+/// How the flags of a chat command change for the models a rule matches
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ModelArgRule {
+    /// Glob matched against the model name, e.g. `o3*`
+    pub model: String,
+    /// Flags the model rejects; dropped with their value
+    #[serde(default)]
+    pub drop: Vec<String>,
+    /// Flags the model knows under another name (old -> new)
+    #[serde(default)]
+    pub rename: HashMap<String, String>,
+}
+
+/// Flags reasoning models reject: they sample at a fixed temperature
+const SAMPLING_FLAGS: &[&str] = &["--temperature", "--top-p", "--top_p"];
+
+/// Codex models that reason and so refuse sampling settings
+const CODEX_REASONING_MODELS: &[&str] = &["o1*", "o3*", "o4*", "gpt-5*", "codex-*"];
+
+/// Gemini models that predate thinking budgets
+const GEMINI_NON_THINKING_MODELS: &[&str] = &["gemini-1.*", "gemini-2.0-*"];
+
+/// Settings key holding the rules for a provider
+pub fn rules_key(provider: &str) -> String {
+    format!("{}_model_arg_rules", provider)
+}
+
+/// Rules used until the user stores their own for a provider
+pub fn default_rules(provider: &str) -> Vec<ModelArgRule> {
+    let rule = |model: &str, drop: &[&str]| ModelArgRule {
+        model: model.to_string(),
+        drop: drop.iter().map(|f| f.to_string()).collect(),
+        rename: HashMap::new(),
+    };
+    match provider {
+        "codex" => CODEX_REASONING_MODELS.iter().map(|m| rule(m, SAMPLING_FLAGS)).collect(),
+        "gemini" => GEMINI_NON_THINKING_MODELS
+            .iter()
+            .map(|m| rule(m, super::capabilities::THINKING_FLAGS))
+            .collect(),
+        _ => Vec::new(),
+    }
+}
+
+/// Rules stored for `provider`, or the built-in ones when none are stored
+/// (or they are malformed)
+pub fn load_rules(app: &AppHandle, provider: &str) -> Vec<ModelArgRule> {
+    super::settings::read_setting(app, &rules_key(provider))
+        .filter(|raw| !raw.is_empty())
+        .and_then(|raw| serde_json::from_str::<Vec<ModelArgRule>>(&raw).ok())
+        .unwrap_or_else(|| default_rules(provider))
+}
+
+/// Reject rules whose model glob doesn't compile or that name no flag
+pub fn validate(rules: &[ModelArgRule]) -> Result<(), String> {
+    for rule in rules {
+        glob::Pattern::new(&rule.model).map_err(|e| format!("Invalid model pattern '{}': {}", rule.model, e))?;
+        let mut flags = rule.drop.iter().chain(rule.rename.keys()).chain(rule.rename.values());
+        if let Some(flag) = flags.find(|f| !f.starts_with('-')) {
+            return Err(format!("'{}' is not a flag", flag));
+        }
+    }
+    Ok(())
+}
+
+/// Flag name of an argument: `--temperature` for both `--temperature` and
+/// `--temperature=0.2`
+fn flag_name(arg: &str) -> &str {
+    arg.split_once('=').map(|(name, _)| name).unwrap_or(arg)
+}
+
+/// Rewrite `args` for `model`: flags a matching rule drops are removed with
+/// their value (`--flag=value`, or the next argument unless that is a flag
+/// itself) and renamed flags keep their value. Without a model nothing changes.
+pub fn apply(rules: &[ModelArgRule], model: &str, args: Vec<String>) -> Vec<String> {
+    let matching: Vec<&ModelArgRule> = rules
+        .iter()
+        .filter(|r| !model.is_empty() && glob::Pattern::new(&r.model).map(|p| p.matches(model)).unwrap_or(false))
+        .collect();
+    if matching.is_empty() {
+        return args;
+    }
+
+    let mut out = Vec::with_capacity(args.len());
+    let mut iter = args.into_iter().peekable();
+    while let Some(arg) = iter.next() {
+        let name = flag_name(&arg);
+        if matching.iter().any(|r| r.drop.iter().any(|f| f == name)) {
+            log::info!("Dropping {} for model {}", name, model);
+            if name == arg && iter.peek().is_some_and(|next| !next.starts_with('-')) {
+                iter.next();
+            }
+            continue;
+        }
+        match matching.iter().find_map(|r| r.rename.get(name)) {
+            Some(renamed) => out.push(format!("{}{}", renamed, &arg[name.len()..])),
+            None => out.push(arg),
+        }
+    }
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn args(list: &[&str]) -> Vec<String> {
+        list.iter().map(|a| a.to_string()).collect()
+    }
+
+    #[test]
+    fn test_apply() {
+        let rules = default_rules("codex");
+        assert_eq!(
+            apply(&rules, "o3-mini", args(&["--temperature", "0.2", "--top-p=0.9", "-c", "x=1"])),
+            args(&["-c", "x=1"])
+        );
+        assert_eq!(apply(&rules, "gpt-4.1", args(&["--temperature", "0.2"])), args(&["--temperature", "0.2"]));
+
+        let rename = vec![ModelArgRule {
+            model: "*".into(),
+            drop: Vec::new(),
+            rename: HashMap::from([("--max-tokens".to_string(), "--max-output-tokens".to_string())]),
+        }];
+        assert_eq!(
+            apply(&rename, "any", args(&["--max-tokens=10", "--max-tokens", "5"])),
+            args(&["--max-output-tokens=10", "--max-output-tokens", "5"])
+        );
+        assert_eq!(apply(&rename, "", args(&["--max-tokens"])), args(&["--max-tokens"]));
+    }
+}
@@ -0,0 +1,101 @@
+use chrono::{DateTime, Utc};
+use rusqlite::Connection;
+use serde::Serialize;
+use tauri::AppHandle;
+
+use super::settings::open_db;
+
+/// Days after which a use counts half as much towards the ranking
+const HALF_LIFE_DAYS: f64 = 14.0;
+
+/// Models returned when no limit is given
+pub const DEFAULT_FREQUENT_LIMIT: usize = 5;
+
+/// How often a model was used, for surfacing it in the model picker
+#[derive(Debug, Clone, Serialize)]
+pub struct FrequentModel {
+    pub model: String,
+    /// Successful runs, all time
+    pub uses: i64,
+    pub last_used_at: String,
+    /// Uses weighted by age; what the list is ranked by
+    pub score: f64,
+}
+
+fn ensure_table(conn: &Connection) -> Result<(), String> {
+    conn.execute(
+        "CREATE TABLE IF NOT EXISTS model_usage (
+            provider TEXT NOT NULL,
+            model TEXT NOT NULL,
+            uses INTEGER NOT NULL DEFAULT 0,
+            score REAL NOT NULL DEFAULT 0,
+            last_used_at TEXT NOT NULL,
+            PRIMARY KEY (provider, model)
+        )",
+        [],
+    )
+    .map_err(|e| e.to_string())?;
+    Ok(())
+}
+
+/// `score` as of `now`: halved for every `HALF_LIFE_DAYS` since the last use
+fn decayed(score: f64, last_used_at: &str, now: DateTime<Utc>) -> f64 {
+    let last = match DateTime::parse_from_rfc3339(last_used_at) {
+        Ok(last) => last.with_timezone(&Utc),
+        Err(_) => return 0.0,
+    };
+    let days = (now - last).num_seconds().max(0) as f64 / 86_400.0;
+    score * 0.5f64.powf(days / HALF_LIFE_DAYS)
+}
+
+/// Count a successful run of `model`
+pub fn record_use(app: &AppHandle, provider: &str, model: &str) -> Result<(), String> {
+    if model.is_empty() {
+        return Ok(());
+    }
+    let conn = open_db(app)?;
+    ensure_table(&conn)?;
+    let now = Utc::now();
+    let previous: Option<(f64, String)> = conn
+        .query_row(
+            "SELECT score, last_used_at FROM model_usage WHERE provider = ?1 AND model = ?2",
+            rusqlite::params![provider, model],
+            |row| Ok((row.get(0)?, row.get(1)?)),
+        )
+        .ok();
+    let score = previous.map(|(score, last)| decayed(score, &last, now)).unwrap_or(0.0) + 1.0;
+    conn.execute(
+        "INSERT INTO model_usage (provider, model, uses, score, last_used_at) VALUES (?1, ?2, 1, ?3, ?4)
+         ON CONFLICT(provider, model) DO UPDATE SET
+            uses = uses + 1, score = excluded.score, last_used_at = excluded.last_used_at",
+        rusqlite::params![provider, model, score, now.to_rfc3339()],
+    )
+    .map_err(|e| e.to_string())?;
+    Ok(())
+}
+
+/// A provider's most used models, recent use weighing more than old use
+pub fn frequent(app: &AppHandle, provider: &str, limit: usize) -> Result<Vec<FrequentModel>, String> {
+    let conn = open_db(app)?;
+    ensure_table(&conn)?;
+    let mut stmt = conn
+        .prepare("SELECT model, uses, score, last_used_at FROM model_usage WHERE provider = ?1")
+        .map_err(|e| e.to_string())?;
+    let now = Utc::now();
+    let mut models = stmt
+        .query_map([provider], |row| {
+            let last_used_at: String = row.get(3)?;
+            Ok(FrequentModel {
+                model: row.get(0)?,
+                uses: row.get(1)?,
+                score: decayed(row.get(2)?, &last_used_at, now),
+                last_used_at,
+            })
+        })
+        .map_err(|e| e.to_string())?
+        .collect::<Result<Vec<_>, _>>()
+        .map_err(|e| e.to_string())?;
+    models.sort_by(|a, b| b.score.total_cmp(&a.score).then_with(|| b.last_used_at.cmp(&a.last_used_at)));
+    models.truncate(limit);
+    Ok(models)
+}
@@ -0,0 +1,69 @@
+use regex::Regex;
+use serde::{Deserialize, Serialize};
+use tauri::AppHandle;
+
+/// A single user-defined output transform (regex pattern -> replacement)
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct NormalizationRule {
+    pub pattern: String,
+    /// Replacement text; supports `$1` / `${name}` capture references
+    pub replacement: String,
+}
+
+/// Settings key holding the rules for a provider
+pub fn rules_key(provider: &str) -> String {
+    format!("{}_normalization_rules", provider)
+}
+
+/// Applies an ordered list of regex rules to every output line
+pub struct Normalizer {
+    rules: Vec<(Regex, String)>,
+}
+
+impl Normalizer {
+    /// Compile `rules`; invalid patterns are logged and skipped
+    pub fn new(rules: &[NormalizationRule]) -> Self {
+        let rules = rules
+            .iter()
+            .filter_map(|r| match Regex::new(&r.pattern) {
+                Ok(re) => Some((re, r.replacement.clone())),
+                Err(e) => {
+                    log::warn!("Ignoring invalid normalization rule '{}': {}", r.pattern, e);
+                    None
+                }
+            })
+            .collect();
+        Self { rules }
+    }
+
+    /// Build a normalizer from the rules stored for `provider`
+    pub fn load(app: &AppHandle, provider: &str) -> Self {
+        Self::new(&load_rules(app, provider))
+    }
+
+    /// Apply every rule in order.
+    ///
+    /// Returns `None` when a non-empty line is rewritten to nothing, which lets
+    /// users drop noise lines entirely.
+    pub fn apply(&self, line: &str) -> Option<String> {
+        if self.rules.is_empty() {
+            return Some(line.to_string());
+        }
+        let mut out = line.to_string();
+        for (re, replacement) in &self.rules {
+            out = re.replace_all(&out, replacement.as_str()).into_owned();
+        }
+        if out.is_empty() && !line.is_empty() {
+            None
+        } else {
+            Some(out)
+        }
+    }
+}
+
+/// Rules stored for `provider` (empty when unset or malformed)
+pub fn load_rules(app: &AppHandle, provider: &str) -> Vec<NormalizationRule> {
+    super::settings::read_setting(app, &rules_key(provider))
+        .and_then(|raw| serde_json::from_str::<Vec<NormalizationRule>>(&raw).ok())
+        .unwrap_or_default()
+}
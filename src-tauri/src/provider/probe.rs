@@ -0,0 +1,103 @@
+use std::io::{self, Read};
+use std::process::{Output, Stdio};
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::time::{Duration, Instant};
+use tauri::AppHandle;
+
+use super::settings::{read_setting, write_setting};
+
+/// Settings key: seconds a login/version/model-list probe may take
+pub const PROBE_TIMEOUT_KEY: &str = "probe_timeout_secs";
+/// Probe timeout used until the setting says otherwise
+pub const DEFAULT_PROBE_TIMEOUT_SECS: u64 = 10;
+/// Upper bound for the setting, so a typo can't bring back endless hangs
+const MAX_PROBE_TIMEOUT_SECS: u64 = 300;
+
+/// Poll interval while waiting for a blocking probe
+const POLL_INTERVAL: Duration = Duration::from_millis(20);
+
+/// Current timeout; kept in memory so blocking callers without an app handle
+/// (binary discovery) honour it too
+static TIMEOUT_SECS: AtomicU64 = AtomicU64::new(DEFAULT_PROBE_TIMEOUT_SECS);
+
+/// How long a probe may run
+pub fn timeout() -> Duration {
+    Duration::from_secs(TIMEOUT_SECS.load(Ordering::Relaxed))
+}
+
+/// Load the stored timeout; called at startup
+pub fn load(app: &AppHandle) {
+    if let Some(secs) = read_setting(app, PROBE_TIMEOUT_KEY).and_then(|v| v.trim().parse::<u64>().ok()) {
+        TIMEOUT_SECS.store(secs.clamp(1, MAX_PROBE_TIMEOUT_SECS), Ordering::Relaxed);
+    }
+}
+
+/// Store a new timeout and use it from now on; returns the value applied
+pub fn set(app: &AppHandle, secs: u64) -> Result<u64, String> {
+    let secs = secs.clamp(1, MAX_PROBE_TIMEOUT_SECS);
+    write_setting(app, PROBE_TIMEOUT_KEY, &secs.to_string())?;
+    TIMEOUT_SECS.store(secs, Ordering::Relaxed);
+    Ok(secs)
+}
+
+fn timeout_error(what: &str, timeout: Duration) -> String {
+    format!(
+        "`{}` did not finish within {}s; the CLI may be waiting on the network or for input",
+        what,
+        timeout.as_secs()
+    )
+}
+
+/// Run a probe to completion within `timeout()`.
+///
+/// The outer error is the timeout (the process is killed); the inner result
+/// is whatever running the command gave, so callers keep their fallbacks for
+/// spawn failures while a hang is reported as one.
+pub async fn output(cmd: &mut tokio::process::Command, what: &str) -> Result<io::Result<Output>, String> {
+    cmd.stdin(Stdio::null()).kill_on_drop(true);
+    let timeout = timeout();
+    tokio::time::timeout(timeout, cmd.output())
+        .await
+        .map_err(|_| timeout_error(what, timeout))
+}
+
+/// Blocking counterpart of `output` for callers outside the async runtime
+pub fn output_blocking(cmd: &mut std::process::Command, what: &str) -> Result<io::Result<Output>, String> {
+    cmd.stdin(Stdio::null()).stdout(Stdio::piped()).stderr(Stdio::piped());
+    let mut child = match cmd.spawn() {
+        Ok(child) => child,
+        Err(e) => return Ok(Err(e)),
+    };
+    // Drain the pipes on their own threads so a chatty CLI can't stall on a full pipe
+    let drain = |pipe: Option<Box<dyn Read + Send>>| {
+        std::thread::spawn(move || {
+            let mut buf = Vec::new();
+            if let Some(mut pipe) = pipe {
+                let _ = pipe.read_to_end(&mut buf);
+            }
+            buf
+        })
+    };
+    let stdout = drain(child.stdout.take().map(|p| Box::new(p) as Box<dyn Read + Send>));
+    let stderr = drain(child.stderr.take().map(|p| Box::new(p) as Box<dyn Read + Send>));
+
+    let timeout = timeout();
+    let deadline = Instant::now() + timeout;
+    let status = loop {
+        match child.try_wait() {
+            Ok(Some(status)) => break status,
+            Ok(None) if Instant::now() >= deadline => {
+                let _ = child.kill();
+                let _ = child.wait();
+                return Err(timeout_error(what, timeout));
+            }
+            Ok(None) => std::thread::sleep(POLL_INTERVAL),
+            Err(e) => return Ok(Err(e)),
+        }
+    };
+    Ok(Ok(Output {
+        status,
+        stdout: stdout.join().unwrap_or_default(),
+        stderr: stderr.join().unwrap_or_default(),
+    }))
+}
@@ -0,0 +1,288 @@
+use rusqlite::Connection;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::sync::{OnceLock, RwLock};
+use tauri::AppHandle;
+
+use super::settings::{open_db, read_setting, write_setting};
+
+/// Profile that owns the historical, un-namespaced settings keys
+pub const DEFAULT_PROFILE: &str = "default";
+
+/// Settings key holding the name of the active profile
+const ACTIVE_PROFILE_KEY: &str = "active_profile";
+
+/// Environment variables the base URL settings are passed to the CLIs through
+const CODEX_BASE_URL_ENV: &str = "OPENAI_BASE_URL";
+const GEMINI_BASE_URL_ENV: &str = "GOOGLE_GEMINI_BASE_URL";
+
+/// A named set of provider settings (e.g. personal vs work accounts)
+#[derive(Debug, Clone, Serialize)]
+pub struct Profile {
+    pub name: String,
+    pub active: bool,
+}
+
+/// Settings stored per profile besides the namespaced keys (binary paths and
+/// default models)
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct ProfileSettings {
+    pub codex_base_url: Option<String>,
+    pub gemini_base_url: Option<String>,
+    /// Passed to every provider CLI; project env overrides win over these
+    #[serde(default)]
+    pub env: HashMap<String, String>,
+    /// Pass the app's complete environment to the CLI instead of only the
+    /// allowlisted variables. Secrets from the app's environment (tokens,
+    /// cloud credentials) then reach the CLI and anything it runs, and show up
+    /// in command previews; only enable it for CLIs that need variables the
+    /// allowlist can't anticipate.
+    #[serde(default)]
+    pub codex_inherit_full_env: bool,
+    #[serde(default)]
+    pub gemini_inherit_full_env: bool,
+}
+
+impl ProfileSettings {
+    /// The `inherit_full_env` flag of a provider
+    pub fn inherit_full_env_mut(&mut self, provider: &str) -> &mut bool {
+        match provider {
+            "codex" => &mut self.codex_inherit_full_env,
+            _ => &mut self.gemini_inherit_full_env,
+        }
+    }
+}
+
+/// Active profile and its settings, so commands can be built without the DB
+struct ActiveProfile {
+    name: String,
+    settings: ProfileSettings,
+}
+
+fn active_cache() -> &'static RwLock<Option<ActiveProfile>> {
+    static ACTIVE: OnceLock<RwLock<Option<ActiveProfile>>> = OnceLock::new();
+    ACTIVE.get_or_init(|| RwLock::new(None))
+}
+
+fn ensure_table(conn: &Connection) -> Result<(), String> {
+    conn.execute(
+        "CREATE TABLE IF NOT EXISTS profiles (
+            name TEXT PRIMARY KEY,
+            settings TEXT NOT NULL DEFAULT '{}',
+            created_at TEXT NOT NULL DEFAULT CURRENT_TIMESTAMP
+        )",
+        [],
+    )
+    .map_err(|e| e.to_string())?;
+    Ok(())
+}
+
+/// Profile names end up in settings keys, so keep them simple
+pub fn validate_name(name: &str) -> Result<(), String> {
+    if name.is_empty() || !name.chars().all(|c| c.is_ascii_alphanumeric() || c == '-' || c == '_') {
+        return Err(format!("Invalid profile name {:?}: use letters, digits, '-' or '_'", name));
+    }
+    Ok(())
+}
+
+/// Settings key of `key` within a profile; the default profile keeps the bare key
+pub fn scoped_key(profile: &str, key: &str) -> String {
+    if profile == DEFAULT_PROFILE {
+        key.to_string()
+    } else {
+        format!("profile.{}.{}", profile, key)
+    }
+}
+
+/// Name of the active profile
+pub fn active_name(app: &AppHandle) -> String {
+    if let Some(active) = active_cache().read().ok().and_then(|a| a.as_ref().map(|a| a.name.clone())) {
+        return active;
+    }
+    read_setting(app, ACTIVE_PROFILE_KEY)
+        .filter(|n| !n.is_empty())
+        .unwrap_or_else(|| DEFAULT_PROFILE.to_string())
+}
+
+/// Read a setting of the active profile; keys a profile never set fall back
+/// to the default profile's value
+pub fn read_profile_setting(app: &AppHandle, key: &str) -> Option<String> {
+    let profile = active_name(app);
+    if profile != DEFAULT_PROFILE {
+        if let Some(value) = read_setting(app, &scoped_key(&profile, key)) {
+            return Some(value);
+        }
+    }
+    read_setting(app, key)
+}
+
+/// Write a setting of the active profile
+pub fn write_profile_setting(app: &AppHandle, key: &str, value: &str) -> Result<(), String> {
+    write_setting(app, &scoped_key(&active_name(app), key), value)
+}
+
+fn exists(conn: &Connection, name: &str) -> Result<bool, String> {
+    ensure_table(conn)?;
+    conn.query_row("SELECT 1 FROM profiles WHERE name = ?1", [name], |_| Ok(()))
+        .map(|_| true)
+        .or_else(|e| match e {
+            rusqlite::Error::QueryReturnedNoRows => Ok(false),
+            e => Err(e.to_string()),
+        })
+}
+
+/// Settings of a profile (defaults when none were saved)
+pub fn load_settings(conn: &Connection, name: &str) -> Result<ProfileSettings, String> {
+    ensure_table(conn)?;
+    let raw: Option<String> = conn
+        .query_row("SELECT settings FROM profiles WHERE name = ?1", [name], |row| row.get(0))
+        .ok();
+    match raw {
+        Some(raw) => serde_json::from_str(&raw).map_err(|e| e.to_string()),
+        None => Ok(ProfileSettings::default()),
+    }
+}
+
+/// Replace the settings of an existing profile (the default one always exists)
+pub fn save_settings(app: &AppHandle, name: &str, settings: &ProfileSettings) -> Result<(), String> {
+    for key in settings.env.keys() {
+        super::project_env::validate_key(key)?;
+    }
+    let conn = open_db(app)?;
+    if name != DEFAULT_PROFILE && !exists(&conn, name)? {
+        return Err(format!("No profile named {}", name));
+    }
+    let raw = serde_json::to_string(settings).map_err(|e| e.to_string())?;
+    conn.execute(
+        "INSERT INTO profiles (name, settings) VALUES (?1, ?2)
+         ON CONFLICT(name) DO UPDATE SET settings = excluded.settings",
+        rusqlite::params![name, raw],
+    )
+    .map_err(|e| e.to_string())?;
+    if name == active_name(app) {
+        load_active(app)?;
+    }
+    Ok(())
+}
+
+/// Every profile, the default one first
+pub fn list(app: &AppHandle) -> Result<Vec<Profile>, String> {
+    let conn = open_db(app)?;
+    ensure_table(&conn)?;
+    let mut stmt = conn
+        .prepare("SELECT name FROM profiles WHERE name != ?1 ORDER BY name")
+        .map_err(|e| e.to_string())?;
+    let names = stmt
+        .query_map([DEFAULT_PROFILE], |row| row.get::<_, String>(0))
+        .map_err(|e| e.to_string())?
+        .collect::<Result<Vec<_>, _>>()
+        .map_err(|e| e.to_string())?;
+    let active = active_name(app);
+    Ok(std::iter::once(DEFAULT_PROFILE.to_string())
+        .chain(names)
+        .map(|name| Profile { active: name == active, name })
+        .collect())
+}
+
+/// Add an empty profile; until set, its keys fall back to the default profile
+pub fn create(app: &AppHandle, name: &str) -> Result<Profile, String> {
+    validate_name(name)?;
+    let conn = open_db(app)?;
+    if name == DEFAULT_PROFILE || exists(&conn, name)? {
+        return Err(format!("Profile {} already exists", name));
+    }
+    conn.execute("INSERT INTO profiles (name) VALUES (?1)", [name])
+        .map_err(|e| e.to_string())?;
+    Ok(Profile { name: name.to_string(), active: false })
+}
+
+/// Remove a profile and its namespaced settings; the active and the default
+/// profile can't be deleted
+pub fn delete(app: &AppHandle, name: &str) -> Result<(), String> {
+    validate_name(name)?;
+    if name == DEFAULT_PROFILE {
+        return Err("The default profile can't be deleted".to_string());
+    }
+    if name == active_name(app) {
+        return Err(format!("Profile {} is active; switch to another one first", name));
+    }
+    let conn = open_db(app)?;
+    if !exists(&conn, name)? {
+        return Err(format!("No profile named {}", name));
+    }
+    conn.execute("DELETE FROM profiles WHERE name = ?1", [name])
+        .map_err(|e| e.to_string())?;
+    conn.execute(
+        "CREATE TABLE IF NOT EXISTS app_settings (key TEXT PRIMARY KEY, value TEXT)",
+        [],
+    )
+    .map_err(|e| e.to_string())?;
+    // Prefix match without LIKE, whose `_` wildcard would hit other profiles
+    let prefix = scoped_key(name, "");
+    conn.execute(
+        "DELETE FROM app_settings WHERE substr(key, 1, length(?1)) = ?1",
+        [prefix],
+    )
+    .map_err(|e| e.to_string())?;
+    Ok(())
+}
+
+/// Make `name` the active profile
+pub fn switch(app: &AppHandle, name: &str) -> Result<(), String> {
+    validate_name(name)?;
+    let conn = open_db(app)?;
+    if name != DEFAULT_PROFILE && !exists(&conn, name)? {
+        return Err(format!("No profile named {}", name));
+    }
+    write_setting(app, ACTIVE_PROFILE_KEY, name)?;
+    load_active(app)
+}
+
+/// Load the active profile's settings for `command_env`; called at startup
+/// and whenever the active profile or its settings change
+pub fn load_active(app: &AppHandle) -> Result<(), String> {
+    let name = read_setting(app, ACTIVE_PROFILE_KEY)
+        .filter(|n| !n.is_empty())
+        .unwrap_or_else(|| DEFAULT_PROFILE.to_string());
+    let settings = load_settings(&open_db(app)?, &name)?;
+    if let Ok(mut active) = active_cache().write() {
+        *active = Some(ActiveProfile { name, settings });
+    }
+    Ok(())
+}
+
+/// Whether the active profile passes the whole environment to a provider CLI
+pub fn inherits_full_env(provider: &str) -> bool {
+    let active = match active_cache().read() {
+        Ok(active) => active,
+        Err(_) => return false,
+    };
+    match (provider, active.as_ref()) {
+        ("codex", Some(active)) => active.settings.codex_inherit_full_env,
+        ("gemini", Some(active)) => active.settings.gemini_inherit_full_env,
+        _ => false,
+    }
+}
+
+/// Environment the active profile adds to a provider CLI: its env overrides
+/// plus the provider's base URL
+pub fn command_env(provider: &str) -> Vec<(String, String)> {
+    let active = match active_cache().read() {
+        Ok(active) => active,
+        Err(_) => return Vec::new(),
+    };
+    let settings = match active.as_ref() {
+        Some(active) => &active.settings,
+        None => return Vec::new(),
+    };
+    let mut env: Vec<(String, String)> = settings.env.iter().map(|(k, v)| (k.clone(), v.clone())).collect();
+    let base_url = match provider {
+        "codex" => settings.codex_base_url.as_ref().map(|url| (CODEX_BASE_URL_ENV, url)),
+        "gemini" => settings.gemini_base_url.as_ref().map(|url| (GEMINI_BASE_URL_ENV, url)),
+        _ => None,
+    };
+    if let Some((key, url)) = base_url.filter(|(_, url)| !url.trim().is_empty()) {
+        env.push((key.to_string(), url.trim().to_string()));
+    }
+    env
+}
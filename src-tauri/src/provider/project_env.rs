@@ -0,0 +1,60 @@
+use rusqlite::Connection;
+use std::collections::HashMap;
+
+fn ensure_table(conn: &Connection) -> Result<(), String> {
+    conn.execute(
+        "CREATE TABLE IF NOT EXISTS project_env (
+            project_path TEXT PRIMARY KEY,
+            env TEXT NOT NULL,
+            updated_at TEXT NOT NULL DEFAULT CURRENT_TIMESTAMP
+        )",
+        [],
+    )
+    .map_err(|e| e.to_string())?;
+    Ok(())
+}
+
+/// Reject names the OS would refuse or silently mangle
+pub fn validate_key(key: &str) -> Result<(), String> {
+    if key.is_empty() || key.contains('=') || key.contains('\0') {
+        return Err(format!("Invalid environment variable name: {:?}", key));
+    }
+    Ok(())
+}
+
+/// Environment overrides stored for a project (empty when none)
+pub fn load(conn: &Connection, project_path: &str) -> Result<HashMap<String, String>, String> {
+    ensure_table(conn)?;
+    let raw: Option<String> = conn
+        .query_row(
+            "SELECT env FROM project_env WHERE project_path = ?1",
+            rusqlite::params![project_path],
+            |row| row.get(0),
+        )
+        .ok();
+    match raw {
+        Some(raw) => serde_json::from_str(&raw).map_err(|e| e.to_string()),
+        None => Ok(HashMap::new()),
+    }
+}
+
+/// Replace the overrides for a project; an empty map removes the entry
+pub fn save(conn: &Connection, project_path: &str, env: &HashMap<String, String>) -> Result<(), String> {
+    ensure_table(conn)?;
+    if env.is_empty() {
+        conn.execute(
+            "DELETE FROM project_env WHERE project_path = ?1",
+            rusqlite::params![project_path],
+        )
+        .map_err(|e| e.to_string())?;
+        return Ok(());
+    }
+    let raw = serde_json::to_string(env).map_err(|e| e.to_string())?;
+    conn.execute(
+        "INSERT INTO project_env (project_path, env) VALUES (?1, ?2)
+         ON CONFLICT(project_path) DO UPDATE SET env = excluded.env, updated_at = CURRENT_TIMESTAMP",
+        rusqlite::params![project_path, raw],
+    )
+    .map_err(|e| e.to_string())?;
+    Ok(())
+}
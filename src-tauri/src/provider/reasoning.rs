@@ -0,0 +1,110 @@
+use rusqlite::Connection;
+use serde::{Deserialize, Serialize};
+use tauri::AppHandle;
+
+use super::settings::open_db;
+use super::ChatOptions;
+
+/// How hard the model should think before answering
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ReasoningEffort {
+    Low,
+    Medium,
+    High,
+}
+
+impl ReasoningEffort {
+    pub fn as_str(self) -> &'static str {
+        match self {
+            Self::Low => "low",
+            Self::Medium => "medium",
+            Self::High => "high",
+        }
+    }
+
+    fn parse(s: &str) -> Option<Self> {
+        match s {
+            "low" => Some(Self::Low),
+            "medium" => Some(Self::Medium),
+            "high" => Some(Self::High),
+            _ => None,
+        }
+    }
+
+    /// Token budget for CLIs that take a thinking budget instead of a level
+    pub fn thinking_budget(self) -> u32 {
+        match self {
+            Self::Low => 1024,
+            Self::Medium => 8192,
+            Self::High => 24576,
+        }
+    }
+}
+
+/// Effort chosen for a run and whether the caller asked for it explicitly
+/// (as opposed to inheriting the project default)
+#[derive(Debug, Clone, Copy)]
+pub struct ResolvedEffort {
+    pub effort: ReasoningEffort,
+    pub explicit: bool,
+}
+
+fn ensure_table(conn: &Connection) -> Result<(), String> {
+    conn.execute(
+        "CREATE TABLE IF NOT EXISTS project_reasoning_effort (
+            project_path TEXT PRIMARY KEY,
+            effort TEXT NOT NULL,
+            updated_at TEXT NOT NULL DEFAULT CURRENT_TIMESTAMP
+        )",
+        [],
+    )
+    .map_err(|e| e.to_string())?;
+    Ok(())
+}
+
+/// Default reasoning effort stored for a project
+pub fn load(conn: &Connection, project_path: &str) -> Result<Option<ReasoningEffort>, String> {
+    ensure_table(conn)?;
+    let raw: Option<String> = conn
+        .query_row(
+            "SELECT effort FROM project_reasoning_effort WHERE project_path = ?1",
+            rusqlite::params![project_path],
+            |row| row.get(0),
+        )
+        .ok();
+    Ok(raw.as_deref().and_then(ReasoningEffort::parse))
+}
+
+/// Set the project default; `None` removes it
+pub fn save(conn: &Connection, project_path: &str, effort: Option<ReasoningEffort>) -> Result<(), String> {
+    ensure_table(conn)?;
+    match effort {
+        Some(effort) => conn.execute(
+            "INSERT INTO project_reasoning_effort (project_path, effort) VALUES (?1, ?2)
+             ON CONFLICT(project_path) DO UPDATE SET effort = excluded.effort, updated_at = CURRENT_TIMESTAMP",
+            rusqlite::params![project_path, effort.as_str()],
+        ),
+        None => conn.execute(
+            "DELETE FROM project_reasoning_effort WHERE project_path = ?1",
+            rusqlite::params![project_path],
+        ),
+    }
+    .map_err(|e| e.to_string())?;
+    Ok(())
+}
+
+/// Effort for a run: the explicit option, else the project default
+pub fn resolve(app: &AppHandle, project_path: &str, options: &ChatOptions) -> Option<ResolvedEffort> {
+    if let Some(effort) = options.reasoning_effort {
+        return Some(ResolvedEffort { effort, explicit: true });
+    }
+    let conn = open_db(app).ok()?;
+    match load(&conn, project_path) {
+        Ok(effort) => effort.map(|effort| ResolvedEffort { effort, explicit: false }),
+        Err(e) => {
+            log::warn!("Failed to load reasoning effort for {}: {}", project_path, e);
+            None
+        }
+    }
+}
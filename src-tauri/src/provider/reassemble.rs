@@ -0,0 +1,132 @@
+use std::time::{Duration, Instant};
+
+/// Largest JSON value that will be buffered before giving up
+const MAX_PENDING_BYTES: usize = 1024 * 1024;
+/// How long a value may stay open before its lines are released as text
+const MAX_PENDING_AGE: Duration = Duration::from_secs(5);
+
+/// Reassembles JSON values that a CLI pretty-prints across several lines.
+///
+/// Lines that don't start a JSON object/array pass straight through. Once a
+/// value is opened, lines are held until the brackets balance; if the result
+/// parses it is released as one compact line, otherwise (or when the size or
+/// age cap is hit) the held lines are released unchanged.
+#[derive(Default)]
+pub struct JsonAssembler {
+    pending: Vec<String>,
+    pending_bytes: usize,
+    started: Option<Instant>,
+    depth: i64,
+    in_string: bool,
+    escaped: bool,
+}
+
+impl JsonAssembler {
+    /// Feed one line; returns the lines ready to be emitted
+    pub fn push(&mut self, line: &str) -> Vec<String> {
+        if self.pending.is_empty() {
+            let trimmed = line.trim_start();
+            if !(trimmed.starts_with('{') || trimmed.starts_with('[')) {
+                return vec![line.to_string()];
+            }
+            self.started = Some(Instant::now());
+        }
+
+        self.scan(line);
+        self.pending_bytes += line.len() + 1;
+        self.pending.push(line.to_string());
+
+        if self.depth <= 0 {
+            return self.finish();
+        }
+        if self.pending_bytes > MAX_PENDING_BYTES || self.is_stale() {
+            return self.release_raw();
+        }
+        Vec::new()
+    }
+
+    /// Release held lines that have been open for too long (call when idle)
+    pub fn flush_stale(&mut self) -> Vec<String> {
+        if self.is_stale() {
+            self.release_raw()
+        } else {
+            Vec::new()
+        }
+    }
+
+    /// Release whatever is still held (end of stream)
+    pub fn flush(&mut self) -> Vec<String> {
+        self.release_raw()
+    }
+
+    fn is_stale(&self) -> bool {
+        self.started.is_some_and(|t| t.elapsed() >= MAX_PENDING_AGE)
+    }
+
+    /// Track bracket depth outside of string literals
+    fn scan(&mut self, line: &str) {
+        for c in line.chars() {
+            if self.in_string {
+                match c {
+                    _ if self.escaped => self.escaped = false,
+                    '\\' => self.escaped = true,
+                    '"' => self.in_string = false,
+                    _ => {}
+                }
+                continue;
+            }
+            match c {
+                '"' => self.in_string = true,
+                '{' | '[' => self.depth += 1,
+                '}' | ']' => self.depth -= 1,
+                _ => {}
+            }
+        }
+    }
+
+    fn finish(&mut self) -> Vec<String> {
+        let joined = self.pending.join("\n");
+        match serde_json::from_str::<serde_json::Value>(&joined) {
+            Ok(value) => {
+                self.reset();
+                vec![value.to_string()]
+            }
+            Err(_) => self.release_raw(),
+        }
+    }
+
+    fn release_raw(&mut self) -> Vec<String> {
+        let lines = std::mem::take(&mut self.pending);
+        self.reset();
+        lines
+    }
+
+    fn reset(&mut self) {
+        *self = Self::default();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_reassembles_pretty_json() {
+        let mut a = JsonAssembler::default();
+        assert_eq!(a.push("plain text"), vec!["plain text"]);
+        assert!(a.push("{").is_empty());
+        assert!(a.push("  \"name\": \"a } in a string\",").is_empty());
+        assert!(a.push("  \"args\": [1, 2]").is_empty());
+        assert_eq!(a.push("}"), vec![r#"{"args":[1,2],"name":"a } in a string"}"#]);
+        assert_eq!(a.push(r#"{"one": "line"}"#), vec![r#"{"one":"line"}"#]);
+    }
+
+    #[test]
+    fn test_unparseable_lines_pass_through() {
+        let mut a = JsonAssembler::default();
+        assert!(a.push("{ not json").is_empty());
+        assert_eq!(a.push("}"), vec!["{ not json", "}"]);
+        assert!(a.push("[unterminated").is_empty());
+        assert_eq!(a.flush(), vec!["[unterminated"]);
+    }
+}
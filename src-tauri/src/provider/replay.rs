@@ -0,0 +1,132 @@
+use serde::Serialize;
+use serde_json::{json, Value};
+use std::time::Duration;
+use tauri::{AppHandle, Emitter};
+
+use super::events::{envelope, event_name};
+use super::transcript::{find_transcript, locate_transcript, was_cancelled};
+use crate::unified_history::{read_jsonl, try_get_ts};
+
+/// Longest pause between two replayed messages at 1x, so time spent between
+/// turns doesn't stall a replay for minutes
+const MAX_GAP: Duration = Duration::from_secs(5);
+
+/// A replay that was started; messages follow on the session's channels
+#[derive(Debug, Clone, Serialize)]
+pub struct ReplayHandle {
+    pub session_id: String,
+    pub provider: String,
+    pub messages: usize,
+}
+
+/// Wait before each message: the recorded gap (capped at `MAX_GAP`) divided
+/// by `speed`; a speed of 0 replays instantly
+pub fn delays(messages: &[Value], speed: f64) -> Vec<Duration> {
+    let mut previous: Option<i64> = None;
+    messages
+        .iter()
+        .map(|message| {
+            let ts = try_get_ts(message);
+            let gap = match (previous, ts) {
+                (Some(prev), Some(ts)) if speed > 0.0 => {
+                    Duration::from_millis((ts - prev).max(0) as u64).min(MAX_GAP)
+                }
+                _ => Duration::ZERO,
+            };
+            if ts.is_some() {
+                previous = ts;
+            }
+            if gap.is_zero() { gap } else { gap.div_f64(speed) }
+        })
+        .collect()
+}
+
+fn emit_both(app: &AppHandle, provider: &str, kind: &str, session_id: &str, payload: &Value) {
+    let _ = app.emit(&event_name(&format!("{}-{}:{}", provider, kind, session_id)), payload);
+    let _ = app.emit(&event_name(&format!("{}-{}", provider, kind)), payload);
+    super::websocket::publish(provider, kind, session_id, payload);
+}
+
+/// Re-emit a stored session's messages on its `output` channels as if it
+/// were streaming, then a `complete` event.
+///
+/// `speed` scales the recorded timing (1.0 real time, 0 instant). Replayed
+/// payloads carry `"replay": true`. A running session can't be replayed
+/// since its channels are live.
+pub fn start(
+    app: &AppHandle,
+    project_path: Option<&str>,
+    session_id: &str,
+    speed: f64,
+) -> Result<ReplayHandle, String> {
+    if !speed.is_finite() || speed < 0.0 {
+        return Err(format!("Invalid replay speed {}", speed));
+    }
+    if super::control::get(session_id).is_some() {
+        return Err(format!("Session {} is running; replay it once it finishes", session_id));
+    }
+    let path = match project_path {
+        Some(project) => find_transcript(project, session_id)?,
+        None => locate_transcript(session_id)?,
+    }
+    .ok_or_else(|| format!("No transcript for session {}", session_id))?;
+    let messages = read_jsonl(&path);
+    let provider = messages
+        .iter()
+        .find_map(|m| m.get("provider").and_then(|p| p.as_str()))
+        .ok_or_else(|| format!("Transcript of {} doesn't name its provider", session_id))?
+        .to_string();
+
+    let handle = ReplayHandle {
+        session_id: session_id.to_string(),
+        provider: provider.clone(),
+        messages: messages.len(),
+    };
+    let app = app.clone();
+    let session_id = session_id.to_string();
+    tauri::async_runtime::spawn(async move {
+        let delays = delays(&messages, speed);
+        let cancelled = was_cancelled(&messages);
+        let count = messages.len();
+        for (mut message, delay) in messages.into_iter().zip(delays) {
+            if !delay.is_zero() {
+                tokio::time::sleep(delay).await;
+            }
+            if let Value::Object(map) = &mut message {
+                map.insert("replay".to_string(), json!(true));
+            }
+            // Output payloads are JSON strings, as when streaming live
+            emit_both(&app, &provider, "output", &session_id, &json!(message.to_string()));
+        }
+        let complete_msg = envelope(json!({
+            "session_id": session_id,
+            "success": !cancelled,
+            "cancelled": cancelled,
+            "output_events": count,
+            "replay": true,
+        }));
+        emit_both(&app, &provider, "complete", &session_id, &complete_msg);
+    });
+    Ok(handle)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_delays() {
+        let messages = vec![
+            json!({"timestamp": "2025-01-01T00:00:00Z"}),
+            json!({"timestamp": "2025-01-01T00:00:02Z"}),
+            json!({"type": "assistant"}),
+            json!({"timestamp": "2025-01-01T01:00:00Z"}),
+        ];
+        assert_eq!(
+            delays(&messages, 1.0),
+            vec![Duration::ZERO, Duration::from_secs(2), Duration::ZERO, MAX_GAP]
+        );
+        assert_eq!(delays(&messages, 2.0)[1], Duration::from_secs(1));
+        assert!(delays(&messages, 0.0).iter().all(|d| d.is_zero()));
+    }
+}
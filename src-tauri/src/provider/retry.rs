@@ -0,0 +1,139 @@
+use std::time::Duration;
+
+use super::ChatOptions;
+
+/// Default number of attempts when retries aren't requested
+const DEFAULT_MAX_ATTEMPTS: u32 = 1;
+/// Default delay before the first retry
+const DEFAULT_BACKOFF_MS: u64 = 1000;
+/// Upper bound for a single backoff delay
+const MAX_BACKOFF_MS: u64 = 60_000;
+
+/// Stderr fragments that indicate the failure won't go away by retrying
+const FATAL_PATTERNS: &[&str] = &[
+    "unauthorized",
+    "401",
+    "403",
+    "forbidden",
+    "invalid api key",
+    "api key not valid",
+    "not logged in",
+    "please login",
+    "please log in",
+    "model not found",
+    "unknown model",
+    "invalid model",
+    "does not exist",
+];
+
+/// Stderr fragments that indicate a transient network or capacity problem
+const TRANSIENT_PATTERNS: &[&str] = &[
+    "429",
+    "rate limit",
+    "rate_limit",
+    "too many requests",
+    "502",
+    "503",
+    "504",
+    "bad gateway",
+    "service unavailable",
+    "temporarily unavailable",
+    "overloaded",
+    "timed out",
+    "timeout",
+    "econnreset",
+    "econnrefused",
+    "etimedout",
+    "connection reset",
+    "network error",
+];
+
+/// How a failed CLI run should be treated
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum FailureKind {
+    /// Worth retrying (network hiccup, rate limit, overload)
+    Transient,
+    /// Retrying won't help (auth, bad model, killed by the user)
+    Fatal,
+}
+
+/// Classify a failed run from its exit code and captured stderr.
+///
+/// Fatal patterns win over transient ones so an auth error that also
+/// mentions a timeout still fails fast. A missing exit code means the
+/// process was killed by a signal (usually a user cancel) and is never retried.
+pub fn classify_failure(exit_code: Option<i32>, stderr: &str) -> FailureKind {
+    let code = match exit_code {
+        Some(code) => code,
+        None => return FailureKind::Fatal,
+    };
+
+    let stderr = stderr.to_lowercase();
+    if FATAL_PATTERNS.iter().any(|p| stderr.contains(p)) {
+        return FailureKind::Fatal;
+    }
+    if TRANSIENT_PATTERNS.iter().any(|p| stderr.contains(p)) {
+        return FailureKind::Transient;
+    }
+
+    // EX_TEMPFAIL from sysexits.h
+    if code == 75 {
+        return FailureKind::Transient;
+    }
+
+    FailureKind::Fatal
+}
+
+/// Retry settings resolved from `ChatOptions`
+#[derive(Debug, Clone, Copy)]
+pub struct RetryPolicy {
+    pub max_attempts: u32,
+    pub base_delay_ms: u64,
+}
+
+impl RetryPolicy {
+    pub fn from_options(options: &ChatOptions) -> Self {
+        Self {
+            max_attempts: options.max_attempts.unwrap_or(DEFAULT_MAX_ATTEMPTS).max(1),
+            base_delay_ms: options.retry_backoff_ms.unwrap_or(DEFAULT_BACKOFF_MS),
+        }
+    }
+
+    /// Delay to wait after the given (1-based) failed attempt
+    pub fn delay_for(&self, attempt: u32) -> Duration {
+        let factor = 1u64 << attempt.saturating_sub(1).min(16);
+        Duration::from_millis(self.base_delay_ms.saturating_mul(factor).min(MAX_BACKOFF_MS))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_classify_failure() {
+        assert_eq!(classify_failure(Some(1), "Error: 429 Too Many Requests"), FailureKind::Transient);
+        assert_eq!(classify_failure(Some(1), "request timed out"), FailureKind::Transient);
+        assert_eq!(classify_failure(Some(75), ""), FailureKind::Transient);
+        assert_eq!(classify_failure(Some(1), "401 Unauthorized: invalid api key"), FailureKind::Fatal);
+        assert_eq!(classify_failure(Some(1), "model not found (timeout)"), FailureKind::Fatal);
+        assert_eq!(classify_failure(Some(1), "something else"), FailureKind::Fatal);
+        assert_eq!(classify_failure(None, "rate limit"), FailureKind::Fatal);
+    }
+
+    #[test]
+    fn test_retry_backoff() {
+        let policy = RetryPolicy::from_options(&ChatOptions {
+            max_attempts: Some(3),
+            retry_backoff_ms: Some(500),
+            ..Default::default()
+        });
+        assert_eq!(policy.delay_for(1), Duration::from_millis(500));
+        assert_eq!(policy.delay_for(2), Duration::from_millis(1000));
+        assert_eq!(policy.delay_for(3), Duration::from_millis(2000));
+        assert_eq!(policy.delay_for(30), Duration::from_millis(MAX_BACKOFF_MS));
+
+        let default = RetryPolicy::from_options(&ChatOptions::default());
+        assert_eq!(default.max_attempts, 1);
+    }
+}
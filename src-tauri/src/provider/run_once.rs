@@ -0,0 +1,148 @@
+use serde::Serialize;
+use std::process::Stdio;
+use std::time::{Duration, Instant};
+use tauri::AppHandle;
+use tokio::process::Command;
+
+use super::redact::Redactor;
+
+/// Timeout used when the caller gives none
+pub const DEFAULT_TIMEOUT_SECS: u64 = 30;
+/// Longest a one-off command may run
+const MAX_TIMEOUT_SECS: u64 = 600;
+/// Bytes of stdout/stderr returned; the rest is cut off
+const MAX_OUTPUT_BYTES: usize = 256 * 1024;
+
+/// Arguments refused outright: they turn off the CLI's sandbox or approvals,
+/// which a one-off command run from the UI should never do
+const BLOCKED_FLAGS: &[&str] = &[
+    "--dangerously-bypass-approvals-and-sandbox",
+    "--yolo",
+    "--full-auto",
+];
+
+/// Result of a one-off provider CLI invocation
+#[derive(Debug, Clone, Serialize)]
+pub struct RunOutput {
+    pub provider: String,
+    pub args: Vec<String>,
+    /// `None` when the process was killed by a signal or the timeout
+    pub exit_code: Option<i32>,
+    pub stdout: String,
+    pub stderr: String,
+    pub timed_out: bool,
+    /// Whether stdout or stderr was cut at `MAX_OUTPUT_BYTES`
+    pub truncated: bool,
+    pub elapsed_ms: u64,
+}
+
+/// Reject arguments that are obviously dangerous; everything else is passed
+/// through to the CLI as-is (no shell is involved)
+pub fn validate_args(args: &[String]) -> Result<(), String> {
+    for arg in args {
+        if arg.contains('\0') {
+            return Err("Arguments can't contain NUL bytes".to_string());
+        }
+        let flag = arg.split('=').next().unwrap_or(arg);
+        if BLOCKED_FLAGS.contains(&flag) || flag.starts_with("--dangerously") {
+            return Err(format!("Refusing to pass {} to a one-off command", flag));
+        }
+        if arg == "--approval-mode=yolo" {
+            return Err("Refusing to run with --approval-mode=yolo".to_string());
+        }
+    }
+    if args.windows(2).any(|w| w[0] == "--approval-mode" && w[1] == "yolo") {
+        return Err("Refusing to run with --approval-mode yolo".to_string());
+    }
+    Ok(())
+}
+
+/// Clamp a requested timeout to a sane range
+pub fn timeout_for(timeout_secs: Option<u64>) -> Duration {
+    Duration::from_secs(timeout_secs.unwrap_or(DEFAULT_TIMEOUT_SECS).clamp(1, MAX_TIMEOUT_SECS))
+}
+
+/// Output as text, cut at `MAX_OUTPUT_BYTES` on a character boundary
+fn capture(bytes: &[u8], truncated: &mut bool) -> String {
+    let text = String::from_utf8_lossy(bytes);
+    if text.len() <= MAX_OUTPUT_BYTES {
+        return text.into_owned();
+    }
+    *truncated = true;
+    let mut end = MAX_OUTPUT_BYTES;
+    while !text.is_char_boundary(end) {
+        end -= 1;
+    }
+    text[..end].to_string()
+}
+
+/// Run `cmd` (already built with the provider's env allowlist) to completion.
+///
+/// Stdin is closed so prompts fail instead of hanging; the process is killed
+/// when the timeout hits. Secrets are masked in the captured output.
+pub async fn run(
+    app: &AppHandle,
+    provider: &str,
+    mut cmd: Command,
+    args: Vec<String>,
+    timeout: Duration,
+) -> Result<RunOutput, String> {
+    validate_args(&args)?;
+    cmd.args(&args)
+        .stdin(Stdio::null())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .kill_on_drop(true);
+    let started = Instant::now();
+    let child = cmd
+        .spawn()
+        .map_err(|e| format!("Failed to run {} {}: {}", provider, args.join(" "), e))?;
+    let (output, timed_out) = match tokio::time::timeout(timeout, child.wait_with_output()).await {
+        Ok(output) => (Some(output.map_err(|e| e.to_string())?), false),
+        // Dropping the future drops the child, which kills it
+        Err(_) => (None, true),
+    };
+    let redactor = Redactor::load(app);
+    let mut truncated = false;
+    let (exit_code, stdout, stderr) = match output {
+        Some(output) => (
+            output.status.code(),
+            redactor.redact(&capture(&output.stdout, &mut truncated)).into_owned(),
+            redactor.redact(&capture(&output.stderr, &mut truncated)).into_owned(),
+        ),
+        None => (
+            None,
+            String::new(),
+            format!("Timed out after {}s", timeout.as_secs()),
+        ),
+    };
+    Ok(RunOutput {
+        provider: provider.to_string(),
+        args,
+        exit_code,
+        stdout,
+        stderr,
+        timed_out,
+        truncated,
+        elapsed_ms: started.elapsed().as_millis() as u64,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn args(list: &[&str]) -> Vec<String> {
+        list.iter().map(|s| s.to_string()).collect()
+    }
+
+    #[test]
+    fn test_validate_args() {
+        assert!(validate_args(&args(&["config", "get", "model"])).is_ok());
+        assert!(validate_args(&args(&["exec", "--yolo", "hi"])).is_err());
+        assert!(validate_args(&args(&["--dangerously-bypass-approvals-and-sandbox"])).is_err());
+        assert!(validate_args(&args(&["--approval-mode", "yolo"])).is_err());
+        assert!(validate_args(&args(&["--approval-mode=yolo"])).is_err());
+        assert!(validate_args(&args(&["--approval-mode", "default"])).is_ok());
+    }
+}
@@ -0,0 +1,1295 @@
+use log::Level;
+use serde::Serialize;
+use serde_json::json;
+use std::fs::{File, OpenOptions};
+use std::io::{BufWriter, Write};
+use std::path::Path;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::Arc;
+use tauri::{AppHandle, Emitter, Manager};
+use tokio::io::{AsyncWriteExt, BufReader as AsyncBufReader};
+use tokio::process::{Child, ChildStderr, ChildStdout, Command};
+use tokio::sync::Mutex;
+
+use super::citations;
+use super::control::{self, SessionControl, SessionHandle, SharedStdin};
+use super::diagnostics::{self, DiagnosticLevel};
+use super::events::{envelope, event_name};
+use super::framing;
+use super::interactive::{
+    looks_like_download_prompt, looks_like_input_request, TuiDetector, AUTO_ACCEPT_DOWNLOADS_KEY,
+    PROMPT_IDLE,
+};
+use super::last_error;
+use super::logs::session_event;
+use super::model_usage;
+use super::normalize::Normalizer;
+use super::reassemble::JsonAssembler;
+use super::redact::Redactor;
+use super::retry::{classify_failure, FailureKind, RetryPolicy};
+use super::streaming::{self, StreamObserver};
+use super::structured;
+use super::transcript::{
+    persist_stderr_key, PartialCheckpoint, RawLog, TranscriptWriter, CANCELLED_SUBTYPE,
+    COMPRESS_TRANSCRIPTS_KEY, STDERR_SUBTYPE,
+};
+use super::websocket;
+use super::ChatOptions;
+
+/// Number of trailing stderr lines kept to classify a failure
+const STDERR_TAIL_LINES: usize = 50;
+
+/// Everything needed to start a provider chat session
+pub struct SpawnRequest {
+    /// Provider name; also used as the event prefix (`codex-output`, ...)
+    pub provider: &'static str,
+    pub session_id: String,
+    pub prompt: String,
+    pub model: String,
+    pub project_path: String,
+    pub options: ChatOptions,
+    /// Fan-out group this session belongs to; stamped on its events
+    pub group_id: Option<String>,
+}
+
+/// Resolve the optional working-directory override.
+///
+/// It must exist and be inside the project (a monorepo package) or contain it.
+fn resolve_working_dir(project_path: &str, working_dir: Option<&str>) -> Result<String, String> {
+    let dir = match working_dir {
+        Some(dir) if !dir.trim().is_empty() => Path::new(project_path).join(dir.trim()),
+        _ => return Ok(project_path.to_string()),
+    };
+    let canonical = dir
+        .canonicalize()
+        .map_err(|_| format!("Working directory does not exist: {}", dir.display()))?;
+    if !canonical.is_dir() {
+        return Err(format!("Working directory is not a directory: {}", dir.display()));
+    }
+    let project = Path::new(project_path)
+        .canonicalize()
+        .map_err(|e| format!("Invalid project path {}: {}", project_path, e))?;
+    if !canonical.starts_with(&project) && !project.starts_with(&canonical) {
+        return Err(format!(
+            "Working directory {} is outside project {}",
+            canonical.display(),
+            project.display()
+        ));
+    }
+    Ok(canonical.to_string_lossy().to_string())
+}
+
+/// Spawn the CLI. When `stdin_prompt` is set it is written to stdin for CLIs
+/// that expect interactive input; otherwise stdin is closed right away.
+///
+/// With `keep_stdin` the pipe stays open and is returned so later answers to
+/// the CLI's questions can be written to it.
+fn spawn_child(
+    cmd: &mut Command,
+    provider: &str,
+    stdin_prompt: Option<&str>,
+    keep_stdin: bool,
+) -> Result<(Child, Option<SharedStdin>), String> {
+    let mut child = cmd
+        .spawn()
+        .map_err(|e| format!("Failed to spawn {}: {}", provider, e))?;
+
+    let mut shared_stdin = None;
+    if let Some(mut stdin) = child.stdin.take() {
+        let prompt = stdin_prompt.map(|p| p.to_string());
+        if keep_stdin {
+            let shared: SharedStdin = Arc::new(Mutex::new(Some(stdin)));
+            if let Some(p) = prompt {
+                let writer = shared.clone();
+                tokio::spawn(async move {
+                    if let Some(stdin) = writer.lock().await.as_mut() {
+                        let _ = stdin.write_all(p.as_bytes()).await;
+                        let _ = stdin.write_all(b"\n").await;
+                        let _ = stdin.flush().await;
+                    }
+                });
+            }
+            shared_stdin = Some(shared);
+        } else if let Some(p) = prompt {
+            tokio::spawn(async move {
+                let _ = stdin.write_all(p.as_bytes()).await;
+                let _ = stdin.write_all(b"\n").await;
+                let _ = stdin.shutdown().await;
+            });
+        }
+    }
+
+    Ok((child, shared_stdin))
+}
+
+/// Take the stdout/stderr pipes from a freshly spawned child
+fn take_pipes(child: &mut Child, provider: &str) -> Result<(ChildStdout, ChildStderr), String> {
+    let stdout = child
+        .stdout
+        .take()
+        .ok_or_else(|| format!("Failed to capture {} stdout", provider))?;
+    let stderr = child
+        .stderr
+        .take()
+        .ok_or_else(|| format!("Failed to capture {} stderr", provider))?;
+    Ok((stdout, stderr))
+}
+
+/// Plain-text copy of a session's stdout for headless/CI consumers
+struct FileSink {
+    path: String,
+    file: std::sync::Mutex<BufWriter<File>>,
+}
+
+impl FileSink {
+    fn open(path: &str, project_path: &str) -> Result<Self, String> {
+        let resolved = Path::new(project_path).join(path);
+        if let Some(parent) = resolved.parent() {
+            std::fs::create_dir_all(parent).map_err(|e| e.to_string())?;
+        }
+        let file = OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(&resolved)
+            .map_err(|e| format!("Cannot open output file {}: {}", resolved.display(), e))?;
+        Ok(Self {
+            path: resolved.to_string_lossy().to_string(),
+            file: std::sync::Mutex::new(BufWriter::new(file)),
+        })
+    }
+
+    fn write_line(&self, line: &str) {
+        if let Ok(mut file) = self.file.lock() {
+            if writeln!(file, "{}", line).and_then(|_| file.flush()).is_err() {
+                log::warn!("Failed to write to output file {}", self.path);
+            }
+        }
+    }
+}
+
+/// Settings key: default `max_output_lines` of sessions that don't pass one
+pub const MAX_OUTPUT_LINES_KEY: &str = "max_output_lines";
+
+/// Output line cap of sessions that don't set their own; `None` when unset
+/// or 0 (unlimited)
+pub fn default_max_output_lines(app: &AppHandle) -> Option<usize> {
+    super::settings::read_setting(app, MAX_OUTPUT_LINES_KEY)
+        .and_then(|v| v.trim().parse().ok())
+        .filter(|limit: &usize| *limit > 0)
+}
+
+/// Per-session state shared by the stream reader tasks
+struct StreamContext {
+    app: AppHandle,
+    provider: &'static str,
+    session_id: String,
+    /// Pause/resume state; events go through here so they can be held back
+    control: Arc<SessionControl>,
+    redactor: Redactor,
+    normalizer: Normalizer,
+    /// Persisted copy of the normalized output, when the file could be opened
+    transcript: Option<TranscriptWriter>,
+    /// File that receives every stdout line when `output_file` is set
+    output_sink: Option<FileSink>,
+    /// Raw stdout/stderr lines when `persist_raw` is set
+    raw_log: Option<RawLog>,
+    /// Crash-recovery copy of the assistant text streamed so far
+    partial: Option<PartialCheckpoint>,
+    /// Join multi-line JSON values before emitting
+    reassemble_json: bool,
+    /// Output of the current attempt, kept when a structured result was requested
+    structured_output: Option<std::sync::Mutex<String>>,
+    /// Model the session runs, reported when the CLI asks to download it
+    model: String,
+    /// Answer model download prompts with "y" when stdin is still open
+    auto_accept_downloads: bool,
+    /// Write stderr lines to the transcript, between the output lines they
+    /// arrived with
+    persist_stderr: bool,
+    /// Read stdout and stderr in one loop and emit every line as a `stream`
+    /// event, in arrival order
+    combined_stream: bool,
+    /// Kill the session once it printed more stdout and stderr lines than this
+    max_output_lines: Option<usize>,
+    /// Lines read from both pipes, over all attempts
+    output_lines: AtomicUsize,
+    /// Bytes ending a record on stdout (`\n` unless configured)
+    delimiter: Vec<u8>,
+}
+
+impl StreamContext {
+    /// Tell the UI the CLI is waiting for an answer
+    fn request_input(&self, prompt: &str) {
+        let msg = with_group(
+            envelope(json!({
+                "session_id": self.session_id,
+                "prompt": self.redactor.redact(prompt),
+                "can_respond": self.control.accepts_input(),
+            })),
+            self.control.group_id.as_deref(),
+        );
+        self.emit("input-request", &msg);
+    }
+
+    /// Handle a line that may be the CLI asking to download a model.
+    ///
+    /// With `auto_accept_downloads` and an open stdin the prompt is confirmed;
+    /// otherwise `setup-required` tells the UI what the CLI is waiting for.
+    /// Returns whether the line was such a prompt.
+    async fn handle_download_prompt(&self, prompt: &str) -> bool {
+        if !looks_like_download_prompt(prompt) {
+            return false;
+        }
+        let prompt = self.redactor.redact(prompt.trim()).into_owned();
+        if self.auto_accept_downloads && self.control.accepts_input() {
+            match self.control.write_stdin("y").await {
+                Ok(()) => {
+                    session_event(
+                        Level::Info,
+                        "setup",
+                        self.provider,
+                        Some(&self.session_id),
+                        None,
+                        format!("auto-accepted download prompt: {}", prompt),
+                    );
+                    return true;
+                }
+                Err(e) => log::warn!("Failed to confirm download for {}: {}", self.session_id, e),
+            }
+        }
+        let reason = if self.control.accepts_input() {
+            "Answer the prompt or enable auto_accept_downloads"
+        } else {
+            "Download the model by running the CLI once in a terminal, or start the session as interactive"
+        };
+        let msg = with_group(
+            envelope(json!({
+                "session_id": self.session_id,
+                "provider": self.provider,
+                "model": self.model,
+                "prompt": prompt,
+                "reason": reason,
+                "can_respond": self.control.accepts_input(),
+            })),
+            self.control.group_id.as_deref(),
+        );
+        self.emit("setup-required", &msg);
+        true
+    }
+
+    /// Stop a CLI that started a full-screen terminal UI: its output is
+    /// control codes, not lines, and it would wait for keyboard input forever
+    async fn reject_tui(&self) {
+        let hint = match self.provider {
+            "codex" => "run it through `codex exec`",
+            "gemini" => "pass the prompt with `--prompt`",
+            _ => "use its non-interactive flag",
+        };
+        let message = format!(
+            "The {} CLI opened a full-screen terminal UI instead of printing its answer; \
+             run it non-interactively ({}) and check the configured binary and its version",
+            self.provider, hint
+        );
+        session_event(
+            Level::Warn,
+            "stream",
+            self.provider,
+            Some(&self.session_id),
+            None,
+            "terminal UI detected; killing the process".to_string(),
+        );
+        emit_error(&self.app, self.provider, &self.session_id, &message);
+        self.control.kill().await;
+    }
+
+    /// Count a line read from either pipe against `max_output_lines`. The
+    /// first line over the cap kills the process and emits an error; from
+    /// then on this returns false and the line must be dropped.
+    async fn admit_line(&self) -> bool {
+        let limit = match self.max_output_lines {
+            Some(limit) => limit,
+            None => return true,
+        };
+        let count = self.output_lines.fetch_add(1, Ordering::AcqRel) + 1;
+        if count <= limit {
+            return true;
+        }
+        if count == limit + 1 {
+            session_event(
+                Level::Warn,
+                "stream",
+                self.provider,
+                Some(&self.session_id),
+                None,
+                format!("output cap of {} lines reached; killing the process", limit),
+            );
+            let message = format!(
+                "The {} CLI was stopped after {} lines of output (max_output_lines); \
+                 raise the cap if the run legitimately prints this much",
+                self.provider, limit
+            );
+            emit_error(&self.app, self.provider, &self.session_id, &message);
+            self.control.kill().await;
+        }
+        false
+    }
+
+    /// Whether the session was killed for exceeding `max_output_lines`
+    fn output_capped(&self) -> bool {
+        self.max_output_lines
+            .is_some_and(|limit| self.output_lines.load(Ordering::Acquire) > limit)
+    }
+
+    /// Normalize, persist and emit one complete stdout line
+    fn handle_stdout_line(&self, line: &str) {
+        if let Some(sink) = &self.output_sink {
+            sink.write_line(&self.redactor.redact(line));
+        }
+        // User-defined rules first, then mask secrets in whatever is left
+        let line = match self.normalizer.apply(line) {
+            Some(line) => self.redactor.redact(&line).into_owned(),
+            None => return,
+        };
+        // Normalize: treat each line as assistant text
+        let mut msg = with_group(
+            envelope(json!({
+                "type": "assistant",
+                "message": { "content": [{"type": "text", "text": line}] },
+                "seq": self.control.next_seq(),
+            })),
+            self.control.group_id.as_deref(),
+        );
+        // Sources of Gemini's grounded answers, for rendering as citation chips
+        if self.provider == "gemini" {
+            let citations = citations::extract(&line);
+            if !citations.is_empty() {
+                if let serde_json::Value::Object(map) = &mut msg {
+                    map.insert("metadata".to_string(), json!({ "citations": citations }));
+                }
+            }
+        }
+        if let Some(transcript) = &self.transcript {
+            transcript.append(&msg);
+        }
+        if let Some(partial) = &self.partial {
+            partial.push(&line);
+        }
+        if let Some(output) = &self.structured_output {
+            if let Ok(mut output) = output.lock() {
+                output.push_str(&line);
+                output.push('\n');
+            }
+        }
+        self.emit("output", &msg.to_string());
+    }
+
+    /// Persist and emit one stderr line as a diagnostic, plus an error when it
+    /// reports a failure. Returns the redacted line.
+    async fn handle_stderr_line(&self, line: &str) -> String {
+        let line = self.redactor.redact(line).into_owned();
+        self.raw_line("stderr", &line);
+        // Everything goes out as a diagnostic; only real failures are errors
+        let level = diagnostics::classify(&line);
+        let seq = self.control.next_seq();
+        if let Some(transcript) = self.transcript.as_ref().filter(|_| self.persist_stderr) {
+            transcript.append(&envelope(json!({
+                "type": "system",
+                "subtype": STDERR_SUBTYPE,
+                "text": line,
+                "seq": seq,
+            })));
+        }
+        let diagnostic = envelope(json!({
+            "session_id": self.session_id,
+            "level": level,
+            "text": line,
+            "seq": seq,
+        }));
+        self.emit("diagnostic", &diagnostic);
+        // Some CLIs ask on stderr so the question stays out of the output
+        self.handle_download_prompt(&line).await;
+        if level == DiagnosticLevel::Error {
+            let error_msg = envelope(json!({ "session_id": self.session_id, "message": line }));
+            self.emit("error", &error_msg);
+        }
+        line
+    }
+
+    /// Record a redacted line as read from `source` ("stdout" or "stderr"):
+    /// into the raw log, and as a `stream` event in combined mode
+    fn raw_line(&self, source: &str, line: &str) {
+        if let Some(raw_log) = &self.raw_log {
+            raw_log.write(source, line);
+        }
+        if self.combined_stream {
+            let msg = with_group(
+                envelope(json!({
+                    "session_id": self.session_id,
+                    "source": source,
+                    "text": line,
+                })),
+                self.control.group_id.as_deref(),
+            );
+            self.emit("stream", &msg);
+        }
+    }
+
+    /// Validate the collected output of a structured run and emit it as
+    /// `structured-result`, or `structured-error` when it isn't valid JSON
+    fn emit_structured_result(&self) {
+        let output = match self.structured_output.as_ref().and_then(|o| o.lock().ok()) {
+            Some(output) => output.clone(),
+            None => return,
+        };
+        match structured::parse_result(&output) {
+            Ok(result) => {
+                let msg = envelope(json!({ "session_id": self.session_id, "result": result }));
+                self.emit("structured-result", &msg);
+            }
+            Err(error) => {
+                let msg = envelope(json!({
+                    "session_id": self.session_id,
+                    "error": error,
+                    "raw": output,
+                }));
+                self.emit("structured-error", &msg);
+            }
+        }
+    }
+
+    /// Emit on both the session-specific and the generic channel, unless the
+    /// session output is paused. Object payloads without a `seq` get the next
+    /// one here.
+    fn emit<S: serde::Serialize>(&self, kind: &str, payload: &S) {
+        match serde_json::to_value(payload) {
+            Ok(mut value) => {
+                if let serde_json::Value::Object(map) = &mut value {
+                    map.entry("seq").or_insert_with(|| json!(self.control.next_seq()));
+                }
+                self.control.emit(&self.app, kind, value)
+            }
+            Err(e) => log::warn!("Failed to serialize {} {} event: {}", self.provider, kind, e),
+        }
+    }
+}
+
+/// Tag a payload with the fan-out group so the UI can lay sessions side by side
+fn with_group(mut payload: serde_json::Value, group_id: Option<&str>) -> serde_json::Value {
+    if let (Some(group_id), serde_json::Value::Object(map)) = (group_id, &mut payload) {
+        map.insert("group_id".to_string(), json!(group_id));
+    }
+    payload
+}
+
+/// Reader state of the stdout pipe, shared by the split and the combined loop
+struct StdoutReader {
+    /// Bytes of the line being read; kept across idle timeouts
+    buf: Vec<u8>,
+    count: usize,
+    assembler: Option<JsonAssembler>,
+    observer: StreamObserver,
+    /// Whether the pending partial line was already reported as a prompt
+    announced: bool,
+    tui: TuiDetector,
+    /// Bytes of `buf` already shown to the TUI detector
+    tui_fed: usize,
+}
+
+impl StdoutReader {
+    fn new(ctx: &StreamContext) -> Self {
+        Self {
+            buf: Vec::new(),
+            count: 0,
+            assembler: ctx.reassemble_json.then(JsonAssembler::default),
+            observer: StreamObserver::new(),
+            announced: false,
+            tui: TuiDetector::default(),
+            tui_fed: 0,
+        }
+    }
+
+    /// No complete line arrived within `PROMPT_IDLE`. Returns false when the
+    /// CLI was killed for opening a terminal UI.
+    async fn idle(&mut self, ctx: &StreamContext) -> bool {
+        // Slow to first token is not hung; let the UI say so
+        if self.observer.should_announce_waiting() {
+            let waiting_msg = envelope(json!({
+                "session_id": ctx.session_id,
+                "waited_ms": self.observer.waited_ms(),
+            }));
+            ctx.emit("waiting", &waiting_msg);
+        }
+        // Proof of life while the model thinks; stops with the first line
+        if self.observer.should_heartbeat() {
+            let heartbeat_msg = envelope(json!({
+                "session_id": ctx.session_id,
+                "elapsed_ms": self.observer.waited_ms(),
+            }));
+            ctx.emit("heartbeat", &heartbeat_msg);
+        }
+        if let Some(assembler) = self.assembler.as_mut() {
+            for line in assembler.flush_stale() {
+                ctx.handle_stdout_line(&line);
+            }
+        }
+        // A TUI redraws without newlines, so check what piled up
+        if self.tui.feed(&self.buf[self.tui_fed..]) {
+            ctx.reject_tui().await;
+            return false;
+        }
+        self.tui_fed = self.buf.len();
+        let pending = String::from_utf8_lossy(&self.buf).into_owned();
+        if !self.announced && ctx.handle_download_prompt(&pending).await {
+            self.announced = true;
+        } else if !self.announced && looks_like_input_request(&pending) {
+            ctx.request_input(pending.trim());
+            self.announced = true;
+        }
+        true
+    }
+
+    /// Handle the record `read_record` left in `buf`, if any. Returns false when
+    /// the CLI was killed for opening a terminal UI or printing too much.
+    async fn line(&mut self, ctx: &StreamContext) -> bool {
+        if self.buf.is_empty() {
+            return true;
+        }
+        if self.tui.feed(&self.buf[self.tui_fed..]) {
+            ctx.reject_tui().await;
+            return false;
+        }
+        if !ctx.admit_line().await {
+            return false;
+        }
+        self.tui_fed = 0;
+        let text = framing::record_text(&self.buf, &ctx.delimiter);
+        let line = text.as_str();
+        self.count += 1;
+        ctx.raw_line("stdout", &ctx.redactor.redact(line));
+        self.observer.on_output();
+        match self.assembler.as_mut() {
+            Some(assembler) => {
+                for line in assembler.push(line) {
+                    ctx.handle_stdout_line(&line);
+                }
+            }
+            None => ctx.handle_stdout_line(line),
+        }
+        if !self.announced && !ctx.handle_download_prompt(line).await && looks_like_input_request(line) {
+            ctx.request_input(line.trim());
+        }
+        self.buf.clear();
+        self.announced = false;
+        true
+    }
+
+    /// Stdout closed: emit whatever JSON is still being joined
+    fn finish(&mut self, ctx: &StreamContext) {
+        if let Some(assembler) = self.assembler.as_mut() {
+            for line in assembler.flush() {
+                ctx.handle_stdout_line(&line);
+            }
+        }
+    }
+}
+
+/// Keep the last `STDERR_TAIL_LINES` stderr lines for the failure summary
+fn push_tail(tail: &mut Vec<String>, line: String) {
+    if tail.len() == STDERR_TAIL_LINES {
+        tail.remove(0);
+    }
+    tail.push(line);
+}
+
+/// What came through the pipes of one attempt
+#[derive(Default)]
+struct StreamTotals {
+    stdout_lines: usize,
+    stderr_lines: usize,
+    stderr_tail: String,
+    /// Whether stdout streamed, when the run was conclusive
+    streamed: Option<bool>,
+}
+
+/// Stream both pipes until they close, returning the tail of stderr and
+/// whether stdout streamed (when the run was conclusive)
+async fn stream_output(
+    ctx: &Arc<StreamContext>,
+    pid: u32,
+    stdout: ChildStdout,
+    stderr: ChildStderr,
+) -> (String, Option<bool>) {
+    let totals = if ctx.combined_stream {
+        stream_combined(ctx, stdout, stderr).await
+    } else {
+        stream_split(ctx, stdout, stderr).await
+    };
+    session_event(
+        Level::Debug,
+        "stream",
+        ctx.provider,
+        Some(&ctx.session_id),
+        Some(pid),
+        format!(
+            "streams closed stdout_lines={} stderr_lines={}",
+            totals.stdout_lines, totals.stderr_lines
+        ),
+    );
+    (totals.stderr_tail, totals.streamed)
+}
+
+/// Read each pipe on its own task. A stderr line written between two stdout
+/// records may be handled before or after either of them.
+///
+/// Only stdout is split on the configured delimiter; stderr is diagnostics
+/// and always read line by line.
+async fn stream_split(ctx: &Arc<StreamContext>, stdout: ChildStdout, stderr: ChildStderr) -> StreamTotals {
+    let ctx_out = ctx.clone();
+    let stdout_task = tokio::spawn(async move {
+        let mut reader = AsyncBufReader::new(stdout);
+        let mut out = StdoutReader::new(&ctx_out);
+        loop {
+            // `read_record` keeps partial bytes in `buf` when the timeout fires,
+            // which lets us spot prompts that never end in a newline
+            let read = framing::read_record(&mut reader, &ctx_out.delimiter, &mut out.buf);
+            let eof = match tokio::time::timeout(PROMPT_IDLE, read).await {
+                Err(_) => {
+                    if !out.idle(&ctx_out).await {
+                        break;
+                    }
+                    continue;
+                }
+                Ok(Ok(0)) | Ok(Err(_)) => true,
+                Ok(Ok(_)) => false,
+            };
+            if !out.line(&ctx_out).await {
+                break;
+            }
+            if eof {
+                out.finish(&ctx_out);
+                break;
+            }
+        }
+        (out.count, out.observer.streamed())
+    });
+
+    let ctx_err = ctx.clone();
+    let stderr_task = tokio::spawn(async move {
+        let mut reader = AsyncBufReader::new(stderr);
+        let mut buf = Vec::new();
+        let mut tail: Vec<String> = Vec::new();
+        let mut count = 0usize;
+        while matches!(framing::read_record(&mut reader, framing::DEFAULT_DELIMITER, &mut buf).await, Ok(n) if n > 0) {
+            if !ctx_err.admit_line().await {
+                break;
+            }
+            count += 1;
+            let line = framing::record_text(&buf, framing::DEFAULT_DELIMITER);
+            buf.clear();
+            push_tail(&mut tail, ctx_err.handle_stderr_line(&line).await);
+        }
+        (tail.join("\n"), count)
+    });
+
+    let (stdout_lines, streamed) = stdout_task.await.unwrap_or_default();
+    let (stderr_tail, stderr_lines) = stderr_task.await.unwrap_or_default();
+    StreamTotals { stdout_lines, stderr_lines, stderr_tail, streamed }
+}
+
+/// A read finished on one of the pipes: its buffer holds a record, or the
+/// pipe closed (`false`, possibly after a last unterminated record)
+enum Arrival {
+    Stdout(bool),
+    Stderr(bool),
+}
+
+/// Read both pipes in one loop so lines are handled, and get their `seq`,
+/// in the order they arrived. Every line is also emitted as a `stream`
+/// event tagged with its source. As in `stream_split`, stderr is line based.
+async fn stream_combined(ctx: &Arc<StreamContext>, stdout: ChildStdout, stderr: ChildStderr) -> StreamTotals {
+    let mut reader = AsyncBufReader::new(stdout);
+    let mut err_reader = AsyncBufReader::new(stderr);
+    let mut err_buf = Vec::new();
+    let mut out = StdoutReader::new(ctx);
+    let mut tail: Vec<String> = Vec::new();
+    let mut stderr_lines = 0usize;
+    let (mut stdout_open, mut stderr_open) = (true, true);
+    while stdout_open || stderr_open {
+        // Both reads are cancel safe: a partial record stays in its buffer
+        let arrival = tokio::time::timeout(PROMPT_IDLE, async {
+            tokio::select! {
+                read = framing::read_record(&mut reader, &ctx.delimiter, &mut out.buf), if stdout_open => {
+                    Arrival::Stdout(matches!(read, Ok(n) if n > 0))
+                }
+                read = framing::read_record(&mut err_reader, framing::DEFAULT_DELIMITER, &mut err_buf), if stderr_open => {
+                    Arrival::Stderr(matches!(read, Ok(n) if n > 0))
+                }
+            }
+        })
+        .await;
+        match arrival {
+            Err(_) => {
+                if stdout_open && !out.idle(ctx).await {
+                    break;
+                }
+            }
+            Ok(Arrival::Stdout(open)) => {
+                if !out.line(ctx).await {
+                    break;
+                }
+                if !open {
+                    out.finish(ctx);
+                    stdout_open = false;
+                }
+            }
+            Ok(Arrival::Stderr(open)) => {
+                if !err_buf.is_empty() {
+                    if !ctx.admit_line().await {
+                        break;
+                    }
+                    stderr_lines += 1;
+                    let line = framing::record_text(&err_buf, framing::DEFAULT_DELIMITER);
+                    err_buf.clear();
+                    push_tail(&mut tail, ctx.handle_stderr_line(&line).await);
+                }
+                stderr_open = open;
+            }
+        }
+    }
+    StreamTotals {
+        stdout_lines: out.count,
+        stderr_lines,
+        stderr_tail: tail.join("\n"),
+        streamed: out.observer.streamed(),
+    }
+}
+
+/// Trailing transcript record of a session that was cancelled mid-run
+fn cancelled_marker(session_id: &str) -> serde_json::Value {
+    envelope(json!({
+        "type": "system",
+        "subtype": CANCELLED_SUBTYPE,
+        "session_id": session_id,
+    }))
+}
+
+/// Emit an error line on the session and generic error channels and keep it
+/// as the provider's last error
+fn emit_error(app: &AppHandle, provider: &str, session_id: &str, message: &str) {
+    last_error::record(app, provider, Some(session_id), message, None);
+    let _ = app.emit(&event_name(&format!("{}-error:{}", provider, session_id)), message);
+    let _ = app.emit(&event_name(&format!("{}-error", provider)), message);
+    websocket::publish(provider, "error", session_id, &json!(message));
+}
+
+/// Tear down a session: kill its child if still running, remove the registry
+/// entry and emit the completion events.
+///
+/// Every exit path after the session is registered must go through here so
+/// no session is left "running" without a process behind it.
+async fn finish_session(
+    app: &AppHandle,
+    control: &Arc<SessionControl>,
+    provider: &str,
+    session_id: &str,
+    run_id: Option<i64>,
+    success: bool,
+) {
+    control.kill().await;
+
+    if let Some(run_id) = run_id {
+        let registry = app.state::<crate::process::ProcessRegistryState>();
+        let _ = registry.0.unregister_process(run_id);
+    }
+
+    // Deliver anything held back by a pause before signalling completion
+    control.resume(app);
+    control::remove(control);
+
+    // A user cancel is never reported as success, whatever the exit status was
+    let cancelled = control.is_cancelled();
+    // Both stream readers have been joined by now, so every output line is
+    // already emitted; the count lets the UI hold completion until it has
+    // received them all
+    let complete_msg = with_group(
+        envelope(json!({
+            "session_id": session_id,
+            "success": success && !cancelled,
+            "cancelled": cancelled,
+            "output_events": control.output_events(),
+            "seq": control.next_seq(),
+        })),
+        control.group_id.as_deref(),
+    );
+    let _ = app.emit(&event_name(&format!("{}-complete:{}", provider, session_id)), &complete_msg);
+    let _ = app.emit(&event_name(&format!("{}-complete", provider)), &complete_msg);
+    websocket::publish(provider, "complete", session_id, &complete_msg);
+    control.mark_finished();
+}
+
+/// What `prepare_command` resolved for a run
+struct PreparedCommand {
+    cwd: String,
+    /// Prompt to write to stdin when the CLI can't take it as an argument
+    stdin_prompt: Option<String>,
+}
+
+/// Apply everything the runner adds to a provider command: working dir,
+/// project env overrides and attachment flags, and decide how the prompt is
+/// delivered. Shared by spawning and `preview_command` so both agree.
+async fn prepare_command(
+    app: &AppHandle,
+    cmd: &mut Command,
+    provider: &str,
+    project_path: &str,
+    prompt: &str,
+    options: &ChatOptions,
+) -> Result<PreparedCommand, String> {
+    let cwd = resolve_working_dir(project_path, options.working_dir.as_deref())?;
+    cmd.current_dir(&cwd);
+
+    // Project overrides win over the allowlisted base environment
+    if let Ok(conn) = super::settings::open_db(app) {
+        match super::project_env::load(&conn, project_path) {
+            Ok(env) => {
+                cmd.envs(env);
+            }
+            Err(e) => log::warn!("Failed to load env overrides for {}: {}", project_path, e),
+        }
+    }
+
+    let program = cmd.as_std().get_program().to_string_lossy().to_string();
+    let caps = super::capabilities::probe(&program).await;
+
+    let attachments = options.attachments.as_deref().unwrap_or_default();
+    if !attachments.is_empty() {
+        let flag = caps.attach_flag.as_deref().ok_or_else(|| {
+            format!("{} CLI ({}) does not support file attachments", provider, program)
+        })?;
+        for path in super::attachments::validate(attachments, project_path)? {
+            cmd.arg(format!("{}={}", flag, path.display()));
+        }
+    }
+
+    // Last, so the shell gets the complete argument list
+    if super::login_shell::enabled(app, provider) {
+        super::login_shell::wrap(cmd);
+    }
+
+    let prompt_via_stdin = options
+        .prompt_via_stdin
+        .unwrap_or_else(|| caps.prompt_via_stdin());
+    Ok(PreparedCommand {
+        cwd,
+        stdin_prompt: prompt_via_stdin.then(|| prompt.to_string()),
+    })
+}
+
+/// Characters of the prompt kept in a command preview
+const PREVIEW_PROMPT_CHARS: usize = 40;
+
+/// A provider command as it would be spawned, without running it
+#[derive(Debug, Clone, Serialize)]
+pub struct CommandPreview {
+    pub provider: String,
+    pub program: String,
+    /// Arguments in order, with the prompt truncated
+    pub args: Vec<String>,
+    pub cwd: String,
+    /// Variables set explicitly on the command (allowlist and project
+    /// overrides); values are left out since they may hold secrets
+    pub env_keys: Vec<String>,
+    pub prompt_via_stdin: bool,
+}
+
+fn elide_prompt(prompt: &str) -> String {
+    let total = prompt.chars().count();
+    if total <= PREVIEW_PROMPT_CHARS {
+        return prompt.to_string();
+    }
+    let head: String = prompt.chars().take(PREVIEW_PROMPT_CHARS).collect();
+    format!("{}… ({} chars)", head, total)
+}
+
+/// Build the preview of `cmd` after the same preparation a spawn would apply
+pub async fn preview_command(
+    app: &AppHandle,
+    mut cmd: Command,
+    provider: &str,
+    project_path: &str,
+    prompt: &str,
+    options: &ChatOptions,
+) -> Result<CommandPreview, String> {
+    let prepared = prepare_command(app, &mut cmd, provider, project_path, prompt, options).await?;
+    let std_cmd = cmd.as_std();
+    let args = std_cmd
+        .get_args()
+        .map(|a| a.to_string_lossy().to_string())
+        .map(|a| {
+            if a == prompt {
+                elide_prompt(&a)
+            } else if !prompt.is_empty() {
+                // Login shell scripts carry the prompt quoted inside them
+                let quoted = super::login_shell::quote(prompt);
+                a.replace(&quoted, &super::login_shell::quote(&elide_prompt(prompt)))
+            } else {
+                a
+            }
+        })
+        .collect();
+    let mut env_keys: Vec<String> = std_cmd
+        .get_envs()
+        .filter(|(_, value)| value.is_some())
+        .map(|(key, _)| key.to_string_lossy().to_string())
+        .collect();
+    env_keys.sort();
+    Ok(CommandPreview {
+        provider: provider.to_string(),
+        program: std_cmd.get_program().to_string_lossy().to_string(),
+        args,
+        cwd: prepared.cwd,
+        env_keys,
+        prompt_via_stdin: prepared.stdin_prompt.is_some(),
+    })
+}
+
+/// Spawn a provider CLI and stream its output as normalized events.
+///
+/// Shared by the Codex and Gemini commands. When `options.max_attempts` is
+/// greater than one, runs that fail with a transient error (rate limit,
+/// network) are retried with exponential backoff and a `<provider>-retry`
+/// event is emitted before each new attempt. Every successful spawn emits
+/// `<provider>-spawned` with the child's pid before any output; until the
+/// first output line, `<provider>-heartbeat` follows every
+/// `HEARTBEAT_INTERVAL` with the attempt's `elapsed_ms`.
+pub async fn spawn_provider_process(
+    app: AppHandle,
+    mut cmd: Command,
+    request: SpawnRequest,
+) -> Result<SessionHandle, String> {
+    let SpawnRequest {
+        provider,
+        session_id,
+        prompt,
+        model,
+        project_path,
+        options,
+        group_id,
+    } = request;
+
+    // A second run would take over the session's controls, leaving the
+    // first one uncancellable
+    if control::get(&session_id).is_some() {
+        return Err(format!("Session {} is still running", session_id));
+    }
+
+    if options.preflight_storage.unwrap_or(false) {
+        super::storage::preflight(&project_path).ensure_usable()?;
+    }
+
+    let PreparedCommand { cwd, stdin_prompt } =
+        prepare_command(&app, &mut cmd, provider, &project_path, &prompt, &options).await?;
+    cmd.stdout(std::process::Stdio::piped())
+        .stderr(std::process::Stdio::piped())
+        .stdin(std::process::Stdio::piped());
+
+    // Open the sink up front so a bad path fails before anything is spawned
+    let output_sink = match options.output_file.as_deref() {
+        Some(path) => Some(FileSink::open(path, &project_path)?),
+        None => None,
+    };
+
+    let keep_stdin = options.interactive.unwrap_or(false);
+
+    let (mut child, stdin) = spawn_child(&mut cmd, provider, stdin_prompt.as_deref(), keep_stdin)?;
+    let pid = child.id().unwrap_or_default();
+    let pipes = take_pipes(&mut child, provider);
+    session_event(
+        Level::Info,
+        "spawn",
+        provider,
+        Some(&session_id),
+        Some(pid),
+        format!("model={} cwd={}", model, cwd),
+    );
+
+    // Register session in process registry (without child handle)
+    let run_id = {
+        let registry = app.state::<crate::process::ProcessRegistryState>();
+        registry
+            .0
+            .register_chat_session(
+                session_id.clone(),
+                provider.to_string(),
+                pid,
+                project_path.clone(),
+                prompt.clone(),
+                model.clone(),
+            )
+            .ok()
+    };
+
+    // Carry over tags from earlier runs of this session (e.g. on resume)
+    if let Ok(conn) = super::settings::open_db(&app) {
+        if let Ok(tags) = super::tags::load_tags(&conn, &session_id) {
+            if !tags.is_empty() {
+                let registry = app.state::<crate::process::ProcessRegistryState>();
+                let _ = registry.0.set_session_tags(&session_id, tags);
+            }
+        }
+    }
+
+    // The session owns its child from here on, for cancellation
+    let control = control::register(provider, &session_id, group_id.clone(), child);
+    control.set_stdin(stdin);
+    let handle = control.handle();
+
+    // Acknowledge the spawn so process UIs can show the pid (and a kill
+    // button) without polling the registry
+    let spawned_msg = with_group(
+        envelope(json!({
+            "session_id": session_id,
+            "pid": pid,
+            "model": model,
+            "cwd": cwd,
+            "attempt": 1,
+            "seq": control.next_seq(),
+        })),
+        group_id.as_deref(),
+    );
+    control.emit(&app, "spawned", spawned_msg);
+
+    // Emit init message immediately so UI can bind to session-specific channel
+    let init_msg = with_group(
+        envelope(json!({
+            "type": "system",
+            "subtype": "init",
+            "session_id": session_id,
+            "model": model,
+            "cwd": cwd,
+            "project_path": project_path,
+            "provider": provider,
+            // Known from earlier runs: `false` means expect a long silence
+            "streams": streaming::lookup(&app, provider, &model),
+            "seq": control.next_seq(),
+        })),
+        group_id.as_deref(),
+    );
+    // Through the control so it counts towards the completion barrier
+    control.emit(&app, "output", json!(init_msg.to_string()));
+
+    // Persist the session alongside the emitted stream
+    let compress =
+        super::settings::read_setting(&app, COMPRESS_TRANSCRIPTS_KEY).as_deref() == Some("true");
+    let transcript = match TranscriptWriter::open(&project_path, &session_id, provider, compress) {
+        Ok(writer) => {
+            writer.append(&init_msg);
+            writer.append(&envelope(json!({
+                "type": "user",
+                "message": { "content": [{"type": "text", "text": prompt}] }
+            })));
+            Some(writer)
+        }
+        Err(e) => {
+            log::warn!("Not persisting {} session {}: {}", provider, session_id, e);
+            None
+        }
+    };
+
+    let raw_log = if options.persist_raw.unwrap_or(false) {
+        match RawLog::open(&project_path, &session_id) {
+            Ok(raw_log) => Some(raw_log),
+            Err(e) => {
+                log::warn!("Not writing raw log for {} session {}: {}", provider, session_id, e);
+                None
+            }
+        }
+    } else {
+        None
+    };
+
+    let (stdout, stderr) = match pipes {
+        Ok(pipes) => pipes,
+        Err(e) => {
+            session_event(Level::Error, "spawn", provider, Some(&session_id), Some(pid), e.clone());
+            emit_error(&app, provider, &session_id, &e);
+            finish_session(&app, &control, provider, &session_id, run_id, false).await;
+            return Err(e);
+        }
+    };
+
+    let policy = RetryPolicy::from_options(&options);
+    let ctx = Arc::new(StreamContext {
+        app: app.clone(),
+        provider,
+        session_id: session_id.clone(),
+        control,
+        // Mask secrets before anything leaves the process
+        redactor: Redactor::load(&app),
+        normalizer: Normalizer::load(&app, provider),
+        transcript,
+        output_sink,
+        raw_log,
+        partial: PartialCheckpoint::new(&project_path, &session_id, provider).ok(),
+        reassemble_json: options.reassemble_json.unwrap_or(false),
+        structured_output: options
+            .response_format
+            .as_ref()
+            .map(|_| std::sync::Mutex::new(String::new())),
+        model: model.clone(),
+        auto_accept_downloads: super::settings::read_setting(&app, AUTO_ACCEPT_DOWNLOADS_KEY).as_deref()
+            == Some("true"),
+        persist_stderr: super::settings::read_setting(&app, &persist_stderr_key(provider)).as_deref()
+            == Some("true"),
+        combined_stream: options.combined_stream.unwrap_or(false),
+        max_output_lines: options
+            .max_output_lines
+            .or_else(|| default_max_output_lines(&app))
+            .filter(|limit| *limit > 0),
+        output_lines: AtomicUsize::new(0),
+        delimiter: framing::load_delimiter(&app, provider),
+    });
+    tokio::spawn(async move {
+        let mut attempt = 1;
+        let mut pid = pid;
+        let mut pipes = (stdout, stderr);
+        let mut failure = None;
+        let success = loop {
+            // A retried attempt produces its answer from scratch
+            if let Some(output) = &ctx.structured_output {
+                if let Ok(mut output) = output.lock() {
+                    output.clear();
+                }
+            }
+            let (stderr_tail, streamed) = stream_output(&ctx, pid, pipes.0, pipes.1).await;
+            if let Some(streamed) = streamed {
+                if let Err(e) = streaming::record(&app, provider, &model, streamed) {
+                    log::warn!("Failed to record streaming for {}: {}", model, e);
+                }
+            }
+            let status = ctx.control.wait().await;
+            let success = status.map(|s| s.success()).unwrap_or(false);
+            if !success {
+                let exit_code = status.and_then(|s| s.code());
+                failure = Some((exit_code, last_error::summarize_failure(exit_code, &stderr_tail)));
+            }
+
+            if success || attempt >= policy.max_attempts || ctx.control.is_cancelled() {
+                break success;
+            }
+            let exit_code = match status {
+                Some(s) => s.code(),
+                // Killed (e.g. cancelled); never retry that
+                None => break false,
+            };
+            if classify_failure(exit_code, &stderr_tail) != FailureKind::Transient {
+                break false;
+            }
+
+            let delay = policy.delay_for(attempt);
+            attempt += 1;
+            session_event(
+                Level::Warn,
+                "retry",
+                provider,
+                Some(&session_id),
+                Some(pid),
+                format!(
+                    "transient failure exit_code={:?}, attempt {}/{} in {:?}",
+                    exit_code, attempt, policy.max_attempts, delay
+                ),
+            );
+            let retry_msg = envelope(json!({
+                "session_id": session_id,
+                "attempt": attempt,
+                "max_attempts": policy.max_attempts,
+                "delay_ms": delay.as_millis() as u64,
+                "exit_code": exit_code,
+            }));
+            ctx.emit("retry", &retry_msg);
+            // A cancel during the backoff ends the session instead of respawning
+            tokio::select! {
+                _ = tokio::time::sleep(delay) => {}
+                _ = ctx.control.stopped() => break false,
+            }
+
+            let taken = match spawn_child(&mut cmd, provider, stdin_prompt.as_deref(), keep_stdin) {
+                Ok((mut child, stdin)) => {
+                    pid = child.id().unwrap_or_default();
+                    let taken = take_pipes(&mut child, provider);
+                    ctx.control.set_child(child).await;
+                    if let Some(run_id) = run_id {
+                        let registry = app.state::<crate::process::ProcessRegistryState>();
+                        let _ = registry.0.set_pid(run_id, pid);
+                    }
+                    ctx.control.set_stdin(stdin);
+                    session_event(
+                        Level::Info,
+                        "spawn",
+                        provider,
+                        Some(&session_id),
+                        Some(pid),
+                        format!("retry attempt {}", attempt),
+                    );
+                    ctx.emit(
+                        "spawned",
+                        &envelope(json!({
+                            "session_id": session_id,
+                            "pid": pid,
+                            "model": model,
+                            "cwd": cwd,
+                            "attempt": attempt,
+                        })),
+                    );
+                    taken
+                }
+                Err(e) => Err(e),
+            };
+            match taken {
+                Ok(p) => pipes = p,
+                Err(e) => {
+                    emit_error(&app, provider, &session_id, &e);
+                    failure = None;
+                    break false;
+                }
+            }
+        };
+
+        session_event(
+            if success { Level::Info } else { Level::Warn },
+            "complete",
+            provider,
+            Some(&session_id),
+            Some(pid),
+            format!("success={} attempts={}", success, attempt),
+        );
+
+        if success && !ctx.control.is_cancelled() {
+            ctx.emit_structured_result();
+            if let Err(e) = model_usage::record_use(&app, provider, &model) {
+                log::warn!("Failed to record use of {}: {}", model, e);
+            }
+        }
+        // A capped run already recorded why it stopped
+        if !success && !ctx.control.is_cancelled() && !ctx.output_capped() {
+            if let Some((exit_code, message)) = &failure {
+                last_error::record(&app, provider, Some(&session_id), message, *exit_code);
+            }
+        }
+        // Close the transcript with a marker so it doesn't read as a finished conversation
+        if ctx.control.is_cancelled() {
+            if let Some(transcript) = &ctx.transcript {
+                transcript.append(&cancelled_marker(&session_id));
+            }
+        }
+
+        // The session ended on its own; nothing left to recover
+        if let Some(partial) = &ctx.partial {
+            partial.clear();
+        }
+
+        finish_session(&app, &ctx.control, provider, &session_id, run_id, success).await;
+    });
+
+    Ok(handle)
+}
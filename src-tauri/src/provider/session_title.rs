@@ -0,0 +1,225 @@
+use chrono::Utc;
+use rusqlite::Connection;
+use serde::Serialize;
+use serde_json::Value;
+use sha2::{Digest, Sha256};
+use std::collections::HashMap;
+use std::path::Path;
+use std::time::Duration;
+use tauri::AppHandle;
+use tokio::process::Command;
+
+use super::compact::{self, turns_from_transcript};
+use super::settings::open_db;
+use super::transcript::{find_transcript, locate_transcript};
+use crate::unified_history::read_jsonl;
+
+/// Characters of the conversation sent along; its opening says what a
+/// session is about, and a short input keeps the call cheap
+const MAX_INPUT_CHARS: usize = 6_000;
+
+/// How long the title call may run
+const TITLE_TIMEOUT: Duration = Duration::from_secs(60);
+
+/// Longest title kept; longer answers are cut at a word boundary
+const MAX_TITLE_CHARS: usize = 80;
+
+/// Markdown and quoting models wrap their answer lines in
+const DECORATION: &[char] = &['#', '*', '"', '\'', '`', ' ', '\t'];
+
+/// Generated title of a session
+#[derive(Debug, Clone, Serialize)]
+pub struct SessionTitle {
+    pub session_id: String,
+    pub title: String,
+    /// One-sentence summary, when the provider gave one
+    pub summary: Option<String>,
+    /// Provider that wrote the title
+    pub provider: String,
+    pub generated_at: String,
+    /// Whether this came from the cache instead of a new provider call
+    pub cached: bool,
+}
+
+fn ensure_table(conn: &Connection) -> Result<(), String> {
+    conn.execute(
+        "CREATE TABLE IF NOT EXISTS session_titles (
+            session_id TEXT PRIMARY KEY,
+            title TEXT NOT NULL,
+            summary TEXT,
+            provider TEXT NOT NULL,
+            transcript_hash TEXT NOT NULL,
+            generated_at TEXT NOT NULL
+        )",
+        [],
+    )
+    .map_err(|e| e.to_string())?;
+    Ok(())
+}
+
+/// Cached title of a session with the hash of the conversation it was made from
+fn load(conn: &Connection, session_id: &str) -> Result<Option<(SessionTitle, String)>, String> {
+    ensure_table(conn)?;
+    Ok(conn
+        .query_row(
+            "SELECT title, summary, provider, generated_at, transcript_hash FROM session_titles
+             WHERE session_id = ?1",
+            [session_id],
+            |row| {
+                let title = SessionTitle {
+                    session_id: session_id.to_string(),
+                    title: row.get(0)?,
+                    summary: row.get(1)?,
+                    provider: row.get(2)?,
+                    generated_at: row.get(3)?,
+                    cached: true,
+                };
+                Ok((title, row.get(4)?))
+            },
+        )
+        .ok())
+}
+
+fn save(conn: &Connection, title: &SessionTitle, hash: &str) -> Result<(), String> {
+    ensure_table(conn)?;
+    conn.execute(
+        "INSERT INTO session_titles (session_id, title, summary, provider, transcript_hash, generated_at)
+         VALUES (?1, ?2, ?3, ?4, ?5, ?6)
+         ON CONFLICT(session_id) DO UPDATE SET title = excluded.title, summary = excluded.summary,
+             provider = excluded.provider, transcript_hash = excluded.transcript_hash,
+             generated_at = excluded.generated_at",
+        rusqlite::params![title.session_id, title.title, title.summary, title.provider, hash, title.generated_at],
+    )
+    .map_err(|e| e.to_string())?;
+    Ok(())
+}
+
+/// Every cached title by session id, for the history list
+pub fn cached_titles(conn: &Connection) -> Result<HashMap<String, String>, String> {
+    ensure_table(conn)?;
+    let mut stmt = conn
+        .prepare("SELECT session_id, title FROM session_titles")
+        .map_err(|e| e.to_string())?;
+    let titles = stmt
+        .query_map([], |row| Ok((row.get::<_, String>(0)?, row.get::<_, String>(1)?)))
+        .map_err(|e| e.to_string())?
+        .collect::<Result<HashMap<_, _>, _>>()
+        .map_err(|e| e.to_string())?;
+    Ok(titles)
+}
+
+/// Title and optional summary from the provider's answer: the first line is
+/// the title (quotes, markdown and a `Title:` label removed), the next
+/// non-empty line the summary
+pub fn parse_reply(reply: &str) -> Option<(String, Option<String>)> {
+    let clean = |line: &str| {
+        let line = line.trim().trim_start_matches('-').trim_matches(DECORATION);
+        let line = ["Title:", "title:", "Summary:", "summary:"]
+            .iter()
+            .find_map(|label| line.strip_prefix(label))
+            .unwrap_or(line);
+        line.trim_matches(DECORATION).to_string()
+    };
+    let mut lines = reply.lines().map(clean).filter(|l| !l.is_empty());
+    let title = lines.next()?;
+    let title = if title.chars().count() > MAX_TITLE_CHARS {
+        let cut: String = title.chars().take(MAX_TITLE_CHARS).collect();
+        match cut.rsplit_once(' ') {
+            Some((head, _)) => format!("{}…", head),
+            None => format!("{}…", cut),
+        }
+    } else {
+        title
+    };
+    Some((title, lines.next()))
+}
+
+/// Title of a stored session, generated by the provider that ran it and
+/// cached until the conversation changes (or `force` is set).
+///
+/// `title_cmd` builds the CLI invocation (binary, env and model) for a
+/// provider; the request is appended as the prompt. Without a project every
+/// project's sessions are searched.
+pub async fn summarize(
+    app: &AppHandle,
+    session_id: &str,
+    project_path: Option<&str>,
+    force: bool,
+    title_cmd: impl FnOnce(&str) -> Result<Command, String>,
+) -> Result<SessionTitle, String> {
+    let path = match project_path {
+        Some(project) => find_transcript(project, session_id)?,
+        None => locate_transcript(session_id)?,
+    }
+    .ok_or_else(|| format!("No transcript for session {}", session_id))?;
+    let messages = read_jsonl(&path);
+    let turns = turns_from_transcript(&messages);
+    if turns.is_empty() {
+        return Err(format!("Session {} has no conversation to summarize", session_id));
+    }
+    let conversation = compact::render(&turns);
+    let hash: String = Sha256::digest(conversation.as_bytes())
+        .iter()
+        .map(|b| format!("{:02x}", b))
+        .collect();
+
+    if !force {
+        if let Some((title, cached_hash)) = load(&open_db(app)?, session_id)? {
+            if cached_hash == hash {
+                return Ok(title);
+            }
+        }
+    }
+
+    let provider = messages
+        .iter()
+        .find_map(|m| m.get("provider").and_then(Value::as_str))
+        .filter(|p| matches!(*p, "codex" | "gemini"))
+        .unwrap_or("codex")
+        .to_string();
+    let excerpt: String = conversation.chars().take(MAX_INPUT_CHARS).collect();
+    let request = format!(
+        "Give the following conversation a title of at most eight words on the first line, \
+         then summarize it in one sentence on the second line. Reply with those two lines only.\n\n{}",
+        excerpt
+    );
+    let dir = match project_path {
+        Some(project) => Path::new(project),
+        None => path.parent().unwrap_or(Path::new(".")),
+    };
+    let reply = compact::ask(title_cmd(&provider)?, dir, request, TITLE_TIMEOUT)
+        .await
+        .map_err(|e| format!("Generating a title for {} failed: {}", session_id, e))?;
+    let (title, summary) =
+        parse_reply(&reply).ok_or_else(|| format!("The {} CLI returned no title for {}", provider, session_id))?;
+
+    let title = SessionTitle {
+        session_id: session_id.to_string(),
+        title,
+        summary,
+        provider,
+        generated_at: Utc::now().to_rfc3339(),
+        cached: false,
+    };
+    save(&open_db(app)?, &title, &hash)?;
+    Ok(title)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_reply() {
+        assert_eq!(
+            parse_reply("**Title:** \"Fix the login redirect\"\nSummary: Debugging a loop after OAuth.\n"),
+            Some(("Fix the login redirect".to_string(), Some("Debugging a loop after OAuth.".to_string())))
+        );
+        assert_eq!(parse_reply("\n  Refactor parser \n"), Some(("Refactor parser".to_string(), None)));
+        assert_eq!(parse_reply("  \n"), None);
+
+        let long = "word ".repeat(30);
+        let (title, _) = parse_reply(&long).unwrap();
+        assert!(title.ends_with('…') && title.chars().count() <= MAX_TITLE_CHARS + 1);
+    }
+}
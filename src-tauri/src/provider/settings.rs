@@ -0,0 +1,164 @@
+use rusqlite::{Connection, ErrorCode};
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::sync::OnceLock;
+use std::time::Duration;
+use tauri::{AppHandle, Manager};
+
+/// File name of the settings/agents database
+const DB_FILE: &str = "agents.db";
+
+/// How long SQLite waits on a lock held by another connection before failing
+/// with "database is locked"; every command opens its own connection
+const BUSY_TIMEOUT: Duration = Duration::from_secs(5);
+
+/// Attempts of a write that still finds the database locked
+const WRITE_ATTEMPTS: u32 = 3;
+
+/// Pause before retrying a locked write, multiplied by the attempt number
+const WRITE_RETRY_DELAY: Duration = Duration::from_millis(50);
+
+/// Directory holding the settings database.
+///
+/// Normally the Tauri app data dir. When that can't be resolved or created
+/// (sandboxed or portable installs) this falls back to `~/.ishinex`, where
+/// unified histories are already stored. Resolved once per app run so reads
+/// and writes always agree.
+pub fn data_dir(app: &AppHandle) -> Result<PathBuf, String> {
+    static DATA_DIR: OnceLock<PathBuf> = OnceLock::new();
+    if let Some(dir) = DATA_DIR.get() {
+        return Ok(dir.clone());
+    }
+
+    let resolved = match app.path().app_data_dir() {
+        Ok(dir) => match fs::create_dir_all(&dir) {
+            Ok(()) => Some(dir),
+            Err(e) => {
+                log::warn!("App data dir {:?} is not usable: {}", dir, e);
+                None
+            }
+        },
+        Err(e) => {
+            log::warn!("App data dir unavailable: {}", e);
+            None
+        }
+    };
+    let dir = match resolved {
+        Some(dir) => dir,
+        None => {
+            let dir = dirs::home_dir()
+                .ok_or_else(|| "Could not find home directory".to_string())?
+                .join(".ishinex");
+            fs::create_dir_all(&dir).map_err(|e| e.to_string())?;
+            log::info!("Using fallback data dir {:?}", dir);
+            dir
+        }
+    };
+    Ok(DATA_DIR.get_or_init(|| dir).clone())
+}
+
+/// Path of the settings database (the directory is created if needed)
+pub fn db_path(app: &AppHandle) -> Result<PathBuf, String> {
+    Ok(data_dir(app)?.join(DB_FILE))
+}
+
+/// Open a database connection that waits for other connections' locks
+pub fn connect(path: &Path) -> rusqlite::Result<Connection> {
+    let conn = Connection::open(path)?;
+    conn.busy_timeout(BUSY_TIMEOUT)?;
+    Ok(conn)
+}
+
+/// Whether an error means another connection held the database
+pub fn is_busy(err: &rusqlite::Error) -> bool {
+    matches!(
+        err,
+        rusqlite::Error::SqliteFailure(e, _) if matches!(e.code, ErrorCode::DatabaseBusy | ErrorCode::DatabaseLocked)
+    )
+}
+
+/// Run a write, retrying a few times while the database stays locked past
+/// the busy timeout
+pub fn with_retry<T>(mut write: impl FnMut() -> rusqlite::Result<T>) -> Result<T, String> {
+    let mut attempt = 1;
+    loop {
+        match write() {
+            Ok(value) => return Ok(value),
+            Err(e) if is_busy(&e) && attempt < WRITE_ATTEMPTS => {
+                log::warn!("Settings database is locked (attempt {}/{}); retrying", attempt, WRITE_ATTEMPTS);
+                std::thread::sleep(WRITE_RETRY_DELAY * attempt);
+                attempt += 1;
+            }
+            Err(e) if is_busy(&e) => {
+                return Err(format!("The settings database is busy in another operation; please try again ({})", e))
+            }
+            Err(e) => return Err(e.to_string()),
+        }
+    }
+}
+
+/// Read a value from the `app_settings` table, if present
+pub fn read_setting(app: &AppHandle, key: &str) -> Option<String> {
+    if let Ok(db_path) = db_path(app) {
+        if db_path.exists() {
+            if let Ok(conn) = connect(&db_path) {
+                if let Ok(val) = conn.query_row(
+                    "SELECT value FROM app_settings WHERE key = ?1",
+                    rusqlite::params![key],
+                    |row| row.get::<_, String>(0),
+                ) {
+                    return Some(val);
+                }
+            }
+        }
+    }
+    None
+}
+
+/// Open the settings database, creating the data dir if needed
+pub fn open_db(app: &AppHandle) -> Result<Connection, String> {
+    connect(&db_path(app)?).map_err(|e| e.to_string())
+}
+
+/// Insert or update a value in the `app_settings` table
+pub fn write_setting(app: &AppHandle, key: &str, value: &str) -> Result<(), String> {
+    let conn = open_db(app)?;
+    with_retry(|| {
+        conn.execute(
+            "CREATE TABLE IF NOT EXISTS app_settings (key TEXT PRIMARY KEY, value TEXT)",
+            [],
+        )?;
+        conn.execute(
+            "INSERT INTO app_settings(key, value) VALUES(?1, ?2)
+             ON CONFLICT(key) DO UPDATE SET value=excluded.value",
+            rusqlite::params![key, value],
+        )
+    })?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_with_retry() {
+        let busy = || rusqlite::Error::SqliteFailure(rusqlite::ffi::Error::new(rusqlite::ffi::SQLITE_BUSY), None);
+        let mut calls = 0;
+        let result = with_retry(|| {
+            calls += 1;
+            if calls < WRITE_ATTEMPTS { Err(busy()) } else { Ok(calls) }
+        });
+        assert_eq!(result, Ok(WRITE_ATTEMPTS));
+
+        let mut calls = 0;
+        assert!(with_retry(|| -> rusqlite::Result<()> {
+            calls += 1;
+            Err(busy())
+        })
+        .unwrap_err()
+        .contains("busy"));
+        assert_eq!(calls, WRITE_ATTEMPTS);
+        assert!(!is_busy(&rusqlite::Error::QueryReturnedNoRows));
+    }
+}
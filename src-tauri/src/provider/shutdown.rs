@@ -0,0 +1,120 @@
+use std::time::{Duration, Instant};
+use tauri::{AppHandle, Manager};
+
+use super::control;
+use crate::process::registry::{ProcessInfo, ProcessType};
+use crate::process::ProcessRegistryState;
+
+/// How long children get to exit after being asked to before they are killed
+const GRACE_PERIOD: Duration = Duration::from_secs(2);
+/// Poll interval while waiting for children to exit
+const POLL_INTERVAL: Duration = Duration::from_millis(50);
+
+/// Session id of a chat session's registry entry
+fn chat_session_id(info: &ProcessInfo) -> Option<&str> {
+    match &info.process_type {
+        ProcessType::ChatSession { session_id, .. } => Some(session_id),
+        _ => None,
+    }
+}
+
+/// Pid to signal for a registry entry. A chat session's pid comes from its
+/// controls, which follow retries; once those are gone the session has ended
+/// and its old pid may belong to an unrelated process, so there is none.
+fn live_pid(info: &ProcessInfo) -> Option<u32> {
+    let pid = match chat_session_id(info) {
+        Some(session_id) => control::get(session_id)?.pid(),
+        None => info.pid,
+    };
+    // A pid of 0 would signal our own process group
+    (pid != 0).then_some(pid)
+}
+
+/// Ask a process to exit (SIGTERM); Windows has no equivalent, so stragglers
+/// are killed once the grace period is over
+fn request_exit(pid: u32) {
+    #[cfg(unix)]
+    unsafe {
+        libc::kill(pid as libc::pid_t, libc::SIGTERM);
+    }
+    #[cfg(not(unix))]
+    let _ = pid;
+}
+
+/// Whether a process is still running. Chat sessions count as ended once the
+/// runner has torn them down; other entries are checked through their child.
+async fn is_running(registry: &ProcessRegistryState, info: &ProcessInfo) -> bool {
+    match chat_session_id(info) {
+        Some(session_id) => control::get(session_id).is_some(),
+        None => registry.0.is_process_running(info.run_id).await.unwrap_or(false),
+    }
+}
+
+/// Terminate every provider, Claude and agent process still running; called
+/// when the app exits so no CLI keeps spending API quota in the background.
+///
+/// Processes are asked to exit, given `GRACE_PERIOD`, then killed. Every
+/// entry is removed from the registry and chat sessions are marked cancelled.
+/// Returns how many processes were still running.
+pub async fn terminate_all(app: &AppHandle) -> usize {
+    let registry = app.state::<ProcessRegistryState>();
+    let running = registry.0.get_running_processes().unwrap_or_default();
+    if running.is_empty() {
+        return 0;
+    }
+    log::info!("Terminating {} running process(es) before exit", running.len());
+
+    for info in &running {
+        if let Some(control) = chat_session_id(info).and_then(control::get) {
+            control.mark_cancelled();
+        }
+        if let Some(pid) = live_pid(info) {
+            request_exit(pid);
+        }
+    }
+
+    let deadline = Instant::now() + GRACE_PERIOD;
+    let mut remaining = running.clone();
+    while !remaining.is_empty() && Instant::now() < deadline {
+        tokio::time::sleep(POLL_INTERVAL).await;
+        let mut still_running = Vec::new();
+        for info in remaining {
+            if is_running(&registry, &info).await {
+                still_running.push(info);
+            }
+        }
+        remaining = still_running;
+    }
+
+    for info in &remaining {
+        match chat_session_id(info) {
+            // The session owns its child and kills it itself; without controls
+            // it has ended, and there is no pid left that is safe to signal
+            Some(session_id) => {
+                if let Some(control) = control::get(session_id) {
+                    log::warn!(
+                        "Session {} (pid {}) ignored the exit request; killing it",
+                        session_id,
+                        control.pid()
+                    );
+                    control.kill().await;
+                }
+            }
+            None => {
+                log::warn!("Process {} (pid {}) ignored the exit request; killing it", info.run_id, info.pid);
+                let killed = registry.0.kill_process(info.run_id).await.unwrap_or(false);
+                if !killed && info.pid != 0 {
+                    let _ = registry.0.kill_process_by_pid(info.run_id, info.pid);
+                }
+            }
+        }
+    }
+
+    for info in &running {
+        let _ = registry.0.unregister_process(info.run_id);
+        if let Some(control) = chat_session_id(info).and_then(control::get) {
+            control::remove(&control);
+        }
+    }
+    running.len()
+}
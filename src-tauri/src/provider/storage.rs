@@ -0,0 +1,79 @@
+use serde::Serialize;
+use std::path::Path;
+
+/// Below this much free space a session is refused when preflight is requested
+const MIN_FREE_BYTES: u64 = 50 * 1024 * 1024;
+
+/// Whether the ishinex data dir can take a session's persisted output
+#[derive(Debug, Clone, Serialize)]
+pub struct StorageStatus {
+    pub path: String,
+    pub writable: bool,
+    /// Bytes available to the current user; `None` where it can't be queried
+    pub free_bytes: Option<u64>,
+    pub error: Option<String>,
+}
+
+impl StorageStatus {
+    /// Turn an unusable status into a clear error
+    pub fn ensure_usable(&self) -> Result<(), String> {
+        if !self.writable {
+            return Err(format!(
+                "Session storage {} is not writable: {}",
+                self.path,
+                self.error.as_deref().unwrap_or("unknown error")
+            ));
+        }
+        match self.free_bytes {
+            Some(free) if free < MIN_FREE_BYTES => Err(format!(
+                "Only {} MB free at {}; at least {} MB are needed",
+                free / (1024 * 1024),
+                self.path,
+                MIN_FREE_BYTES / (1024 * 1024)
+            )),
+            _ => Ok(()),
+        }
+    }
+}
+
+#[cfg(unix)]
+#[allow(clippy::unnecessary_cast)] // statvfs field widths differ between platforms
+pub(crate) fn free_bytes(path: &Path) -> Option<u64> {
+    use std::os::unix::ffi::OsStrExt;
+    let c_path = std::ffi::CString::new(path.as_os_str().as_bytes()).ok()?;
+    let mut stat: libc::statvfs = unsafe { std::mem::zeroed() };
+    // SAFETY: c_path is a valid NUL-terminated string and stat is a valid out pointer
+    if unsafe { libc::statvfs(c_path.as_ptr(), &mut stat) } != 0 {
+        return None;
+    }
+    Some(stat.f_bavail as u64 * stat.f_frsize as u64)
+}
+
+#[cfg(not(unix))]
+pub(crate) fn free_bytes(_path: &Path) -> Option<u64> {
+    None
+}
+
+/// Check that the project's session directory can be created and written
+pub fn preflight(project_path: &str) -> StorageStatus {
+    let dir = match super::transcript::sessions_dir(project_path) {
+        Ok(dir) => dir,
+        Err(e) => {
+            return StorageStatus {
+                path: String::new(),
+                writable: false,
+                free_bytes: None,
+                error: Some(e),
+            }
+        }
+    };
+    let write_check = std::fs::create_dir_all(&dir)
+        .and_then(|_| tempfile::NamedTempFile::new_in(&dir).map(|_| ()))
+        .map_err(|e| e.to_string());
+    StorageStatus {
+        path: dir.to_string_lossy().to_string(),
+        writable: write_check.is_ok(),
+        free_bytes: free_bytes(&dir),
+        error: write_check.err(),
+    }
+}
@@ -0,0 +1,128 @@
+use rusqlite::Connection;
+use std::time::{Duration, Instant};
+use tauri::AppHandle;
+
+use super::settings::open_db;
+
+/// Silence after spawn before a `<provider>-waiting` event is emitted
+pub const WAITING_AFTER: Duration = Duration::from_secs(10);
+
+/// Interval of `<provider>-heartbeat` events until the first output
+pub const HEARTBEAT_INTERVAL: Duration = Duration::from_secs(5);
+
+/// Output spread over at least this long counts as streamed
+const STREAM_SPREAD: Duration = Duration::from_secs(1);
+
+/// Watches when stdout lines arrive during one attempt to tell a model that
+/// streams from one that prints its whole answer at the end
+pub struct StreamObserver {
+    started: Instant,
+    first_output: Option<Instant>,
+    last_output: Option<Instant>,
+    waited: bool,
+    last_heartbeat: Option<Instant>,
+}
+
+impl Default for StreamObserver {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl StreamObserver {
+    pub fn new() -> Self {
+        Self {
+            started: Instant::now(),
+            first_output: None,
+            last_output: None,
+            waited: false,
+            last_heartbeat: None,
+        }
+    }
+
+    pub fn on_output(&mut self) {
+        let now = Instant::now();
+        self.first_output.get_or_insert(now);
+        self.last_output = Some(now);
+    }
+
+    /// True once, when nothing has been printed for `WAITING_AFTER`
+    pub fn should_announce_waiting(&mut self) -> bool {
+        if self.waited || self.first_output.is_some() || self.started.elapsed() < WAITING_AFTER {
+            return false;
+        }
+        self.waited = true;
+        true
+    }
+
+    /// True every `HEARTBEAT_INTERVAL` of silence, until the first output
+    pub fn should_heartbeat(&mut self) -> bool {
+        if self.first_output.is_some() {
+            return false;
+        }
+        let since = self.last_heartbeat.unwrap_or(self.started);
+        if since.elapsed() < HEARTBEAT_INTERVAL {
+            return false;
+        }
+        self.last_heartbeat = Some(Instant::now());
+        true
+    }
+
+    pub fn waited_ms(&self) -> u64 {
+        self.started.elapsed().as_millis() as u64
+    }
+
+    /// Whether the output streamed, when the run tells: spread-out output
+    /// streamed; a long silence followed by everything at once did not.
+    /// Fast runs are inconclusive.
+    pub fn streamed(&self) -> Option<bool> {
+        let (first, last) = (self.first_output?, self.last_output?);
+        if last.duration_since(first) >= STREAM_SPREAD {
+            Some(true)
+        } else if self.waited {
+            Some(false)
+        } else {
+            None
+        }
+    }
+}
+
+fn ensure_table(conn: &Connection) -> Result<(), String> {
+    conn.execute(
+        "CREATE TABLE IF NOT EXISTS model_streaming (
+            provider TEXT NOT NULL,
+            model TEXT NOT NULL,
+            streamed INTEGER NOT NULL,
+            updated_at TEXT NOT NULL DEFAULT CURRENT_TIMESTAMP,
+            PRIMARY KEY (provider, model)
+        )",
+        [],
+    )
+    .map_err(|e| e.to_string())?;
+    Ok(())
+}
+
+/// Remember whether a model's last conclusive run streamed
+pub fn record(app: &AppHandle, provider: &str, model: &str, streamed: bool) -> Result<(), String> {
+    let conn = open_db(app)?;
+    ensure_table(&conn)?;
+    conn.execute(
+        "INSERT INTO model_streaming (provider, model, streamed) VALUES (?1, ?2, ?3)
+         ON CONFLICT(provider, model) DO UPDATE SET streamed = excluded.streamed, updated_at = CURRENT_TIMESTAMP",
+        rusqlite::params![provider, model, streamed],
+    )
+    .map_err(|e| e.to_string())?;
+    Ok(())
+}
+
+/// Whether the model streamed last time, if known
+pub fn lookup(app: &AppHandle, provider: &str, model: &str) -> Option<bool> {
+    let conn = open_db(app).ok()?;
+    ensure_table(&conn).ok()?;
+    conn.query_row(
+        "SELECT streamed FROM model_streaming WHERE provider = ?1 AND model = ?2",
+        rusqlite::params![provider, model],
+        |row| row.get(0),
+    )
+    .ok()
+}
@@ -0,0 +1,67 @@
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use sha2::{Digest, Sha256};
+use std::path::PathBuf;
+
+/// Ask the model for JSON output, optionally conforming to a JSON Schema
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct ResponseFormat {
+    /// JSON Schema the answer must follow; `None` accepts any JSON value
+    pub schema: Option<Value>,
+}
+
+/// Write the schema to a content-addressed file in the temp dir and return
+/// its path, for CLIs that take the schema as a file. Identical schemas share
+/// one file, so nothing needs cleaning up after the run.
+pub fn schema_file(format: &ResponseFormat) -> Result<PathBuf, String> {
+    let schema = format.schema.clone().unwrap_or_else(|| serde_json::json!({}));
+    let raw = serde_json::to_string_pretty(&schema).map_err(|e| e.to_string())?;
+    let digest = Sha256::digest(raw.as_bytes());
+    let name: String = digest.iter().take(8).map(|b| format!("{:02x}", b)).collect();
+    let path = std::env::temp_dir().join(format!("ishinex-schema-{}.json", name));
+    if !path.exists() {
+        std::fs::write(&path, raw).map_err(|e| format!("Cannot write schema file: {}", e))?;
+    }
+    Ok(path)
+}
+
+/// Parse the final output of a structured run.
+///
+/// Tolerates a surrounding Markdown code fence, and unwraps Gemini's
+/// `--output-format json` envelope (`{"response": "..."}`) when the answer
+/// itself is JSON text.
+pub fn parse_result(output: &str) -> Result<Value, String> {
+    let text = strip_fence(output.trim());
+    let value: Value =
+        serde_json::from_str(text).map_err(|e| format!("Output is not valid JSON: {}", e))?;
+    if let Some(inner) = value.get("response").and_then(|r| r.as_str()) {
+        if let Ok(inner) = serde_json::from_str::<Value>(strip_fence(inner.trim())) {
+            return Ok(inner);
+        }
+    }
+    Ok(value)
+}
+
+fn strip_fence(text: &str) -> &str {
+    let body = match text.strip_prefix("```") {
+        Some(body) => body,
+        None => return text,
+    };
+    // Drop the info string (`json`) on the opening line
+    let body = body.split_once('\n').map(|(_, rest)| rest).unwrap_or("");
+    body.trim_end().strip_suffix("```").unwrap_or(body).trim()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_result() {
+        assert_eq!(parse_result(" {\"a\": 1}\n").unwrap()["a"], 1);
+        assert_eq!(parse_result("```json\n[1, 2]\n```").unwrap()[1], 2);
+        let gemini = r#"{"response": "```json\n{\"ok\": true}\n```", "stats": {}}"#;
+        assert_eq!(parse_result(gemini).unwrap()["ok"], true);
+        assert!(parse_result("Sure! Here you go").is_err());
+    }
+}
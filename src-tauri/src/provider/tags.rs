@@ -0,0 +1,111 @@
+use rusqlite::Connection;
+use serde::Serialize;
+
+/// A session and the tags attached to it
+#[derive(Debug, Clone, Serialize)]
+pub struct TaggedSession {
+    pub session_id: String,
+    pub project_path: String,
+    pub tags: Vec<String>,
+}
+
+pub fn ensure_tags_table(conn: &Connection) -> Result<(), String> {
+    conn.execute(
+        "CREATE TABLE IF NOT EXISTS session_tags (
+            session_id TEXT NOT NULL,
+            tag TEXT NOT NULL,
+            project_path TEXT NOT NULL DEFAULT '',
+            created_at TEXT NOT NULL DEFAULT CURRENT_TIMESTAMP,
+            PRIMARY KEY (session_id, tag)
+        )",
+        [],
+    )
+    .map_err(|e| e.to_string())?;
+    Ok(())
+}
+
+/// Trim, drop empty and de-duplicate tags, keeping first-seen order
+pub fn clean_tags(tags: Vec<String>) -> Vec<String> {
+    let mut out: Vec<String> = Vec::new();
+    for tag in tags {
+        let tag = tag.trim().to_string();
+        if !tag.is_empty() && !out.contains(&tag) {
+            out.push(tag);
+        }
+    }
+    out
+}
+
+/// Tags currently stored for a session
+pub fn load_tags(conn: &Connection, session_id: &str) -> Result<Vec<String>, String> {
+    ensure_tags_table(conn)?;
+    let mut stmt = conn
+        .prepare("SELECT tag FROM session_tags WHERE session_id = ?1 ORDER BY created_at, tag")
+        .map_err(|e| e.to_string())?;
+    let tags = stmt
+        .query_map(rusqlite::params![session_id], |row| row.get::<_, String>(0))
+        .map_err(|e| e.to_string())?
+        .collect::<Result<Vec<_>, _>>()
+        .map_err(|e| e.to_string())?;
+    Ok(tags)
+}
+
+/// Project path previously recorded for a session, if any
+pub fn stored_project_path(conn: &Connection, session_id: &str) -> Option<String> {
+    conn.query_row(
+        "SELECT project_path FROM session_tags WHERE session_id = ?1 AND project_path != '' LIMIT 1",
+        rusqlite::params![session_id],
+        |row| row.get::<_, String>(0),
+    )
+    .ok()
+}
+
+/// Replace all tags for a session
+pub fn replace_tags(
+    conn: &Connection,
+    session_id: &str,
+    project_path: &str,
+    tags: &[String],
+) -> Result<(), String> {
+    ensure_tags_table(conn)?;
+    conn.execute(
+        "DELETE FROM session_tags WHERE session_id = ?1",
+        rusqlite::params![session_id],
+    )
+    .map_err(|e| e.to_string())?;
+    for tag in tags {
+        conn.execute(
+            "INSERT INTO session_tags (session_id, tag, project_path) VALUES (?1, ?2, ?3)",
+            rusqlite::params![session_id, tag, project_path],
+        )
+        .map_err(|e| e.to_string())?;
+    }
+    Ok(())
+}
+
+/// Sessions in a project carrying `tag`, with all of their tags
+pub fn sessions_by_tag(conn: &Connection, project_path: &str, tag: &str) -> Result<Vec<TaggedSession>, String> {
+    ensure_tags_table(conn)?;
+    let mut stmt = conn
+        .prepare(
+            "SELECT DISTINCT session_id FROM session_tags
+             WHERE project_path = ?1 AND tag = ?2 ORDER BY created_at DESC",
+        )
+        .map_err(|e| e.to_string())?;
+    let ids = stmt
+        .query_map(rusqlite::params![project_path, tag], |row| row.get::<_, String>(0))
+        .map_err(|e| e.to_string())?
+        .collect::<Result<Vec<_>, _>>()
+        .map_err(|e| e.to_string())?;
+
+    ids.into_iter()
+        .map(|session_id| {
+            let tags = load_tags(conn, &session_id)?;
+            Ok(TaggedSession {
+                session_id,
+                project_path: project_path.to_string(),
+                tags,
+            })
+        })
+        .collect()
+}
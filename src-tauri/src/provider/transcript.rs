@@ -0,0 +1,901 @@
+use chrono::{SecondsFormat, Utc};
+use serde::Serialize;
+use serde_json::{json, Value};
+use std::collections::{BTreeMap, HashSet, VecDeque};
+use std::fs::{self, File, OpenOptions};
+use flate2::write::GzEncoder;
+use flate2::Compression;
+use std::io::{self, BufRead, BufWriter, Read, Seek, SeekFrom, Write};
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+use std::time::{Duration, Instant, SystemTime};
+
+use crate::unified_history::{encode_project_id, ishinex_dir, open_history, read_jsonl, try_get_ts};
+
+/// Directory holding persisted provider transcripts for a project:
+/// `~/.ishinex/projects/<project_id>/sessions`
+pub fn sessions_dir(project_path: &str) -> Result<PathBuf, String> {
+    Ok(ishinex_dir()?
+        .join("projects")
+        .join(encode_project_id(project_path))
+        .join("sessions"))
+}
+
+/// Path of a session's persisted transcript
+pub fn transcript_path(project_path: &str, session_id: &str) -> Result<PathBuf, String> {
+    if session_id.is_empty() || session_id.contains(['/', '\\']) || session_id.contains("..") {
+        return Err(format!("Invalid session id: {}", session_id));
+    }
+    Ok(sessions_dir(project_path)?.join(format!("{}.jsonl", session_id)))
+}
+
+/// Path of a session's gzip-compressed transcript
+pub fn compressed_transcript_path(project_path: &str, session_id: &str) -> Result<PathBuf, String> {
+    Ok(transcript_path(project_path, session_id)?.with_extension("jsonl.gz"))
+}
+
+/// Where a session's transcript is stored, plain or compressed, if anywhere
+pub fn find_transcript(project_path: &str, session_id: &str) -> Result<Option<PathBuf>, String> {
+    let plain = transcript_path(project_path, session_id)?;
+    if plain.exists() {
+        return Ok(Some(plain));
+    }
+    let compressed = compressed_transcript_path(project_path, session_id)?;
+    Ok(compressed.exists().then_some(compressed))
+}
+
+/// Find a session's transcript in any project when the project isn't known
+pub fn locate_transcript(session_id: &str) -> Result<Option<PathBuf>, String> {
+    // Validates the id before it is joined into paths
+    transcript_path("", session_id)?;
+    let projects = match fs::read_dir(ishinex_dir()?.join("projects")) {
+        Ok(entries) => entries,
+        Err(_) => return Ok(None),
+    };
+    let names = [format!("{}.jsonl", session_id), format!("{}.jsonl.gz", session_id)];
+    Ok(projects
+        .flatten()
+        .flat_map(|project| names.iter().map(move |name| project.path().join("sessions").join(name)))
+        .find(|path| path.exists()))
+}
+
+/// Chunk size used when scanning a transcript backwards
+const TAIL_CHUNK: u64 = 64 * 1024;
+
+/// Last `n` non-empty lines of a file.
+///
+/// Plain files are read backwards from the end in chunks, so only the tail
+/// is touched; compressed ones can't be seeked and are streamed through.
+pub fn tail_lines(path: &Path, n: usize) -> io::Result<Vec<String>> {
+    if n == 0 {
+        return Ok(Vec::new());
+    }
+    if is_compressed(path) {
+        let reader = open_history(path).ok_or_else(|| io::Error::other("cannot open transcript"))?;
+        let mut tail = VecDeque::with_capacity(n);
+        for line in reader.lines().map_while(Result::ok).filter(|l| !l.trim().is_empty()) {
+            if tail.len() == n {
+                tail.pop_front();
+            }
+            tail.push_back(line);
+        }
+        return Ok(tail.into());
+    }
+
+    let mut file = File::open(path)?;
+    let mut pos = file.seek(SeekFrom::End(0))?;
+    let mut buf: Vec<u8> = Vec::new();
+    loop {
+        // One more line break than needed so the oldest kept line is complete
+        let breaks = buf.iter().filter(|&&b| b == b'\n').count();
+        if pos == 0 || breaks > n {
+            break;
+        }
+        let step = TAIL_CHUNK.min(pos);
+        pos -= step;
+        file.seek(SeekFrom::Start(pos))?;
+        let mut chunk = vec![0; step as usize];
+        file.read_exact(&mut chunk)?;
+        chunk.extend_from_slice(&buf);
+        buf = chunk;
+    }
+    let text = String::from_utf8_lossy(&buf);
+    let mut lines: Vec<&str> = text.lines().filter(|l| !l.trim().is_empty()).collect();
+    // Unless the start of the file was reached, the first line may be cut off
+    if pos > 0 && !lines.is_empty() {
+        lines.remove(0);
+    }
+    let skip = lines.len().saturating_sub(n);
+    Ok(lines[skip..].iter().map(|l| l.to_string()).collect())
+}
+
+/// Settings key: write new transcripts gzip-compressed ("true")
+pub const COMPRESS_TRANSCRIPTS_KEY: &str = "compress_transcripts";
+
+/// Subtype of the `system` records stderr lines are persisted as
+pub const STDERR_SUBTYPE: &str = "stderr";
+
+/// Settings key: persist a provider's stderr lines in its transcripts ("true")
+pub fn persist_stderr_key(provider: &str) -> String {
+    format!("{}_persist_stderr", provider)
+}
+
+fn is_compressed(path: &Path) -> bool {
+    path.extension().and_then(|e| e.to_str()) == Some("gz")
+}
+
+/// Replace a transcript with `messages` through a sibling temp file, keeping
+/// its compression, so a crash can't truncate it
+fn rewrite_transcript(path: &Path, messages: &[Value]) -> Result<(), String> {
+    let dir = path.parent().ok_or("Transcript has no parent directory")?;
+    let tmp = tempfile::NamedTempFile::new_in(dir).map_err(|e| e.to_string())?;
+    let file = tmp.as_file().try_clone().map_err(|e| e.to_string())?;
+    let mut sink = if is_compressed(path) {
+        Sink::Gzip(GzEncoder::new(file, Compression::default()))
+    } else {
+        Sink::Plain(BufWriter::new(file))
+    };
+    for message in messages {
+        let line = serde_json::to_string(message).map_err(|e| e.to_string())?;
+        writeln!(sink, "{}", line).map_err(|e| e.to_string())?;
+    }
+    sink.finish().map_err(|e| e.to_string())?;
+    tmp.persist(path).map_err(|e| e.to_string())?;
+    Ok(())
+}
+
+/// Path of a session's in-flight assistant text checkpoint
+pub fn partial_path(project_path: &str, session_id: &str) -> Result<PathBuf, String> {
+    Ok(transcript_path(project_path, session_id)?.with_extension("partial"))
+}
+
+/// `subtype` of the system record closing the transcript of a cancelled run
+pub const CANCELLED_SUBTYPE: &str = "cancelled";
+
+/// Whether a message is the marker left by a cancelled run
+pub fn is_cancel_marker(message: &Value) -> bool {
+    message.get("type").and_then(|t| t.as_str()) == Some("system")
+        && message.get("subtype").and_then(|s| s.as_str()) == Some(CANCELLED_SUBTYPE)
+}
+
+/// Whether the last run recorded in a transcript was cancelled; a later
+/// resume that ran to completion clears it
+pub fn was_cancelled(messages: &[Value]) -> bool {
+    messages
+        .iter()
+        .rev()
+        .find(|m| is_cancel_marker(m) || m.get("subtype").and_then(|s| s.as_str()) == Some("init"))
+        .is_some_and(is_cancel_marker)
+}
+
+/// Read every message persisted for a session
+pub fn read_transcript(project_path: &str, session_id: &str) -> Result<Vec<Value>, String> {
+    match find_transcript(project_path, session_id)? {
+        Some(path) => Ok(read_jsonl(&path)),
+        None => Err(format!("No transcript for session {}", session_id)),
+    }
+}
+
+/// The end of a session's transcript, for clients reconnecting mid-run
+#[derive(Debug, Serialize)]
+pub struct TranscriptTail {
+    pub session_id: String,
+    pub path: String,
+    /// Last messages in file order; lines that don't parse are left out
+    pub messages: Vec<Value>,
+    /// Whether the session is still running, so the caller knows to subscribe
+    pub running: bool,
+}
+
+/// Last `last_n` messages of a session without reading the whole transcript.
+///
+/// Without a project path every project's sessions are searched.
+pub fn tail_transcript(project_path: Option<&str>, session_id: &str, last_n: usize) -> Result<TranscriptTail, String> {
+    let path = match project_path {
+        Some(project) => find_transcript(project, session_id)?,
+        None => locate_transcript(session_id)?,
+    }
+    .ok_or_else(|| format!("No transcript for session {}", session_id))?;
+    let messages = tail_lines(&path, last_n)
+        .map_err(|e| format!("Failed to read {}: {}", path.display(), e))?
+        .iter()
+        .filter_map(|line| serde_json::from_str(line).ok())
+        .collect();
+    Ok(TranscriptTail {
+        session_id: session_id.to_string(),
+        path: path.to_string_lossy().into_owned(),
+        messages,
+        running: super::control::get(session_id).is_some(),
+    })
+}
+
+/// Whether a message was replaced by a regenerated response
+pub fn is_superseded(message: &Value) -> bool {
+    message.get("superseded").and_then(|v| v.as_bool()) == Some(true)
+}
+
+/// Text of the last assistant reply in a transcript: every assistant line
+/// after the last live prompt, joined, or the final `result` text when the
+/// CLI only reported that
+pub fn last_assistant_text(messages: &[Value]) -> Option<String> {
+    let live: Vec<&Value> = messages.iter().filter(|m| !is_superseded(m)).collect();
+    let start = live
+        .iter()
+        .rposition(|m| m.get("type").and_then(|t| t.as_str()) == Some("user"))
+        .map_or(0, |i| i + 1);
+    let parts: Vec<String> = live[start..]
+        .iter()
+        .filter(|m| m.get("type").and_then(|t| t.as_str()) == Some("assistant"))
+        .filter_map(|m| super::compact::message_text(m))
+        .filter(|text| !text.trim().is_empty())
+        .collect();
+    if !parts.is_empty() {
+        return Some(parts.join("\n"));
+    }
+    live[start..]
+        .iter()
+        .rev()
+        .find_map(|m| m.get("result").and_then(|r| r.as_str()))
+        .filter(|text| !text.trim().is_empty())
+        .map(|text| text.to_string())
+}
+
+/// Mark the last exchange of a session as superseded and return its prompt.
+///
+/// The last live user message and everything after it get
+/// `"superseded": true`; nothing is removed, so history stays intact while
+/// resumes leave the old answer out of the replayed context.
+pub fn supersede_last_exchange(project_path: &str, session_id: &str) -> Result<String, String> {
+    let mut messages = read_transcript(project_path, session_id)?;
+    let (start, prompt) = messages
+        .iter()
+        .enumerate()
+        .rev()
+        .filter(|(_, m)| !is_superseded(m))
+        .find(|(_, m)| m.get("type").and_then(|t| t.as_str()) == Some("user"))
+        .and_then(|(i, m)| super::compact::message_text(m).map(|text| (i, text)))
+        .ok_or_else(|| format!("No prompt to regenerate in session {}", session_id))?;
+
+    let superseded_at = Utc::now().to_rfc3339();
+    for message in &mut messages[start..] {
+        if let Value::Object(map) = message {
+            if !map.contains_key("superseded") {
+                map.insert("superseded".to_string(), json!(true));
+                map.insert("superseded_at".to_string(), json!(superseded_at));
+            }
+        }
+    }
+
+    let path = find_transcript(project_path, session_id)?
+        .ok_or_else(|| format!("No transcript for session {}", session_id))?;
+    rewrite_transcript(&path, &messages)?;
+    Ok(prompt)
+}
+
+/// Id of a persisted message: the CLI's own id when it has one, else the
+/// `seq` the runner stamped on it
+pub fn message_id(message: &Value) -> Option<String> {
+    ["/uuid", "/id", "/message/id", "/seq"]
+        .iter()
+        .filter_map(|pointer| message.pointer(pointer))
+        .find_map(|id| match id {
+            Value::String(s) if !s.is_empty() => Some(s.clone()),
+            Value::Number(n) => Some(n.to_string()),
+            _ => None,
+        })
+}
+
+/// Number of leading messages to keep so the transcript ends with
+/// `message_id`. Consecutive lines sharing the id (one streamed message)
+/// are kept together; an id found in separate places is ambiguous.
+fn keep_count(messages: &[Value], message_id: &str) -> Result<usize, String> {
+    let matches: Vec<usize> = messages
+        .iter()
+        .enumerate()
+        .filter(|(_, m)| self::message_id(m).as_deref() == Some(message_id))
+        .map(|(i, _)| i)
+        .collect();
+    let (first, last) = match (matches.first(), matches.last()) {
+        (Some(first), Some(last)) => (*first, *last),
+        _ => return Err(format!("No message with id {}", message_id)),
+    };
+    if last - first + 1 != matches.len() {
+        return Err(format!("Message id {} occurs more than once in the transcript", message_id));
+    }
+    Ok(last + 1)
+}
+
+/// Outcome of `truncate`
+#[derive(Debug, Serialize)]
+pub struct TruncateResult {
+    pub session_id: String,
+    /// Messages dropped after the kept one
+    pub removed: usize,
+    pub kept: usize,
+    /// Copy of the transcript as it was before truncating
+    pub backup_path: String,
+}
+
+/// Cut a session's transcript after `keep_until` so it can be resumed from
+/// that message ("edit and resubmit").
+///
+/// Resumes replay context from the transcript, so dropped turns are gone for
+/// the provider too. The original file is first copied next to it as
+/// `<name>.<timestamp>.bak`, which history readers ignore.
+pub fn truncate(project_path: &str, session_id: &str, keep_until: &str) -> Result<TruncateResult, String> {
+    if super::control::get(session_id).is_some() {
+        return Err(format!("Session {} is running; truncate it once it finishes", session_id));
+    }
+    let path = find_transcript(project_path, session_id)?
+        .ok_or_else(|| format!("No transcript for session {}", session_id))?;
+    let mut messages = read_jsonl(&path);
+    let kept = keep_count(&messages, keep_until)?;
+    let removed = messages.len() - kept;
+
+    let file_name = path.file_name().and_then(|n| n.to_str()).unwrap_or(session_id);
+    let backup = path.with_file_name(format!("{}.{}.bak", file_name, Utc::now().format("%Y%m%dT%H%M%S%.3fZ")));
+    fs::copy(&path, &backup).map_err(|e| format!("Failed to back up {}: {}", path.display(), e))?;
+    if removed > 0 {
+        messages.truncate(kept);
+        rewrite_transcript(&path, &messages)?;
+    }
+    Ok(TruncateResult {
+        session_id: session_id.to_string(),
+        removed,
+        kept,
+        backup_path: backup.to_string_lossy().into_owned(),
+    })
+}
+
+/// Transcript file being written: plain, or gzip flushed after every line so
+/// a crash loses at most the line in flight
+enum Sink {
+    Plain(BufWriter<File>),
+    Gzip(GzEncoder<File>),
+}
+
+impl Sink {
+    /// Flush and, for gzip, write the trailer
+    fn finish(self) -> io::Result<()> {
+        match self {
+            Sink::Plain(mut w) => w.flush(),
+            Sink::Gzip(w) => w.finish().map(|_| ()),
+        }
+    }
+}
+
+impl Write for Sink {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        match self {
+            Sink::Plain(w) => w.write(buf),
+            Sink::Gzip(w) => w.write(buf),
+        }
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        match self {
+            Sink::Plain(w) => w.flush(),
+            Sink::Gzip(w) => w.flush(),
+        }
+    }
+}
+
+/// Appends normalized messages to a session transcript as they are emitted.
+///
+/// Each line is the emitted message plus `timestamp` and `provider`, so
+/// transcripts can be merged and unified with other histories later.
+pub struct TranscriptWriter {
+    provider: String,
+    file: Mutex<Sink>,
+}
+
+impl TranscriptWriter {
+    /// Open (or continue, on resume) the transcript for a session.
+    ///
+    /// New transcripts are gzip-compressed (`.jsonl.gz`) when `compress` is
+    /// set; an existing transcript keeps the format it was started in. A
+    /// resumed compressed transcript gets a new gzip member appended.
+    pub fn open(project_path: &str, session_id: &str, provider: &str, compress: bool) -> Result<Self, String> {
+        let plain = transcript_path(project_path, session_id)?;
+        let compressed = compressed_transcript_path(project_path, session_id)?;
+        let gzip = compressed.exists() || (compress && !plain.exists());
+        let path = if gzip { compressed } else { plain };
+        if let Some(parent) = path.parent() {
+            fs::create_dir_all(parent).map_err(|e| e.to_string())?;
+        }
+        let file = OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(&path)
+            .map_err(|e| e.to_string())?;
+        let sink = if gzip {
+            Sink::Gzip(GzEncoder::new(file, Compression::default()))
+        } else {
+            Sink::Plain(BufWriter::new(file))
+        };
+        Ok(Self {
+            provider: provider.to_string(),
+            file: Mutex::new(sink),
+        })
+    }
+
+    /// Persist one message; failures are logged rather than interrupting the stream
+    pub fn append(&self, message: &Value) {
+        let mut record = message.clone();
+        if let Value::Object(map) = &mut record {
+            map.entry("timestamp")
+                .or_insert_with(|| json!(Utc::now().to_rfc3339()));
+            map.entry("provider")
+                .or_insert_with(|| json!(self.provider));
+        }
+        let mut file = match self.file.lock() {
+            Ok(file) => file,
+            Err(_) => return,
+        };
+        let result = serde_json::to_string(&record)
+            .map_err(|e| e.to_string())
+            .and_then(|line| writeln!(file, "{}", line).map_err(|e| e.to_string()))
+            .and_then(|_| file.flush().map_err(|e| e.to_string()));
+        if let Err(e) = result {
+            log::warn!("Failed to persist {} transcript line: {}", self.provider, e);
+        }
+    }
+}
+
+/// Path of a session's raw CLI output log
+pub fn raw_log_path(project_path: &str, session_id: &str) -> Result<PathBuf, String> {
+    Ok(transcript_path(project_path, session_id)?.with_extension("raw.log"))
+}
+
+/// Appends the CLI's output lines as they were read, before normalization,
+/// for debugging provider output changes.
+///
+/// Each line is `<timestamp> <stdout|stderr> <line>`; secrets are masked by
+/// the caller like everywhere else.
+pub struct RawLog {
+    path: PathBuf,
+    file: Mutex<BufWriter<File>>,
+}
+
+impl RawLog {
+    /// Open (or continue, on resume) the raw log of a session
+    pub fn open(project_path: &str, session_id: &str) -> Result<Self, String> {
+        let path = raw_log_path(project_path, session_id)?;
+        if let Some(parent) = path.parent() {
+            fs::create_dir_all(parent).map_err(|e| e.to_string())?;
+        }
+        let file = OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(&path)
+            .map_err(|e| e.to_string())?;
+        Ok(Self {
+            path,
+            file: Mutex::new(BufWriter::new(file)),
+        })
+    }
+
+    /// Record one line read from `stream`; failures are logged, not raised
+    pub fn write(&self, stream: &str, line: &str) {
+        if let Ok(mut file) = self.file.lock() {
+            let ts = Utc::now().to_rfc3339_opts(SecondsFormat::Millis, true);
+            if writeln!(file, "{} {} {}", ts, stream, line).and_then(|_| file.flush()).is_err() {
+                log::warn!("Failed to write raw log {}", self.path.display());
+            }
+        }
+    }
+}
+
+/// How often the accumulated assistant text is written to the `.partial` file
+const PARTIAL_FLUSH_INTERVAL: Duration = Duration::from_secs(2);
+
+/// Assistant text recovered from a session that never completed
+#[derive(Debug, Clone, Serialize, serde::Deserialize)]
+pub struct PartialSession {
+    pub session_id: String,
+    pub provider: String,
+    pub updated_at: String,
+    pub text: String,
+}
+
+/// Periodically checkpoints the assistant text of a running session so it
+/// survives an app crash. The file is removed once the session finishes.
+pub struct PartialCheckpoint {
+    path: PathBuf,
+    session_id: String,
+    provider: String,
+    state: Mutex<(String, Option<Instant>)>,
+}
+
+impl PartialCheckpoint {
+    pub fn new(project_path: &str, session_id: &str, provider: &str) -> Result<Self, String> {
+        Ok(Self {
+            path: partial_path(project_path, session_id)?,
+            session_id: session_id.to_string(),
+            provider: provider.to_string(),
+            state: Mutex::new((String::new(), None)),
+        })
+    }
+
+    /// Add a line of assistant text, flushing to disk at most every couple of seconds
+    pub fn push(&self, line: &str) {
+        let mut state = match self.state.lock() {
+            Ok(state) => state,
+            Err(_) => return,
+        };
+        if !state.0.is_empty() {
+            state.0.push('\n');
+        }
+        state.0.push_str(line);
+        if state.1.map_or(true, |at| at.elapsed() >= PARTIAL_FLUSH_INTERVAL) {
+            state.1 = Some(Instant::now());
+            if let Err(e) = self.write(&state.0) {
+                log::warn!("Failed to checkpoint {} session {}: {}", self.provider, self.session_id, e);
+            }
+        }
+    }
+
+    /// Write via a temp file and rename so a crash never leaves a torn checkpoint
+    fn write(&self, text: &str) -> Result<(), String> {
+        if let Some(parent) = self.path.parent() {
+            fs::create_dir_all(parent).map_err(|e| e.to_string())?;
+        }
+        let partial = PartialSession {
+            session_id: self.session_id.clone(),
+            provider: self.provider.clone(),
+            updated_at: Utc::now().to_rfc3339(),
+            text: text.to_string(),
+        };
+        let tmp = self.path.with_extension("partial.tmp");
+        let data = serde_json::to_vec(&partial).map_err(|e| e.to_string())?;
+        fs::write(&tmp, data).map_err(|e| e.to_string())?;
+        fs::rename(&tmp, &self.path).map_err(|e| e.to_string())
+    }
+
+    /// Remove the checkpoint after the session finished normally
+    pub fn clear(&self) {
+        let _ = fs::remove_file(&self.path);
+    }
+}
+
+/// Checkpoints left behind by sessions that didn't finish (e.g. an app crash)
+pub fn recover_partials(project_path: &str) -> Result<Vec<PartialSession>, String> {
+    let dir = sessions_dir(project_path)?;
+    let entries = match fs::read_dir(&dir) {
+        Ok(entries) => entries,
+        Err(_) => return Ok(Vec::new()),
+    };
+    let mut partials: Vec<PartialSession> = entries
+        .flatten()
+        .map(|e| e.path())
+        .filter(|p| p.extension().and_then(|s| s.to_str()) == Some("partial"))
+        .filter_map(|p| fs::read(&p).ok())
+        .filter_map(|data| serde_json::from_slice::<PartialSession>(&data).ok())
+        // Sessions still streaming in this run aren't crashed
+        .filter(|p| super::control::get(&p.session_id).is_none())
+        .collect();
+    partials.sort_by(|a, b| b.updated_at.cmp(&a.updated_at));
+    Ok(partials)
+}
+
+/// Characters of the first prompt kept in a `SessionSummary`
+const FIRST_PROMPT_CHARS: usize = 200;
+
+/// One recorded session of a project, for history lists
+#[derive(Debug, Clone, Serialize)]
+pub struct SessionSummary {
+    pub session_id: String,
+    pub provider: Option<String>,
+    pub model: Option<String>,
+    pub started_at: Option<String>,
+    /// Time of the last persisted message; `None` while running
+    pub ended_at: Option<String>,
+    /// `running`, `cancelled` or `completed`
+    pub status: String,
+    /// User and assistant messages, superseded ones included
+    pub message_count: usize,
+    /// Start of the first prompt
+    pub first_prompt: Option<String>,
+    /// Generated title cached by `summarize_session`, possibly for an earlier
+    /// state of the conversation
+    pub title: Option<String>,
+}
+
+/// Id of the session a transcript file belongs to (`<id>.jsonl[.gz]`)
+fn transcript_session_id(path: &Path) -> Option<&str> {
+    let name = path.file_name()?.to_str()?;
+    name.strip_suffix(".jsonl").or_else(|| name.strip_suffix(".jsonl.gz"))
+}
+
+/// Summary of one stored transcript
+fn summarize(session_id: &str, messages: &[Value]) -> SessionSummary {
+    let field = |key: &str| {
+        messages
+            .iter()
+            .find_map(|m| m.get(key).and_then(|v| v.as_str()))
+            .map(|v| v.to_string())
+    };
+    let role = |m: &Value| m.get("type").and_then(|t| t.as_str()).map(|t| t.to_string());
+    let timestamp = |m: &Value| m.get("timestamp").and_then(|t| t.as_str()).map(|t| t.to_string());
+    let first_prompt = messages
+        .iter()
+        .find(|m| role(m).as_deref() == Some("user"))
+        .and_then(super::compact::message_text)
+        .map(|text| text.trim().chars().take(FIRST_PROMPT_CHARS).collect());
+    let running = super::control::get(session_id).is_some();
+    let status = if running {
+        "running"
+    } else if was_cancelled(messages) {
+        "cancelled"
+    } else {
+        "completed"
+    };
+    SessionSummary {
+        session_id: session_id.to_string(),
+        provider: field("provider"),
+        model: field("model"),
+        started_at: messages.iter().find_map(timestamp),
+        ended_at: if running { None } else { messages.iter().rev().find_map(timestamp) },
+        status: status.to_string(),
+        message_count: messages
+            .iter()
+            .filter(|m| matches!(role(m).as_deref(), Some("user" | "assistant")))
+            .count(),
+        first_prompt,
+        title: None,
+    }
+}
+
+/// Every session with a transcript under a project's sessions directory
+pub fn list_sessions(project_path: &str) -> Result<Vec<SessionSummary>, String> {
+    let entries = match fs::read_dir(sessions_dir(project_path)?) {
+        Ok(entries) => entries,
+        Err(_) => return Ok(Vec::new()),
+    };
+    Ok(entries
+        .flatten()
+        .map(|e| e.path())
+        .filter_map(|path| {
+            let session_id = transcript_session_id(&path)?.to_string();
+            Some(summarize(&session_id, &read_jsonl(&path)))
+        })
+        .collect())
+}
+
+/// Outcome of `compress_old_sessions`
+#[derive(Debug, Serialize)]
+pub struct CompressResult {
+    pub compressed: usize,
+    /// Old transcripts left alone: still running or already compressed
+    pub skipped: usize,
+    pub bytes_before: u64,
+    pub bytes_after: u64,
+}
+
+/// Gzip one plain transcript next to itself, then remove the original
+fn compress_file(plain: &Path, compressed: &Path) -> Result<u64, String> {
+    let dir = plain.parent().ok_or("Transcript has no parent directory")?;
+    let tmp = tempfile::NamedTempFile::new_in(dir).map_err(|e| e.to_string())?;
+    let file = tmp.as_file().try_clone().map_err(|e| e.to_string())?;
+    let mut encoder = GzEncoder::new(file, Compression::best());
+    let mut source = File::open(plain).map_err(|e| e.to_string())?;
+    io::copy(&mut source, &mut encoder).map_err(|e| e.to_string())?;
+    encoder.finish().map_err(|e| e.to_string())?;
+    tmp.persist(compressed).map_err(|e| e.to_string())?;
+    fs::remove_file(plain).map_err(|e| e.to_string())?;
+    Ok(fs::metadata(compressed).map(|m| m.len()).unwrap_or(0))
+}
+
+/// Gzip the plain transcripts of a project not written to for `older_than_days`.
+///
+/// Sessions still running are skipped; readers handle both formats, so
+/// compressed sessions can still be resumed, merged and unified.
+pub fn compress_old_sessions(project_path: &str, older_than_days: u64) -> Result<CompressResult, String> {
+    let cutoff = SystemTime::now() - Duration::from_secs(older_than_days.saturating_mul(86_400));
+    let mut result = CompressResult { compressed: 0, skipped: 0, bytes_before: 0, bytes_after: 0 };
+    let entries = match fs::read_dir(sessions_dir(project_path)?) {
+        Ok(entries) => entries,
+        Err(_) => return Ok(result),
+    };
+    for path in entries.flatten().map(|e| e.path()) {
+        if path.extension().and_then(|e| e.to_str()) != Some("jsonl") {
+            continue;
+        }
+        let session_id = match path.file_stem().and_then(|s| s.to_str()) {
+            Some(id) => id.to_string(),
+            None => continue,
+        };
+        let meta = match fs::metadata(&path) {
+            Ok(meta) => meta,
+            Err(_) => continue,
+        };
+        if !meta.modified().is_ok_and(|m| m < cutoff) {
+            continue;
+        }
+        let compressed = path.with_extension("jsonl.gz");
+        if super::control::get(&session_id).is_some() || compressed.exists() {
+            result.skipped += 1;
+            continue;
+        }
+        match compress_file(&path, &compressed) {
+            Ok(size) => {
+                result.compressed += 1;
+                result.bytes_before += meta.len();
+                result.bytes_after += size;
+            }
+            Err(e) => log::warn!("Failed to compress transcript {}: {}", path.display(), e),
+        }
+    }
+    Ok(result)
+}
+
+/// Messages contributed by one source session to a merge
+#[derive(Debug, Serialize)]
+pub struct MergeSource {
+    pub session_id: String,
+    pub provider: Option<String>,
+    pub count: usize,
+    /// The source's last run was cancelled, so its tail may be incomplete
+    pub cancelled: bool,
+}
+
+#[derive(Debug, Serialize)]
+pub struct MergeResult {
+    pub session_id: String,
+    pub path: String,
+    pub total_messages: usize,
+    pub duplicates_skipped: usize,
+    pub sources: Vec<MergeSource>,
+}
+
+/// Provider recorded in a transcript (from any message, usually the init line)
+fn transcript_provider(messages: &[Value]) -> Option<String> {
+    messages
+        .iter()
+        .find_map(|m| m.get("provider").and_then(|p| p.as_str()))
+        .map(|p| p.to_string())
+}
+
+/// Key used to drop duplicates: the message without per-session bookkeeping
+fn dedupe_key(message: &Value) -> String {
+    let mut m = message.clone();
+    if let Value::Object(map) = &mut m {
+        map.remove("session_id");
+        map.remove("source_session_id");
+        map.remove("v");
+        map.remove("seq");
+    }
+    m.to_string()
+}
+
+/// Merge the transcripts of `session_ids` into a new session transcript.
+///
+/// Messages are interleaved by timestamp (messages without one keep the
+/// position of the message before them), exact duplicates are dropped, and
+/// every line keeps its `provider` plus a `source_session_id`.
+pub fn merge(project_path: &str, session_ids: &[String], new_session_id: &str) -> Result<MergeResult, String> {
+    if session_ids.is_empty() {
+        return Err("No sessions to merge".to_string());
+    }
+    if session_ids.iter().any(|s| s == new_session_id) {
+        return Err("The merged session id must differ from the source sessions".to_string());
+    }
+    let target = transcript_path(project_path, new_session_id)?;
+    if find_transcript(project_path, new_session_id)?.is_some() {
+        return Err(format!("Session {} already has a transcript", new_session_id));
+    }
+
+    // (timestamp, source index, line index) keeps the sort stable per source
+    let mut keyed: Vec<((i64, usize, usize), Value)> = Vec::new();
+    let mut sources = Vec::new();
+    for (src_idx, session_id) in session_ids.iter().enumerate() {
+        let messages = read_transcript(project_path, session_id)?;
+        let provider = transcript_provider(&messages);
+        let cancelled = was_cancelled(&messages);
+        let mut last_ts = 0;
+        for (line_idx, mut message) in messages.into_iter().enumerate() {
+            let ts = try_get_ts(&message).unwrap_or(last_ts);
+            last_ts = ts;
+            if let Value::Object(map) = &mut message {
+                if let Some(p) = &provider {
+                    map.entry("provider").or_insert_with(|| json!(p));
+                }
+                map.insert("source_session_id".to_string(), json!(session_id));
+                if map.contains_key("session_id") {
+                    map.insert("session_id".to_string(), json!(new_session_id));
+                }
+            }
+            keyed.push(((ts, src_idx, line_idx), message));
+        }
+        sources.push(MergeSource {
+            session_id: session_id.clone(),
+            provider,
+            count: 0,
+            cancelled,
+        });
+    }
+    keyed.sort_by_key(|(k, _)| *k);
+
+    let mut seen = HashSet::new();
+    let mut counts: BTreeMap<usize, usize> = BTreeMap::new();
+    let mut merged = Vec::new();
+    let mut duplicates_skipped = 0;
+    for ((_, src_idx, _), message) in keyed {
+        if !seen.insert(dedupe_key(&message)) {
+            duplicates_skipped += 1;
+            continue;
+        }
+        *counts.entry(src_idx).or_default() += 1;
+        merged.push(message);
+    }
+    for (idx, count) in counts {
+        sources[idx].count = count;
+    }
+
+    if let Some(parent) = target.parent() {
+        fs::create_dir_all(parent).map_err(|e| e.to_string())?;
+    }
+    let mut file = BufWriter::new(File::create(&target).map_err(|e| e.to_string())?);
+    for message in &merged {
+        let line = serde_json::to_string(message).map_err(|e| e.to_string())?;
+        writeln!(file, "{}", line).map_err(|e| e.to_string())?;
+    }
+    file.flush().map_err(|e| e.to_string())?;
+
+    Ok(MergeResult {
+        session_id: new_session_id.to_string(),
+        path: target.to_string_lossy().to_string(),
+        total_messages: merged.len(),
+        duplicates_skipped,
+        sources,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_tail_lines() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("s.jsonl");
+        let body: String = (0..5000).map(|i| format!("{{\"i\":{}}}\n", i)).collect();
+        fs::write(&path, body).unwrap();
+        let tail = tail_lines(&path, 3).unwrap();
+        assert_eq!(tail, vec![r#"{"i":4997}"#, r#"{"i":4998}"#, r#"{"i":4999}"#]);
+        assert_eq!(tail_lines(&path, 10_000).unwrap().len(), 5000);
+    }
+
+    #[test]
+    fn test_last_assistant_text() {
+        let text = |role: &str, text: &str| json!({"type": role, "message": {"content": [{"type": "text", "text": text}]}});
+        let messages = vec![
+            text("user", "first"),
+            text("assistant", "old answer"),
+            text("user", "second"),
+            text("assistant", "part one"),
+            text("assistant", "part two"),
+            json!({"type": "result", "result": "done"}),
+        ];
+        assert_eq!(last_assistant_text(&messages).as_deref(), Some("part one\npart two"));
+        let result_only = vec![text("user", "q"), json!({"type": "result", "result": "answer"})];
+        assert_eq!(last_assistant_text(&result_only).as_deref(), Some("answer"));
+        assert_eq!(last_assistant_text(&[text("user", "q")]), None);
+    }
+
+    #[test]
+    fn test_keep_count() {
+        let messages = vec![
+            json!({"type": "system", "subtype": "init", "seq": 1}),
+            json!({"type": "user", "uuid": "u1"}),
+            json!({"type": "assistant", "message": {"id": "m1"}}),
+            json!({"type": "assistant", "message": {"id": "m1"}}),
+            json!({"type": "user", "uuid": "u2"}),
+            json!({"type": "result", "seq": 1}),
+        ];
+        assert_eq!(keep_count(&messages, "u1"), Ok(2));
+        assert_eq!(keep_count(&messages, "m1"), Ok(4));
+        assert!(keep_count(&messages, "1").is_err());
+        assert!(keep_count(&messages, "missing").is_err());
+    }
+}
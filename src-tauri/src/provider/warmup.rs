@@ -0,0 +1,144 @@
+use serde::Serialize;
+use std::collections::HashMap;
+use std::sync::{Mutex, OnceLock};
+use std::time::{Instant, SystemTime};
+use tauri::{AppHandle, Emitter};
+
+use super::capabilities::binary_mtime;
+use super::events::event_name;
+use super::settings::read_setting;
+
+/// Settings key turning on the warm-up at startup ("true"); off by default
+pub const WARM_ON_STARTUP_KEY: &str = "warm_providers_on_startup";
+
+/// What warming found out about one provider
+#[derive(Debug, Clone, Serialize)]
+pub struct WarmedProvider {
+    pub provider: String,
+    pub installed: bool,
+    pub version: Option<String>,
+    pub logged_in: Option<bool>,
+    /// Number of models cached, when listing worked
+    pub models: Option<usize>,
+    pub elapsed_ms: u64,
+}
+
+/// `--version` output by binary path, with the modification time of the
+/// binary it was read from
+type VersionCache = HashMap<String, (Option<SystemTime>, String)>;
+
+fn version_cache() -> &'static Mutex<VersionCache> {
+    static CACHE: OnceLock<Mutex<VersionCache>> = OnceLock::new();
+    CACHE.get_or_init(|| Mutex::new(HashMap::new()))
+}
+
+/// Version of the provider CLI at `path`. Cached until the binary changes
+/// (e.g. an in-place upgrade); a failed probe isn't cached, so the next call
+/// asks again.
+pub async fn cached_version(provider: &str, path: &str) -> Option<String> {
+    let mtime = binary_mtime(path);
+    let cached = version_cache()
+        .lock()
+        .ok()
+        .and_then(|c| c.get(path).filter(|(seen, _)| *seen == mtime).map(|(_, version)| version.clone()));
+    if cached.is_some() {
+        return cached;
+    }
+    let (provider_owned, path_owned) = (provider.to_string(), path.to_string());
+    let version = tokio::task::spawn_blocking(move || match provider_owned.as_str() {
+        "codex" => crate::codex_binary::get_codex_version(&path_owned),
+        _ => crate::gemini_binary::get_gemini_version(&path_owned),
+    })
+    .await
+    .ok()
+    .flatten();
+    if let (Some(version), Ok(mut cache)) = (&version, version_cache().lock()) {
+        cache.insert(path.to_string(), (mtime, version.clone()));
+    }
+    version
+}
+
+/// Fill the version, capability, login and model caches of one provider.
+///
+/// Nothing is spawned when no binary is found or it doesn't answer
+/// `--version`.
+async fn warm_provider(app: &AppHandle, provider: &str) -> WarmedProvider {
+    let started = Instant::now();
+    let path = match provider {
+        "codex" => crate::codex_binary::find_codex_binary(app),
+        _ => crate::gemini_binary::find_gemini_binary(app),
+    }
+    .ok();
+    let version = match &path {
+        Some(path) => cached_version(provider, path).await,
+        None => None,
+    };
+    let mut warmed = WarmedProvider {
+        provider: provider.to_string(),
+        installed: version.is_some(),
+        version,
+        logged_in: None,
+        models: None,
+        elapsed_ms: 0,
+    };
+
+    if let (true, Some(path)) = (warmed.installed, path) {
+        // Same program key the runner probes with
+        let program = crate::binary_path::command_for(&path).0;
+        let (logged_in, models, _) = match provider {
+            "codex" => {
+                let (login, models, caps) = tokio::join!(
+                    crate::commands::codex::check_codex_login(app.clone(), None),
+                    crate::commands::codex::list_codex_models(app.clone(), None),
+                    super::capabilities::probe(&program),
+                );
+                (login.ok().map(|s| s.logged_in), models.ok().map(|m| m.len()), caps)
+            }
+            _ => {
+                let (login, models, caps) = tokio::join!(
+                    crate::commands::gemini::check_gemini_login(app.clone(), None),
+                    crate::commands::gemini::list_gemini_models(app.clone(), None),
+                    super::capabilities::probe(&program),
+                );
+                (login.ok().map(|s| s.logged_in), models.ok().map(|m| m.len()), caps)
+            }
+        };
+        warmed.logged_in = logged_in;
+        warmed.models = models;
+    }
+    warmed.elapsed_ms = started.elapsed().as_millis() as u64;
+    warmed
+}
+
+/// Warm every provider concurrently and emit `providers-warmed` with the results
+pub async fn warm(app: &AppHandle) -> Vec<WarmedProvider> {
+    let (codex, gemini) = tokio::join!(warm_provider(app, "codex"), warm_provider(app, "gemini"));
+    let warmed = vec![codex, gemini];
+    let _ = app.emit(&event_name("providers-warmed"), &warmed);
+    warmed
+}
+
+/// Whether the warm-up should run at startup
+pub fn enabled_on_startup(app: &AppHandle) -> bool {
+    read_setting(app, WARM_ON_STARTUP_KEY).as_deref() == Some("true")
+}
+
+/// Start the warm-up in the background when enabled; never blocks startup
+pub fn spawn_on_startup(app: AppHandle) {
+    if !enabled_on_startup(&app) {
+        return;
+    }
+    tauri::async_runtime::spawn(async move {
+        let warmed = warm(&app).await;
+        for provider in &warmed {
+            log::info!(
+                "Warmed {}: installed={} logged_in={:?} models={:?} in {}ms",
+                provider.provider,
+                provider.installed,
+                provider.logged_in,
+                provider.models,
+                provider.elapsed_ms
+            );
+        }
+    });
+}
@@ -0,0 +1,418 @@
+use futures::{SinkExt, StreamExt};
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Value};
+use std::io;
+use std::sync::{Arc, Mutex, OnceLock};
+use tauri::AppHandle;
+use tokio::io::{AsyncReadExt, AsyncWriteExt};
+use tokio::net::{TcpListener, TcpStream};
+use tokio::sync::{broadcast, watch};
+use tokio::task::JoinHandle;
+use tokio_tungstenite::tungstenite::handshake::derive_accept_key;
+use tokio_tungstenite::tungstenite::protocol::{Role, WebSocketConfig};
+use tokio_tungstenite::tungstenite::Message;
+use tokio_tungstenite::WebSocketStream;
+
+use super::settings::{read_setting, write_setting};
+
+/// Settings keys; the server only runs while enabled ("true")
+pub const WEBSOCKET_ENABLED_KEY: &str = "websocket_enabled";
+pub const WEBSOCKET_PORT_KEY: &str = "websocket_port";
+pub const WEBSOCKET_TOKEN_KEY: &str = "websocket_token";
+
+/// Port used until one is configured
+pub const DEFAULT_PORT: u16 = 8765;
+
+/// Messages buffered per subscriber; a slower one skips ahead and is told so
+const CHANNEL_CAPACITY: usize = 1024;
+/// Largest handshake request accepted
+const MAX_REQUEST_BYTES: usize = 8 * 1024;
+/// Largest message accepted from a client; clients only send control frames
+const MAX_CLIENT_MESSAGE: usize = 64 * 1024;
+/// Interval of SSE keep-alive comments, so idle proxies don't drop the stream
+const SSE_KEEPALIVE: std::time::Duration = std::time::Duration::from_secs(15);
+
+/// WebSocket relay settings as shown to the UI
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct WebSocketSettings {
+    pub enabled: bool,
+    pub port: u16,
+    /// Clients connect to `ws://127.0.0.1:<port>/?token=<token>`; the same
+    /// server streams one session as Server-Sent Events at
+    /// `http://127.0.0.1:<port>/sessions/<id>/events?token=<token>`
+    pub token: String,
+    /// Whether the server is listening right now
+    #[serde(default)]
+    pub running: bool,
+}
+
+/// One event as sent to subscribers
+#[derive(Debug, Clone)]
+struct Frame {
+    session_id: String,
+    kind: String,
+    text: Arc<str>,
+}
+
+fn channel() -> &'static broadcast::Sender<Frame> {
+    static CHANNEL: OnceLock<broadcast::Sender<Frame>> = OnceLock::new();
+    CHANNEL.get_or_init(|| broadcast::channel(CHANNEL_CAPACITY).0)
+}
+
+/// Port, shutdown signal and accept loop of the running server
+type Server = (u16, watch::Sender<bool>, JoinHandle<()>);
+
+fn server() -> &'static Mutex<Option<Server>> {
+    static SERVER: OnceLock<Mutex<Option<Server>>> = OnceLock::new();
+    SERVER.get_or_init(|| Mutex::new(None))
+}
+
+/// Relay a session event to WebSocket subscribers, if any are connected.
+///
+/// Output payloads are JSON strings when emitted to the UI; they are sent
+/// parsed so remote clients get one JSON document per frame.
+pub fn publish(provider: &str, kind: &str, session_id: &str, payload: &Value) {
+    let sender = channel();
+    if sender.receiver_count() == 0 {
+        return;
+    }
+    let payload = match payload {
+        Value::String(raw) => serde_json::from_str(raw).unwrap_or_else(|_| payload.clone()),
+        other => other.clone(),
+    };
+    let message = json!({
+        "event": format!("{}-{}", provider, kind),
+        "provider": provider,
+        "kind": kind,
+        "session_id": session_id,
+        "payload": payload,
+    });
+    let _ = sender.send(Frame {
+        session_id: session_id.to_string(),
+        kind: kind.to_string(),
+        text: message.to_string().into(),
+    });
+}
+
+/// Stored settings; a token is generated the first time one is needed
+pub fn load_settings(app: &AppHandle) -> Result<WebSocketSettings, String> {
+    let token = match read_setting(app, WEBSOCKET_TOKEN_KEY).filter(|t| !t.is_empty()) {
+        Some(token) => token,
+        None => {
+            let token = uuid::Uuid::new_v4().simple().to_string();
+            write_setting(app, WEBSOCKET_TOKEN_KEY, &token)?;
+            token
+        }
+    };
+    Ok(WebSocketSettings {
+        enabled: read_setting(app, WEBSOCKET_ENABLED_KEY).as_deref() == Some("true"),
+        port: read_setting(app, WEBSOCKET_PORT_KEY)
+            .and_then(|p| p.parse().ok())
+            .unwrap_or(DEFAULT_PORT),
+        token,
+        running: running_port().is_some(),
+    })
+}
+
+/// Store the settings and start, restart or stop the server to match.
+///
+/// The server is only stored as enabled once it listens; when it can't be
+/// started it is left stopped and disabled.
+pub async fn apply_settings(app: &AppHandle, enabled: bool, port: u16, regenerate_token: bool) -> Result<WebSocketSettings, String> {
+    if port == 0 {
+        return Err("Port must be between 1 and 65535".to_string());
+    }
+    write_setting(app, WEBSOCKET_PORT_KEY, &port.to_string())?;
+    if regenerate_token {
+        write_setting(app, WEBSOCKET_TOKEN_KEY, "")?;
+    }
+    let settings = load_settings(app)?;
+    // The old server must have released its port before a restart binds it
+    stop().await;
+    if enabled {
+        if let Err(e) = start(settings.port, settings.token.clone()).await {
+            write_setting(app, WEBSOCKET_ENABLED_KEY, "false")?;
+            return Err(e);
+        }
+    }
+    write_setting(app, WEBSOCKET_ENABLED_KEY, if enabled { "true" } else { "false" })?;
+    load_settings(app)
+}
+
+/// Port the server is listening on, if it runs
+pub fn running_port() -> Option<u16> {
+    server().lock().ok().and_then(|s| s.as_ref().map(|(port, _, _)| *port))
+}
+
+/// Stop the server and drop every connection; returns once the listener is
+/// closed
+pub async fn stop() {
+    let running = server().lock().ok().and_then(|mut s| s.take());
+    if let Some((_, shutdown, accept_loop)) = running {
+        let _ = shutdown.send(true);
+        let _ = accept_loop.await;
+    }
+}
+
+/// Listen on `127.0.0.1:<port>`; remote machines reach it through an SSH
+/// port-forward rather than an open port
+async fn start(port: u16, token: String) -> Result<(), String> {
+    let listener = TcpListener::bind(("127.0.0.1", port))
+        .await
+        .map_err(|e| format!("Cannot listen on 127.0.0.1:{}: {}", port, e))?;
+    let (shutdown_tx, mut shutdown) = watch::channel(false);
+    log::info!("WebSocket relay listening on 127.0.0.1:{}", port);
+    let token: Arc<str> = token.into();
+    let accept_loop = tokio::spawn(async move {
+        loop {
+            tokio::select! {
+                _ = shutdown.changed() => break,
+                accepted = listener.accept() => match accepted {
+                    Ok((stream, _)) => {
+                        let (token, shutdown) = (token.clone(), shutdown.clone());
+                        tokio::spawn(async move {
+                            if let Err(e) = serve(stream, &token, shutdown).await {
+                                log::debug!("WebSocket connection closed: {}", e);
+                            }
+                        });
+                    }
+                    Err(e) => log::warn!("WebSocket accept failed: {}", e),
+                },
+            }
+        }
+        log::info!("WebSocket relay on 127.0.0.1:{} stopped", port);
+    });
+    if let Ok(mut server) = server().lock() {
+        *server = Some((port, shutdown_tx, accept_loop));
+    }
+    Ok(())
+}
+
+/// Start the server at launch when it was left enabled
+pub fn start_on_startup(app: &AppHandle) {
+    let settings = match load_settings(app) {
+        Ok(settings) if settings.enabled => settings,
+        Ok(_) => return,
+        Err(e) => {
+            log::warn!("Failed to load WebSocket settings: {}", e);
+            return;
+        }
+    };
+    tauri::async_runtime::spawn(async move {
+        if let Err(e) = start(settings.port, settings.token).await {
+            log::warn!("WebSocket relay not started: {}", e);
+        }
+    });
+}
+
+/// `Sec-WebSocket-Accept` for a client key
+fn accept_key(key: &str) -> String {
+    derive_accept_key(key.trim().as_bytes())
+}
+
+/// Compare without an early exit, so the token can't be guessed byte by byte
+fn token_matches(given: &str, expected: &str) -> bool {
+    given.len() == expected.len() && given.bytes().zip(expected.bytes()).fold(0u8, |acc, (a, b)| acc | (a ^ b)) == 0
+}
+
+/// What a client asked for: a WebSocket upgrade (with a key) or a session's
+/// SSE stream
+struct Request {
+    path: String,
+    /// `Sec-WebSocket-Key`; absent for plain HTTP requests
+    key: Option<String>,
+    /// From `?token=` or an `Authorization: Bearer` header (EventSource can't
+    /// set headers, curl scripts usually do)
+    token: Option<String>,
+    /// Only relay this session's events
+    session_id: Option<String>,
+}
+
+impl Request {
+    /// Session of an SSE request (`/sessions/<id>/events`)
+    fn sse_session(&self) -> Option<&str> {
+        self.path
+            .strip_prefix("/sessions/")?
+            .strip_suffix("/events")
+            .filter(|id| !id.is_empty() && !id.contains('/'))
+    }
+}
+
+fn parse_request(request: &str) -> Option<Request> {
+    let mut lines = request.split("\r\n");
+    let target = lines.next()?.strip_prefix("GET ")?.split(' ').next()?;
+    let mut key = None;
+    let mut bearer = None;
+    for line in lines {
+        if let Some((name, value)) = line.split_once(':') {
+            let name = name.trim();
+            if name.eq_ignore_ascii_case("sec-websocket-key") {
+                key = Some(value.trim().to_string());
+            } else if name.eq_ignore_ascii_case("authorization") {
+                bearer = value.trim().strip_prefix("Bearer ").map(|t| t.trim().to_string());
+            }
+        }
+    }
+    let (path, query) = target.split_once('?').unwrap_or((target, ""));
+    let param = |wanted: &str| {
+        query
+            .split('&')
+            .filter_map(|pair| pair.split_once('='))
+            .find(|(name, _)| *name == wanted)
+            .map(|(_, value)| value.to_string())
+    };
+    Some(Request {
+        path: path.to_string(),
+        key,
+        token: param("token").or(bearer),
+        session_id: param("session_id").filter(|s| !s.is_empty()),
+    })
+}
+
+/// Handshake one connection, then relay events until either side closes
+async fn serve(mut stream: TcpStream, token: &str, mut shutdown: watch::Receiver<bool>) -> io::Result<()> {
+    let mut request = Vec::new();
+    let mut buf = [0u8; 1024];
+    while !request.windows(4).any(|w| w == b"\r\n\r\n") {
+        let n = stream.read(&mut buf).await?;
+        if n == 0 || request.len() + n > MAX_REQUEST_BYTES {
+            return Ok(());
+        }
+        request.extend_from_slice(&buf[..n]);
+    }
+    let request = match parse_request(&String::from_utf8_lossy(&request)) {
+        Some(request) => request,
+        None => {
+            stream.write_all(b"HTTP/1.1 400 Bad Request\r\nContent-Length: 0\r\n\r\n").await?;
+            return Ok(());
+        }
+    };
+    if !request.token.as_deref().is_some_and(|t| token_matches(t, token)) {
+        stream.write_all(b"HTTP/1.1 401 Unauthorized\r\nContent-Length: 0\r\n\r\n").await?;
+        return Ok(());
+    }
+    if let Some(session_id) = request.sse_session() {
+        return serve_sse(stream, session_id, shutdown).await;
+    }
+    let key = match &request.key {
+        Some(key) => key,
+        None => {
+            stream.write_all(b"HTTP/1.1 404 Not Found\r\nContent-Length: 0\r\n\r\n").await?;
+            return Ok(());
+        }
+    };
+    let response = format!(
+        "HTTP/1.1 101 Switching Protocols\r\nUpgrade: websocket\r\nConnection: Upgrade\r\nSec-WebSocket-Accept: {}\r\n\r\n",
+        accept_key(key)
+    );
+    stream.write_all(response.as_bytes()).await?;
+
+    // The HTTP side is ours since the port also serves SSE; frames, pings
+    // and fragmented messages are left to the protocol implementation
+    let config = WebSocketConfig {
+        max_message_size: Some(MAX_CLIENT_MESSAGE),
+        max_frame_size: Some(MAX_CLIENT_MESSAGE),
+        ..Default::default()
+    };
+    let mut socket = WebSocketStream::from_raw_socket(stream, Role::Server, Some(config)).await;
+    let mut events = channel().subscribe();
+    loop {
+        tokio::select! {
+            _ = shutdown.changed() => {
+                let _ = socket.close(None).await;
+                return Ok(());
+            }
+            // Pings are answered while reading; anything else a client sends
+            // besides a close is ignored
+            message = socket.next() => match message {
+                Some(Ok(Message::Close(_))) | None => {
+                    let _ = socket.close(None).await;
+                    return Ok(());
+                }
+                Some(Ok(_)) => {}
+                Some(Err(e)) => return Err(io::Error::other(e)),
+            },
+            event = events.recv() => {
+                let text = match event {
+                    Ok(frame) => {
+                        if request.session_id.as_deref().is_some_and(|s| s != frame.session_id) {
+                            continue;
+                        }
+                        frame.text.to_string()
+                    }
+                    Err(broadcast::error::RecvError::Lagged(skipped)) => {
+                        json!({ "event": "lagged", "skipped": skipped }).to_string()
+                    }
+                    Err(broadcast::error::RecvError::Closed) => return Ok(()),
+                };
+                socket.send(Message::Text(text)).await.map_err(io::Error::other)?;
+            }
+        }
+    }
+}
+
+/// Stream one session's events as Server-Sent Events until it completes or
+/// the client goes away. Each event is named after its kind (`output`,
+/// `error`, `complete`, ...) and carries the same JSON as a WebSocket frame.
+async fn serve_sse(mut stream: TcpStream, session_id: &str, mut shutdown: watch::Receiver<bool>) -> io::Result<()> {
+    // Subscribe before answering so nothing emitted in between is lost
+    let mut events = channel().subscribe();
+    stream
+        .write_all(
+            b"HTTP/1.1 200 OK\r\nContent-Type: text/event-stream\r\nCache-Control: no-cache\r\n\
+              Connection: keep-alive\r\nAccess-Control-Allow-Origin: *\r\n\r\n",
+        )
+        .await?;
+    stream.write_all(b": connected\n\n").await?;
+    let mut keepalive = tokio::time::interval(SSE_KEEPALIVE);
+    keepalive.tick().await;
+    loop {
+        tokio::select! {
+            _ = shutdown.changed() => return Ok(()),
+            _ = keepalive.tick() => stream.write_all(b": keepalive\n\n").await?,
+            event = events.recv() => match event {
+                Ok(frame) if frame.session_id == session_id => {
+                    let message = format!("event: {}\ndata: {}\n\n", frame.kind, frame.text);
+                    stream.write_all(message.as_bytes()).await?;
+                    if frame.kind == "complete" {
+                        return Ok(());
+                    }
+                }
+                Ok(_) => {}
+                Err(broadcast::error::RecvError::Lagged(skipped)) => {
+                    let notice = format!("event: lagged\ndata: {}\n\n", json!({ "skipped": skipped }));
+                    stream.write_all(notice.as_bytes()).await?;
+                }
+                Err(broadcast::error::RecvError::Closed) => return Ok(()),
+            },
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_accept_key() {
+        // Example from RFC 6455, section 1.3
+        assert_eq!(accept_key("dGhlIHNhbXBsZSBub25jZQ=="), "s3pPLMBiTxaQ9kYGzzhZRbK+xOo=");
+    }
+
+    #[test]
+    fn test_parse_request() {
+        let request = "GET /?token=abc&session_id=s1 HTTP/1.1\r\nHost: localhost\r\nSec-WebSocket-Key: k==\r\n\r\n";
+        let parsed = parse_request(request).unwrap();
+        assert_eq!(parsed.key.as_deref(), Some("k=="));
+        assert_eq!(parsed.token.as_deref(), Some("abc"));
+        assert_eq!(parsed.session_id.as_deref(), Some("s1"));
+        assert!(parsed.sse_session().is_none());
+        let sse = parse_request("GET /sessions/s2/events HTTP/1.1\r\nAuthorization: Bearer xyz\r\n\r\n").unwrap();
+        assert_eq!(sse.sse_session(), Some("s2"));
+        assert_eq!(sse.token.as_deref(), Some("xyz"));
+        assert!(sse.key.is_none());
+        assert!(parse_request("POST / HTTP/1.1\r\n\r\n").is_none());
+        assert!(token_matches("abc", "abc"));
+        assert!(!token_matches("abd", "abc"));
+    }
+}
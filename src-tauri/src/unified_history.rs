@@ -1,56 +1,372 @@
-use chrono::DateTime;
+use chrono::{DateTime, NaiveDate, NaiveDateTime};
 use serde_json::Value;
+use sha2::{Digest, Sha256};
 use std::fs;
-use std::io::{BufRead, BufReader};
+use std::io::{BufRead, BufReader, BufWriter, Write};
+use std::collections::{BTreeMap, BTreeSet, HashMap};
 use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, Mutex, OnceLock};
+use tauri::{AppHandle, Emitter};
+
+use crate::provider::events::event_name;
+use crate::provider::settings::{open_db, read_setting, write_setting};
+
+/// Settings key for the default unified output directory
+const UNIFIED_OUTPUT_DIR_KEY: &str = "unified_output_dir";
+
+/// Settings key for how history roots are walked (JSON `HistoryWalkSettings`)
+const HISTORY_WALK_KEY: &str = "history_walk_settings";
+
+/// Default depth below each history root
+const DEFAULT_WALK_DEPTH: usize = 4;
+
+/// Directories skipped by default: caches and dependencies, never history
+const DEFAULT_WALK_IGNORE: &[&str] = &["node_modules", "cache", "Cache", "tmp", ".git"];
+
+/// Default history locations searched for Codex
+const CODEX_ROOTS: &[&str] = &[
+    "~/.codex", "~/.openai", "~/.config/openai", "~/.config/codex", "~/Library/Application Support/OpenAI",
+];
+
+/// Default history locations searched for Gemini
+const GEMINI_ROOTS: &[&str] = &[
+    "~/.gemini", "~/.config/gemini", "~/Library/Application Support/Gemini",
+];
 
 fn home_dir() -> Result<PathBuf, String> {
     dirs::home_dir().ok_or_else(|| "Could not find home directory".to_string())
 }
 
-fn ishinex_dir() -> Result<PathBuf, String> {
+pub(crate) fn ishinex_dir() -> Result<PathBuf, String> {
     let dir = home_dir()?.join(".ishinex");
     if !dir.exists() { fs::create_dir_all(&dir).map_err(|e| e.to_string())?; }
     Ok(dir)
 }
 
-fn encode_project_id(path: &str) -> String { path.replace('/', "-") }
+pub(crate) fn encode_project_id(path: &str) -> String { path.replace('/', "-") }
 
-fn read_jsonl(path: &Path) -> Vec<Value> {
+/// Plain, gzip-rotated (`.jsonl.gz`) or zstd (`.jsonl.zst`) history file
+pub(crate) fn is_history_file(path: &Path) -> bool {
+    let name = path.file_name().and_then(|n| n.to_str()).unwrap_or_default();
+    name.ends_with(".jsonl") || name.ends_with(".jsonl.gz") || name.ends_with(".jsonl.zst")
+}
+
+/// Open a history file, decompressing `.gz`/`.zst` on the fly so it can be
+/// read line by line without inflating it in memory
+pub(crate) fn open_history(path: &Path) -> Option<Box<dyn BufRead>> {
+    let file = fs::File::open(path).ok()?;
+    let reader: Box<dyn BufRead> = match path.extension().and_then(|s| s.to_str()) {
+        // Multi-member so concatenated (appended-to) archives read fully
+        Some("gz") => Box::new(BufReader::new(flate2::read::MultiGzDecoder::new(file))),
+        Some("zst") => Box::new(BufReader::new(zstd::stream::read::Decoder::new(file).ok()?)),
+        _ => Box::new(BufReader::new(file)),
+    };
+    Some(reader)
+}
+
+pub(crate) fn read_jsonl(path: &Path) -> Vec<Value> {
     let mut items = Vec::new();
-    if let Ok(file) = fs::File::open(path) {
-        let reader = BufReader::new(file);
-        for line in reader.lines().flatten() {
+    if let Some(reader) = open_history(path) {
+        for line in reader.lines().map_while(Result::ok) {
             if let Ok(v) = serde_json::from_str::<Value>(&line) { items.push(v); }
         }
     }
     items
 }
 
-fn try_get_ts(v: &Value) -> Option<i64> {
-    // Try ISO string timestamp field
-    if let Some(ts) = v.get("timestamp").and_then(|x| x.as_str()) { DateTime::parse_from_rfc3339(ts).ok().map(|d| d.timestamp_millis()) }
-    else { None }
+/// Normalized timestamp (epoch millis) injected into gathered records
+const ISHINEX_TS: &str = "_ishinex_ts";
+
+/// Provider (or import label) a unified record came from
+const ISHINEX_PROVIDER: &str = "_ishinex_provider";
+/// Source file a unified record was read from, so unchanged files can be
+/// carried over from the previous output instead of re-read
+const ISHINEX_SOURCE: &str = "_ishinex_source";
+/// Claude session a unified record belongs to, for `claude --resume`; set on
+/// records Claude writes without one (summaries)
+const ISHINEX_SESSION_ID: &str = "_ishinex_session_id";
+
+/// Where providers keep a record's time, most specific first
+const TS_FIELDS: &[&str] = &[
+    "/_ishinex_ts", "/timestamp", "/created_at", "/createdAt", "/time", "/ts", "/date",
+    "/message/timestamp", "/payload/timestamp", "/meta/timestamp",
+];
+
+/// Epoch number in seconds, millis or micros (guessed from magnitude) to millis
+fn epoch_to_millis(n: f64) -> Option<i64> {
+    if !n.is_finite() || n <= 0.0 { return None; }
+    let millis = if n < 1e11 { n * 1000.0 } else if n < 1e14 { n } else { n / 1000.0 };
+    Some(millis as i64)
+}
+
+fn parse_ts_value(v: &Value) -> Option<i64> {
+    if let Some(n) = v.as_f64() { return epoch_to_millis(n); }
+    let s = v.as_str()?.trim();
+    if let Ok(n) = s.parse::<f64>() { return epoch_to_millis(n); }
+    if let Ok(d) = DateTime::parse_from_rfc3339(s) { return Some(d.timestamp_millis()); }
+    if let Ok(d) = DateTime::parse_from_rfc2822(s) { return Some(d.timestamp_millis()); }
+    // Naive forms (e.g. SQLite CURRENT_TIMESTAMP) are taken as UTC
+    ["%Y-%m-%d %H:%M:%S%.f", "%Y-%m-%dT%H:%M:%S%.f"]
+        .iter()
+        .find_map(|fmt| NaiveDateTime::parse_from_str(s, fmt).ok())
+        .map(|d| d.and_utc().timestamp_millis())
+}
+
+/// Record time in epoch millis from the first recognizable timestamp field
+pub(crate) fn try_get_ts(v: &Value) -> Option<i64> {
+    TS_FIELDS.iter().find_map(|f| v.pointer(f).and_then(parse_ts_value))
+}
+
+/// Inject `_ishinex_ts` into the records of one file.
+///
+/// Records without a derivable time inherit the previous record's (or, at the
+/// start of the file, the next one's) so they keep their place in the
+/// timeline instead of sorting to the epoch.
+fn stamp_timestamps(items: &mut [Value]) {
+    let own: Vec<Option<i64>> = items.iter().map(try_get_ts).collect();
+    let mut carried = own.iter().flatten().next().copied();
+    for (item, ts) in items.iter_mut().zip(own) {
+        if ts.is_some() { carried = ts; }
+        if let (Some(ts), Value::Object(map)) = (carried, item) {
+            map.insert(ISHINEX_TS.to_string(), Value::from(ts));
+        }
+    }
+}
+
+/// Inject `_ishinex_provider` into gathered records
+fn tag_provider(items: &mut [Value], provider: &str) {
+    for item in items {
+        if let Value::Object(map) = item {
+            map.insert(ISHINEX_PROVIDER.to_string(), Value::from(provider));
+        }
+    }
+}
+
+/// Inject `_ishinex_source` into gathered records
+fn tag_source(items: &mut [Value], path: &Path) {
+    let source = path.to_string_lossy();
+    for item in items {
+        if let Value::Object(map) = item {
+            map.insert(ISHINEX_SOURCE.to_string(), Value::from(source.as_ref()));
+        }
+    }
 }
 
-fn gather_claude(project_path: &str) -> Vec<Value> {
-    // ~/.claude/projects/<project_id>/*.jsonl
+/// Claude transcript files for a project: ~/.claude/projects/<project_id>/*.jsonl.
+/// A project directory that exists but can't be read is noted in `warnings`.
+fn claude_files(project_path: &str, warnings: &mut Vec<String>) -> Vec<PathBuf> {
     let mut res = Vec::new();
     if let Some(home) = dirs::home_dir() {
         let project_id = encode_project_id(project_path);
         let dir = home.join(".claude").join("projects").join(project_id);
-        if let Ok(entries) = fs::read_dir(dir) {
-            for e in entries.flatten() {
-                let p = e.path();
-                if p.is_file() && p.extension().and_then(|s| s.to_str()) == Some("jsonl") {
-                    res.extend(read_jsonl(&p));
+        match fs::read_dir(&dir) {
+            Ok(entries) => {
+                for e in entries.flatten() {
+                    let p = e.path();
+                    if p.is_file() && is_history_file(&p) {
+                        res.push(p);
+                    }
                 }
             }
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => {}
+            Err(e) => warnings.push(unreadable(&dir, &e)),
         }
     }
     res
 }
 
+/// Warning for a history directory that was skipped
+fn unreadable(path: &Path, err: &dyn std::fmt::Display) -> String {
+    let warning = format!("Skipped {}: {}", path.display(), err);
+    log::warn!("{}", warning);
+    warning
+}
+
+/// Characters of the first prompt kept in a `ClaudeSession`
+const CLAUDE_PROMPT_CHARS: usize = 200;
+
+/// A Claude Code session found in a project's transcripts, with what is
+/// needed to resume it (`claude --resume <session_id>`)
+#[derive(Debug, Clone, serde::Serialize)]
+pub struct ClaudeSession {
+    pub session_id: String,
+    pub path: String,
+    /// Title Claude wrote for the conversation, the one for its latest leaf first
+    pub summary: Option<String>,
+    /// Last message of the conversation; the point a resume continues from
+    pub leaf_uuid: Option<String>,
+    pub cwd: Option<String>,
+    pub git_branch: Option<String>,
+    /// Claude Code version that wrote the session
+    pub version: Option<String>,
+    pub started_at: Option<String>,
+    pub updated_at: Option<String>,
+    pub message_count: usize,
+    pub first_prompt: Option<String>,
+    /// Has messages and a session id Claude can resume
+    pub resumable: bool,
+}
+
+fn claude_str<'a>(record: &'a Value, key: &str) -> Option<&'a str> {
+    record.get(key).and_then(|v| v.as_str()).filter(|s| !s.is_empty())
+}
+
+/// Text a user typed, leaving out tool results and injected meta messages
+fn claude_prompt_text(record: &Value) -> Option<String> {
+    if claude_str(record, "type") != Some("user") || record.get("isMeta").and_then(|v| v.as_bool()) == Some(true) {
+        return None;
+    }
+    let text = match record.pointer("/message/content")? {
+        Value::String(text) => text.clone(),
+        Value::Array(blocks) => blocks
+            .iter()
+            .filter(|b| b.get("type").and_then(|t| t.as_str()) == Some("text"))
+            .filter_map(|b| b.get("text").and_then(|t| t.as_str()))
+            .collect::<Vec<_>>()
+            .join("\n"),
+        _ => return None,
+    };
+    Some(text.trim().to_string()).filter(|t| !t.is_empty())
+}
+
+/// Session id of a Claude transcript: the `sessionId` its records carry, or
+/// the file name Claude gives it
+fn claude_session_id(path: &Path, records: &[Value]) -> Option<String> {
+    records
+        .iter()
+        .find_map(|r| claude_str(r, "sessionId"))
+        .map(|id| id.to_string())
+        .or_else(|| {
+            let name = path.file_name()?.to_str()?;
+            name.split('.').next().filter(|s| !s.is_empty()).map(|s| s.to_string())
+        })
+}
+
+/// Resume metadata of one Claude transcript
+fn claude_session(path: &Path, records: &[Value]) -> Option<ClaudeSession> {
+    let session_id = claude_session_id(path, records)?;
+    let messages: Vec<&Value> = records
+        .iter()
+        .filter(|r| matches!(claude_str(r, "type"), Some("user" | "assistant")))
+        .collect();
+    let leaf_uuid = messages.iter().rev().find_map(|m| claude_str(m, "uuid")).map(|u| u.to_string());
+    let summaries: Vec<&Value> = records.iter().filter(|r| claude_str(r, "type") == Some("summary")).collect();
+    let summary = summaries
+        .iter()
+        .find(|s| leaf_uuid.is_some() && claude_str(s, "leafUuid") == leaf_uuid.as_deref())
+        .or(summaries.last())
+        .and_then(|s| claude_str(s, "summary"))
+        .map(|s| s.to_string());
+    let field = |key: &str| messages.iter().find_map(|m| claude_str(m, key)).map(|v| v.to_string());
+    let timestamp = |m: &&Value| claude_str(m, "timestamp").map(|t| t.to_string());
+    Some(ClaudeSession {
+        path: path.to_string_lossy().to_string(),
+        summary,
+        cwd: field("cwd"),
+        git_branch: field("gitBranch"),
+        version: messages.iter().rev().find_map(|m| claude_str(m, "version")).map(|v| v.to_string()),
+        started_at: messages.iter().find_map(timestamp),
+        updated_at: messages.iter().rev().find_map(timestamp),
+        message_count: messages.len(),
+        first_prompt: messages
+            .iter()
+            .find_map(|m| claude_prompt_text(m))
+            .map(|text| text.chars().take(CLAUDE_PROMPT_CHARS).collect()),
+        resumable: !messages.is_empty(),
+        leaf_uuid,
+        session_id,
+    })
+}
+
+/// Stamp Claude records with their session id so the unified history says
+/// which session to resume
+fn tag_claude_session(items: &mut [Value], path: &Path) {
+    let session_id = match claude_session_id(path, items) {
+        Some(session_id) => session_id,
+        None => return,
+    };
+    for item in items {
+        if let Value::Object(map) = item {
+            let id = map.get("sessionId").and_then(|v| v.as_str()).unwrap_or(session_id.as_str()).to_string();
+            map.insert(ISHINEX_SESSION_ID.to_string(), Value::from(id));
+        }
+    }
+}
+
+/// Claude Code sessions recorded for a project, most recently updated first,
+/// with their resume metadata (session id, summary, leaf message)
+#[tauri::command]
+pub async fn list_claude_sessions(project_path: String) -> Result<Vec<ClaudeSession>, String> {
+    tokio::task::spawn_blocking(move || {
+        let mut sessions: Vec<ClaudeSession> = claude_files(&project_path, &mut Vec::new())
+            .iter()
+            .filter_map(|path| claude_session(path, &read_jsonl(path)))
+            .collect();
+        // RFC 3339 timestamps sort chronologically as strings
+        sessions.sort_by(|a, b| b.updated_at.cmp(&a.updated_at));
+        sessions
+    })
+    .await
+    .map_err(|e| e.to_string())
+}
+
+/// Optional filters applied while gathering messages for unification
+#[derive(Debug, Clone, Default, serde::Serialize, serde::Deserialize)]
+pub struct UnifyFilter {
+    /// Only these providers (claude, codex, gemini)
+    pub providers: Option<Vec<String>>,
+    /// Only messages with these roles (user, assistant, system, ...)
+    pub roles: Option<Vec<String>>,
+    /// Earliest timestamp to keep, in epoch milliseconds
+    pub since: Option<i64>,
+    /// Latest timestamp to keep, in epoch milliseconds
+    pub until: Option<i64>,
+    /// Also take Codex/Gemini sessions whose recorded directory only shares
+    /// the project's basename (e.g. a checkout that has since moved). Weaker
+    /// than the path match, so it can pull in an unrelated same-named project.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub match_basename: Option<bool>,
+}
+
+impl UnifyFilter {
+    fn wants_provider(&self, provider: &str) -> bool {
+        self.providers
+            .as_ref()
+            .map_or(true, |ps| ps.iter().any(|p| p.eq_ignore_ascii_case(provider)))
+    }
+
+    /// Messages without a timestamp (own or inherited through
+    /// `stamp_timestamps`) are dropped whenever a time bound is set
+    fn matches(&self, v: &Value) -> bool {
+        if let Some(roles) = &self.roles {
+            match message_role(v) {
+                Some(role) if roles.iter().any(|r| r.eq_ignore_ascii_case(role)) => {}
+                _ => return false,
+            }
+        }
+        if self.since.is_some() || self.until.is_some() {
+            let ts = match try_get_ts(v) {
+                Some(ts) => ts,
+                None => return false,
+            };
+            if self.since.map_or(false, |since| ts < since) || self.until.map_or(false, |until| ts > until) {
+                return false;
+            }
+        }
+        true
+    }
+}
+
+/// Role of a message across the provider formats (`message.role`, `role`, `type`)
+fn message_role(v: &Value) -> Option<&str> {
+    v.get("message")
+        .and_then(|m| m.get("role"))
+        .or_else(|| v.get("role"))
+        .or_else(|| v.get("type"))
+        .and_then(|r| r.as_str())
+}
+
 fn expand_tilde(p: &str) -> PathBuf {
     if let Some(stripped) = p.strip_prefix("~/") {
         if let Some(home) = dirs::home_dir() {
@@ -60,38 +376,815 @@ fn expand_tilde(p: &str) -> PathBuf {
     PathBuf::from(p)
 }
 
-fn gather_from_candidates(project_path: &str, roots: &[&str]) -> Vec<Value> {
+/// How deep history roots are walked and which entries are skipped
+#[derive(Debug, Clone, serde::Serialize, serde::Deserialize)]
+pub struct HistoryWalkSettings {
+    pub max_depth: usize,
+    /// Glob patterns matched against each entry's name and full path;
+    /// a matching directory is not descended into
+    pub ignore: Vec<String>,
+}
+
+impl Default for HistoryWalkSettings {
+    fn default() -> Self {
+        Self {
+            max_depth: DEFAULT_WALK_DEPTH,
+            ignore: DEFAULT_WALK_IGNORE.iter().map(|s| s.to_string()).collect(),
+        }
+    }
+}
+
+impl HistoryWalkSettings {
+    fn load(app: &AppHandle) -> Self {
+        read_setting(app, HISTORY_WALK_KEY)
+            .and_then(|raw| serde_json::from_str(&raw).ok())
+            .unwrap_or_default()
+    }
+
+    fn patterns(&self) -> Vec<glob::Pattern> {
+        self.ignore.iter().filter_map(|p| glob::Pattern::new(p).ok()).collect()
+    }
+}
+
+fn is_ignored(entry: &walkdir::DirEntry, patterns: &[glob::Pattern]) -> bool {
+    let name = entry.file_name().to_string_lossy();
+    patterns.iter().any(|p| p.matches(&name) || p.matches_path(entry.path()))
+}
+
+/// Every history file under `roots`, whichever project it belongs to.
+///
+/// Directories the walk can't enter (permissions, broken links, loops) are
+/// skipped and noted in `warnings`, so a hidden subtree doesn't go unnoticed.
+fn history_files(roots: &[String], walk: &HistoryWalkSettings, warnings: &mut Vec<String>) -> Vec<PathBuf> {
     let mut out = Vec::new();
-    let proj = project_path.to_string();
+    let ignore = walk.patterns();
     for root in roots {
         let path = expand_tilde(root);
-        if !path.exists() { continue; }
-        let walker = walkdir::WalkDir::new(path).max_depth(4);
-        for entry in walker.into_iter().flatten() {
-            let p = entry.path();
-            if p.is_file() && p.extension().and_then(|s| s.to_str()) == Some("jsonl") {
-                // Quick probe for project path presence to avoid over-collecting
-                let mut matched = false;
-                if let Ok(file) = fs::File::open(p) {
-                    let reader = BufReader::new(file);
-                    for line in reader.lines().flatten().take(10) {
-                        if line.contains(&proj) { matched = true; break; }
+        match fs::metadata(&path) {
+            Ok(_) => {}
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => continue,
+            Err(e) => {
+                warnings.push(unreadable(&path, &e));
+                continue;
+            }
+        }
+        let walker = walkdir::WalkDir::new(&path)
+            .max_depth(walk.max_depth)
+            .into_iter()
+            // The root itself is always walked, even if its name matches
+            .filter_entry(|e| e.depth() == 0 || !is_ignored(e, &ignore));
+        for entry in walker {
+            let entry = match entry {
+                Ok(entry) => entry,
+                Err(e) => {
+                    let skipped = e.path().unwrap_or(&path).to_path_buf();
+                    match e.io_error() {
+                        Some(io) => warnings.push(unreadable(&skipped, io)),
+                        None => warnings.push(unreadable(&skipped, &e)),
                     }
+                    continue;
                 }
-                if matched {
-                    out.extend(read_jsonl(p));
-                }
+            };
+            let p = entry.path();
+            if p.is_file() && is_history_file(p) {
+                out.push(p.to_path_buf());
             }
         }
     }
     out
 }
 
+/// Fields a session's working directory is recorded in across CLI versions
+const RECORDED_CWD_POINTERS: &[&str] = &["/cwd", "/project_path", "/workdir", "/payload/cwd", "/payload/workdir"];
+
+/// A path without trailing separators (the root stays as is)
+fn trim_separators(path: &str) -> &str {
+    let trimmed = path.trim_end_matches(['/', '\\']);
+    if trimmed.is_empty() { &path[..path.len().min(1)] } else { trimmed }
+}
+
+/// The ways a project's path may show up in a history file
+struct ProjectMatcher {
+    /// The path as given and canonicalized (symlinks resolved), both
+    /// without trailing separators
+    forms: Vec<String>,
+    /// Last path component; only used when basename matching is on
+    basename: Option<String>,
+}
+
+impl ProjectMatcher {
+    fn new(project_path: &str, match_basename: bool) -> Self {
+        let mut forms = vec![trim_separators(project_path).to_string()];
+        if let Ok(canonical) = fs::canonicalize(project_path) {
+            let canonical = trim_separators(&canonical.to_string_lossy()).to_string();
+            if !forms.contains(&canonical) {
+                forms.push(canonical);
+            }
+        }
+        let basename = match_basename
+            .then(|| Path::new(&forms[0]).file_name().map(|n| n.to_string_lossy().to_string()))
+            .flatten();
+        Self { forms, basename }
+    }
+
+    /// Whether a recorded working directory is the project: equal once
+    /// separators are trimmed and symlinks resolved, or (weaker) sharing the
+    /// basename when that fallback is enabled
+    fn matches_recorded(&self, recorded: &str) -> bool {
+        let trimmed = trim_separators(recorded);
+        if self.forms.iter().any(|f| f == trimmed) {
+            return true;
+        }
+        if let Ok(canonical) = fs::canonicalize(trimmed) {
+            if self.forms.iter().any(|f| f == trim_separators(&canonical.to_string_lossy())) {
+                return true;
+            }
+        }
+        self.basename
+            .as_deref()
+            .is_some_and(|name| Path::new(trimmed).file_name().is_some_and(|n| n == name))
+    }
+
+    fn matches_line(&self, line: &str) -> bool {
+        if self.forms.iter().any(|f| line.contains(f.as_str())) {
+            return true;
+        }
+        let value: Value = match serde_json::from_str(line) {
+            Ok(value) => value,
+            Err(_) => return false,
+        };
+        RECORDED_CWD_POINTERS
+            .iter()
+            .filter_map(|pointer| value.pointer(pointer).and_then(|v| v.as_str()))
+            .any(|recorded| self.matches_recorded(recorded))
+    }
+}
+
+/// Quick probe for project path presence near the top, to avoid over-collecting
+fn mentions_project(path: &Path, project: &ProjectMatcher) -> bool {
+    match open_history(path) {
+        Some(reader) => reader.lines().map_while(Result::ok).take(10).any(|line| project.matches_line(&line)),
+        None => false,
+    }
+}
+
+/// History files under `roots` that mention the project path near the top
+fn candidate_files(
+    project: &ProjectMatcher,
+    roots: &[String],
+    walk: &HistoryWalkSettings,
+    warnings: &mut Vec<String>,
+) -> Vec<PathBuf> {
+    history_files(roots, walk, warnings)
+        .into_iter()
+        .filter(|p| mentions_project(p, project))
+        .collect()
+}
+
+/// Transcripts imported with `import_transcript`:
+/// `~/.ishinex/projects/<project_id>/imports/<label>.jsonl`
+fn imports_dir(project_path: &str) -> Result<PathBuf, String> {
+    Ok(ishinex_dir()?.join("projects").join(encode_project_id(project_path)).join("imports"))
+}
+
+/// Imported files of a project with their label
+fn import_files(project_path: &str) -> Vec<(String, PathBuf)> {
+    let dir = match imports_dir(project_path) {
+        Ok(dir) => dir,
+        Err(_) => return Vec::new(),
+    };
+    let mut files: Vec<(String, PathBuf)> = fs::read_dir(dir)
+        .into_iter()
+        .flatten()
+        .flatten()
+        .map(|e| e.path())
+        .filter(|p| p.extension().and_then(|s| s.to_str()) == Some("jsonl"))
+        .filter_map(|p| Some((p.file_stem()?.to_str()?.to_string(), p)))
+        .collect();
+    files.sort();
+    files
+}
+
+/// How a source file takes part in unification
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum SourceKind {
+    /// Belongs to the project by where it lives (Claude's project dir)
+    Project,
+    /// Found under a history root; only used if it mentions the project
+    Candidate,
+    /// Imported transcript, stamped and tagged when it was imported
+    Import,
+}
+
+/// Read one source file's records for the unified history.
+///
+/// Records are stamped before filtering, so one without its own time is
+/// judged by the time it inherits; imports were stamped when imported.
+fn gather_source(provider: &str, path: &Path, kind: SourceKind, filter: &UnifyFilter) -> Vec<Value> {
+    let mut items = read_jsonl(path);
+    if kind != SourceKind::Import {
+        stamp_timestamps(&mut items);
+    }
+    items.retain(|v| filter.matches(v));
+    if kind != SourceKind::Import {
+        tag_provider(&mut items, provider);
+    }
+    if provider == "claude" {
+        tag_claude_session(&mut items, path);
+    }
+    tag_source(&mut items, path);
+    items
+}
+
+/// What the last unify into an output file saw of one source file
+#[derive(Debug, Clone)]
+struct Fingerprint {
+    provider: String,
+    mtime_ms: i64,
+    size: i64,
+    /// SHA-256 of the content; only computed when mtime or size moved
+    hash: String,
+    /// Whether the file belongs to the project (candidates are probed)
+    matched: bool,
+    /// Records it contributed to the unified output
+    count: usize,
+}
+
+fn ensure_fingerprints_table(conn: &rusqlite::Connection) -> Result<(), String> {
+    conn.execute(
+        "CREATE TABLE IF NOT EXISTS unify_fingerprints (
+            unified_path TEXT NOT NULL,
+            source_path TEXT NOT NULL,
+            provider TEXT NOT NULL,
+            mtime_ms INTEGER NOT NULL,
+            size INTEGER NOT NULL,
+            hash TEXT NOT NULL,
+            matched INTEGER NOT NULL,
+            count INTEGER NOT NULL,
+            filter TEXT NOT NULL,
+            PRIMARY KEY (unified_path, source_path)
+        )",
+        [],
+    )
+    .map_err(|e| e.to_string())?;
+    Ok(())
+}
+
+/// Fingerprints of the last unify into `unified_path`; empty when it used a
+/// different filter, since its records then can't be reused
+fn load_fingerprints(
+    conn: &rusqlite::Connection,
+    unified_path: &str,
+    filter_key: &str,
+) -> Result<HashMap<String, Fingerprint>, String> {
+    ensure_fingerprints_table(conn)?;
+    let mut stmt = conn
+        .prepare(
+            "SELECT source_path, provider, mtime_ms, size, hash, matched, count, filter
+             FROM unify_fingerprints WHERE unified_path = ?1",
+        )
+        .map_err(|e| e.to_string())?;
+    let rows = stmt
+        .query_map([unified_path], |row| {
+            let fingerprint = Fingerprint {
+                provider: row.get(1)?,
+                mtime_ms: row.get(2)?,
+                size: row.get(3)?,
+                hash: row.get(4)?,
+                matched: row.get::<_, i64>(5)? != 0,
+                count: row.get::<_, i64>(6)? as usize,
+            };
+            Ok((row.get::<_, String>(0)?, fingerprint, row.get::<_, String>(7)?))
+        })
+        .map_err(|e| e.to_string())?
+        .collect::<Result<Vec<_>, _>>()
+        .map_err(|e| e.to_string())?;
+    if rows.iter().any(|(_, _, filter)| filter != filter_key) {
+        return Ok(HashMap::new());
+    }
+    Ok(rows.into_iter().map(|(path, fingerprint, _)| (path, fingerprint)).collect())
+}
+
+/// Replace the fingerprints stored for `unified_path`
+fn save_fingerprints(
+    conn: &mut rusqlite::Connection,
+    unified_path: &str,
+    filter_key: &str,
+    fingerprints: &[(String, Fingerprint)],
+) -> Result<(), String> {
+    ensure_fingerprints_table(conn)?;
+    let tx = conn.transaction().map_err(|e| e.to_string())?;
+    tx.execute("DELETE FROM unify_fingerprints WHERE unified_path = ?1", [unified_path])
+        .map_err(|e| e.to_string())?;
+    for (path, fp) in fingerprints {
+        tx.execute(
+            "INSERT INTO unify_fingerprints
+                (unified_path, source_path, provider, mtime_ms, size, hash, matched, count, filter)
+             VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9)",
+            rusqlite::params![
+                unified_path,
+                path,
+                fp.provider,
+                fp.mtime_ms,
+                fp.size,
+                fp.hash,
+                fp.matched as i64,
+                fp.count as i64,
+                filter_key
+            ],
+        )
+        .map_err(|e| e.to_string())?;
+    }
+    tx.commit().map_err(|e| e.to_string())
+}
+
+fn ensure_relabels_table(conn: &rusqlite::Connection) -> Result<(), String> {
+    conn.execute(
+        "CREATE TABLE IF NOT EXISTS provider_relabels (
+            project_path TEXT NOT NULL,
+            from_label TEXT NOT NULL,
+            to_label TEXT NOT NULL,
+            PRIMARY KEY (project_path, from_label)
+        )",
+        [],
+    )
+    .map_err(|e| e.to_string())?;
+    Ok(())
+}
+
+/// Provider renames made by `relabel_provider` for a project, from -> to
+fn load_relabels(conn: &rusqlite::Connection, project_path: &str) -> Result<HashMap<String, String>, String> {
+    ensure_relabels_table(conn)?;
+    let mut stmt = conn
+        .prepare("SELECT from_label, to_label FROM provider_relabels WHERE project_path = ?1")
+        .map_err(|e| e.to_string())?;
+    let relabels = stmt
+        .query_map([trim_separators(project_path)], |row| Ok((row.get::<_, String>(0)?, row.get::<_, String>(1)?)))
+        .map_err(|e| e.to_string())?
+        .collect::<Result<HashMap<_, _>, _>>()
+        .map_err(|e| e.to_string())?;
+    Ok(relabels)
+}
+
+/// Remember that `from` is shown as `to`. Earlier renames into `from` now
+/// lead to `to`, so no label maps to one that is renamed again, and renaming
+/// a label back to itself forgets it.
+fn save_relabel(conn: &mut rusqlite::Connection, project_path: &str, from: &str, to: &str) -> Result<(), String> {
+    ensure_relabels_table(conn)?;
+    let project = trim_separators(project_path);
+    let tx = conn.transaction().map_err(|e| e.to_string())?;
+    tx.execute(
+        "UPDATE provider_relabels SET to_label = ?3 WHERE project_path = ?1 AND to_label = ?2",
+        rusqlite::params![project, from, to],
+    )
+    .map_err(|e| e.to_string())?;
+    tx.execute(
+        "INSERT INTO provider_relabels (project_path, from_label, to_label) VALUES (?1, ?2, ?3)
+         ON CONFLICT(project_path, from_label) DO UPDATE SET to_label = excluded.to_label",
+        rusqlite::params![project, from, to],
+    )
+    .map_err(|e| e.to_string())?;
+    tx.execute(
+        "DELETE FROM provider_relabels WHERE project_path = ?1 AND from_label = to_label",
+        [project],
+    )
+    .map_err(|e| e.to_string())?;
+    tx.commit().map_err(|e| e.to_string())
+}
+
+/// Apply the project's renames to freshly gathered records
+fn apply_relabels(items: &mut [Value], relabels: &HashMap<String, String>) {
+    for item in items {
+        if let Value::Object(map) = item {
+            let renamed = map.get(ISHINEX_PROVIDER).and_then(|p| p.as_str()).and_then(|p| relabels.get(p));
+            if let Some(to) = renamed {
+                map.insert(ISHINEX_PROVIDER.to_string(), Value::from(to.as_str()));
+            }
+        }
+    }
+}
+
+/// Modification time (epoch millis) and size of a file
+fn file_stat(path: &Path) -> Option<(i64, i64)> {
+    let meta = fs::metadata(path).ok()?;
+    let mtime = meta.modified().ok()?.duration_since(std::time::UNIX_EPOCH).ok()?.as_millis() as i64;
+    Some((mtime, meta.len() as i64))
+}
+
+/// SHA-256 of a file's bytes, hex encoded
+fn file_hash(path: &Path) -> Option<String> {
+    let mut file = fs::File::open(path).ok()?;
+    let mut hasher = Sha256::new();
+    std::io::copy(&mut file, &mut hasher).ok()?;
+    Some(format!("{:x}", hasher.finalize()))
+}
+
+/// Fingerprint a source file, reusing `previous` when it is unchanged.
+///
+/// Returns the fingerprint and whether the file changed. Hashing only happens
+/// when mtime or size moved, so a touched-but-identical file still counts as
+/// unchanged; candidates are probed only when they changed.
+fn fingerprint_source(
+    provider: &str,
+    path: &Path,
+    kind: SourceKind,
+    project: &ProjectMatcher,
+    previous: Option<&Fingerprint>,
+) -> Option<(Fingerprint, bool)> {
+    let (mtime_ms, size) = file_stat(path)?;
+    if let Some(prev) = previous.filter(|p| p.mtime_ms == mtime_ms && p.size == size) {
+        return Some((prev.clone(), false));
+    }
+    let hash = file_hash(path)?;
+    if let Some(prev) = previous.filter(|p| p.hash == hash) {
+        return Some((Fingerprint { mtime_ms, size, ..prev.clone() }, false));
+    }
+    let matched = kind != SourceKind::Candidate || mentions_project(path, project);
+    let fingerprint = Fingerprint {
+        provider: provider.to_string(),
+        mtime_ms,
+        size,
+        hash,
+        matched,
+        count: 0,
+    };
+    Some((fingerprint, true))
+}
+
+/// Records of the previous unified output grouped by source file, for the
+/// sources in `keep`
+fn previous_records(outputs: &[PathBuf], keep: &BTreeSet<String>) -> HashMap<String, Vec<Value>> {
+    let mut by_source: HashMap<String, Vec<Value>> = HashMap::new();
+    if keep.is_empty() {
+        return by_source;
+    }
+    for record in outputs.iter().flat_map(|path| read_jsonl(path)) {
+        let source = match record.get(ISHINEX_SOURCE).and_then(|s| s.as_str()) {
+            Some(source) if keep.contains(source) => source.to_string(),
+            _ => continue,
+        };
+        by_source.entry(source).or_default().push(record);
+    }
+    by_source
+}
+
+/// Count non-empty lines without parsing them, with the (decompressed) bytes read
+fn count_lines(path: &Path) -> (usize, u64) {
+    let mut count = 0;
+    let mut bytes = 0;
+    if let Some(mut reader) = open_history(path) {
+        let mut line_has_content = false;
+        loop {
+            let buf = match reader.fill_buf() {
+                Ok(buf) if !buf.is_empty() => buf,
+                _ => break,
+            };
+            for &b in buf {
+                if b == b'\n' {
+                    if line_has_content { count += 1; }
+                    line_has_content = false;
+                } else if !b.is_ascii_whitespace() {
+                    line_has_content = true;
+                }
+            }
+            let len = buf.len();
+            bytes += len as u64;
+            reader.consume(len);
+        }
+        if line_has_content { count += 1; }
+    }
+    (count, bytes)
+}
+
+/// Bytes the provider, timestamp and source tags add to each unified record,
+/// besides the source path itself
+const RECORD_TAG_BYTES: u64 = 80;
+
+/// Rough size of a source's records in the unified file: its own bytes plus
+/// the tags every record gets
+fn estimated_output_bytes(source_bytes: u64, records: usize, source: &Path) -> u64 {
+    source_bytes + records as u64 * (RECORD_TAG_BYTES + source.as_os_str().len() as u64)
+}
+
+/// Free space where `dir` is or would be created (its closest existing ancestor)
+fn free_bytes_at(dir: &Path) -> Option<u64> {
+    dir.ancestors()
+        .find(|d| d.exists())
+        .and_then(crate::provider::storage::free_bytes)
+}
+
+/// A user-registered history directory for a provider
+#[derive(serde::Serialize)]
+pub struct HistorySource {
+    pub id: i64,
+    pub provider: String,
+    pub path: String,
+    pub created_at: String,
+}
+
+fn ensure_history_sources_table(conn: &rusqlite::Connection) -> Result<(), String> {
+    conn.execute(
+        "CREATE TABLE IF NOT EXISTS history_sources (
+            id INTEGER PRIMARY KEY AUTOINCREMENT,
+            provider TEXT NOT NULL,
+            path TEXT NOT NULL,
+            created_at TEXT NOT NULL DEFAULT CURRENT_TIMESTAMP,
+            UNIQUE(provider, path)
+        )",
+        [],
+    )
+    .map_err(|e| e.to_string())?;
+    Ok(())
+}
+
+fn load_history_sources(app: &AppHandle) -> Result<Vec<HistorySource>, String> {
+    let conn = open_db(app)?;
+    ensure_history_sources_table(&conn)?;
+    let mut stmt = conn
+        .prepare("SELECT id, provider, path, created_at FROM history_sources ORDER BY id")
+        .map_err(|e| e.to_string())?;
+    let sources = stmt
+        .query_map([], |row| {
+            Ok(HistorySource {
+                id: row.get(0)?,
+                provider: row.get(1)?,
+                path: row.get(2)?,
+                created_at: row.get(3)?,
+            })
+        })
+        .map_err(|e| e.to_string())?
+        .collect::<Result<Vec<_>, _>>()
+        .map_err(|e| e.to_string())?;
+    Ok(sources)
+}
+
+/// Explicit user sources for `provider` followed by the default candidate roots
+fn candidate_roots(sources: &[HistorySource], provider: &str, defaults: &[&str]) -> Vec<String> {
+    let mut roots: Vec<String> = sources
+        .iter()
+        .filter(|s| s.provider == provider)
+        .map(|s| s.path.clone())
+        .collect();
+    roots.extend(defaults.iter().map(|d| d.to_string()));
+    roots
+}
+
+/// Register an extra history directory for a provider (codex or gemini)
+#[tauri::command]
+pub async fn add_history_source(app: AppHandle, provider: String, path: String) -> Result<HistorySource, String> {
+    if provider != "codex" && provider != "gemini" {
+        return Err(format!("Unsupported provider for history sources: {}", provider));
+    }
+    let resolved = expand_tilde(&path);
+    if !resolved.exists() {
+        return Err(format!("Path does not exist: {}", resolved.display()));
+    }
+    let resolved = resolved.to_string_lossy().to_string();
+
+    let conn = open_db(&app)?;
+    ensure_history_sources_table(&conn)?;
+    conn.execute(
+        "INSERT OR IGNORE INTO history_sources (provider, path) VALUES (?1, ?2)",
+        rusqlite::params![provider, resolved],
+    )
+    .map_err(|e| e.to_string())?;
+    conn.query_row(
+        "SELECT id, provider, path, created_at FROM history_sources WHERE provider = ?1 AND path = ?2",
+        rusqlite::params![provider, resolved],
+        |row| {
+            Ok(HistorySource {
+                id: row.get(0)?,
+                provider: row.get(1)?,
+                path: row.get(2)?,
+                created_at: row.get(3)?,
+            })
+        },
+    )
+    .map_err(|e| e.to_string())
+}
+
+/// List user-registered history directories
+#[tauri::command]
+pub async fn list_history_sources(app: AppHandle) -> Result<Vec<HistorySource>, String> {
+    load_history_sources(&app)
+}
+
+/// Remove a user-registered history directory by id
+#[tauri::command]
+pub async fn remove_history_source(app: AppHandle, id: i64) -> Result<(), String> {
+    let conn = open_db(&app)?;
+    ensure_history_sources_table(&conn)?;
+    conn.execute("DELETE FROM history_sources WHERE id = ?1", rusqlite::params![id])
+        .map_err(|e| e.to_string())?;
+    Ok(())
+}
+
+/// Where the unified file goes.
+///
+/// `output_dir` (or the persisted default) may be absolute, `~/`-relative or
+/// relative to the project. Without either, the file lands in
+/// `~/.ishinex/projects/<project_id>/unified`.
+fn unified_output_path(app: &AppHandle, project_path: &str, output_dir: Option<String>) -> Result<PathBuf, String> {
+    let configured = output_dir
+        .or_else(|| read_setting(app, UNIFIED_OUTPUT_DIR_KEY))
+        .filter(|d| !d.trim().is_empty());
+    Ok(match configured {
+        Some(dir) => {
+            let dir = expand_tilde(dir.trim());
+            if dir.is_absolute() { dir } else { Path::new(project_path).join(dir) }
+        }
+        None => ishinex_dir()?.join("projects").join(encode_project_id(project_path)).join("unified"),
+    })
+}
+
+/// `unified_output_path`, created and checked to be writable
+fn unified_output_dir(app: &AppHandle, project_path: &str, output_dir: Option<String>) -> Result<PathBuf, String> {
+    let dir = unified_output_path(app, project_path, output_dir)?;
+    fs::create_dir_all(&dir).map_err(|e| format!("Cannot create {}: {}", dir.display(), e))?;
+    // Probe with a throwaway file so a read-only target fails before gathering
+    tempfile::NamedTempFile::new_in(&dir)
+        .map_err(|e| format!("Output directory {} is not writable: {}", dir.display(), e))?;
+    Ok(dir)
+}
+
+/// Get how history roots are walked (depth and ignore globs)
+#[tauri::command]
+pub async fn get_history_walk_settings(app: AppHandle) -> Result<HistoryWalkSettings, String> {
+    Ok(HistoryWalkSettings::load(&app))
+}
+
+/// Set the history walk depth and ignore globs; invalid globs are rejected
+#[tauri::command]
+pub async fn set_history_walk_settings(app: AppHandle, settings: HistoryWalkSettings) -> Result<(), String> {
+    if settings.max_depth == 0 {
+        return Err("max_depth must be at least 1".to_string());
+    }
+    for pattern in &settings.ignore {
+        glob::Pattern::new(pattern).map_err(|e| format!("Invalid ignore pattern {:?}: {}", pattern, e))?;
+    }
+    let raw = serde_json::to_string(&settings).map_err(|e| e.to_string())?;
+    write_setting(&app, HISTORY_WALK_KEY, &raw)
+}
+
+/// Get the persisted default output directory for unified histories
+#[tauri::command]
+pub async fn get_unified_output_dir(app: AppHandle) -> Result<Option<String>, String> {
+    Ok(read_setting(&app, UNIFIED_OUTPUT_DIR_KEY).filter(|d| !d.is_empty()))
+}
+
+/// Set (or clear, with `None`) the default output directory for unified histories
+#[tauri::command]
+pub async fn set_unified_output_dir(app: AppHandle, dir: Option<String>) -> Result<(), String> {
+    write_setting(&app, UNIFIED_OUTPUT_DIR_KEY, dir.as_deref().unwrap_or("").trim())
+}
+
+/// The unified history when it isn't split
+const UNIFIED_FILE: &str = "unified.jsonl";
+
+/// File of records without a timestamp when the output is split
+const UNDATED_FILE: &str = "unified-undated.jsonl";
+
+/// Period the unified output is partitioned by, in UTC
+#[derive(Debug, Clone, Copy, PartialEq, Eq, serde::Serialize, serde::Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum SplitBy {
+    /// `unified-2024-06-01.jsonl`
+    Day,
+    /// `unified-2024-06.jsonl`
+    Month,
+}
+
+impl SplitBy {
+    fn date_format(self) -> &'static str {
+        match self {
+            SplitBy::Day => "%Y-%m-%d",
+            SplitBy::Month => "%Y-%m",
+        }
+    }
+
+    /// File a record with normalized timestamp `ts` belongs in
+    fn file_name(self, ts: Option<i64>) -> String {
+        match ts.and_then(DateTime::from_timestamp_millis) {
+            Some(time) => format!("unified-{}.jsonl", time.format(self.date_format())),
+            None => UNDATED_FILE.to_string(),
+        }
+    }
+
+    /// Whether `name` is a file this split writes
+    fn owns(self, name: &str) -> bool {
+        if name == UNDATED_FILE {
+            return true;
+        }
+        let period = match name.strip_prefix("unified-").and_then(|n| n.strip_suffix(".jsonl")) {
+            Some(period) => period,
+            None => return false,
+        };
+        match self {
+            SplitBy::Day => NaiveDate::parse_from_str(period, "%Y-%m-%d").is_ok(),
+            SplitBy::Month => {
+                period.len() == 7 && NaiveDate::parse_from_str(&format!("{}-01", period), "%Y-%m-%d").is_ok()
+            }
+        }
+    }
+
+    /// Key the split output's fingerprints are stored under, apart from the
+    /// single file's
+    fn output_key(self, dir: &Path) -> String {
+        let name = match self {
+            SplitBy::Day => "unified-by-day",
+            SplitBy::Month => "unified-by-month",
+        };
+        dir.join(name).to_string_lossy().to_string()
+    }
+
+    /// Files of this split already in `dir`, sorted by name
+    fn existing_files(self, dir: &Path) -> Vec<PathBuf> {
+        let mut files: Vec<PathBuf> = fs::read_dir(dir)
+            .map(|entries| {
+                entries
+                    .flatten()
+                    .map(|e| e.path())
+                    .filter(|p| p.file_name().and_then(|n| n.to_str()).is_some_and(|n| self.owns(n)))
+                    .collect()
+            })
+            .unwrap_or_default();
+        files.sort();
+        files
+    }
+}
+
+/// Write sorted records into one file per period of `split`, then remove the
+/// files of periods that no longer have records. Returns the files written.
+fn write_split(dir: &Path, split: SplitBy, records: Vec<Value>) -> Result<Vec<PathBuf>, String> {
+    let mut partitions: BTreeMap<String, Vec<Value>> = BTreeMap::new();
+    for record in records {
+        partitions.entry(split.file_name(try_get_ts(&record))).or_default().push(record);
+    }
+    let mut written = Vec::new();
+    for (name, records) in &partitions {
+        let path = dir.join(name);
+        write_jsonl_atomic(&path, records)?;
+        written.push(path);
+    }
+    for stale in split.existing_files(dir) {
+        if !written.contains(&stale) {
+            if let Err(e) = fs::remove_file(&stale) {
+                log::warn!("Failed to remove stale unified file {}: {}", stale.display(), e);
+            }
+        }
+    }
+    Ok(written)
+}
+
+/// Key the fingerprints of the unified history in `dir` are stored under, and
+/// its output files there: `unified.jsonl` or the period files of `split_by`.
+/// Everything reading or rewriting the output resolves it through here.
+fn unified_outputs(dir: &Path, split_by: Option<SplitBy>) -> (String, Vec<PathBuf>) {
+    match split_by {
+        Some(split) => (split.output_key(dir), split.existing_files(dir)),
+        None => {
+            let path = dir.join(UNIFIED_FILE);
+            let outputs = if path.exists() { vec![path.clone()] } else { Vec::new() };
+            (path.to_string_lossy().to_string(), outputs)
+        }
+    }
+}
+
+/// Write sorted records as the unified history in `dir`; returns the files
+fn write_outputs(dir: &Path, split_by: Option<SplitBy>, records: Vec<Value>) -> Result<Vec<PathBuf>, String> {
+    match split_by {
+        Some(split) => write_split(dir, split, records),
+        None => {
+            let path = dir.join(UNIFIED_FILE);
+            write_jsonl_atomic(&path, &records)?;
+            Ok(vec![path])
+        }
+    }
+}
+
+/// What results report as the unified history: the file, or the directory
+/// of the period files when split
+fn unified_display_path(dir: &Path, split_by: Option<SplitBy>) -> String {
+    match split_by {
+        Some(_) => dir.to_string_lossy().to_string(),
+        None => dir.join(UNIFIED_FILE).to_string_lossy().to_string(),
+    }
+}
+
 #[derive(serde::Serialize)]
 pub struct UnifyResult {
+    /// The unified file, or the directory of the files when split
     pub unified_path: String,
+    /// Every output file, one per period when split
+    pub files: Vec<String>,
     pub total_messages: usize,
     pub sources: Vec<SourceStat>,
+    /// Source files read this time; unchanged ones are carried over
+    pub files_read: usize,
+    /// Source files whose fingerprint matched the last unify
+    pub files_unchanged: usize,
+    /// History directories that couldn't be read, so their files are missing
+    pub warnings: Vec<String>,
 }
 
 #[derive(serde::Serialize)]
@@ -100,47 +1193,975 @@ pub struct SourceStat {
     pub count: usize,
 }
 
+/// Lines and bytes one provider (or import) contributes to a project
+struct MeasuredSource {
+    provider: String,
+    count: usize,
+    bytes: u64,
+    estimated_bytes: u64,
+}
+
+/// Measure the files the unifier would read for a project, per provider
+/// (imports by label), in source order
+fn measure_sources(app: &AppHandle, project_path: &str) -> Vec<MeasuredSource> {
+    let sources = load_history_sources(app).unwrap_or_default();
+    let walk = HistoryWalkSettings::load(app);
+    let project = ProjectMatcher::new(project_path, false);
+    // Estimates only; unify reports the unreadable directories
+    let warnings = &mut Vec::new();
+    let claude = claude_files(project_path, warnings);
+    let mut candidates = |provider: &str, defaults: &[&str]| {
+        candidate_files(&project, &candidate_roots(&sources, provider, defaults), &walk, warnings)
+    };
+    let mut files: Vec<(String, Vec<PathBuf>)> = vec![
+        ("claude".into(), claude),
+        ("codex".into(), candidates("codex", CODEX_ROOTS)),
+        ("gemini".into(), candidates("gemini", GEMINI_ROOTS)),
+    ];
+    files.extend(import_files(project_path).into_iter().map(|(label, path)| (label, vec![path])));
+
+    let mut measured = Vec::new();
+    for (provider, paths) in files {
+        let mut source = MeasuredSource { provider, count: 0, bytes: 0, estimated_bytes: 0 };
+        for path in &paths {
+            let (lines, bytes) = count_lines(path);
+            source.count += lines;
+            source.bytes += bytes;
+            source.estimated_bytes += estimated_output_bytes(bytes, lines, path);
+        }
+        if source.count > 0 {
+            measured.push(source);
+        }
+    }
+    measured
+}
+
+/// Approximate per-provider message counts for a project.
+///
+/// Counts lines in the same files the unifier would read, without parsing
+/// them or writing anything, so it is cheap enough to call on project open.
+#[tauri::command]
+pub async fn count_provider_messages(app: AppHandle, project_path: String) -> Result<Vec<SourceStat>, String> {
+    Ok(measure_sources(&app, &project_path)
+        .into_iter()
+        .map(|m| SourceStat { provider: m.provider, count: m.count })
+        .collect())
+}
+
+#[derive(serde::Serialize)]
+pub struct UnifyEstimate {
+    /// Directory the unified file would be written to
+    pub output_dir: String,
+    pub total_messages: usize,
+    pub sources: Vec<SourceStat>,
+    /// Bytes of the source files, decompressed
+    pub source_bytes: u64,
+    /// Expected size of the unified file
+    pub estimated_bytes: u64,
+    /// Space available at the output directory; `None` where it can't be queried
+    pub free_bytes: Option<u64>,
+    /// Whether the estimate fits in the free space, when that is known
+    pub fits: Option<bool>,
+}
+
+/// Estimate how large a project's unified history would be, before writing it.
+///
+/// Counts lines and bytes of the source files without parsing them (no
+/// provider or time filter applies) and compares the estimate to the free
+/// space where the file would go. Nothing is created.
+#[tauri::command]
+pub async fn estimate_unify_size(
+    app: AppHandle,
+    project_path: String,
+    output_dir: Option<String>,
+) -> Result<UnifyEstimate, String> {
+    let dir = unified_output_path(&app, &project_path, output_dir)?;
+    let measured = tokio::task::spawn_blocking(move || measure_sources(&app, &project_path))
+        .await
+        .map_err(|e| e.to_string())?;
+    let estimated_bytes = measured.iter().map(|m| m.estimated_bytes).sum();
+    let free_bytes = free_bytes_at(&dir);
+    Ok(UnifyEstimate {
+        output_dir: dir.to_string_lossy().to_string(),
+        total_messages: measured.iter().map(|m| m.count).sum(),
+        source_bytes: measured.iter().map(|m| m.bytes).sum(),
+        estimated_bytes,
+        free_bytes,
+        fits: free_bytes.map(|free| estimated_bytes <= free),
+        sources: measured.into_iter().map(|m| SourceStat { provider: m.provider, count: m.count }).collect(),
+    })
+}
+
+/// A source file of this unify run
+struct TrackedSource {
+    key: String,
+    path: PathBuf,
+    kind: SourceKind,
+    fingerprint: Fingerprint,
+    changed: bool,
+}
+
+/// Source files that feed a project's unified history, in output order, with
+/// warnings for the directories that couldn't be read.
+///
+/// The Claude, Codex and Gemini directories are walked concurrently; the
+/// result is the same as walking them one after the other.
+fn unify_sources(
+    app: &AppHandle,
+    project_path: &str,
+    filter: &UnifyFilter,
+) -> (Vec<(String, PathBuf, SourceKind)>, Vec<String>) {
+    let sources = load_history_sources(app).unwrap_or_default();
+    let walk = HistoryWalkSettings::load(app);
+    // Each walk collects its own warnings; they are merged in provider order
+    let candidates = |provider: &str, defaults: &[&str]| {
+        let mut warnings = Vec::new();
+        let files = if filter.wants_provider(provider) {
+            history_files(&candidate_roots(&sources, provider, defaults), &walk, &mut warnings)
+        } else {
+            Vec::new()
+        };
+        (files, warnings)
+    };
+    let (claude, codex, gemini) = std::thread::scope(|scope| {
+        let claude = scope.spawn(|| {
+            let mut warnings = Vec::new();
+            let files =
+                if filter.wants_provider("claude") { claude_files(project_path, &mut warnings) } else { Vec::new() };
+            (files, warnings)
+        });
+        let codex = scope.spawn(|| candidates("codex", CODEX_ROOTS));
+        let gemini = scope.spawn(|| candidates("gemini", GEMINI_ROOTS));
+        (join_walk(claude), join_walk(codex), join_walk(gemini))
+    });
+    let ((claude, mut warnings), (codex, codex_warnings), (gemini, gemini_warnings)) = (claude, codex, gemini);
+    warnings.extend(codex_warnings);
+    warnings.extend(gemini_warnings);
+
+    let mut files = Vec::new();
+    files.extend(claude.into_iter().map(|path| ("claude".to_string(), path, SourceKind::Project)));
+    files.extend(codex.into_iter().map(|path| ("codex".to_string(), path, SourceKind::Candidate)));
+    files.extend(gemini.into_iter().map(|path| ("gemini".to_string(), path, SourceKind::Candidate)));
+    for (label, path) in import_files(project_path) {
+        if filter.wants_provider(&label) {
+            files.push((label, path, SourceKind::Import));
+        }
+    }
+    (files, warnings)
+}
+
+/// Result of a scoped worker thread; a panic in it is re-raised here
+fn join_walk<T>(handle: std::thread::ScopedJoinHandle<'_, T>) -> T {
+    handle.join().unwrap_or_else(|e| std::panic::resume_unwind(e))
+}
+
+/// Thread a source file is handled on: one per provider, since each keeps its
+/// history in its own directory tree; imports share one
+fn worker_for(provider: &str, kind: SourceKind) -> &str {
+    if kind == SourceKind::Import { "imports" } else { provider }
+}
+
+/// Apply `f` to every item with one thread per worker group, returning the
+/// results in input order so the output stays deterministic
+fn map_per_worker<T: Sync, R: Send>(
+    items: &[T],
+    worker: impl Fn(&T) -> &str,
+    f: impl Fn(&T) -> R + Sync,
+) -> Vec<R> {
+    let mut groups: Vec<(&str, Vec<usize>)> = Vec::new();
+    for (i, item) in items.iter().enumerate() {
+        let name = worker(item);
+        match groups.iter_mut().find(|(g, _)| *g == name) {
+            Some((_, indices)) => indices.push(i),
+            None => groups.push((name, vec![i])),
+        }
+    }
+    let mut results: Vec<Option<R>> = items.iter().map(|_| None).collect();
+    std::thread::scope(|scope| {
+        let f = &f;
+        let handles: Vec<_> = groups
+            .iter()
+            .map(|(_, indices)| scope.spawn(move || indices.iter().map(|&i| (i, f(&items[i]))).collect::<Vec<_>>()))
+            .collect();
+        for handle in handles {
+            for (i, result) in join_walk(handle) {
+                results[i] = Some(result);
+            }
+        }
+    });
+    results.into_iter().flatten().collect()
+}
+
+/// Merge Claude, Codex and Gemini histories for a project into one jsonl file.
+///
+/// `filter` narrows the output by provider, role and time range; it is applied
+/// while gathering so skipped providers are never read.
+///
+/// Each source file's (mtime, size, hash) is remembered per output file, so
+/// only new or changed files are read again; the records of unchanged ones
+/// are carried over from the previous output, and when nothing changed the
+/// output is left as is.
+///
+/// With `split_by` the records go into one file per UTC day or month
+/// (`unified-2024-06-01.jsonl`, untimed ones in `unified-undated.jsonl`)
+/// instead of `unified.jsonl`, so a period can be loaded on its own.
 #[tauri::command]
-pub async fn unify_provider_histories(project_path: String) -> Result<UnifyResult, String> {
-    // Gather
-    let mut claude = gather_claude(&project_path);
-    let codex = gather_from_candidates(&project_path, &[
-        "~/.codex", "~/.openai", "~/.config/openai", "~/.config/codex", "~/Library/Application Support/OpenAI",
-    ]);
-    let gemini = gather_from_candidates(&project_path, &[
-        "~/.gemini", "~/.config/gemini", "~/Library/Application Support/Gemini",
-    ]);
+pub async fn unify_provider_histories(
+    app: AppHandle,
+    project_path: String,
+    output_dir: Option<String>,
+    filter: Option<UnifyFilter>,
+    split_by: Option<SplitBy>,
+) -> Result<UnifyResult, String> {
+    let filter = filter.unwrap_or_default();
+    let target_dir = unified_output_dir(&app, &project_path, output_dir)?;
+    // Walking, hashing and reading the histories blocks on scoped threads,
+    // so keep it off the async runtime
+    tokio::task::spawn_blocking(move || unify_into(app, project_path, target_dir, filter, split_by))
+        .await
+        .map_err(|e| e.to_string())?
+}
+
+/// Body of `unify_provider_histories`, run on a blocking thread
+fn unify_into(
+    app: AppHandle,
+    project_path: String,
+    target_dir: PathBuf,
+    filter: UnifyFilter,
+    split_by: Option<SplitBy>,
+) -> Result<UnifyResult, String> {
+    let (unified_key, mut outputs) = unified_outputs(&target_dir, split_by);
+    let filter_key = serde_json::to_string(&filter).map_err(|e| e.to_string())?;
+
+    let mut conn = open_db(&app)?;
+    let previous = if !outputs.is_empty() {
+        load_fingerprints(&conn, &unified_key, &filter_key).unwrap_or_else(|e| {
+            log::warn!("Ignoring stored unify fingerprints: {}", e);
+            HashMap::new()
+        })
+    } else {
+        HashMap::new()
+    };
+
+    let relabels = load_relabels(&conn, &project_path).unwrap_or_else(|e| {
+        log::warn!("Ignoring provider relabels of {}: {}", project_path, e);
+        HashMap::new()
+    });
 
-    let mut all = Vec::new();
-    let mut sources = Vec::new();
+    // Fingerprint every source; only changed ones get probed or hashed
+    let (found, mut warnings) = unify_sources(&app, &project_path, &filter);
+    let project = ProjectMatcher::new(&project_path, filter.match_basename.unwrap_or(false));
+    let fingerprints = map_per_worker(
+        &found,
+        |(provider, _, kind)| worker_for(provider, *kind),
+        |(provider, path, kind)| {
+            let previous = previous.get(&*path.to_string_lossy());
+            fingerprint_source(provider, path, *kind, &project, previous)
+        },
+    );
+    let mut current: Vec<TrackedSource> = found
+        .into_iter()
+        .zip(fingerprints)
+        .filter_map(|((_, path, kind), fingerprint)| {
+            let (fingerprint, changed) = fingerprint?;
+            Some(TrackedSource { key: path.to_string_lossy().to_string(), path, kind, fingerprint, changed })
+        })
+        .collect();
+    let files_unchanged = current.iter().filter(|s| !s.changed).count();
+    let same_files = current.len() == previous.len() && current.iter().all(|s| previous.contains_key(&s.key));
 
-    if !claude.is_empty() { sources.push(SourceStat { provider: "claude".into(), count: claude.len() }); }
-    if !codex.is_empty() { sources.push(SourceStat { provider: "codex".into(), count: codex.len() }); }
-    if !gemini.is_empty() { sources.push(SourceStat { provider: "gemini".into(), count: gemini.len() }); }
+    let mut files_read = 0;
+    if outputs.is_empty() || !same_files || files_unchanged < current.len() {
+        let keep: BTreeSet<String> = current
+            .iter()
+            .filter(|s| !s.changed && s.fingerprint.matched && s.fingerprint.count > 0)
+            .map(|s| s.key.clone())
+            .collect();
+        let mut carried = previous_records(&outputs, &keep);
+        // Changed files are read concurrently per provider
+        let gathered = map_per_worker(
+            &current,
+            |s| worker_for(&s.fingerprint.provider, s.kind),
+            |s| {
+                (s.changed && s.fingerprint.matched)
+                    .then(|| gather_source(&s.fingerprint.provider, &s.path, s.kind, &filter))
+            },
+        );
+        let mut all = Vec::new();
+        for (source, items) in current.iter_mut().zip(gathered) {
+            if !source.fingerprint.matched {
+                continue;
+            }
+            // Sources read again come back under their original label
+            if let Some(to) = relabels.get(&source.fingerprint.provider) {
+                source.fingerprint.provider = to.clone();
+            }
+            let items = match items {
+                Some(mut items) => {
+                    files_read += 1;
+                    apply_relabels(&mut items, &relabels);
+                    items
+                }
+                None => carried.remove(&source.key).unwrap_or_default(),
+            };
+            source.fingerprint.count = items.len();
+            all.extend(items);
+        }
 
-    all.append(&mut claude);
-    all.extend(codex);
-    all.extend(gemini);
+        // Stable sort on the normalized timestamp; untimed files keep their order
+        all.sort_by_key(|v| try_get_ts(v).unwrap_or(0));
+        // The write is atomic, so running out of space leaves the old file intact
+        let estimated: u64 = current
+            .iter()
+            .filter(|s| s.fingerprint.matched)
+            .map(|s| estimated_output_bytes(s.fingerprint.size.max(0) as u64, s.fingerprint.count, &s.path))
+            .sum();
+        match free_bytes_at(&target_dir) {
+            Some(free) if estimated > free => {
+                let warning = format!(
+                    "Unified history of about {} MB may not fit: only {} MB free at {}",
+                    estimated / (1024 * 1024),
+                    free / (1024 * 1024),
+                    target_dir.display()
+                );
+                log::warn!("{}", warning);
+                warnings.push(warning);
+            }
+            _ => {}
+        }
+        outputs = write_outputs(&target_dir, split_by, all)?;
 
-    // Sort by timestamp if available
-    all.sort_by_key(|v| try_get_ts(v).unwrap_or(0));
+        let fingerprints: Vec<(String, Fingerprint)> =
+            current.iter().map(|s| (s.key.clone(), s.fingerprint.clone())).collect();
+        if let Err(e) = save_fingerprints(&mut conn, &unified_key, &filter_key, &fingerprints) {
+            log::warn!("Failed to store unify fingerprints for {}: {}", project_path, e);
+        }
+    }
 
-    // Write to ~/.ishinex/projects/<project_id>/unified/unified.jsonl
-    let base = ishinex_dir()?;
-    let project_id = encode_project_id(&project_path);
-    let target_dir = base.join("projects").join(project_id).join("unified");
-    fs::create_dir_all(&target_dir).map_err(|e| e.to_string())?;
-    let unified_path = target_dir.join("unified.jsonl");
-    let mut file = fs::File::create(&unified_path).map_err(|e| e.to_string())?;
-    use std::io::Write;
-    for v in &all {
-        let line = serde_json::to_string(v).map_err(|e| e.to_string())?;
-        writeln!(file, "{}", line).map_err(|e| e.to_string())?;
+    // Per-provider counts straight from the fingerprints, in source order
+    let mut sources: Vec<SourceStat> = Vec::new();
+    for fp in current.iter().map(|s| &s.fingerprint).filter(|fp| fp.matched && fp.count > 0) {
+        match sources.iter_mut().find(|s| s.provider == fp.provider) {
+            Some(stat) => stat.count += fp.count,
+            None => sources.push(SourceStat { provider: fp.provider.clone(), count: fp.count }),
+        }
     }
 
     Ok(UnifyResult {
-        unified_path: unified_path.to_string_lossy().to_string(),
-        total_messages: all.len(),
+        unified_path: unified_display_path(&target_dir, split_by),
+        files: outputs.iter().map(|p| p.to_string_lossy().to_string()).collect(),
+        total_messages: sources.iter().map(|s| s.count).sum(),
         sources,
+        files_read,
+        files_unchanged,
+        warnings,
+    })
+}
+
+/// Write records next to `path` and rename on success so a crash mid-write
+/// never leaves a half-written file behind
+fn write_jsonl_atomic(path: &Path, records: &[Value]) -> Result<(), String> {
+    let dir = path.parent().ok_or_else(|| format!("{} has no parent directory", path.display()))?;
+    let tmp = tempfile::NamedTempFile::new_in(dir).map_err(|e| e.to_string())?;
+    {
+        let mut writer = BufWriter::new(tmp.as_file());
+        for v in records {
+            let line = serde_json::to_string(v).map_err(|e| e.to_string())?;
+            writeln!(writer, "{}", line).map_err(|e| e.to_string())?;
+        }
+        writer.flush().map_err(|e| e.to_string())?;
+    }
+    tmp.persist(path).map_err(|e| e.to_string())?;
+    Ok(())
+}
+
+#[derive(serde::Serialize)]
+pub struct ImportResult {
+    pub imported: usize,
+    /// Records already present (from an earlier import or twice in the file)
+    pub skipped: usize,
+    pub import_path: String,
+    pub unified_path: String,
+}
+
+/// Text of an OpenAI export message (`content.parts`, strings only)
+fn openai_message_text(message: &Value) -> Option<String> {
+    let parts: Vec<&str> = message
+        .pointer("/content/parts")?
+        .as_array()?
+        .iter()
+        .filter_map(|p| p.as_str())
+        .filter(|p| !p.trim().is_empty())
+        .collect();
+    if parts.is_empty() { None } else { Some(parts.join("\n")) }
+}
+
+/// Records from an OpenAI (ChatGPT) `conversations.json` export.
+///
+/// Each conversation is a tree of messages; the branch ending at
+/// `current_node` is the one the user saw, so that's the one kept. Exports
+/// without it fall back to every message ordered by creation time.
+fn openai_export_records(export: &Value) -> Vec<Value> {
+    let conversations: Vec<&Value> = match export {
+        Value::Array(items) => items.iter().collect(),
+        other => vec![other],
+    };
+    let mut records = Vec::new();
+    for conversation in conversations {
+        let mapping = match conversation.get("mapping").and_then(|m| m.as_object()) {
+            Some(mapping) => mapping,
+            None => continue,
+        };
+        let mut nodes: Vec<&Value> = Vec::new();
+        let mut current = conversation.get("current_node").and_then(|n| n.as_str());
+        while let Some(node) = current.and_then(|id| mapping.get(id)) {
+            nodes.push(node);
+            current = node.get("parent").and_then(|p| p.as_str());
+            if nodes.len() > mapping.len() { break; }
+        }
+        nodes.reverse();
+        if nodes.is_empty() {
+            nodes = mapping.values().collect();
+            nodes.sort_by(|a, b| {
+                let at = a.pointer("/message/create_time").and_then(|t| t.as_f64()).unwrap_or(0.0);
+                let bt = b.pointer("/message/create_time").and_then(|t| t.as_f64()).unwrap_or(0.0);
+                at.total_cmp(&bt)
+            });
+        }
+        for message in nodes.into_iter().filter_map(|n| n.get("message")) {
+            let role = match message.pointer("/author/role").and_then(|r| r.as_str()) {
+                Some(role @ ("user" | "assistant")) => role,
+                _ => continue,
+            };
+            let text = match openai_message_text(message) {
+                Some(text) => text,
+                None => continue,
+            };
+            records.push(serde_json::json!({
+                "type": role,
+                "message": { "role": role, "content": [{ "type": "text", "text": text }] },
+                "timestamp": message.get("create_time").cloned().unwrap_or(Value::Null),
+                "conversation_id": conversation.get("id").or_else(|| conversation.get("conversation_id")),
+                "conversation_title": conversation.get("title"),
+            }));
+        }
+    }
+    records
+}
+
+/// Key used to skip records imported before: the record without the
+/// normalized timestamp, which is recomputed on every import
+fn import_key(record: &Value) -> String {
+    let mut record = record.clone();
+    if let Value::Object(map) = &mut record { map.remove(ISHINEX_TS); }
+    record.to_string()
+}
+
+/// Provider labels name import files, so keep them simple
+fn validate_label(label: &str) -> Result<&str, String> {
+    let trimmed = label.trim();
+    if trimmed.is_empty() || !trimmed.chars().all(|c| c.is_ascii_alphanumeric() || c == '-' || c == '_') {
+        return Err(format!("Invalid provider label {:?}: use letters, digits, '-' or '_'", label));
+    }
+    Ok(trimmed)
+}
+
+/// Fold an external transcript into a project's unified history.
+///
+/// Accepts a jsonl file (optionally compressed) or an OpenAI chat export
+/// (`conversations.json`). Records are tagged with `_ishinex_provider` =
+/// `provider_label`, kept under the project's `imports` directory so later
+/// unifications include them, and merged into the current unified history
+/// (in `output_dir`, split by `split_by`, as `unify_provider_histories` wrote
+/// it). Records already imported under the same label are skipped.
+#[tauri::command]
+pub async fn import_transcript(
+    app: AppHandle,
+    project_path: String,
+    provider_label: String,
+    path: String,
+    output_dir: Option<String>,
+    split_by: Option<SplitBy>,
+) -> Result<ImportResult, String> {
+    let label = validate_label(&provider_label)?;
+    let source = expand_tilde(path.trim());
+    if !source.is_file() {
+        return Err(format!("{} is not a file", source.display()));
+    }
+
+    let mut records = if source.extension().and_then(|s| s.to_str()) == Some("json") {
+        let data = fs::read(&source).map_err(|e| e.to_string())?;
+        let export: Value = serde_json::from_slice(&data)
+            .map_err(|e| format!("{} is not valid JSON: {}", source.display(), e))?;
+        openai_export_records(&export)
+    } else {
+        read_jsonl(&source).into_iter().filter(|v| v.is_object()).collect()
+    };
+    if records.is_empty() {
+        return Err(format!("No messages found in {}", source.display()));
+    }
+    stamp_timestamps(&mut records);
+    tag_provider(&mut records, label);
+
+    let import_path = imports_dir(&project_path)?.join(format!("{}.jsonl", label));
+    let mut existing = if import_path.exists() { read_jsonl(&import_path) } else { Vec::new() };
+    let mut seen: std::collections::HashSet<String> = existing.iter().map(import_key).collect();
+    let total = records.len();
+    let fresh: Vec<Value> = records.into_iter().filter(|r| seen.insert(import_key(r))).collect();
+    let skipped = total - fresh.len();
+
+    if let Some(parent) = import_path.parent() {
+        fs::create_dir_all(parent).map_err(|e| e.to_string())?;
+    }
+    existing.extend(fresh.iter().cloned());
+    write_jsonl_atomic(&import_path, &existing)?;
+
+    // Merge into the current unified history in timeline order
+    let target_dir = unified_output_dir(&app, &project_path, output_dir)?;
+    if !fresh.is_empty() {
+        let (_, outputs) = unified_outputs(&target_dir, split_by);
+        let mut unified: Vec<Value> = outputs.iter().flat_map(|p| read_jsonl(p)).collect();
+        unified.extend(fresh.iter().cloned());
+        unified.sort_by_key(|v| try_get_ts(v).unwrap_or(0));
+        write_outputs(&target_dir, split_by, unified)?;
+    }
+
+    Ok(ImportResult {
+        imported: fresh.len(),
+        skipped,
+        import_path: import_path.to_string_lossy().to_string(),
+        unified_path: unified_display_path(&target_dir, split_by),
+    })
+}
+
+/// Rename provider `from` to `to` in a project's unified history (e.g. fold
+/// "chatgpt" and "codex" into "openai"); returns the records relabeled.
+///
+/// The unified history (the file, or the period files of `split_by`) and
+/// the stored per-file stats are rewritten in place. The rename is remembered
+/// for the project and applied whenever a later unify reads a `from` source
+/// again, so the history never mixes both labels; relabeling back to `from`
+/// undoes it. An import labeled `from` is moved into the `to` import.
+#[tauri::command]
+pub async fn relabel_provider(
+    app: AppHandle,
+    project_path: String,
+    from: String,
+    to: String,
+    output_dir: Option<String>,
+    split_by: Option<SplitBy>,
+) -> Result<usize, String> {
+    let from = from.trim();
+    let to = validate_label(&to)?;
+    if from.is_empty() || from == to {
+        return Ok(0);
+    }
+    let target_dir = unified_output_dir(&app, &project_path, output_dir)?;
+    let (unified_key, outputs) = unified_outputs(&target_dir, split_by);
+    if outputs.is_empty() {
+        return Err(format!(
+            "No unified history at {}; unify the project first",
+            unified_display_path(&target_dir, split_by)
+        ));
+    }
+
+    // Labels don't move records between periods, so each file is rewritten on its own
+    let mut relabeled = 0;
+    for output in &outputs {
+        let mut records = read_jsonl(output);
+        let mut changed = 0;
+        for record in &mut records {
+            if let Value::Object(map) = record {
+                if map.get(ISHINEX_PROVIDER).and_then(|p| p.as_str()) == Some(from) {
+                    map.insert(ISHINEX_PROVIDER.to_string(), Value::from(to));
+                    changed += 1;
+                }
+            }
+        }
+        if changed > 0 {
+            write_jsonl_atomic(output, &records)?;
+            relabeled += changed;
+        }
+    }
+
+    let mut conn = open_db(&app)?;
+    ensure_fingerprints_table(&conn)?;
+    conn.execute(
+        "UPDATE unify_fingerprints SET provider = ?1 WHERE unified_path = ?2 AND provider = ?3",
+        rusqlite::params![to, unified_key, from],
+    )
+    .map_err(|e| e.to_string())?;
+    save_relabel(&mut conn, &project_path, from, to)?;
+
+    let imports = imports_dir(&project_path)?;
+    let from_import = imports.join(format!("{}.jsonl", from));
+    if validate_label(from).is_ok() && from_import.is_file() {
+        let to_import = imports.join(format!("{}.jsonl", to));
+        let mut merged = if to_import.exists() { read_jsonl(&to_import) } else { Vec::new() };
+        let mut seen: std::collections::HashSet<String> = merged.iter().map(import_key).collect();
+        let mut moved = read_jsonl(&from_import);
+        tag_provider(&mut moved, to);
+        merged.extend(moved.into_iter().filter(|r| seen.insert(import_key(r))));
+        merged.sort_by_key(|v| try_get_ts(v).unwrap_or(0));
+        write_jsonl_atomic(&to_import, &merged)?;
+        fs::remove_file(&from_import).map_err(|e| format!("Failed to remove {}: {}", from_import.display(), e))?;
+    }
+    Ok(relabeled)
+}
+
+/// Cancel flags of the running `unify_all_projects` calls by run id; set by
+/// `cancel_unify_all` and checked between projects
+fn unify_runs() -> &'static Mutex<HashMap<String, Arc<AtomicBool>>> {
+    static RUNS: OnceLock<Mutex<HashMap<String, Arc<AtomicBool>>>> = OnceLock::new();
+    RUNS.get_or_init(|| Mutex::new(HashMap::new()))
+}
+
+#[derive(serde::Serialize)]
+pub struct UnifyFailure {
+    pub project_path: String,
+    pub error: String,
+}
+
+#[derive(serde::Serialize)]
+pub struct UnifyAllResult {
+    /// Id of this run, as passed to `cancel_unify_all`
+    pub run_id: String,
+    pub results: Vec<UnifyResult>,
+    pub failures: Vec<UnifyFailure>,
+    /// Projects found, including any skipped by a cancel
+    pub total_projects: usize,
+    pub total_messages: usize,
+    pub cancelled: bool,
+}
+
+/// Real paths of the projects ishinex has persisted sessions for, read from
+/// the init message each transcript starts with (the directory name is a
+/// lossy encoding of the path)
+fn ishinex_project_paths() -> Vec<String> {
+    let projects_dir = match ishinex_dir() {
+        Ok(dir) => dir.join("projects"),
+        Err(_) => return Vec::new(),
+    };
+    let mut paths = Vec::new();
+    for project in fs::read_dir(projects_dir).into_iter().flatten().flatten() {
+        let sessions = project.path().join("sessions");
+        let found = fs::read_dir(sessions)
+            .into_iter()
+            .flatten()
+            .flatten()
+            .map(|e| e.path())
+            .filter(|p| is_history_file(p))
+            .find_map(|p| {
+                let first = open_history(&p)?.lines().next()?.ok()?;
+                let v: Value = serde_json::from_str(&first).ok()?;
+                v.get("project_path")?.as_str().map(|s| s.to_string())
+            });
+        if let Some(path) = found {
+            paths.push(path);
+        }
+    }
+    paths
+}
+
+/// Rebuild the unified history of every known project (Claude projects and
+/// those with ishinex sessions).
+///
+/// Emits `unify-progress` (with the `run_id`) after each project and stops
+/// early, keeping what was done, when `cancel_unify_all` is called with the
+/// run's id. `run_id` lets the caller pick the id up front; one is generated
+/// otherwise. Several runs may go on at once, each cancelled on its own.
+#[tauri::command]
+pub async fn unify_all_projects(app: AppHandle, run_id: Option<String>) -> Result<UnifyAllResult, String> {
+    let run_id = run_id
+        .map(|id| id.trim().to_string())
+        .filter(|id| !id.is_empty())
+        .unwrap_or_else(|| uuid::Uuid::new_v4().to_string());
+    let cancel = Arc::new(AtomicBool::new(false));
+    {
+        let mut runs = unify_runs().lock().map_err(|e| e.to_string())?;
+        if runs.contains_key(&run_id) {
+            return Err(format!("Unify run {} is already running", run_id));
+        }
+        runs.insert(run_id.clone(), cancel.clone());
+    }
+
+    let mut projects: BTreeSet<String> = crate::commands::claude::list_projects()
+        .await
+        .unwrap_or_default()
+        .into_iter()
+        .map(|p| p.path)
+        .collect();
+    projects.extend(ishinex_project_paths());
+    projects.retain(|p| Path::new(p).is_dir());
+
+    let total_projects = projects.len();
+    let mut results = Vec::new();
+    let mut failures = Vec::new();
+    let mut cancelled = false;
+    for (index, project_path) in projects.into_iter().enumerate() {
+        if cancel.load(Ordering::Acquire) {
+            cancelled = true;
+            break;
+        }
+        let outcome = unify_provider_histories(app.clone(), project_path.clone(), None, None, None).await;
+        let error = outcome.as_ref().err().cloned();
+        let _ = app.emit(
+            &event_name("unify-progress"),
+            serde_json::json!({
+                "run_id": run_id,
+                "project_path": project_path,
+                "index": index + 1,
+                "total": total_projects,
+                "success": error.is_none(),
+                "error": error,
+            }),
+        );
+        match outcome {
+            Ok(result) => results.push(result),
+            Err(error) => failures.push(UnifyFailure { project_path, error }),
+        }
+    }
+
+    if let Ok(mut runs) = unify_runs().lock() {
+        runs.remove(&run_id);
+    }
+    Ok(UnifyAllResult {
+        run_id,
+        total_messages: results.iter().map(|r| r.total_messages).sum(),
+        results,
+        failures,
+        total_projects,
+        cancelled,
+    })
+}
+
+/// Stop the `unify_all_projects` run `run_id` after the project in progress
+#[tauri::command]
+pub async fn cancel_unify_all(run_id: String) -> Result<(), String> {
+    let runs = unify_runs().lock().map_err(|e| e.to_string())?;
+    let cancel = runs.get(&run_id).ok_or_else(|| format!("No running unify run {}", run_id))?;
+    cancel.store(true, Ordering::Release);
+    Ok(())
+}
+
+#[derive(serde::Serialize)]
+pub struct JsonlValidation {
+    pub path: String,
+    /// Non-blank lines inspected
+    pub total_lines: usize,
+    pub invalid_lines: usize,
+    /// 1-based number of the first line that isn't valid JSON
+    pub first_invalid_line: Option<usize>,
+}
+
+#[derive(serde::Serialize)]
+pub struct JsonlRepair {
+    pub path: String,
+    pub backup_path: String,
+    pub kept_lines: usize,
+    pub dropped_lines: usize,
+}
+
+/// Visit every non-blank line of a jsonl file with its 1-based number and
+/// whether it parses
+fn scan_jsonl(path: &Path, mut visit: impl FnMut(usize, &[u8], bool) -> Result<(), String>) -> Result<(), String> {
+    let file = fs::File::open(path).map_err(|e| format!("Cannot open {}: {}", path.display(), e))?;
+    for (i, line) in BufReader::new(file).split(b'\n').enumerate() {
+        let line = line.map_err(|e| e.to_string())?;
+        if line.iter().all(|b| b.is_ascii_whitespace()) {
+            continue;
+        }
+        let valid = serde_json::from_slice::<Value>(&line).is_ok();
+        visit(i + 1, &line, valid)?;
+    }
+    Ok(())
+}
+
+/// Check that every line of a jsonl file parses, reporting the first bad line
+#[tauri::command]
+pub async fn validate_jsonl(path: String) -> Result<JsonlValidation, String> {
+    let mut report = JsonlValidation {
+        path: path.clone(),
+        total_lines: 0,
+        invalid_lines: 0,
+        first_invalid_line: None,
+    };
+    scan_jsonl(Path::new(&path), |n, _, valid| {
+        report.total_lines += 1;
+        if !valid {
+            report.invalid_lines += 1;
+            report.first_invalid_line.get_or_insert(n);
+        }
+        Ok(())
+    })?;
+    Ok(report)
+}
+
+/// Drop lines that aren't valid JSON (typically a partial last line left by a
+/// crash). The original is kept as `<file>.bak` and the repaired file is
+/// swapped in atomically.
+#[tauri::command]
+pub async fn repair_jsonl(path: String) -> Result<JsonlRepair, String> {
+    let source = PathBuf::from(&path);
+    let dir = source
+        .parent()
+        .filter(|d| !d.as_os_str().is_empty())
+        .unwrap_or(Path::new("."));
+    let tmp = tempfile::NamedTempFile::new_in(dir).map_err(|e| e.to_string())?;
+    let (mut kept_lines, mut dropped_lines) = (0, 0);
+    {
+        let mut writer = BufWriter::new(tmp.as_file());
+        scan_jsonl(&source, |_, line, valid| {
+            if !valid {
+                dropped_lines += 1;
+                return Ok(());
+            }
+            kept_lines += 1;
+            writer.write_all(line).and_then(|_| writer.write_all(b"\n")).map_err(|e| e.to_string())
+        })?;
+        writer.flush().map_err(|e| e.to_string())?;
+    }
+
+    let mut backup = source.clone().into_os_string();
+    backup.push(".bak");
+    let backup = PathBuf::from(backup);
+    fs::copy(&source, &backup).map_err(|e| format!("Cannot back up {}: {}", source.display(), e))?;
+    tmp.persist(&source).map_err(|e| e.to_string())?;
+
+    Ok(JsonlRepair {
+        path,
+        backup_path: backup.to_string_lossy().to_string(),
+        kept_lines,
+        dropped_lines,
     })
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn test_claude_session() {
+        let records = vec![
+            json!({"type": "summary", "summary": "Old title", "leafUuid": "u0"}),
+            json!({"type": "summary", "summary": "Fix the build", "leafUuid": "u2"}),
+            json!({"type": "user", "uuid": "u1", "sessionId": "abc", "cwd": "/p", "isMeta": true,
+                "message": {"content": "<command>init</command>"}, "timestamp": "2025-01-01T00:00:00Z"}),
+            json!({"type": "user", "uuid": "u1b", "sessionId": "abc",
+                "message": {"content": [{"type": "text", "text": " make it build "}]}}),
+            json!({"type": "assistant", "uuid": "u2", "sessionId": "abc", "version": "1.0.3",
+                "timestamp": "2025-01-01T00:01:00Z"}),
+        ];
+        let session = claude_session(Path::new("/x/other.jsonl"), &records).unwrap();
+        assert_eq!(session.session_id, "abc");
+        assert_eq!(session.summary.as_deref(), Some("Fix the build"));
+        assert_eq!(session.leaf_uuid.as_deref(), Some("u2"));
+        assert_eq!(session.first_prompt.as_deref(), Some("make it build"));
+        assert_eq!(session.updated_at.as_deref(), Some("2025-01-01T00:01:00Z"));
+        assert_eq!(session.message_count, 3);
+        assert!(session.resumable);
+
+        let summary_only = [json!({"type": "summary", "summary": "t"})];
+        let session = claude_session(Path::new("/x/def.jsonl.gz"), &summary_only).unwrap();
+        assert_eq!(session.session_id, "def");
+        assert!(!session.resumable);
+    }
+
+    #[test]
+    fn test_try_get_ts_formats() {
+        let millis = 1_700_000_000_000;
+        assert_eq!(try_get_ts(&json!({"timestamp": "2023-11-14T22:13:20Z"})), Some(millis));
+        assert_eq!(try_get_ts(&json!({"created_at": 1_700_000_000})), Some(millis));
+        assert_eq!(try_get_ts(&json!({"ts": millis})), Some(millis));
+        assert_eq!(try_get_ts(&json!({"time": "1700000000000"})), Some(millis));
+        assert_eq!(try_get_ts(&json!({"date": "Tue, 14 Nov 2023 22:13:20 +0000"})), Some(millis));
+        assert_eq!(try_get_ts(&json!({"payload": {"timestamp": "2023-11-14 22:13:20"}})), Some(millis));
+        assert_eq!(try_get_ts(&json!({"text": "no time"})), None);
+    }
+
+    #[test]
+    fn test_stamp_timestamps_carries_forward() {
+        let mut items = vec![json!({"a": 1}), json!({"ts": 2_000_000_000_000i64}), json!({"b": 2})];
+        stamp_timestamps(&mut items);
+        assert!(items.iter().all(|v| v[ISHINEX_TS] == json!(2_000_000_000_000i64)));
+    }
+
+    #[test]
+    fn test_gather_source_keeps_untimed_records_in_range() {
+        let mut file = tempfile::NamedTempFile::new().unwrap();
+        for record in [
+            json!({"ts": 2_000_000_000_000i64, "n": 1}),
+            json!({"n": 2}),
+            json!({"ts": 2_000_000_001_000i64, "n": 3}),
+        ] {
+            writeln!(file, "{}", record).unwrap();
+        }
+        let filter = UnifyFilter {
+            since: Some(1_999_999_999_000),
+            until: Some(2_000_000_002_000),
+            ..Default::default()
+        };
+        let items = gather_source("codex", file.path(), SourceKind::Project, &filter);
+        let kept: Vec<_> = items.iter().map(|v| v["n"].as_i64()).collect();
+        assert_eq!(kept, vec![Some(1), Some(2), Some(3)]);
+        assert_eq!(items[1][ISHINEX_TS], json!(2_000_000_000_000i64));
+    }
+
+    #[test]
+    fn test_openai_export_follows_current_branch() {
+        let msg = |role: &str, text: &str, t: f64| {
+            json!({"author": {"role": role}, "content": {"parts": [text]}, "create_time": t})
+        };
+        let export = json!([{
+            "id": "c1",
+            "title": "Chat",
+            "current_node": "b",
+            "mapping": {
+                "root": {"message": null, "parent": null},
+                "q": {"message": msg("user", "hi", 1.0), "parent": "root"},
+                "a": {"message": msg("assistant", "edited away", 2.0), "parent": "q"},
+                "b": {"message": msg("assistant", "hello", 3.0), "parent": "q"},
+            }
+        }]);
+        let records = openai_export_records(&export);
+        assert_eq!(records.len(), 2);
+        assert_eq!(records[0]["type"], "user");
+        assert_eq!(records[1]["message"]["content"][0]["text"], "hello");
+        assert_eq!(records[1]["conversation_title"], "Chat");
+    }
+
+    #[test]
+    fn test_project_matcher() {
+        let project = ProjectMatcher::new("/nonexistent/work/app/", false);
+        assert!(project.matches_line(r#"{"payload":{"cwd":"/nonexistent/work/app"}}"#));
+        assert!(project.matches_line(r#"{"cwd":"/nonexistent/work/app//"}"#));
+        assert!(!project.matches_line(r#"{"cwd":"/elsewhere/app"}"#));
+        let loose = ProjectMatcher::new("/nonexistent/work/app", true);
+        assert!(loose.matches_line(r#"{"cwd":"/elsewhere/app/"}"#));
+        assert!(!loose.matches_line(r#"{"cwd":"/elsewhere/api"}"#));
+        assert_eq!(trim_separators("/"), "/");
+    }
+
+    #[test]
+    fn test_map_per_worker_keeps_order() {
+        let items = vec![("codex", 1), ("claude", 2), ("codex", 3), ("gemini", 4), ("claude", 5)];
+        let doubled = map_per_worker(&items, |(worker, _)| *worker, |(_, n)| n * 2);
+        assert_eq!(doubled, vec![2, 4, 6, 8, 10]);
+    }
+
+    #[test]
+    fn test_split_by_file_names() {
+        // 2024-06-01T12:00:00Z
+        let ts = Some(1_717_243_200_000);
+        assert_eq!(SplitBy::Day.file_name(ts), "unified-2024-06-01.jsonl");
+        assert_eq!(SplitBy::Month.file_name(ts), "unified-2024-06.jsonl");
+        assert_eq!(SplitBy::Day.file_name(None), UNDATED_FILE);
+
+        assert!(SplitBy::Day.owns("unified-2024-06-01.jsonl"));
+        assert!(!SplitBy::Day.owns("unified-2024-06.jsonl"));
+        assert!(SplitBy::Month.owns("unified-2024-06.jsonl"));
+        assert!(!SplitBy::Month.owns("unified.jsonl"));
+        assert!(SplitBy::Month.owns(UNDATED_FILE));
+    }
+}
@@ -63,6 +63,33 @@ interface ClaudeCodeSessionProps {
   onProjectPathChange?: (path: string) => void;
 }
 
+/**
+ * Completion payload: Claude emits a bare boolean, Codex/Gemini emit a
+ * versioned envelope `{ v, session_id, success, output_events }`
+ */
+type CompletePayload =
+  | boolean
+  | { v?: number; session_id?: string; success: boolean; cancelled?: boolean; output_events?: number };
+
+const completeSuccess = (payload: CompletePayload): boolean =>
+  typeof payload === 'boolean' ? payload : !!payload?.success;
+
+/** Number of output events the backend emitted before completing (0 for Claude) */
+const completeOutputEvents = (payload: CompletePayload): number =>
+  typeof payload === 'boolean' ? 0 : payload?.output_events ?? 0;
+
+/**
+ * Error payload: plain strings for spawn failures, `{ v, session_id, seq, message }`
+ * for provider stderr lines
+ */
+type ErrorPayload = string | { v?: number; session_id?: string; seq?: number; message: string };
+
+const errorMessage = (payload: ErrorPayload): string =>
+  typeof payload === 'string' ? payload : payload?.message ?? '';
+
+/** Last-resort wait for output events that will never arrive (e.g. listener swapped mid-stream) */
+const COMPLETE_BARRIER_TIMEOUT_MS = 2000;
+
 /**
  * ClaudeCodeSession component for interactive Claude Code sessions
  * 
@@ -411,10 +438,10 @@ export const ClaudeCodeSession: React.FC<ClaudeCodeSessionProps> = ({
       }
     });
 
-    const errorUnlisten = await listen<string>(`claude-error:${sessionId}`, (event) => {
+    const errorUnlisten = await listen<ErrorPayload>(`claude-error:${sessionId}`, (event) => {
       console.error("Claude error:", event.payload);
       if (isMountedRef.current) {
-        setError(event.payload);
+        setError(errorMessage(event.payload));
       }
     });
 
@@ -495,6 +522,24 @@ export const ClaudeCodeSession: React.FC<ClaudeCodeSessionProps> = ({
         let currentSessionId: string | null = claudeSessionId || effectiveSession?.id || null;
         const eventPrefix = provider === 'claude' ? 'claude' : provider; // 'codex' or 'gemini'
 
+        // Completion barrier: Codex/Gemini report how many output events they
+        // emitted, so completion is held until all of them have been handled
+        let receivedOutputEvents = 0;
+        let pendingComplete: CompletePayload | null = null;
+        const handleComplete = (payload: CompletePayload) => {
+          if (receivedOutputEvents >= completeOutputEvents(payload)) {
+            processComplete(completeSuccess(payload));
+            return;
+          }
+          pendingComplete = payload;
+          setTimeout(() => {
+            if (pendingComplete === payload) {
+              pendingComplete = null;
+              processComplete(completeSuccess(payload));
+            }
+          }, COMPLETE_BARRIER_TIMEOUT_MS);
+        };
+
         // Helper to attach session-specific listeners **once we are sure**
         const attachSessionSpecificListeners = async (sid: string) => {
           console.log('[ClaudeCodeSession] Attaching session-specific listeners for', sid);
@@ -503,14 +548,14 @@ export const ClaudeCodeSession: React.FC<ClaudeCodeSessionProps> = ({
             handleStreamMessage(evt.payload);
           });
 
-          const specificErrorUnlisten = await listen<string>(`${eventPrefix}-error:${sid}`, (evt) => {
+          const specificErrorUnlisten = await listen<ErrorPayload>(`${eventPrefix}-error:${sid}`, (evt) => {
             console.error('Claude error (scoped):', evt.payload);
-            setError(evt.payload);
+            setError(errorMessage(evt.payload));
           });
 
-          const specificCompleteUnlisten = await listen<boolean>(`${eventPrefix}-complete:${sid}`, (evt) => {
+          const specificCompleteUnlisten = await listen<CompletePayload>(`${eventPrefix}-complete:${sid}`, (evt) => {
             console.log('[ClaudeCodeSession] Received claude-complete (scoped):', evt.payload);
-            processComplete(evt.payload);
+            handleComplete(evt.payload);
           });
 
           // Replace existing unlisten refs with these new ones (after cleaning up)
@@ -556,6 +601,13 @@ export const ClaudeCodeSession: React.FC<ClaudeCodeSessionProps> = ({
 
         // Helper to process any JSONL stream message string
         function handleStreamMessage(payload: string) {
+          receivedOutputEvents += 1;
+          if (pendingComplete !== null && receivedOutputEvents >= completeOutputEvents(pendingComplete)) {
+            const payloadToComplete = pendingComplete;
+            pendingComplete = null;
+            // Let this last message render before completing
+            queueMicrotask(() => processComplete(completeSuccess(payloadToComplete)));
+          }
           try {
             // Don't process if component unmounted
             if (!isMountedRef.current) return;
@@ -737,14 +789,14 @@ export const ClaudeCodeSession: React.FC<ClaudeCodeSessionProps> = ({
           }
         };
 
-        const genericErrorUnlisten = await listen<string>(`${eventPrefix}-error`, (evt) => {
+        const genericErrorUnlisten = await listen<ErrorPayload>(`${eventPrefix}-error`, (evt) => {
           console.error('Claude error:', evt.payload);
-          setError(evt.payload);
+          setError(errorMessage(evt.payload));
         });
 
-        const genericCompleteUnlisten = await listen<boolean>(`${eventPrefix}-complete`, (evt) => {
+        const genericCompleteUnlisten = await listen<CompletePayload>(`${eventPrefix}-complete`, (evt) => {
           console.log('[ClaudeCodeSession] Received claude-complete (generic):', evt.payload);
-          processComplete(evt.payload);
+          handleComplete(evt.payload);
         });
 
         // Store the generic unlisteners for now; they may be replaced later.
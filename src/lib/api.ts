@@ -444,6 +444,45 @@ export interface ImportServerResult {
   error?: string;
 }
 
+/**
+ * Returned when a Codex/Gemini session starts. The cancel token can be passed
+ * to `cancelByToken` without knowing the session's provider.
+ */
+export interface SessionHandle {
+  session_id: string;
+  cancel_token: string;
+}
+
+/**
+ * Per-profile settings besides binary paths and default models, which the
+ * existing setters store for the active profile
+ */
+export interface ProfileSettings {
+  codex_base_url?: string | null;
+  gemini_base_url?: string | null;
+  env?: Record<string, string>;
+  /** Pass the app's whole environment to the CLI, secrets included */
+  codex_inherit_full_env?: boolean;
+  gemini_inherit_full_env?: boolean;
+}
+
+/** Features a provider and model support, for gating them in the UI */
+export interface ProviderCapabilities {
+  provider: string;
+  model: string;
+  /** The CLI's `--help` could be read; otherwise flags are assumed present */
+  probed: boolean;
+  attachments: boolean;
+  vision: boolean;
+  structured_output: boolean;
+  tools: boolean;
+  reasoning_effort: boolean;
+  /** Whether the model streamed its last run, null when unknown */
+  streams: boolean | null;
+  context_window: number | null;
+  checked_at: string;
+}
+
 /**
  * API client for interacting with the Rust backend
  */
@@ -1072,14 +1111,22 @@ export const api = {
   },
 
   // Codex (OpenAI) provider APIs
-  async executeCodexChat(projectPath: string, prompt: string, model: string): Promise<void> {
+  async executeCodexChat(projectPath: string, prompt: string, model: string): Promise<SessionHandle> {
     return invoke("execute_codex_chat", { projectPath, prompt, model });
   },
-  async resumeCodexChat(projectPath: string, sessionId: string, prompt: string, model: string): Promise<void> {
+  async resumeCodexChat(projectPath: string, sessionId: string, prompt: string, model: string): Promise<SessionHandle> {
     return invoke("resume_codex_chat", { projectPath, sessionId, prompt, model });
   },
-  async cancelCodexExecution(): Promise<void> {
-    return invoke("cancel_codex_execution");
+  async cancelCodexExecution(sessionId?: string): Promise<void> {
+    return invoke("cancel_codex_execution", { sessionId });
+  },
+  async previewCodexCommand(
+    projectPath: string,
+    prompt: string,
+    model: string,
+    extraArgs?: string[]
+  ): Promise<{ provider: string; program: string; args: string[]; cwd: string; env_keys: string[]; prompt_via_stdin: boolean }> {
+    return invoke("preview_codex_command", { projectPath, prompt, model, extraArgs });
   },
   async listRunningCodexSessions(): Promise<any[]> {
     return invoke("list_running_codex_sessions");
@@ -1087,7 +1134,8 @@ export const api = {
   async getCodexBinaryPath(): Promise<string> {
     return invoke("get_codex_binary_path");
   },
-  async setCodexBinaryPath(path: string): Promise<void> {
+  /** Stores the canonical form of `path` and returns it; fails if it isn't an executable */
+  async setCodexBinaryPath(path: string): Promise<string> {
     return invoke("set_codex_binary_path", { path });
   },
   async checkCodexVersion(): Promise<string | null> {
@@ -1102,7 +1150,21 @@ export const api = {
   async setCodexDefaultModel(model: string): Promise<void> {
     return invoke("set_codex_default_model", { model });
   },
-  async listCodexModels(): Promise<string[]> {
+  async getCodexConfigDir(): Promise<string | null> {
+    return invoke("get_codex_config_dir");
+  },
+  /** Pins the Codex config directory and returns its canonical form; an empty path unpins it */
+  async setCodexConfigDir(path: string): Promise<string | null> {
+    return invoke("set_codex_config_dir", { path });
+  },
+  async listCodexModels(): Promise<Array<{
+    id: string;
+    context_window: number | null;
+    supports_tools: boolean | null;
+    supports_vision: boolean | null;
+    input_price: number | null;
+    output_price: number | null;
+  }>> {
     return invoke("list_codex_models");
   },
   async loginCodex(): Promise<void> {
@@ -1110,14 +1172,347 @@ export const api = {
   },
 
   // Gemini provider APIs
-  async executeGeminiChat(projectPath: string, prompt: string, model: string): Promise<void> {
+  async executeGeminiChat(projectPath: string, prompt: string, model: string): Promise<SessionHandle> {
     return invoke("execute_gemini_chat", { projectPath, prompt, model });
   },
-  async resumeGeminiChat(projectPath: string, sessionId: string, prompt: string, model: string): Promise<void> {
+  async resumeGeminiChat(projectPath: string, sessionId: string, prompt: string, model: string): Promise<SessionHandle> {
     return invoke("resume_gemini_chat", { projectPath, sessionId, prompt, model });
   },
-  async cancelGeminiExecution(): Promise<void> {
-    return invoke("cancel_gemini_execution");
+  async cancelGeminiExecution(sessionId?: string): Promise<void> {
+    return invoke("cancel_gemini_execution", { sessionId });
+  },
+  async previewGeminiCommand(
+    projectPath: string,
+    prompt: string,
+    model: string,
+    extraArgs?: string[]
+  ): Promise<{ provider: string; program: string; args: string[]; cwd: string; env_keys: string[]; prompt_via_stdin: boolean }> {
+    return invoke("preview_gemini_command", { projectPath, prompt, model, extraArgs });
+  },
+  async cancelByToken(token: string): Promise<void> {
+    return invoke("cancel_by_token", { token });
+  },
+  /** Stops every session and agent run working in a project, whatever the provider */
+  async cancelProjectSessions(
+    projectPath: string
+  ): Promise<{ stopped: number; session_ids: string[]; agent_run_ids: number[] }> {
+    return invoke("cancel_project_sessions", { projectPath });
+  },
+  async getCompressTranscripts(): Promise<boolean> {
+    return invoke("get_compress_transcripts");
+  },
+  async setCompressTranscripts(enabled: boolean): Promise<void> {
+    return invoke("set_compress_transcripts", { enabled });
+  },
+  async getAutoAcceptDownloads(): Promise<boolean> {
+    return invoke("get_auto_accept_downloads");
+  },
+  /** Confirm model download prompts in interactive sessions instead of emitting setup-required */
+  async setAutoAcceptDownloads(enabled: boolean): Promise<void> {
+    return invoke("set_auto_accept_downloads", { enabled });
+  },
+  /** Gzip a project's session transcripts untouched for `olderThanDays` */
+  async compactOldSessions(
+    projectPath: string,
+    olderThanDays: number
+  ): Promise<{ compressed: number; skipped: number; bytes_before: number; bytes_after: number }> {
+    return invoke("compact_old_sessions", { projectPath, olderThanDays });
+  },
+  /** Last messages of a session's transcript and whether it is still running */
+  async tailSessionTranscript(
+    sessionId: string,
+    lastN: number,
+    projectPath?: string
+  ): Promise<{ session_id: string; path: string; messages: any[]; running: boolean }> {
+    return invoke("tail_session_transcript", { sessionId, lastN, projectPath });
+  },
+  /** Every recorded Codex/Gemini session of a project (running or finished), newest first */
+  async listAllSessions(projectPath: string): Promise<Array<{
+    session_id: string;
+    provider: string | null;
+    model: string | null;
+    started_at: string | null;
+    ended_at: string | null;
+    status: "running" | "cancelled" | "completed";
+    message_count: number;
+    first_prompt: string | null;
+    /** Cached title from `summarizeSession` */
+    title: string | null;
+  }>> {
+    return invoke("list_all_sessions", { projectPath });
+  },
+  /** Generated one-line title and summary of a session; cached until its transcript changes */
+  async summarizeSession(sessionId: string, projectPath?: string, force?: boolean): Promise<{
+    session_id: string;
+    title: string;
+    summary: string | null;
+    provider: string;
+    generated_at: string;
+    cached: boolean;
+  }> {
+    return invoke("summarize_session", { sessionId, projectPath, force });
+  },
+  /** Diff the final answers of two stored sessions by line (default) or word, with similarity metrics */
+  async diffSessions(
+    sessionA: string,
+    sessionB: string,
+    mode?: "line" | "word",
+    projectPath?: string
+  ): Promise<{
+    a: { session_id: string; provider: string | null; model: string | null; text: string };
+    b: { session_id: string; provider: string | null; model: string | null; text: string };
+    mode: "line" | "word";
+    chunks: Array<{ kind: "equal" | "insert" | "delete"; text: string }>;
+    metrics: {
+      units_a: number;
+      units_b: number;
+      unchanged: number;
+      inserted: number;
+      deleted: number;
+      similarity: number;
+      word_overlap: number;
+    };
+  }> {
+    return invoke("diff_sessions", { sessionA, sessionB, mode, projectPath });
+  },
+  /** Writes a stored session as a standalone HTML page for sharing; resolves to its path */
+  async exportSessionHtml(sessionId: string, projectPath?: string): Promise<string> {
+    return invoke("export_session_html", { sessionId, projectPath });
+  },
+  /** Keep a running session going in the background without emitting its events (e.g. its tab was closed) */
+  async detachSession(sessionId: string): Promise<void> {
+    return invoke("detach_session", { sessionId });
+  },
+  /** Replay the last `tail` transcript messages of a detached session, then resume its live events; returns how many were replayed */
+  async reattachSession(sessionId: string, tail?: number, projectPath?: string): Promise<number> {
+    return invoke("reattach_session", { sessionId, tail, projectPath });
+  },
+  /** Re-emit a finished session's messages on its output channel; speed 1 = real time, 0 = instant */
+  async replaySession(
+    sessionId: string,
+    speed?: number,
+    projectPath?: string
+  ): Promise<{ session_id: string; provider: string; messages: number }> {
+    return invoke("replay_session", { sessionId, speed, projectPath });
+  },
+  // Profiles (e.g. personal vs work accounts)
+  async listProfiles(): Promise<Array<{ name: string; active: boolean }>> {
+    return invoke("list_profiles");
+  },
+  async createProfile(name: string): Promise<{ name: string; active: boolean }> {
+    return invoke("create_profile", { name });
+  },
+  async deleteProfile(name: string): Promise<void> {
+    return invoke("delete_profile", { name });
+  },
+  async switchProfile(name: string): Promise<void> {
+    return invoke("switch_profile", { name });
+  },
+  async getPersistStderr(provider: "codex" | "gemini"): Promise<boolean> {
+    return invoke("get_persist_stderr", { provider });
+  },
+  /** Write a provider's stderr lines into session transcripts as `system`/`stderr` records */
+  async setPersistStderr(provider: "codex" | "gemini", enabled: boolean): Promise<void> {
+    return invoke("set_persist_stderr", { provider, enabled });
+  },
+  async getRecordDelimiter(provider: "codex" | "gemini"): Promise<string> {
+    return invoke("get_record_delimiter", { provider });
+  },
+  /** Split a provider's stdout on this delimiter (escapes like `\0` or `\x1e`); null restores newlines */
+  async setRecordDelimiter(provider: "codex" | "gemini", delimiter: string | null): Promise<void> {
+    return invoke("set_record_delimiter", { provider, delimiter });
+  },
+  async getMaxOutputLines(): Promise<number | null> {
+    return invoke("get_max_output_lines");
+  },
+  /** Default cap on the lines a session may print before it is killed; null or 0 removes it */
+  async setMaxOutputLines(limit: number | null): Promise<void> {
+    return invoke("set_max_output_lines", { limit });
+  },
+  async getUseLoginShell(provider: "codex" | "gemini"): Promise<boolean> {
+    return invoke("get_use_login_shell", { provider });
+  },
+  /** Per-model flag rules (stored, or the built-in defaults) */
+  async getModelArgRules(
+    provider: "codex" | "gemini"
+  ): Promise<{ model: string; drop: string[]; rename: Record<string, string> }[]> {
+    return invoke("get_model_arg_rules", { provider });
+  },
+  /** Flags dropped or renamed for models matching each glob; null restores the defaults */
+  async setModelArgRules(
+    provider: "codex" | "gemini",
+    rules: { model: string; drop?: string[]; rename?: Record<string, string> }[] | null
+  ): Promise<void> {
+    return invoke("set_model_arg_rules", { provider, rules });
+  },
+  /** Launch a provider's CLI through `$SHELL -lc` so nvm/asdf/direnv setups apply (not on Windows) */
+  async setUseLoginShell(provider: "codex" | "gemini", enabled: boolean): Promise<void> {
+    return invoke("set_use_login_shell", { provider, enabled });
+  },
+  /** Capability matrix of a provider and model; cached for a day unless `force` */
+  async getProviderCapabilities(
+    provider: "codex" | "gemini",
+    model: string,
+    force?: boolean
+  ): Promise<ProviderCapabilities> {
+    return invoke("get_provider_capabilities", { provider, model, force });
+  },
+  async getProjectModel(projectPath: string, provider: "codex" | "gemini"): Promise<string | null> {
+    return invoke("get_project_model", { projectPath, provider });
+  },
+  /** Model a provider uses for this project, ahead of its default; null clears it */
+  async setProjectModel(projectPath: string, provider: "codex" | "gemini", model: string | null): Promise<void> {
+    return invoke("set_project_model", { projectPath, provider, model });
+  },
+  async getGlobalDefaultModel(): Promise<string | null> {
+    return invoke("get_global_default_model");
+  },
+  /** Fallback for providers without a default of their own; null clears it */
+  async setGlobalDefaultModel(model: string | null): Promise<void> {
+    return invoke("set_global_default_model", { model });
+  },
+  /**
+   * Model a "default" chat would launch with: project model, provider default,
+   * config file, then global default. Rejects when none names one.
+   */
+  async resolveEffectiveModel(
+    provider: "codex" | "gemini",
+    projectPath?: string
+  ): Promise<{ provider: string; model: string; source: "project" | "provider_default" | "config_file" | "global" }> {
+    return invoke("resolve_effective_model", { projectPath, provider });
+  },
+  async getInheritFullEnv(provider: "codex" | "gemini"): Promise<boolean> {
+    return invoke("get_inherit_full_env", { provider });
+  },
+  /** Bypass the env allowlist for a provider (active profile); secrets in the app's env reach the CLI */
+  async setInheritFullEnv(provider: "codex" | "gemini", enabled: boolean): Promise<void> {
+    return invoke("set_inherit_full_env", { provider, enabled });
+  },
+  async getProfileSettings(name?: string): Promise<ProfileSettings> {
+    return invoke("get_profile_settings", { name });
+  },
+  async setProfileSettings(settings: ProfileSettings, name?: string): Promise<void> {
+    return invoke("set_profile_settings", { name, settings });
+  },
+  /** Every app setting as a snapshot; credentials are left out (and listed in `sensitive`) unless requested */
+  async exportSettings(includeSensitive?: boolean): Promise<{
+    version: number;
+    exported_at: string;
+    settings: Record<string, string>;
+    sensitive: string[];
+    includes_sensitive: boolean;
+  }> {
+    return invoke("export_settings", { includeSensitive });
+  },
+  /** Restore a snapshot from `exportSettings`; existing settings are kept unless `overwrite` */
+  async importSettings(json: string, overwrite?: boolean): Promise<{ imported: string[]; kept: string[] }> {
+    return invoke("import_settings", { json, overwrite });
+  },
+  /** One gate for automated runs: installed, logged in and the model exists; `reason` names the first failed check */
+  async ensureProviderReady(provider: "codex" | "gemini", model?: string, force?: boolean): Promise<{
+    provider: string;
+    ready: boolean;
+    reason: "not_installed" | "not_logged_in" | "model_missing" | null;
+    message: string | null;
+    binary_path: string | null;
+    version: string | null;
+    model: string | null;
+    model_verified: boolean;
+  }> {
+    return invoke("ensure_provider_ready", { provider, model, force });
+  },
+  /** Run a provider CLI subcommand the app doesn't wrap, e.g. `["config", "get"]` */
+  async runProviderCommand(
+    provider: "codex" | "gemini",
+    args: string[],
+    timeoutSecs?: number,
+    projectPath?: string
+  ): Promise<{
+    provider: string;
+    args: string[];
+    exit_code: number | null;
+    stdout: string;
+    stderr: string;
+    timed_out: boolean;
+    truncated: boolean;
+    elapsed_ms: number;
+  }> {
+    return invoke("run_provider_command", { provider, args, timeoutSecs, projectPath });
+  },
+  /** Fill the provider caches (version, login, models) now; also emits `providers-warmed` */
+  async warmProviders(): Promise<Array<{
+    provider: string;
+    installed: boolean;
+    version: string | null;
+    logged_in: boolean | null;
+    models: number | null;
+    elapsed_ms: number;
+  }>> {
+    return invoke("warm_providers");
+  },
+  async getWarmProvidersOnStartup(): Promise<boolean> {
+    return invoke("get_warm_providers_on_startup");
+  },
+  async setWarmProvidersOnStartup(enabled: boolean): Promise<void> {
+    return invoke("set_warm_providers_on_startup", { enabled });
+  },
+  /** Seconds a login/version/model-list probe may run before it fails */
+  async getProbeTimeout(): Promise<number> {
+    return invoke("get_probe_timeout");
+  },
+  async setProbeTimeout(secs: number): Promise<number> {
+    return invoke("set_probe_timeout", { secs });
+  },
+  /**
+   * Local relay of session events: WebSocket at ws://127.0.0.1:<port>/?token=<token>, and one
+   * session as Server-Sent Events at http://127.0.0.1:<port>/sessions/<id>/events?token=<token>
+   */
+  async getWebSocketSettings(): Promise<{ enabled: boolean; port: number; token: string; running: boolean }> {
+    return invoke("get_websocket_settings");
+  },
+  async setWebSocketSettings(
+    enabled: boolean,
+    port?: number,
+    regenerateToken?: boolean
+  ): Promise<{ enabled: boolean; port: number; token: string; running: boolean }> {
+    return invoke("set_websocket_settings", { enabled, port, regenerateToken });
+  },
+  /** Most used models of a provider, recent runs weighing more */
+  async getFrequentModels(
+    provider: "codex" | "gemini",
+    limit?: number
+  ): Promise<Array<{ model: string; uses: number; last_used_at: string; score: number }>> {
+    return invoke("get_frequent_models", { provider, limit });
+  },
+  /** Prefix the backend puts in front of every event name ("" unless configured) */
+  async getEventNamespace(): Promise<string> {
+    return invoke("get_event_namespace");
+  },
+  /** Re-run the last prompt of a Codex/Gemini session; the old answer is kept but marked superseded */
+  async regenerateLast(projectPath: string, sessionId: string, model?: string): Promise<SessionHandle> {
+    return invoke("regenerate_last", { projectPath, sessionId, model });
+  },
+  /** Cancel a running session and re-run its last prompt on another model under the same session id */
+  async restartSessionWithModel(sessionId: string, newModel: string, projectPath?: string): Promise<SessionHandle> {
+    return invoke("restart_session_with_model", { sessionId, newModel, projectPath });
+  },
+  /** Drop everything after a message so the session resumes from it; the original is backed up */
+  async truncateSession(
+    projectPath: string,
+    sessionId: string,
+    keepUntilMessageId: string
+  ): Promise<{ session_id: string; removed: number; kept: number; backup_path: string }> {
+    return invoke("truncate_session", { projectPath, sessionId, keepUntilMessageId });
+  },
+  /** Starts a session on another provider with `{previous_output}` in `template` replaced by the source's last reply */
+  async chainSession(
+    sourceSessionId: string,
+    targetProvider: "codex" | "gemini",
+    template: string,
+    targetModel?: string,
+    projectPath?: string
+  ): Promise<SessionHandle> {
+    return invoke("chain_session", { sourceSessionId, targetProvider, targetModel, template, projectPath });
   },
   async listRunningGeminiSessions(): Promise<any[]> {
     return invoke("list_running_gemini_sessions");
@@ -1125,7 +1520,8 @@ export const api = {
   async getGeminiBinaryPath(): Promise<string> {
     return invoke("get_gemini_binary_path");
   },
-  async setGeminiBinaryPath(path: string): Promise<void> {
+  /** Stores the canonical form of `path` and returns it; fails if it isn't an executable */
+  async setGeminiBinaryPath(path: string): Promise<string> {
     return invoke("set_gemini_binary_path", { path });
   },
   async checkGeminiVersion(): Promise<string | null> {
@@ -1148,8 +1544,117 @@ export const api = {
   },
 
   // Unified history
-  async unifyProviderHistories(projectPath: string): Promise<{ unified_path: string; total_messages: number; sources: { provider: string; count: number }[] }> {
-    return invoke("unify_provider_histories", { projectPath });
+  async unifyProviderHistories(
+    projectPath: string,
+    outputDir?: string,
+    filter?: { providers?: string[]; roles?: string[]; since?: number; until?: number; match_basename?: boolean },
+    /** Write one file per UTC day or month instead of a single `unified.jsonl` */
+    splitBy?: "day" | "month"
+  ): Promise<{
+    /** The unified file, or their directory when split */
+    unified_path: string;
+    files: string[];
+    total_messages: number;
+    sources: { provider: string; count: number }[];
+    /** Source files re-read; unchanged ones are carried over from the last run */
+    files_read: number;
+    files_unchanged: number;
+    /** History directories that couldn't be read (e.g. permission denied) */
+    warnings: string[];
+  }> {
+    return invoke("unify_provider_histories", { projectPath, outputDir, filter, splitBy });
+  },
+  /**
+   * Rebuilds every known project's unified history; progress arrives as `unify-progress` events
+   * carrying the `run_id` that `cancelUnifyAll` takes (pass `runId` to choose it up front)
+   */
+  async unifyAllProjects(runId?: string): Promise<{
+    run_id: string;
+    results: { unified_path: string; total_messages: number; sources: { provider: string; count: number }[] }[];
+    failures: { project_path: string; error: string }[];
+    total_projects: number;
+    total_messages: number;
+    cancelled: boolean;
+  }> {
+    return invoke("unify_all_projects", { runId });
+  },
+  async cancelUnifyAll(runId: string): Promise<void> {
+    return invoke("cancel_unify_all", { runId });
+  },
+  /** Expected unified-file size and message count, compared with the free space; writes nothing */
+  async estimateUnifySize(
+    projectPath: string,
+    outputDir?: string
+  ): Promise<{
+    output_dir: string;
+    total_messages: number;
+    sources: { provider: string; count: number }[];
+    source_bytes: number;
+    estimated_bytes: number;
+    free_bytes: number | null;
+    fits: boolean | null;
+  }> {
+    return invoke("estimate_unify_size", { projectPath, outputDir });
+  },
+  async getUnifiedOutputDir(): Promise<string | null> {
+    return invoke("get_unified_output_dir");
+  },
+  async setUnifiedOutputDir(dir: string | null): Promise<void> {
+    return invoke("set_unified_output_dir", { dir });
+  },
+  async getHistoryWalkSettings(): Promise<{ max_depth: number; ignore: string[] }> {
+    return invoke("get_history_walk_settings");
+  },
+  async setHistoryWalkSettings(settings: { max_depth: number; ignore: string[] }): Promise<void> {
+    return invoke("set_history_walk_settings", { settings });
+  },
+  /** Fold a jsonl file or an OpenAI chat export into a project's unified history under `providerLabel` */
+  /** Pass the `outputDir`/`splitBy` the project was unified with so the right files are updated */
+  async importTranscript(
+    projectPath: string,
+    providerLabel: string,
+    path: string,
+    outputDir?: string,
+    splitBy?: "day" | "month"
+  ): Promise<{ imported: number; skipped: number; import_path: string; unified_path: string }> {
+    return invoke("import_transcript", { projectPath, providerLabel, path, outputDir, splitBy });
+  },
+  /** Claude Code sessions of a project with what `claude --resume` needs, most recently updated first */
+  async listClaudeSessions(projectPath: string): Promise<Array<{
+    session_id: string;
+    path: string;
+    summary: string | null;
+    leaf_uuid: string | null;
+    cwd: string | null;
+    git_branch: string | null;
+    version: string | null;
+    started_at: string | null;
+    updated_at: string | null;
+    message_count: number;
+    first_prompt: string | null;
+    resumable: boolean;
+  }>> {
+    return invoke("list_claude_sessions", { projectPath });
+  },
+  /** Renames a provider label in the unified history; returns how many records changed */
+  async relabelProvider(
+    projectPath: string,
+    from: string,
+    to: string,
+    outputDir?: string,
+    splitBy?: "day" | "month"
+  ): Promise<number> {
+    return invoke("relabel_provider", { projectPath, from, to, outputDir, splitBy });
+  },
+  async validateJsonl(
+    path: string
+  ): Promise<{ path: string; total_lines: number; invalid_lines: number; first_invalid_line: number | null }> {
+    return invoke("validate_jsonl", { path });
+  },
+  async repairJsonl(
+    path: string
+  ): Promise<{ path: string; backup_path: string; kept_lines: number; dropped_lines: number }> {
+    return invoke("repair_jsonl", { path });
   },
 
   /**
@@ -1232,6 +1737,19 @@ export const api = {
     }
   },
 
+  /**
+   * Exports per-session usage for a project as CSV
+   * @param since - Inclusive start date (YYYY-MM-DD)
+   * @param until - Inclusive end date (YYYY-MM-DD)
+   */
+  async exportUsageCsv(
+    projectPath: string,
+    since?: string,
+    until?: string
+  ): Promise<{ path: string; sessions: number; total_input_tokens: number; total_output_tokens: number; total_cost: number }> {
+    return invoke("export_usage_csv", { projectPath, since, until });
+  },
+
   /**
    * Creates a checkpoint for the current session state
    */